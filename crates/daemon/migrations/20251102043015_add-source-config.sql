@@ -0,0 +1,3 @@
+-- Add column for source specific configuration
+ALTER TABLE sources
+    ADD COLUMN source_config TEXT;
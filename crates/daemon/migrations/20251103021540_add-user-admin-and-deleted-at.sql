@@ -0,0 +1,22 @@
+-- Add a column marking admin users
+ALTER TABLE users
+    ADD COLUMN admin BOOLEAN DEFAULT FALSE;
+
+-- Add a column for when user was soft deleted
+ALTER TABLE users
+    ADD COLUMN deleted_at TIMESTAMP;
+
+-- Recreate view and insert trigger so new columns are included
+DROP TRIGGER users_active_insert;
+DROP VIEW users_active;
+
+CREATE VIEW IF NOT EXISTS users_active AS
+    SELECT *
+        FROM users
+        WHERE users.deleted = FALSE;
+
+CREATE TRIGGER users_active_insert INSTEAD OF INSERT ON users_active
+    BEGIN
+        INSERT INTO users (uuid, username, password_hash, salt, argon2_iters, argon2_memory_mb, argon2_parallelism, admin)
+            VALUES (new.uuid, new.username, new.password_hash, new.salt, new.argon2_iters, new.argon2_memory_mb, new.argon2_parallelism, COALESCE(new.admin, FALSE));
+    END;
@@ -0,0 +1,77 @@
+-- Add table for roles which can be granted to users
+CREATE TABLE roles (
+    name TEXT NOT NULL UNIQUE,
+
+    PRIMARY KEY (name)
+);
+
+-- Add table for permissions checked by the daemon
+CREATE TABLE permissions (
+    name TEXT NOT NULL UNIQUE,
+
+    PRIMARY KEY (name)
+);
+
+-- Add table for correlating permissions with a role
+CREATE TABLE role_permissions (
+    role        TEXT NOT NULL,
+    permission  TEXT NOT NULL,
+
+    UNIQUE (role, permission),
+    FOREIGN KEY (role) REFERENCES roles(name),
+    FOREIGN KEY (permission) REFERENCES permissions(name)
+);
+
+INSERT INTO roles (name)
+    VALUES ('admin'), ('writer'), ('reader');
+
+INSERT INTO permissions (name)
+    VALUES ('read'), ('write'), ('manage');
+
+INSERT INTO role_permissions (role, permission)
+    VALUES
+        ('admin', 'read'),
+        ('admin', 'write'),
+        ('admin', 'manage'),
+        ('writer', 'read'),
+        ('writer', 'write'),
+        ('reader', 'read');
+
+-- Add table for granting a role to a user.
+-- Grants are either global (scope_type 0) or scoped to a collection (1) or source (2).
+CREATE TABLE grants (
+    uuid        BLOB NOT NULL UNIQUE,
+    uuid_user   BLOB NOT NULL,
+    role        TEXT NOT NULL,
+    scope_type  INTEGER NOT NULL,
+    uuid_scope  BLOB,
+    created_at  TIMESTAMP,
+
+    PRIMARY KEY (uuid),
+    FOREIGN KEY (uuid_user) REFERENCES users(uuid) ON DELETE CASCADE,
+    FOREIGN KEY (role) REFERENCES roles(name)
+);
+
+CREATE TRIGGER grants_trigger_after_insert AFTER INSERT ON grants
+    BEGIN
+        UPDATE grants
+            SET created_at = DATETIME('NOW')
+            WHERE ROWID = NEW.ROWID;
+    END;
+
+-- Users registered before roles existed could use every endpoint, keep that as a global writer grant.
+INSERT INTO grants (uuid, uuid_user, role, scope_type)
+    SELECT
+        LOWER(
+            HEX(RANDOMBLOB(4)) || '-' ||
+            HEX(RANDOMBLOB(2)) || '-4' ||
+            SUBSTR(HEX(RANDOMBLOB(2)), 2) || '-' ||
+            SUBSTR('89AB', 1 + (ABS(RANDOM()) % 4), 1) ||
+            SUBSTR(HEX(RANDOMBLOB(2)), 2) || '-' ||
+            HEX(RANDOMBLOB(6))
+        ),
+        uuid,
+        'writer',
+        0
+    FROM users
+    WHERE admin = FALSE;
@@ -0,0 +1,48 @@
+-- Add a column marking service accounts, they can't login and only authenticate with api keys
+ALTER TABLE users
+    ADD COLUMN service BOOLEAN DEFAULT FALSE;
+
+-- Recreate view and insert trigger so new columns are included
+DROP TRIGGER users_active_insert;
+DROP VIEW users_active;
+
+CREATE VIEW IF NOT EXISTS users_active AS
+    SELECT *
+        FROM users
+        WHERE users.deleted = FALSE;
+
+CREATE TRIGGER users_active_insert INSTEAD OF INSERT ON users_active
+    BEGIN
+        INSERT INTO users (uuid, username, password_hash, salt, argon2_iters, argon2_memory_mb, argon2_parallelism, admin, service)
+            VALUES (new.uuid, new.username, new.password_hash, new.salt, new.argon2_iters, new.argon2_memory_mb, new.argon2_parallelism, COALESCE(new.admin, FALSE), COALESCE(new.service, FALSE));
+    END;
+
+-- Add table for api keys owned by service accounts.
+-- Only a hash of the key secret is stored.
+CREATE TABLE api_keys (
+    uuid            BLOB NOT NULL UNIQUE,
+    uuid_user       BLOB NOT NULL,
+    name            TEXT NOT NULL,
+    key_hash        TEXT NOT NULL,
+    expires_at      TIMESTAMP,
+    last_used_at    TIMESTAMP,
+    created_at      TIMESTAMP,
+    updated_at      TIMESTAMP,
+
+    PRIMARY KEY (uuid),
+    UNIQUE (uuid_user, name),
+    FOREIGN KEY (uuid_user) REFERENCES users(uuid) ON DELETE CASCADE
+);
+
+CREATE TRIGGER api_keys_trigger_after_insert AFTER INSERT ON api_keys
+    BEGIN
+        UPDATE api_keys
+            SET
+                created_at = DATETIME('NOW'),
+                updated_at = DATETIME('NOW')
+            WHERE ROWID = NEW.ROWID;
+    END;
+
+-- Scopes of api keys are grants which only apply to requests using the key
+ALTER TABLE grants
+    ADD COLUMN uuid_api_key BLOB REFERENCES api_keys(uuid) ON DELETE CASCADE;
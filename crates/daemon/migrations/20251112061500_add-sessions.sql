@@ -0,0 +1,25 @@
+-- Add table tracking refresh tokens, each token has a unique id (jti) stored as uuid.
+-- Tokens issued by refreshing share the family of the token they replace, reusing a rotated
+-- token revokes the whole family.
+CREATE TABLE sessions (
+    uuid            BLOB NOT NULL UNIQUE,
+    uuid_user       BLOB NOT NULL,
+    uuid_family     BLOB NOT NULL,
+    rotated         BOOLEAN NOT NULL DEFAULT FALSE,
+    revoked         BOOLEAN NOT NULL DEFAULT FALSE,
+    expires_at      TIMESTAMP NOT NULL,
+    created_at      TIMESTAMP,
+
+    PRIMARY KEY (uuid),
+    FOREIGN KEY (uuid_user) REFERENCES users(uuid) ON DELETE CASCADE
+);
+
+CREATE INDEX sessions_uuid_family ON sessions(uuid_family);
+CREATE INDEX sessions_uuid_user ON sessions(uuid_user);
+
+CREATE TRIGGER sessions_trigger_after_insert AFTER INSERT ON sessions
+    BEGIN
+        UPDATE sessions
+            SET created_at = DATETIME('NOW')
+            WHERE ROWID = NEW.ROWID;
+    END;
@@ -0,0 +1,27 @@
+-- Password hashes are PHC strings which already contain the salt and argon2 parameters they
+-- were hashed with, legacy rows keep their hash and only lose the duplicated columns.
+DROP TRIGGER users_active_insert;
+DROP VIEW users_active;
+
+ALTER TABLE users
+    DROP COLUMN salt;
+
+ALTER TABLE users
+    DROP COLUMN argon2_iters;
+
+ALTER TABLE users
+    DROP COLUMN argon2_memory_mb;
+
+ALTER TABLE users
+    DROP COLUMN argon2_parallelism;
+
+CREATE VIEW IF NOT EXISTS users_active AS
+    SELECT *
+        FROM users
+        WHERE users.deleted = FALSE;
+
+CREATE TRIGGER users_active_insert INSTEAD OF INSERT ON users_active
+    BEGIN
+        INSERT INTO users (uuid, username, password_hash, admin, service)
+            VALUES (new.uuid, new.username, new.password_hash, COALESCE(new.admin, FALSE), COALESCE(new.service, FALSE));
+    END;
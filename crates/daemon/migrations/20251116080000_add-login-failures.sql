@@ -0,0 +1,11 @@
+-- Add table tracking failed logins per username and per peer address.
+-- Rows are removed on successful login, when unlocked or once failures are forgotten.
+CREATE TABLE login_failures (
+    kind            TEXT NOT NULL,
+    name            TEXT NOT NULL,
+    failures        INTEGER NOT NULL,
+    locked_until    TIMESTAMP,
+    last_failed_at  TIMESTAMP NOT NULL,
+
+    PRIMARY KEY (kind, name)
+);
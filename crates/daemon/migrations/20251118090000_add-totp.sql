@@ -0,0 +1,30 @@
+-- Add table for TOTP second factors of users, at most one per user. The secret is only
+-- required on login once enrollment was confirmed with a code. The last accepted time step is
+-- kept so codes can't be replayed.
+CREATE TABLE totp (
+    uuid_user       BLOB NOT NULL UNIQUE,
+    secret          TEXT NOT NULL,
+    enabled         BOOLEAN NOT NULL DEFAULT FALSE,
+    last_step       INTEGER,
+    created_at      TIMESTAMP,
+
+    PRIMARY KEY (uuid_user),
+    FOREIGN KEY (uuid_user) REFERENCES users(uuid) ON DELETE CASCADE
+);
+
+CREATE TRIGGER totp_trigger_after_insert AFTER INSERT ON totp
+    BEGIN
+        UPDATE totp
+            SET created_at = DATETIME('NOW')
+            WHERE ROWID = NEW.ROWID;
+    END;
+
+-- Add table for one-time recovery codes, used in place of a TOTP code.
+-- Only a hash of each code is stored, used codes are removed.
+CREATE TABLE recovery_codes (
+    uuid_user       BLOB NOT NULL,
+    code_hash       TEXT NOT NULL,
+
+    PRIMARY KEY (uuid_user, code_hash),
+    FOREIGN KEY (uuid_user) REFERENCES users(uuid) ON DELETE CASCADE
+);
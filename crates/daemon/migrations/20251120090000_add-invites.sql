@@ -0,0 +1,21 @@
+-- Add table for one-time invite codes allowing to register a user.
+-- Only a hash of each code is stored, used codes are removed. The setup token registering the
+-- first admin is an invite without creator marked as admin.
+CREATE TABLE invites (
+    uuid            BLOB NOT NULL UNIQUE,
+    uuid_creator    BLOB,
+    code_hash       TEXT NOT NULL UNIQUE,
+    admin           BOOLEAN NOT NULL DEFAULT FALSE,
+    expires_at      TIMESTAMP,
+    created_at      TIMESTAMP,
+
+    PRIMARY KEY (uuid),
+    FOREIGN KEY (uuid_creator) REFERENCES users(uuid) ON DELETE CASCADE
+);
+
+CREATE TRIGGER invites_trigger_after_insert AFTER INSERT ON invites
+    BEGIN
+        UPDATE invites
+            SET created_at = DATETIME('NOW')
+            WHERE ROWID = NEW.ROWID;
+    END;
@@ -0,0 +1,22 @@
+-- Add table for data keys encrypting secret values, wrapped by the master key kept outside of
+-- the database. Collections get their own data key, the key without collection is the default.
+-- Keys outlive their collection as secrets encrypted with them can be linked elsewhere.
+CREATE TABLE data_keys (
+    uuid            BLOB NOT NULL UNIQUE,
+    uuid_collection BLOB UNIQUE,
+    master_key_id   TEXT NOT NULL,
+    wrapped_key     TEXT NOT NULL,
+    created_at      TIMESTAMP,
+
+    PRIMARY KEY (uuid)
+);
+
+CREATE TRIGGER data_keys_trigger_after_insert AFTER INSERT ON data_keys
+    BEGIN
+        UPDATE data_keys
+            SET created_at = DATETIME('NOW')
+            WHERE ROWID = NEW.ROWID;
+    END;
+
+-- Secrets without data key still hold their plaintext value, they are encrypted on startup.
+ALTER TABLE secrets ADD COLUMN uuid_data_key BLOB REFERENCES data_keys(uuid);
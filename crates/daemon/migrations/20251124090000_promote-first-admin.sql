@@ -0,0 +1,6 @@
+-- Promote the earliest active user so existing installs keep someone who can manage users,
+-- installs which already have an admin are left alone.
+UPDATE users
+    SET admin = TRUE
+    WHERE ROWID = (SELECT MIN(ROWID) FROM users WHERE deleted = FALSE AND service = FALSE)
+        AND NOT EXISTS (SELECT 1 FROM users WHERE admin = TRUE);
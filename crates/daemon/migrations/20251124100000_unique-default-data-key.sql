@@ -0,0 +1,17 @@
+-- Only one default data key may exist. Concurrent first use could create more than one, the
+-- oldest stays the default and unused extras are dropped. Extras which already encrypt secrets
+-- are retired, they still decrypt those secrets but are never handed out again.
+ALTER TABLE data_keys ADD COLUMN retired BOOLEAN NOT NULL DEFAULT FALSE;
+
+DELETE FROM data_keys
+    WHERE uuid_collection IS NULL
+        AND ROWID != (SELECT MIN(ROWID) FROM data_keys WHERE uuid_collection IS NULL)
+        AND uuid NOT IN (SELECT uuid_data_key FROM secrets WHERE uuid_data_key IS NOT NULL);
+
+UPDATE data_keys
+    SET retired = TRUE
+    WHERE uuid_collection IS NULL
+        AND ROWID != (SELECT MIN(ROWID) FROM data_keys WHERE uuid_collection IS NULL);
+
+CREATE UNIQUE INDEX data_keys_default ON data_keys (uuid_collection IS NULL)
+    WHERE uuid_collection IS NULL AND retired = FALSE;
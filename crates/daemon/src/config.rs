@@ -1,41 +1,124 @@
 use crate::constants;
 
-use shared_core::{config, rng};
+use shared_core::{config, crypt};
+use std::path::PathBuf;
 use tokio::sync::RwLock;
 
 /// Name of config file
 const CONFIG_FILE_NAME: &str = "config.toml";
 
-/// Length of default database encryption key
-const DATABASE_ENCRYPTION_KEY_DEFAULT_LENGTH: usize = 32;
+/// Length of generated database encryption keys
+pub const DATABASE_ENCRYPTION_KEY_LENGTH: usize = 32;
 
 /// Global config
 #[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
 pub struct Config {
     pub database: DatabaseConfig,
     pub encryption: EncryptionConfig,
+    pub import: ImportConfig,
+    pub jwt: JwtConfig,
+    pub login: LoginConfig,
+    pub peer_auth: PeerAuthConfig,
+    pub registration: RegistrationConfig,
+    pub user: UserConfig,
 }
 
 /// Database config
+///
+/// The database is encrypted with a key read from the configured source, only the `config`
+/// source keeps it next to the database.
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct DatabaseConfig {
-    pub encryption_key: String,
+    pub key_source: DatabaseKeySource,
+    /// Key the database is encrypted with if the key source is `config`, generated on first
+    /// start. Change it with the `rekey` subcommand or endpoint.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encryption_key: Option<String>,
+    /// Environment variable holding the key if the key source is `env`.
+    pub key_env: String,
+    /// File holding the key if the key source is `file`, `database.key` in the config folder if
+    /// not set. It's created on first start and must only be accessible by the daemon user.
+    pub key_file: Option<PathBuf>,
+    /// Name of the systemd credential holding the key if the key source is `systemd`.
+    pub key_credential: String,
+    /// Number of shares a new key is split into if the key source is `shamir`, at most 255.
+    pub shamir_shares: u8,
+    /// Number of shares unsealing needs if the key source is `shamir`.
+    pub shamir_threshold: u8,
+    /// Hashes of the shares of the key if the key source is `shamir`, ordered by index. Saved
+    /// when the key is split, unsealing checks every share against them as it's given.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shamir_share_hashes: Vec<String>,
 }
 
 impl Default for DatabaseConfig {
     fn default() -> Self {
         Self {
-            encryption_key: rng::random_bytes_str(DATABASE_ENCRYPTION_KEY_DEFAULT_LENGTH),
+            key_source: DatabaseKeySource::default(),
+            encryption_key: None,
+            key_env: "MY_VAULT_DATABASE_KEY".into(),
+            key_file: None,
+            key_credential: "database-key".into(),
+            shamir_shares: 5,
+            shamir_threshold: 3,
+            shamir_share_hashes: Vec::new(),
         }
     }
 }
 
+/// Where the database key is read from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DatabaseKeySource {
+    /// Key is stored in the config.
+    #[default]
+    Config,
+    /// Key is read from an environment variable.
+    Env,
+    /// Key is read from a file.
+    File,
+    /// Key is read from a systemd credential in `$CREDENTIALS_DIRECTORY`.
+    Systemd,
+    /// Daemon starts sealed until the passphrase is given through the unseal endpoint.
+    Passphrase,
+    /// Daemon starts sealed until enough shares of the key are given through the unseal
+    /// endpoint. The shares of a new key are printed on first start.
+    Shamir,
+}
+
+impl std::fmt::Display for DatabaseKeySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Config => "config",
+            Self::Env => "env",
+            Self::File => "file",
+            Self::Systemd => "systemd",
+            Self::Passphrase => "passphrase",
+            Self::Shamir => "shamir",
+        };
+
+        f.write_str(name)
+    }
+}
+
 /// Encryption config
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct EncryptionConfig {
     pub argon2_iters: u32,
+    /// Memory used per password hash in MiB. Before PHC hashes the value was taken as multiples
+    /// of 8 KiB, existing hashes made that way are upgraded on the next login.
     pub argon2_memory_mb: u32,
     pub argon2_parallelism: u32,
+    /// File containing a secret pepper mixed into password hashes, keep it outside of the
+    /// database and its backups. It must only be accessible by the daemon user.
+    pub pepper_file: Option<PathBuf>,
+    /// File containing the master key wrapping the data keys secret values are encrypted with,
+    /// `master.key` in the config folder if not set. It's created on first start and must only
+    /// be accessible by the daemon user, keep a copy outside of the database backups.
+    pub master_key_file: Option<PathBuf>,
+    /// Cipher new secret values and data keys are encrypted with, one of `aes-256-gcm` or
+    /// `xchacha20-poly1305`. Existing values keep their cipher.
+    pub cipher: crypt::EnvelopeCipher,
 }
 
 impl Default for EncryptionConfig {
@@ -46,12 +129,173 @@ impl Default for EncryptionConfig {
             argon2_iters: 2,
             argon2_memory_mb: 32,
             argon2_parallelism: 2,
+            pepper_file: None,
+            master_key_file: None,
+            cipher: crypt::EnvelopeCipher::default(),
+        }
+    }
+}
+
+/// Import config
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct ImportConfig {
+    /// Directory source files are imported from, `import` in the config folder if not set.
+    /// Sources can't read files outside of it, relative paths of sources start in it.
+    pub directory: Option<PathBuf>,
+}
+
+/// Jwt config
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct JwtConfig {
+    /// Algorithm new signing keys use, one of `RS256`, `PS256`, `ES256` or `EdDSA`.
+    /// Changing it rotates the signing key on the next start.
+    pub algorithm: crypt::JwtAlgorithm,
+    /// Size of new RSA signing keys in bits, at least 2048.
+    pub rsa_bits: usize,
+}
+
+impl Default for JwtConfig {
+    fn default() -> Self {
+        let options = crypt::JwtKeyOptions::default();
+
+        Self {
+            algorithm: options.algorithm,
+            rsa_bits: options.rsa_bits,
+        }
+    }
+}
+
+impl From<JwtConfig> for crypt::JwtKeyOptions {
+    fn from(value: JwtConfig) -> Self {
+        Self {
+            algorithm: value.algorithm,
+            rsa_bits: value.rsa_bits,
+        }
+    }
+}
+
+/// Login config
+///
+/// Failed logins are tracked per username and per peer address. Every failure doubles the delay
+/// before the next login attempt is accepted, too many failures lock logins for a while.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct LoginConfig {
+    /// Failed logins of a username before it's locked.
+    pub max_failures_username: u32,
+    /// Failed logins from a peer address before it's locked.
+    pub max_failures_peer: u32,
+    /// Delay after the first failed login in seconds.
+    pub backoff_secs: u64,
+    /// Maximum delay between failed logins in seconds.
+    pub backoff_max_secs: u64,
+    /// Seconds a username or peer address stays locked.
+    pub lockout_secs: u64,
+    /// Seconds without failed logins after which failures are forgotten.
+    pub reset_secs: u64,
+}
+
+impl Default for LoginConfig {
+    fn default() -> Self {
+        Self {
+            max_failures_username: 5,
+            max_failures_peer: 20,
+            backoff_secs: 1,
+            backoff_max_secs: 60,
+            lockout_secs: 15 * 60,
+            reset_secs: 60 * 60,
+        }
+    }
+}
+
+/// Peer credential authentication config
+///
+/// Local processes connecting to the unix socket without a token are authenticated by the uid
+/// and gid of the process. The first rule matching the peer picks the user or service account
+/// requests are made as, peers without a matching rule need a token.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct PeerAuthConfig {
+    pub rules: Vec<PeerAuthRule>,
+}
+
+impl PeerAuthConfig {
+    /// Username mapped to peer with uid and gid.
+    pub fn username(&self, uid: u32, gid: u32) -> Option<&str> {
+        self.rules
+            .iter()
+            .find(|x| x.matches(uid, gid))
+            .map(|x| x.username.as_str())
+    }
+}
+
+/// Peer credential rule, mapping a uid, gid or both to a user.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct PeerAuthRule {
+    /// Uid of the peer, any uid if not set.
+    pub uid: Option<u32>,
+    /// Effective gid of the peer, any gid if not set.
+    pub gid: Option<u32>,
+    /// User or service account the peer is authenticated as.
+    pub username: String,
+}
+
+impl PeerAuthRule {
+    /// Checks if rule matches peer, rules without uid and gid never match.
+    pub fn matches(&self, uid: u32, gid: u32) -> bool {
+        (self.uid.is_some() || self.gid.is_some())
+            && self.uid.is_none_or(|x| x == uid)
+            && self.gid.is_none_or(|x| x == gid)
+    }
+}
+
+/// Registration config
+///
+/// The first admin registers with the setup token printed at startup, the policy decides how
+/// users after them get an account.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct RegistrationConfig {
+    pub policy: RegistrationPolicy,
+    /// Hours invites are valid for when no expiry is given.
+    pub invite_expiry_hours: u32,
+}
+
+impl Default for RegistrationConfig {
+    fn default() -> Self {
+        Self {
+            policy: RegistrationPolicy::default(),
+            invite_expiry_hours: 72,
         }
     }
 }
 
-// New type wrappers for config because i'm too lazy to implement derive macros.
-// TODO ALLAN: create derive macros for implementing traits.
+/// Who can register users.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RegistrationPolicy {
+    /// Anyone who can reach the daemon can register.
+    Open,
+    /// Registering needs an invite code issued by an admin.
+    #[default]
+    Invite,
+    /// Only admins create users.
+    Admin,
+}
+
+/// User config
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct UserConfig {
+    /// Number of days soft deleted users are kept before they can be purged.
+    pub deleted_retention_days: u32,
+}
+
+impl Default for UserConfig {
+    fn default() -> Self {
+        Self {
+            deleted_retention_days: 30,
+        }
+    }
+}
+
+// Config of the daemon, loading and saving is handled by the generic local config.
 pub type LocalConfig = config::LocalConfig<Config>;
 
 /// Local config handler
@@ -62,6 +306,9 @@ pub struct ConfigManager {
 
     /// Read only config file
     read_only: bool,
+
+    /// File config is saved to
+    path: PathBuf,
 }
 
 impl ConfigManager {
@@ -72,37 +319,42 @@ impl ConfigManager {
         Self {
             config: RwLock::new(config),
             read_only: true,
+            path: constants::GLOBAL_CONFIG_PATH.join(CONFIG_FILE_NAME),
+        }
+    }
+
+    /// Mocked config which is saved to the given file.
+    #[cfg(test)]
+    pub fn mocked_file(path: PathBuf) -> Self {
+        Self {
+            path,
+            read_only: false,
+            ..Self::mocked()
         }
     }
 
     pub async fn load() -> anyhow::Result<Self> {
-        let config = LocalConfig::load(
-            constants::GLOBAL_CONFIG_PATH
-                .join(CONFIG_FILE_NAME)
-                .as_path(),
-        )
-        .await?;
+        let path = constants::GLOBAL_CONFIG_PATH.join(CONFIG_FILE_NAME);
+        let config = LocalConfig::load(&path).await?;
 
         Ok(Self {
             config: RwLock::new(config),
             read_only: false,
+            path,
         })
     }
 
     pub async fn save(&self) -> anyhow::Result<()> {
+        self.save_config(&*self.config.read().await).await
+    }
+
+    /// Save given config, used by callers holding the config lock while they change it.
+    pub async fn save_config(&self, config: &LocalConfig) -> anyhow::Result<()> {
         if self.read_only {
             return Err(anyhow::anyhow!("tried to save read only config"));
         }
 
-        self.config
-            .write()
-            .await
-            .save(
-                constants::GLOBAL_CONFIG_PATH
-                    .join(CONFIG_FILE_NAME)
-                    .as_path(),
-            )
-            .await?;
+        config.save(&self.path).await?;
 
         Ok(())
     }
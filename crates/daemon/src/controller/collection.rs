@@ -0,0 +1,377 @@
+use crate::client::DaemonClient;
+use crate::{error, model, schema};
+
+use shared_core::database;
+use std::sync::Arc;
+use validator::Validate;
+
+/// Collection controller
+#[derive(Debug, Clone)]
+pub struct ControllerCollection {
+    pub(crate) client: Arc<DaemonClient>,
+    pub(crate) encryption: super::ControllerEncryption,
+}
+
+impl ControllerCollection {
+    pub fn new(client: Arc<DaemonClient>) -> Self {
+        let encryption = super::ControllerEncryption::new(client.clone());
+
+        Self { client, encryption }
+    }
+
+    /// Checks if collection with name exists
+    pub async fn exists(&self, name: String) -> Result<bool, error::ServiceError> {
+        let result = model::ModelCollection::does_collection_exist(
+            self.client.get_database().get_pool(),
+            name,
+        )
+        .await
+        .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    /// Get collection from uuid.
+    pub async fn get(&self, uuid: uuid::Uuid) -> Result<schema::Collection, error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+
+        if !model::ModelCollection::does_collection_exist_uuid(pool, uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(error::ServiceError::NotFound(format!(
+                "could not find collection with uuid {}",
+                &uuid
+            )));
+        }
+
+        let collection = model::ModelCollection::get_collection_from_uuid(pool, uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(collection)
+    }
+
+    /// List collections sorted by name.
+    /// Only collections whose name contains `search` are listed and pages are only used if `limit` is set.
+    pub async fn list(
+        &self,
+        search: Option<String>,
+        cursor: Option<String>,
+        limit: Option<u64>,
+    ) -> Result<database::Page<schema::Collection>, error::ServiceError> {
+        let query = super::list_query(schema::Collection::COLUMN_NAME, search, cursor, limit);
+
+        let result =
+            model::ModelCollection::get_collections(self.client.get_database().get_pool(), query)
+                .await
+                .map_err(super::list_error)?;
+
+        Ok(result)
+    }
+
+    /// Add a new collection.
+    pub async fn add(&self, name: String) -> Result<schema::Collection, error::ServiceError> {
+        if self.exists(name.clone()).await? {
+            return Err(error::ServiceError::AlreadyExists(format!(
+                "collection with name {} already exists",
+                &name
+            )));
+        }
+
+        let data = schema::Collection::new(name)
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        let collection =
+            model::ModelCollection::add_collection(self.client.get_database().get_pool(), data)
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(collection)
+    }
+
+    /// Rename an existing collection.
+    pub async fn rename(
+        &self,
+        uuid: uuid::Uuid,
+        name: String,
+    ) -> Result<schema::Collection, error::ServiceError> {
+        let current = self.get(uuid).await?;
+
+        if name != current.name && self.exists(name.clone()).await? {
+            return Err(error::ServiceError::AlreadyExists(format!(
+                "collection with name {} already exists",
+                &name
+            )));
+        }
+
+        let data = schema::Collection { name, ..current };
+
+        data.validate()
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        let collection =
+            model::ModelCollection::update_collection(self.client.get_database().get_pool(), data)
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(collection)
+    }
+
+    /// Delete an existing collection.
+    /// Sources attached to the collection are detached but not deleted.
+    pub async fn delete(&self, uuid: uuid::Uuid) -> Result<(), error::ServiceError> {
+        self.get(uuid).await?;
+
+        model::ModelCollection::delete_collection(self.client.get_database().get_pool(), uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Get uuid of every source attached to collection.
+    pub async fn sources(&self, uuid: uuid::Uuid) -> Result<Vec<uuid::Uuid>, error::ServiceError> {
+        self.get(uuid).await?;
+
+        let sources = model::ModelCollection::get_collection_sources(
+            self.client.get_database().get_pool(),
+            uuid,
+        )
+        .await
+        .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(sources
+            .into_iter()
+            .map(|x| x.uuid_source.into_uuid())
+            .collect())
+    }
+
+    /// Attach source to collection.
+    pub async fn attach(
+        &self,
+        uuid: uuid::Uuid,
+        uuid_source: uuid::Uuid,
+    ) -> Result<(), error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+
+        self.get(uuid).await?;
+
+        if !model::ModelSource::does_source_exist_uuid(pool, uuid_source)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(error::ServiceError::NotFound(format!(
+                "could not find source with uuid {}",
+                &uuid_source
+            )));
+        }
+
+        if model::ModelCollection::is_source_attached(pool, uuid, uuid_source)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(error::ServiceError::AlreadyExists(format!(
+                "source {} is already attached to collection {}",
+                &uuid_source, &uuid
+            )));
+        }
+
+        model::ModelCollection::attach_source(pool, uuid, uuid_source)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Detach source from collection.
+    pub async fn detach(
+        &self,
+        uuid: uuid::Uuid,
+        uuid_source: uuid::Uuid,
+    ) -> Result<(), error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+
+        if !model::ModelCollection::is_source_attached(pool, uuid, uuid_source)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(error::ServiceError::NotFound(format!(
+                "source {} is not attached to collection {}",
+                &uuid_source, &uuid
+            )));
+        }
+
+        model::ModelCollection::detach_source(pool, uuid, uuid_source)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Get every secret reachable from collection.
+    pub async fn secrets(
+        &self,
+        uuid: uuid::Uuid,
+    ) -> Result<Vec<schema::Secret>, error::ServiceError> {
+        self.get(uuid).await?;
+
+        let secrets = model::ModelCollection::get_collection_secrets(
+            self.client.get_database().get_pool(),
+            uuid,
+        )
+        .await
+        .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        self.encryption.open_all(secrets).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client;
+    use crate::controller::{ControllerCollection, ControllerSource};
+    use crate::schema::{SourceAuthType, SourceType};
+
+    use sqlx::sqlite;
+    use std::sync::Arc;
+
+    #[sqlx::test]
+    async fn add(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let client = client::DaemonClient::mocked(pool)
+            .await
+            .expect("could not create mocked client");
+
+        let controller = ControllerCollection::new(Arc::new(client));
+
+        let result_ok = controller.add("project".into()).await;
+
+        // Duplicate entry should error
+        let result_err_1 = controller.add("project".into()).await;
+
+        // Name too short
+        let result_err_2 = controller.add("pr".into()).await;
+
+        assert!(result_ok.is_ok());
+        assert!(result_err_1.is_err());
+        assert!(result_err_2.is_err());
+
+        let result_list = controller.list(None, None, None).await;
+
+        assert!(result_list.is_ok());
+        assert_eq!(result_list.unwrap().items.len(), 1);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn rename(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let client = client::DaemonClient::mocked(pool)
+            .await
+            .expect("could not create mocked client");
+
+        let controller = ControllerCollection::new(Arc::new(client));
+
+        let result_add_1 = controller.add("project-1".into()).await;
+        let result_add_2 = controller.add("project-2".into()).await;
+
+        assert!(result_add_1.is_ok());
+        assert!(result_add_2.is_ok());
+
+        let uuid = result_add_1.unwrap().uuid.into_uuid();
+
+        let result_1 = controller.rename(uuid, "project-3".into()).await;
+        let result_2 = controller.rename(uuid, "project-2".into()).await;
+        let result_3 = controller
+            .rename(uuid::Uuid::new_v4(), "project-4".into())
+            .await;
+
+        assert!(result_1.is_ok());
+        assert!(result_2.is_err());
+        assert!(result_3.is_err());
+
+        assert_eq!(result_1.unwrap().name, "project-3");
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn attach(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let client = client::DaemonClient::mocked(pool)
+            .await
+            .expect("could not create mocked client");
+
+        let client = Arc::new(client);
+        let controller = ControllerCollection::new(client.clone());
+        let controller_source = ControllerSource::new(client);
+
+        let uuid_source = controller_source
+            .add(
+                "source".into(),
+                None,
+                SourceType::Csv as u32,
+                None,
+                None,
+                SourceAuthType::None as u32,
+            )
+            .await
+            .expect("could not add source")
+            .uuid
+            .into_uuid();
+
+        let result_add = controller.add("project".into()).await;
+
+        assert!(result_add.is_ok());
+
+        let uuid = result_add.unwrap().uuid.into_uuid();
+
+        let result_1 = controller.attach(uuid, uuid_source).await;
+        let result_2 = controller.attach(uuid, uuid_source).await;
+        let result_3 = controller.attach(uuid, uuid::Uuid::new_v4()).await;
+
+        assert!(result_1.is_ok());
+        assert!(result_2.is_err());
+        assert!(result_3.is_err());
+
+        let result_sources = controller.sources(uuid).await;
+
+        assert!(result_sources.is_ok());
+        assert_eq!(result_sources.unwrap(), vec![uuid_source]);
+
+        let result_secrets = controller.secrets(uuid).await;
+
+        assert!(result_secrets.is_ok());
+        assert!(result_secrets.unwrap().is_empty());
+
+        let result_4 = controller.detach(uuid, uuid_source).await;
+        let result_5 = controller.detach(uuid, uuid_source).await;
+
+        assert!(result_4.is_ok());
+        assert!(result_5.is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn delete(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let client = client::DaemonClient::mocked(pool)
+            .await
+            .expect("could not create mocked client");
+
+        let controller = ControllerCollection::new(Arc::new(client));
+
+        let result_add = controller.add("project".into()).await;
+
+        assert!(result_add.is_ok());
+
+        let uuid = result_add.unwrap().uuid.into_uuid();
+
+        let result_1 = controller.delete(uuid).await;
+        let result_2 = controller.delete(uuid).await;
+
+        assert!(result_1.is_ok());
+        assert!(result_2.is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,457 @@
+use crate::client::DaemonClient;
+use crate::config::{self, ConfigManager, DatabaseKeySource};
+use crate::error;
+
+use shared_core::{crypt, database, rng};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Database rekey result
+#[derive(Debug, Clone)]
+pub struct DatabaseRekey {
+    /// Copy of the database taken before the key changed, encrypted with the previous key unless
+    /// the rekey was asked to encrypt it with the new key.
+    pub backup_file: PathBuf,
+    /// Shares of the new key if the key source is `shamir`.
+    pub shares: Vec<crypt::SecretShare>,
+}
+
+/// Database controller
+#[derive(Debug, Clone)]
+pub struct ControllerDatabase {
+    pub(crate) config: Arc<ConfigManager>,
+    pub(crate) client: Arc<DaemonClient>,
+}
+
+impl ControllerDatabase {
+    pub fn new(config: Arc<ConfigManager>, client: Arc<DaemonClient>) -> Self {
+        Self { config, client }
+    }
+
+    /// Encrypt the database with a new key, a random key is created if none is given.
+    ///
+    /// A backup is written next to the database first, the new key is only saved to the config
+    /// or key file once the database opens with it. If it can't be saved the previous key is
+    /// restored. Keys split into shares are split again and the new shares returned. Keys from
+    /// other sources can't be saved, they need a new key which the caller puts in place of the
+    /// previous one.
+    ///
+    /// The backup keeps the previous key unless `backup_new_key` is set, then it's encrypted with
+    /// the new key too and only opens with it.
+    pub async fn rekey(
+        &self,
+        encryption_key: Option<String>,
+        backup_new_key: bool,
+    ) -> Result<DatabaseRekey, error::ServiceError> {
+        let database = self.client.get_database();
+        let database_file =
+            self.client
+                .get_database_file()
+                .ok_or(error::ServiceError::Internal(
+                    "database isn't stored in a file".into(),
+                ))?;
+
+        let config_database = self.config.config.read().await.database.clone();
+
+        let encryption_key_new = match encryption_key {
+            Some(x) if x.is_empty() => {
+                return Err(error::ServiceError::InvalidArgument(
+                    "database key must not be empty".into(),
+                ));
+            }
+            Some(x) => x,
+            None if matches!(
+                config_database.key_source,
+                DatabaseKeySource::Config | DatabaseKeySource::File | DatabaseKeySource::Shamir
+            ) =>
+            {
+                rng::random_bytes_str(config::DATABASE_ENCRYPTION_KEY_LENGTH)
+            }
+            None => {
+                return Err(error::ServiceError::InvalidArgument(format!(
+                    "database key from {} can't be saved by the daemon, a new key is required",
+                    config_database.key_source
+                )));
+            }
+        };
+
+        let shares = match config_database.key_source {
+            DatabaseKeySource::Shamir => {
+                DaemonClient::split_database_key(&config_database, &encryption_key_new)
+                    .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+            }
+            _ => Vec::new(),
+        };
+
+        // Held until the new key is saved so rekeys can't run concurrently.
+        let mut encryption_key = self.client.get_database_key().lock().await;
+
+        let mut backup_file = database_file.as_os_str().to_owned();
+        backup_file.push(format!(
+            ".{}.bak",
+            chrono::Utc::now().format("%Y%m%d%H%M%S")
+        ));
+        let backup_file = PathBuf::from(backup_file);
+
+        database
+            .backup(&backup_file)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        tracing::info!("backed up database to {}", backup_file.display());
+
+        database
+            .rekey(encryption_key_new.clone())
+            .await
+            .map_err(|e| {
+                error::ServiceError::Internal(format!(
+                    "could not rekey database, backup is {} - {e}",
+                    backup_file.display()
+                ))
+            })?;
+
+        if let Err(e) = self
+            .save_key(&config_database, &encryption_key_new, &shares)
+            .await
+        {
+            database
+                .rekey(encryption_key.to_string())
+                .await
+                .map_err(|e| {
+                    error::ServiceError::Internal(format!(
+                        "could not restore previous database key, backup is {} - {e}",
+                        backup_file.display()
+                    ))
+                })?;
+
+            return Err(error::ServiceError::Internal(format!(
+                "could not save database key, restored previous key - {e}"
+            )));
+        }
+
+        if backup_new_key
+            && let Err(e) = Self::rekey_backup(
+                &backup_file,
+                encryption_key.to_string(),
+                encryption_key_new.clone(),
+            )
+            .await
+        {
+            tracing::warn!(
+                "backup {} is still encrypted with the previous key - {e}",
+                backup_file.display()
+            );
+        }
+
+        *encryption_key = encryption_key_new.into();
+
+        tracing::info!(
+            "rekeyed database with key from {}",
+            config_database.key_source
+        );
+
+        Ok(DatabaseRekey {
+            backup_file,
+            shares,
+        })
+    }
+
+    /// Save new database key where the key source reads it from, keys split into shares save
+    /// the hashes of the shares instead.
+    async fn save_key(
+        &self,
+        config_database: &config::DatabaseConfig,
+        encryption_key: &str,
+        shares: &[crypt::SecretShare],
+    ) -> anyhow::Result<()> {
+        match config_database.key_source {
+            DatabaseKeySource::Config => {
+                let mut config = self.config.config.write().await;
+                let encryption_key_previous = config
+                    .database
+                    .encryption_key
+                    .replace(encryption_key.to_string());
+
+                if let Err(e) = self.config.save_config(&config).await {
+                    config.database.encryption_key = encryption_key_previous;
+
+                    return Err(e);
+                }
+            }
+            DatabaseKeySource::File => {
+                let path = DaemonClient::database_key_file(config_database);
+
+                DaemonClient::save_database_key(&path, encryption_key).await?;
+            }
+            DatabaseKeySource::Shamir => {
+                DaemonClient::save_share_hashes(&self.config, shares).await?;
+            }
+            DatabaseKeySource::Env | DatabaseKeySource::Systemd | DatabaseKeySource::Passphrase => {
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn rekey_backup(
+        backup_file: &std::path::Path,
+        encryption_key: String,
+        encryption_key_new: String,
+    ) -> Result<(), shared_core::error::Error> {
+        let backup = database::Database::load(backup_file, encryption_key).await?;
+        let result = backup.rekey(encryption_key_new).await;
+
+        backup.close().await;
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::DatabaseKeySource;
+    use crate::controller::ControllerDatabase;
+    use crate::{client, config, error, model, schema};
+
+    use shared_core::{crypt, database, rng};
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    /// Path of a new database file in the temp test data.
+    fn database_file() -> PathBuf {
+        PathBuf::from(env!("WORKSPACE_DIR"))
+            .join("test-data")
+            .join("temp")
+            .join(format!("{}.sqlite3", rng::random_bytes_str(10)))
+    }
+
+    async fn add_user(client: &client::DaemonClient) {
+        let user = schema::User {
+            uuid: uuid::Uuid::new_v4().into(),
+            username: "carl".into(),
+            ..schema::User::default()
+        };
+
+        model::ModelUser::add_user(client.get_database().get_pool(), user)
+            .await
+            .unwrap();
+    }
+
+    async fn count_users(database: &database::Database) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM users")
+            .fetch_one(database.get_pool())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn rekey() {
+        let path = database_file();
+        let path_key = path.with_extension("key");
+
+        let config = config::ConfigManager::mocked();
+        {
+            let mut config = config.config.write().await;
+            config.database.key_source = DatabaseKeySource::File;
+            config.database.key_file = Some(path_key.clone());
+        }
+
+        let client = client::DaemonClient::mocked_file(&path, "old-key".into())
+            .await
+            .unwrap();
+
+        add_user(&client).await;
+
+        let client = Arc::new(client);
+        let controller = ControllerDatabase::new(Arc::new(config), client.clone());
+
+        let result_1 = controller.rekey(None, true).await;
+
+        assert!(result_1.is_ok());
+
+        let result_1 = result_1.unwrap();
+        let encryption_key = tokio::fs::read_to_string(&path_key).await.unwrap();
+
+        // The new key is saved and used by the client, the database keeps its rows.
+        assert_ne!(encryption_key, "old-key");
+        assert_eq!(
+            client.get_database_key().lock().await.as_str(),
+            encryption_key
+        );
+        assert!(result_1.shares.is_empty());
+        assert_eq!(count_users(client.get_database()).await, 1);
+
+        client.get_database().close().await;
+
+        // Database and backup asked for the new key only open with the new key.
+        for path in [&path, &result_1.backup_file] {
+            let result_2 = database::Database::load(path, "old-key".into()).await;
+            let result_3 = database::Database::load(path, encryption_key.clone()).await;
+
+            assert!(matches!(
+                result_2,
+                Err(shared_core::error::Error::DatabaseKey)
+            ));
+            assert!(result_3.is_ok());
+
+            let result_3 = result_3.unwrap();
+
+            assert_eq!(count_users(&result_3).await, 1);
+
+            result_3.close().await;
+        }
+
+        for path in [&path, &result_1.backup_file, &path_key] {
+            assert!(tokio::fs::remove_file(path).await.is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn rekey_config() {
+        let path = database_file();
+        let path_config = path.with_extension("toml");
+
+        let config = Arc::new(config::ConfigManager::mocked_file(path_config.clone()));
+        config.config.write().await.database.encryption_key = Some("old-key".into());
+
+        let client = Arc::new(
+            client::DaemonClient::mocked_file(&path, "old-key".into())
+                .await
+                .unwrap(),
+        );
+        let controller = ControllerDatabase::new(config.clone(), client.clone());
+
+        let result_1 = controller.rekey(Some("new-key".into()), false).await;
+
+        assert!(result_1.is_ok());
+
+        let result_1 = result_1.unwrap();
+        let config_saved = config::LocalConfig::load(&path_config).await.unwrap();
+
+        // Keys in the config are saved to the config file.
+        assert_eq!(
+            config_saved.database.encryption_key.as_deref(),
+            Some("new-key")
+        );
+        assert_eq!(client.get_database_key().lock().await.as_str(), "new-key");
+
+        client.get_database().close().await;
+
+        // The backup keeps the previous key.
+        let result_2 = database::Database::load(&result_1.backup_file, "new-key".into()).await;
+        let result_3 = database::Database::load(&result_1.backup_file, "old-key".into()).await;
+
+        assert!(matches!(
+            result_2,
+            Err(shared_core::error::Error::DatabaseKey)
+        ));
+        assert!(result_3.is_ok());
+
+        result_3.unwrap().close().await;
+
+        for path in [&path, &result_1.backup_file, &path_config] {
+            assert!(tokio::fs::remove_file(path).await.is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn rekey_shamir() {
+        let path = database_file();
+        let path_config = path.with_extension("toml");
+
+        let config = config::ConfigManager::mocked_file(path_config.clone());
+        config.config.write().await.database.key_source = DatabaseKeySource::Shamir;
+
+        let client = Arc::new(
+            client::DaemonClient::mocked_file(&path, "old-key".into())
+                .await
+                .unwrap(),
+        );
+        let controller = ControllerDatabase::new(Arc::new(config), client.clone());
+
+        let result_1 = controller.rekey(None, false).await.unwrap();
+
+        // Any threshold of the new shares combine into the new key.
+        assert_eq!(result_1.shares.len(), 5);
+
+        let encryption_key = crypt::combine_shares(&result_1.shares[2..]).unwrap();
+
+        assert_eq!(
+            client.get_database_key().lock().await.as_bytes(),
+            &encryption_key[..]
+        );
+
+        // Hashes of the new shares replace the previous ones.
+        let config_saved = config::LocalConfig::load(&path_config).await.unwrap();
+
+        assert_eq!(
+            config_saved.database.shamir_share_hashes,
+            result_1.shares.iter().map(|x| x.hash()).collect::<Vec<_>>()
+        );
+
+        client.get_database().close().await;
+
+        for path in [&path, &result_1.backup_file, &path_config] {
+            assert!(tokio::fs::remove_file(path).await.is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn rekey_restore() {
+        let path = database_file();
+
+        // Mocked config is read only so saving a key to it fails.
+        let config = Arc::new(config::ConfigManager::mocked());
+
+        let client = client::DaemonClient::mocked_file(&path, "old-key".into())
+            .await
+            .unwrap();
+
+        add_user(&client).await;
+
+        let client = Arc::new(client);
+        let controller = ControllerDatabase::new(config.clone(), client.clone());
+
+        let result_1 = controller.rekey(Some("new-key".into()), false).await;
+        let result_2 = controller.rekey(Some("".into()), false).await;
+
+        assert!(matches!(result_1, Err(error::ServiceError::Internal(_))));
+        assert!(matches!(
+            result_2,
+            Err(error::ServiceError::InvalidArgument(_))
+        ));
+
+        // The previous key is restored and still used by the client.
+        assert_eq!(client.get_database_key().lock().await.as_str(), "old-key");
+        assert_eq!(config.config.read().await.database.encryption_key, None);
+        assert_eq!(count_users(client.get_database()).await, 1);
+
+        client.get_database().close().await;
+
+        let result_3 = database::Database::load(&path, "old-key".into()).await;
+
+        assert!(result_3.is_ok());
+
+        result_3.unwrap().close().await;
+
+        // Keys from the environment need a new key given.
+        config.config.write().await.database.key_source = DatabaseKeySource::Env;
+
+        let result_4 = controller.rekey(None, false).await;
+
+        assert!(matches!(
+            result_4,
+            Err(error::ServiceError::InvalidArgument(_))
+        ));
+
+        // Backups are left behind by failed rekeys.
+        let mut entries = tokio::fs::read_dir(path.parent().unwrap()).await.unwrap();
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+
+        while let Some(entry) = entries.next_entry().await.unwrap() {
+            if entry.file_name().to_string_lossy().starts_with(&name) {
+                assert!(tokio::fs::remove_file(entry.path()).await.is_ok());
+            }
+        }
+    }
+}
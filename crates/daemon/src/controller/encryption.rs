@@ -0,0 +1,440 @@
+use crate::client::DaemonClient;
+use crate::{error, model, schema};
+
+use shared_core::crypt;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Data key unwrapped with the master key, encrypting secret values.
+#[derive(Debug)]
+pub struct SecretKey {
+    uuid: uuid::fmt::Hyphenated,
+    key: crypt::DataKey,
+    cipher: crypt::EnvelopeCipher,
+}
+
+impl SecretKey {
+    /// Encrypt value of secret, the uuid of the secret is authenticated with it so values can't
+    /// be swapped between secrets.
+    pub fn seal(&self, secret: schema::Secret) -> Result<schema::Secret, error::ServiceError> {
+        let value = self
+            .key
+            .encrypt(
+                self.cipher,
+                secret.secret.as_bytes(),
+                secret.uuid.to_string().as_bytes(),
+            )
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(schema::Secret {
+            secret: value,
+            uuid_data_key: Some(self.uuid),
+            ..secret
+        })
+    }
+
+    /// Decrypt value of secret encrypted with this key.
+    fn open(&self, secret: schema::Secret) -> Result<schema::Secret, error::ServiceError> {
+        let value = self
+            .key
+            .decrypt(&secret.secret, secret.uuid.to_string().as_bytes())
+            .ok()
+            .and_then(|x| String::from_utf8(x).ok())
+            .ok_or(error::ServiceError::Internal(format!(
+                "could not decrypt secret {}",
+                &secret.uuid
+            )))?;
+
+        Ok(schema::Secret {
+            secret: value,
+            ..secret
+        })
+    }
+}
+
+/// Master key rotation result
+#[derive(Debug, Clone)]
+pub struct MasterKeyRotation {
+    pub master_key_id: String,
+    pub data_keys: usize,
+}
+
+/// Encryption controller
+///
+/// Secret values are encrypted with a data key of their collection, or the default data key.
+/// Data keys are stored wrapped by the master key which never touches the database.
+#[derive(Debug, Clone)]
+pub struct ControllerEncryption {
+    pub(crate) client: Arc<DaemonClient>,
+}
+
+impl ControllerEncryption {
+    pub fn new(client: Arc<DaemonClient>) -> Self {
+        Self { client }
+    }
+
+    /// Get data key of collection, or the default data key if no collection is given.
+    /// The data key is created on first use.
+    pub async fn data_key(
+        &self,
+        uuid_collection: Option<uuid::Uuid>,
+    ) -> Result<SecretKey, error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+        let master_key = self.client.get_master_key().read().await;
+
+        let data_key = model::ModelDataKey::get_collection_data_key(pool, uuid_collection)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        let data_key = match data_key {
+            Some(x) => x,
+            None => {
+                let key = crypt::DataKey::generate();
+                let wrapped_key = master_key
+                    .wrap(&key, self.client.get_cipher())
+                    .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+                let data = schema::DataKey::new(uuid_collection, master_key.id(), wrapped_key);
+
+                model::ModelDataKey::get_or_add_collection_data_key(pool, data)
+                    .await
+                    .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+            }
+        };
+
+        self.unwrap(&master_key, data_key)
+    }
+
+    /// Get data key from uuid.
+    pub async fn data_key_from_uuid(
+        &self,
+        uuid: uuid::Uuid,
+    ) -> Result<SecretKey, error::ServiceError> {
+        let master_key = self.client.get_master_key().read().await;
+
+        let data_key = model::ModelDataKey::get_data_key_from_uuid(
+            self.client.get_database().get_pool(),
+            uuid,
+        )
+        .await
+        .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        self.unwrap(&master_key, data_key)
+    }
+
+    /// Decrypt value of secret, values without data key are returned as is.
+    pub async fn open(
+        &self,
+        secret: schema::Secret,
+    ) -> Result<schema::Secret, error::ServiceError> {
+        Ok(self.open_all(vec![secret]).await?.remove(0))
+    }
+
+    /// Decrypt values of secrets, every data key is only unwrapped once.
+    pub async fn open_all(
+        &self,
+        secrets: Vec<schema::Secret>,
+    ) -> Result<Vec<schema::Secret>, error::ServiceError> {
+        let mut keys: HashMap<uuid::fmt::Hyphenated, SecretKey> = HashMap::new();
+        let mut result = Vec::with_capacity(secrets.len());
+
+        for secret in secrets {
+            let Some(uuid_data_key) = secret.uuid_data_key else {
+                result.push(secret);
+                continue;
+            };
+
+            let key = match keys.entry(uuid_data_key) {
+                std::collections::hash_map::Entry::Occupied(x) => x.into_mut(),
+                std::collections::hash_map::Entry::Vacant(x) => {
+                    x.insert(self.data_key_from_uuid(uuid_data_key.into_uuid()).await?)
+                }
+            };
+
+            result.push(key.open(secret)?);
+        }
+
+        Ok(result)
+    }
+
+    /// Encrypt values of secrets stored before values were encrypted with the default data key.
+    /// Returns the number of secrets encrypted.
+    pub async fn seal_plaintext_secrets(&self) -> Result<usize, error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+
+        let secrets = model::ModelSecret::get_unsealed_secrets(pool)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        if secrets.is_empty() {
+            return Ok(0);
+        }
+
+        let key = self.data_key(None).await?;
+        let count = secrets.len();
+
+        let mut tx = self
+            .client
+            .get_database()
+            .begin()
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        for secret in secrets {
+            model::ModelSecret::update_secret(&mut *tx, key.seal(secret)?)
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        tracing::info!("encrypted {} plaintext secret(s)", count);
+
+        Ok(count)
+    }
+
+    /// Replace master key and rewrap every data key with it, secret values stay as they are.
+    ///
+    /// The new key is written next to the current key file before the data keys are updated and
+    /// replaces it afterwards, so a crash in between is finished on the next start.
+    pub async fn rotate_master_key(&self) -> Result<MasterKeyRotation, error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+        let cipher = self.client.get_cipher();
+
+        // Secrets can't be encrypted or decrypted until the rotation is done.
+        let mut master_key = self.client.get_master_key().write().await;
+        let master_key_new = crypt::MasterKey::generate();
+
+        let data_keys = model::ModelDataKey::get_data_keys(pool)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+            .into_iter()
+            .map(|x| {
+                let wrapped_key = master_key_new
+                    .rewrap(&master_key, &x.wrapped_key, cipher)
+                    .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+                Ok(schema::DataKey {
+                    master_key_id: master_key_new.id(),
+                    wrapped_key,
+                    ..x
+                })
+            })
+            .collect::<Result<Vec<_>, error::ServiceError>>()?;
+
+        let count = data_keys.len();
+        let path = self.client.get_master_key_file();
+        let path_pending = path.map(DaemonClient::master_key_pending_path);
+
+        if let Some(path_pending) = &path_pending {
+            DaemonClient::save_master_key(path_pending, &master_key_new)
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+        }
+
+        if let Err(e) = model::ModelDataKey::update_data_keys(pool, data_keys).await {
+            if let Some(path_pending) = &path_pending {
+                let _ = tokio::fs::remove_file(path_pending).await;
+            }
+
+            return Err(error::ServiceError::Internal(e.to_string()));
+        }
+
+        if let (Some(path), Some(path_pending)) = (path, &path_pending) {
+            tokio::fs::rename(path_pending, path)
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+        }
+
+        tracing::info!(
+            "rotated master key from {} to {}, rewrapped {} data key(s)",
+            master_key.id(),
+            master_key_new.id(),
+            count
+        );
+
+        *master_key = master_key_new;
+
+        Ok(MasterKeyRotation {
+            master_key_id: master_key.id(),
+            data_keys: count,
+        })
+    }
+
+    fn unwrap(
+        &self,
+        master_key: &crypt::MasterKey,
+        data_key: schema::DataKey,
+    ) -> Result<SecretKey, error::ServiceError> {
+        let key = master_key.unwrap(&data_key.wrapped_key).map_err(|_| {
+            error::ServiceError::Internal(format!("could not unwrap data key {}", &data_key.uuid))
+        })?;
+
+        Ok(SecretKey {
+            uuid: data_key.uuid,
+            key,
+            cipher: self.client.get_cipher(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client;
+    use crate::controller::{ControllerEncryption, ControllerSecret};
+    use crate::model::{ModelCollection, ModelDataKey, ModelSecret};
+    use crate::schema::{Collection, Secret, SecretType};
+
+    use sqlx::sqlite;
+    use std::sync::Arc;
+
+    #[sqlx::test]
+    async fn seal(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let client = Arc::new(
+            client::DaemonClient::mocked(pool.clone())
+                .await
+                .expect("could not create mocked client"),
+        );
+
+        let controller = ControllerEncryption::new(client.clone());
+        let controller_secret = ControllerSecret::new(client);
+
+        let collection =
+            ModelCollection::add_collection(&pool, Collection::new("project").unwrap())
+                .await
+                .unwrap();
+        let uuid_collection = collection.uuid.into_uuid();
+
+        let secret_1 = controller_secret
+            .add(
+                "github-token".into(),
+                None,
+                None,
+                "ghp_123".into(),
+                SecretType::Key as u32,
+                None,
+            )
+            .await
+            .unwrap();
+        let secret_2 = controller_secret
+            .add(
+                "gitlab-token".into(),
+                None,
+                None,
+                "glpat_123".into(),
+                SecretType::Key as u32,
+                Some(uuid_collection),
+            )
+            .await
+            .unwrap();
+
+        // Values are only stored encrypted, with a data key per collection
+        let stored_1 = ModelSecret::get_secret_from_uuid(&pool, secret_1.uuid.into_uuid())
+            .await
+            .unwrap();
+        let stored_2 = ModelSecret::get_secret_from_uuid(&pool, secret_2.uuid.into_uuid())
+            .await
+            .unwrap();
+
+        assert_eq!(secret_1.secret, "ghp_123");
+        assert!(!stored_1.secret.contains("ghp_123"));
+        assert!(!stored_2.secret.contains("glpat_123"));
+        assert!(stored_1.uuid_data_key.is_some());
+        assert_ne!(stored_1.uuid_data_key, stored_2.uuid_data_key);
+
+        let data_key = ModelDataKey::get_collection_data_key(&pool, Some(uuid_collection))
+            .await
+            .unwrap();
+
+        assert_eq!(stored_2.uuid_data_key, data_key.map(|x| x.uuid));
+
+        // Values can't be moved to another secret
+        let swapped = Secret {
+            secret: stored_1.secret.clone(),
+            ..stored_2
+        };
+
+        assert!(controller.open(swapped).await.is_err());
+
+        // Plaintext values stored before encryption are sealed with the default data key
+        let legacy = ModelSecret::add_secret(
+            &pool,
+            Secret::new("legacy", None, None, "hunter2", SecretType::Cipher as u32).unwrap(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(controller.seal_plaintext_secrets().await.unwrap(), 1);
+        assert_eq!(controller.seal_plaintext_secrets().await.unwrap(), 0);
+
+        let stored_legacy = ModelSecret::get_secret_from_uuid(&pool, legacy.uuid.into_uuid())
+            .await
+            .unwrap();
+
+        assert_ne!(stored_legacy.secret, "hunter2");
+        assert_eq!(stored_legacy.uuid_data_key, stored_1.uuid_data_key);
+        assert_eq!(
+            controller.open(stored_legacy).await.unwrap().secret,
+            "hunter2"
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn rotate_master_key(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let client = Arc::new(
+            client::DaemonClient::mocked(pool.clone())
+                .await
+                .expect("could not create mocked client"),
+        );
+
+        let controller = ControllerEncryption::new(client.clone());
+        let controller_secret = ControllerSecret::new(client.clone());
+
+        let secret = controller_secret
+            .add(
+                "github-token".into(),
+                None,
+                None,
+                "ghp_123".into(),
+                SecretType::Key as u32,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let master_key_id = client.get_master_key().read().await.id();
+        let stored_1 = ModelSecret::get_secret_from_uuid(&pool, secret.uuid.into_uuid())
+            .await
+            .unwrap();
+
+        let result = controller.rotate_master_key().await.unwrap();
+
+        assert_ne!(result.master_key_id, master_key_id);
+        assert_eq!(
+            result.master_key_id,
+            client.get_master_key().read().await.id()
+        );
+        assert_eq!(result.data_keys, 1);
+
+        // Values are not re-encrypted and still decrypt with the new master key
+        let stored_2 = ModelSecret::get_secret_from_uuid(&pool, secret.uuid.into_uuid())
+            .await
+            .unwrap();
+
+        assert_eq!(stored_1.secret, stored_2.secret);
+        assert_eq!(
+            controller_secret
+                .get(secret.uuid.into_uuid())
+                .await
+                .unwrap()
+                .secret,
+            "ghp_123"
+        );
+
+        Ok(())
+    }
+}
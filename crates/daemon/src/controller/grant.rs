@@ -0,0 +1,325 @@
+use crate::client::DaemonClient;
+use crate::{error, model, schema};
+
+use std::sync::Arc;
+
+/// Grant controller
+#[derive(Debug, Clone)]
+pub struct ControllerGrant {
+    pub(crate) client: Arc<DaemonClient>,
+}
+
+impl ControllerGrant {
+    pub fn new(client: Arc<DaemonClient>) -> Self {
+        Self { client }
+    }
+
+    /// Get resource covered by grant scope.
+    pub fn scope_resource(
+        scope_type: u32,
+        uuid_scope: Option<uuid::Uuid>,
+    ) -> Result<schema::Resource, error::ServiceError> {
+        let resource = match (Self::scope_type(scope_type)?, uuid_scope) {
+            (schema::ScopeType::Global, _) => schema::Resource::Global,
+            (schema::ScopeType::Collection, Some(x)) => schema::Resource::Collection(x),
+            (schema::ScopeType::Source, Some(x)) => schema::Resource::Source(x),
+            (_, None) => {
+                return Err(error::ServiceError::InvalidArgument(
+                    "scoped grants require a scope uuid".into(),
+                ));
+            }
+        };
+
+        Ok(resource)
+    }
+
+    /// Parse scope type of grant.
+    fn scope_type(scope_type: u32) -> Result<schema::ScopeType, error::ServiceError> {
+        schema::ScopeType::try_from(scope_type)
+            .map_err(|x| error::ServiceError::InvalidArgument(format!("invalid scope type {}", x)))
+    }
+
+    /// Get grant from uuid.
+    pub async fn get(&self, uuid: uuid::Uuid) -> Result<schema::Grant, error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+
+        if !model::ModelGrant::does_grant_exist_uuid(pool, uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(error::ServiceError::NotFound(format!(
+                "could not find grant with uuid {}",
+                &uuid
+            )));
+        }
+
+        let grant = model::ModelGrant::get_grant_from_uuid(pool, uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(grant)
+    }
+
+    /// Get every grant of user with username.
+    pub async fn list(&self, username: String) -> Result<Vec<schema::Grant>, error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+        let user = self.get_user(username).await?;
+
+        let grants = model::ModelGrant::get_user_grants(pool, user.uuid.into_uuid())
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(grants)
+    }
+
+    /// Grant role to user with username on scope.
+    pub async fn add(
+        &self,
+        username: String,
+        role: String,
+        scope_type: u32,
+        uuid_scope: Option<uuid::Uuid>,
+    ) -> Result<schema::Grant, error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+        let user = self.get_user(username).await?;
+
+        self.check_role(&role).await?;
+        let scope_type = self.check_scope(scope_type, uuid_scope).await?;
+
+        let data = schema::Grant::new(user.uuid.into_uuid(), role, scope_type, uuid_scope);
+
+        if model::ModelGrant::does_grant_exist(pool, &data)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(error::ServiceError::AlreadyExists(format!(
+                "user {} already has role {} on scope",
+                &user.username, &data.role
+            )));
+        }
+
+        let grant = model::ModelGrant::add_grant(pool, data)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        tracing::info!("granted role {} to user {}", &grant.role, &user.username);
+
+        Ok(grant)
+    }
+
+    /// Revoke grant with uuid.
+    pub async fn delete(&self, uuid: uuid::Uuid) -> Result<(), error::ServiceError> {
+        self.get(uuid).await?;
+
+        model::ModelGrant::delete_grant(self.client.get_database().get_pool(), uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Make sure role with name exists.
+    pub(crate) async fn check_role(&self, role: &str) -> Result<(), error::ServiceError> {
+        if !model::ModelGrant::does_role_exist(self.client.get_database().get_pool(), role.into())
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(error::ServiceError::InvalidArgument(format!(
+                "role {} does not exist",
+                role
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Make sure scope is valid, scoped grants must point to an existing collection or source.
+    pub(crate) async fn check_scope(
+        &self,
+        scope_type: u32,
+        uuid_scope: Option<uuid::Uuid>,
+    ) -> Result<schema::ScopeType, error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+
+        let exists = match Self::scope_resource(scope_type, uuid_scope)? {
+            schema::Resource::Collection(x) => {
+                model::ModelCollection::does_collection_exist_uuid(pool, x).await
+            }
+            schema::Resource::Source(x) => {
+                model::ModelSource::does_source_exist_uuid(pool, x).await
+            }
+            _ => Ok(true),
+        }
+        .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        if !exists {
+            return Err(error::ServiceError::NotFound(format!(
+                "could not find scope with uuid {}",
+                uuid_scope.unwrap_or_default()
+            )));
+        }
+
+        Self::scope_type(scope_type)
+    }
+
+    /// Get active user with username.
+    async fn get_user(&self, username: String) -> Result<schema::User, error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+
+        if !model::ModelUser::does_user_exist(pool, username.clone())
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(error::ServiceError::NotFound(format!(
+                "could not find user with username {}",
+                &username
+            )));
+        }
+
+        let user = model::ModelUser::get_user_from_username(pool, username)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(user)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::controller::{ControllerCollection, ControllerGrant, ControllerUser};
+    use crate::schema::ScopeType;
+    use crate::{client, config, error};
+
+    use sqlx::sqlite;
+    use std::sync::Arc;
+
+    #[sqlx::test]
+    async fn add(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let config = Arc::new(config::ConfigManager::mocked());
+        let client = Arc::new(
+            client::DaemonClient::mocked(pool)
+                .await
+                .expect("could not create mocked client"),
+        );
+
+        let controller = ControllerGrant::new(client.clone());
+        let controller_user = ControllerUser::new(config, client.clone());
+        let controller_collection = ControllerCollection::new(client);
+
+        controller_user
+            .add("admin".into(), "admin-password".into())
+            .await
+            .unwrap();
+        controller_user
+            .add("ci-runner".into(), "ci-runner-password".into())
+            .await
+            .unwrap();
+
+        let collection = controller_collection
+            .add("project".into())
+            .await
+            .unwrap()
+            .uuid
+            .into_uuid();
+
+        let collection_id = Some(collection);
+        let scope_collection = ScopeType::Collection as u32;
+
+        let result_ok = controller
+            .add(
+                "ci-runner".into(),
+                "reader".into(),
+                scope_collection,
+                collection_id,
+            )
+            .await;
+
+        // Duplicate grant
+        let result_err_1 = controller
+            .add(
+                "ci-runner".into(),
+                "reader".into(),
+                scope_collection,
+                collection_id,
+            )
+            .await;
+
+        // Unknown role, scope type, user and collection
+        let result_err_2 = controller
+            .add(
+                "ci-runner".into(),
+                "owner".into(),
+                scope_collection,
+                collection_id,
+            )
+            .await;
+        let result_err_3 = controller
+            .add("ci-runner".into(), "reader".into(), 3, collection_id)
+            .await;
+        let result_err_4 = controller
+            .add(
+                "jeff".into(),
+                "reader".into(),
+                scope_collection,
+                collection_id,
+            )
+            .await;
+        let result_err_5 = controller
+            .add(
+                "ci-runner".into(),
+                "reader".into(),
+                scope_collection,
+                Some(uuid::Uuid::new_v4()),
+            )
+            .await;
+
+        // Scoped grant without scope
+        let result_err_6 = controller
+            .add("ci-runner".into(), "reader".into(), scope_collection, None)
+            .await;
+
+        assert!(result_ok.is_ok());
+        assert!(matches!(
+            result_err_1,
+            Err(error::ServiceError::AlreadyExists(_))
+        ));
+        assert!(matches!(
+            result_err_2,
+            Err(error::ServiceError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            result_err_3,
+            Err(error::ServiceError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            result_err_4,
+            Err(error::ServiceError::NotFound(_))
+        ));
+        assert!(matches!(
+            result_err_5,
+            Err(error::ServiceError::NotFound(_))
+        ));
+        assert!(matches!(
+            result_err_6,
+            Err(error::ServiceError::InvalidArgument(_))
+        ));
+
+        let result_list = controller.list("ci-runner".into()).await;
+
+        assert_eq!(result_list.unwrap().len(), 1);
+
+        // Deleting the collection also revokes grants on it.
+        controller_collection.delete(collection).await.unwrap();
+
+        let result_list = controller.list("ci-runner".into()).await;
+        let result_delete = controller.delete(result_ok.unwrap().uuid.into_uuid()).await;
+
+        assert!(result_list.unwrap().is_empty());
+        assert!(matches!(
+            result_delete,
+            Err(error::ServiceError::NotFound(_))
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,392 @@
+use crate::client::DaemonClient;
+use crate::config::ConfigManager;
+use crate::{error, model, schema};
+
+use shared_core::crypt;
+use std::sync::Arc;
+
+/// Minimum length of a setup token given through the environment.
+const SETUP_TOKEN_MIN_LENGTH: usize = 16;
+
+/// Invite controller
+///
+/// Admins issue one-time invite codes which expire, depending on the registration policy users
+/// need one to register. While there is no admin, the setup token is the only invite.
+#[derive(Debug, Clone)]
+pub struct ControllerInvite {
+    pub(crate) config: Arc<ConfigManager>,
+    pub(crate) client: Arc<DaemonClient>,
+}
+
+impl ControllerInvite {
+    pub fn new(config: Arc<ConfigManager>, client: Arc<DaemonClient>) -> Self {
+        Self { config, client }
+    }
+
+    /// Create the setup token registering an admin if there is no active admin, the token of a
+    /// previous start is replaced. This also recovers vaults whose admins were all deleted.
+    /// Returns the token if none was given.
+    pub async fn bootstrap(
+        &self,
+        setup_token: Option<String>,
+    ) -> Result<Option<crypt::InviteCode>, error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+
+        let admin_count = model::ModelUser::get_admin_count(pool)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        if admin_count > 0 {
+            return Ok(None);
+        }
+
+        let (code, generated) = match setup_token {
+            Some(x) if x.trim().len() < SETUP_TOKEN_MIN_LENGTH => {
+                return Err(error::ServiceError::InvalidArgument(format!(
+                    "setup token must be at least {} characters",
+                    SETUP_TOKEN_MIN_LENGTH
+                )));
+            }
+            Some(x) => (crypt::InviteCode::parse(&x), false),
+            None => (crypt::InviteCode::generate(), true),
+        };
+
+        model::ModelInvite::set_setup_invite(pool, schema::Invite::setup(&code))
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        let user_count = model::ModelUser::get_user_count(pool)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        if user_count == 0 {
+            tracing::warn!(
+                "no users registered yet, register the first admin with the setup token"
+            );
+        } else {
+            tracing::warn!("no active admin left, register a new admin with the setup token");
+        }
+
+        Ok(generated.then_some(code))
+    }
+
+    /// Create invite valid for `expires_in_hours` or the configured default.
+    /// Returns the invite and its code, the code is only shown once.
+    pub async fn create(
+        &self,
+        caller: uuid::Uuid,
+        expires_in_hours: Option<u32>,
+    ) -> Result<(schema::Invite, String), error::ServiceError> {
+        let expires_in_hours = match expires_in_hours {
+            Some(0) => {
+                return Err(error::ServiceError::InvalidArgument(
+                    "invites must be valid for at least an hour".into(),
+                ));
+            }
+            Some(x) => x,
+            None => {
+                self.config
+                    .config
+                    .read()
+                    .await
+                    .registration
+                    .invite_expiry_hours
+            }
+        };
+
+        let code = crypt::InviteCode::generate();
+        let expires_at =
+            chrono::Utc::now().naive_utc() + chrono::Duration::hours(expires_in_hours.into());
+
+        let invite = model::ModelInvite::add_invite(
+            self.client.get_database().get_pool(),
+            schema::Invite::new(&code, caller, expires_at),
+        )
+        .await
+        .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        tracing::info!("user {} created invite {}", &caller, &invite.uuid);
+
+        Ok((invite, code.to_string()))
+    }
+
+    /// List unexpired invites.
+    pub async fn list(&self) -> Result<Vec<schema::Invite>, error::ServiceError> {
+        let result = model::ModelInvite::get_invites(self.client.get_database().get_pool())
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    /// Revoke unused invite.
+    pub async fn revoke(&self, uuid: uuid::Uuid) -> Result<(), error::ServiceError> {
+        if !model::ModelInvite::delete_invite(self.client.get_database().get_pool(), uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(error::ServiceError::NotFound(format!(
+                "could not find invite with uuid {}",
+                &uuid
+            )));
+        }
+
+        tracing::info!("revoked invite {}", &uuid);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::RegistrationPolicy;
+    use crate::controller::{ControllerInvite, ControllerUser};
+    use crate::{client, config, error, middleware, model, schema};
+
+    use sqlx::sqlite;
+    use std::sync::Arc;
+
+    #[sqlx::test]
+    async fn register(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let config = Arc::new(config::ConfigManager::mocked());
+        let client = Arc::new(
+            client::DaemonClient::mocked(pool)
+                .await
+                .expect("could not create mocked client"),
+        );
+
+        let controller = ControllerInvite::new(config.clone(), client.clone());
+        let controller_user = ControllerUser::new(config.clone(), client);
+
+        // The first admin needs the setup token
+        let setup_token = controller.bootstrap(None).await.unwrap().unwrap();
+
+        let result_1 = controller_user
+            .register("admin".into(), "admin-password".into(), None)
+            .await;
+        let result_2 = controller_user
+            .register(
+                "admin".into(),
+                "admin-password".into(),
+                Some("wrong-setup-token".into()),
+            )
+            .await;
+        let result_3 = controller_user
+            .register(
+                "admin".into(),
+                "admin-password".into(),
+                Some(setup_token.to_string()),
+            )
+            .await;
+        let result_4 = controller_user
+            .register(
+                "carl".into(),
+                "carl-loves-cars1234".into(),
+                Some(setup_token.to_string()),
+            )
+            .await;
+
+        assert!(matches!(
+            result_1,
+            Err(error::ServiceError::PermissionDenied(_))
+        ));
+        assert!(matches!(
+            result_2,
+            Err(error::ServiceError::PermissionDenied(_))
+        ));
+        assert!(result_3.is_ok());
+        assert!(matches!(
+            result_4,
+            Err(error::ServiceError::PermissionDenied(_))
+        ));
+
+        // No setup token once an admin exists
+        assert!(controller.bootstrap(None).await.unwrap().is_none());
+
+        let admin = model::ModelUser::get_user_from_username(
+            controller.client.get_database().get_pool(),
+            "admin".into(),
+        )
+        .await
+        .unwrap();
+
+        assert!(admin.is_admin());
+
+        // Invites are used once
+        let (invite, code) = controller
+            .create(admin.uuid.into_uuid(), Some(1))
+            .await
+            .unwrap();
+
+        assert!(invite.expires_at.is_some());
+        assert_eq!(controller.list().await.unwrap().len(), 1);
+
+        let result_5 = controller_user
+            .register("carl".into(), "carl-loves-cars1234".into(), None)
+            .await;
+        let result_6 = controller_user
+            .register(
+                "carl".into(),
+                "carl-loves-cars1234".into(),
+                Some(code.clone()),
+            )
+            .await;
+        let result_7 = controller_user
+            .register("bob".into(), "bob-loves-boats1234".into(), Some(code))
+            .await;
+
+        assert!(matches!(
+            result_5,
+            Err(error::ServiceError::PermissionDenied(_))
+        ));
+        assert!(result_6.is_ok());
+        assert!(matches!(
+            result_7,
+            Err(error::ServiceError::PermissionDenied(_))
+        ));
+        assert_eq!(controller.list().await.unwrap().len(), 0);
+
+        // Revoked invites can't be used
+        let (invite, code) = controller
+            .create(admin.uuid.into_uuid(), None)
+            .await
+            .unwrap();
+
+        controller.revoke(invite.uuid.into_uuid()).await.unwrap();
+
+        assert!(matches!(
+            controller.revoke(invite.uuid.into_uuid()).await,
+            Err(error::ServiceError::NotFound(_))
+        ));
+        assert!(
+            controller_user
+                .register("bob".into(), "bob-loves-boats1234".into(), Some(code))
+                .await
+                .is_err()
+        );
+
+        // Open registration needs no invite, admin only registration is closed
+        config.config.write().await.registration.policy = RegistrationPolicy::Open;
+
+        assert!(
+            controller_user
+                .register("bob".into(), "bob-loves-boats1234".into(), None)
+                .await
+                .is_ok()
+        );
+
+        config.config.write().await.registration.policy = RegistrationPolicy::Admin;
+
+        let result_8 = controller_user
+            .register("dave".into(), "dave-loves-planes1234".into(), None)
+            .await;
+        let result_9 = controller_user
+            .create(
+                &middleware::User {
+                    uuid: admin.uuid.into_uuid(),
+                    username: admin.username.clone(),
+                    api_key: None,
+                },
+                "dave".into(),
+                "dave-loves-planes1234".into(),
+            )
+            .await;
+
+        assert!(matches!(
+            result_8,
+            Err(error::ServiceError::PermissionDenied(_))
+        ));
+        assert!(!result_9.unwrap().is_admin());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn bootstrap(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let config = Arc::new(config::ConfigManager::mocked());
+        let client = Arc::new(
+            client::DaemonClient::mocked(pool)
+                .await
+                .expect("could not create mocked client"),
+        );
+
+        let controller = ControllerInvite::new(config.clone(), client.clone());
+        let controller_user = ControllerUser::new(config, client);
+
+        // Given tokens are not returned and must be long enough
+        let result_1 = controller.bootstrap(Some("short".into())).await;
+        let result_2 = controller
+            .bootstrap(Some("setup-token-from-env".into()))
+            .await;
+
+        assert!(matches!(
+            result_1,
+            Err(error::ServiceError::InvalidArgument(_))
+        ));
+        assert!(result_2.unwrap().is_none());
+
+        assert!(
+            controller_user
+                .register(
+                    "admin".into(),
+                    "admin-password".into(),
+                    Some(" setup-token-from-env ".into()),
+                )
+                .await
+                .is_ok()
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn bootstrap_without_admin(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let config = Arc::new(config::ConfigManager::mocked());
+        let client = Arc::new(
+            client::DaemonClient::mocked(pool)
+                .await
+                .expect("could not create mocked client"),
+        );
+
+        let controller = ControllerInvite::new(config.clone(), client.clone());
+        let controller_user = ControllerUser::new(config, client);
+        let pool = controller.client.get_database().get_pool();
+
+        // Users of an upgraded vault are not admins
+        let user = schema::User {
+            username: "carl".into(),
+            admin: Some(false),
+            ..schema::User::default()
+        };
+
+        model::ModelUser::add_user(pool, user).await.unwrap();
+
+        let setup_token = controller.bootstrap(None).await.unwrap();
+
+        assert!(setup_token.is_some());
+
+        let admin = controller_user
+            .register(
+                "admin".into(),
+                "admin-password".into(),
+                Some(setup_token.unwrap().to_string()),
+            )
+            .await;
+
+        assert!(admin.is_ok());
+        assert!(controller.bootstrap(None).await.unwrap().is_none());
+
+        // Deleting the only admin brings the setup token back
+        let admin = model::ModelUser::get_user_from_username(pool, "admin".into())
+            .await
+            .unwrap();
+
+        model::ModelUser::delete_user(pool, admin.uuid.into_uuid())
+            .await
+            .unwrap();
+
+        assert!(controller.bootstrap(None).await.unwrap().is_some());
+
+        Ok(())
+    }
+}
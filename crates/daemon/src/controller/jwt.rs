@@ -0,0 +1,84 @@
+use crate::client::DaemonClient;
+use crate::error;
+
+use shared_core::crypt;
+use std::sync::Arc;
+
+/// Jwt controller
+#[derive(Debug, Clone)]
+pub struct ControllerJwt {
+    pub(crate) client: Arc<DaemonClient>,
+}
+
+impl ControllerJwt {
+    pub fn new(client: Arc<DaemonClient>) -> Self {
+        Self { client }
+    }
+
+    /// Get keys tokens are verified with.
+    pub fn keys(&self) -> Vec<crypt::JwtKeyInfo> {
+        self.client.get_jwt_factory().keys()
+    }
+
+    /// Get public keys tokens are verified with as JWK set.
+    pub fn jwks(&self) -> crypt::JwkSet {
+        self.client.get_jwt_factory().jwks()
+    }
+
+    /// Replace signing key.
+    /// Previous keys are retired once every token they signed has expired.
+    pub async fn rotate(&self) -> Result<crypt::JwtKeyInfo, error::ServiceError> {
+        let key = self
+            .client
+            .get_jwt_factory()
+            .rotate(crypt::JwtClaimRefresh::lifetime())
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client;
+    use crate::config;
+    use crate::controller::{ControllerJwt, ControllerUser};
+
+    use sqlx::sqlite;
+    use std::sync::Arc;
+
+    #[sqlx::test]
+    async fn rotate(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let config = Arc::new(config::ConfigManager::mocked());
+        let client = Arc::new(
+            client::DaemonClient::mocked(pool)
+                .await
+                .expect("could not create mocked client"),
+        );
+
+        let controller = ControllerJwt::new(client.clone());
+        let controller_user = ControllerUser::new(config, client);
+
+        let (_, token_refresh) = controller_user
+            .add("carl".into(), "carl-loves-cars1234".into())
+            .await
+            .unwrap();
+
+        let result = controller.rotate().await;
+
+        assert!(result.is_ok());
+
+        let key = result.unwrap();
+        let keys = controller.keys();
+
+        assert!(key.active);
+        assert_eq!(keys.len(), 2);
+        assert_eq!(controller.jwks().keys.len(), 2);
+
+        // Tokens issued before rotating keep working
+        assert!(controller_user.refresh(token_refresh).await.is_ok());
+
+        Ok(())
+    }
+}
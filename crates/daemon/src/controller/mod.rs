@@ -1,5 +1,91 @@
 mod client;
+mod collection;
+mod database;
+mod encryption;
+mod grant;
+mod invite;
+mod jwt;
+mod seal;
+mod secret;
+mod service_account;
+mod source;
+mod throttle;
+mod totp;
 mod user;
 
 pub use client::*;
+pub use collection::*;
+pub use database::*;
+pub use encryption::*;
+pub use grant::*;
+pub use invite::*;
+pub use jwt::*;
+pub use seal::*;
+pub use secret::*;
+pub use service_account::*;
+pub use source::*;
+pub use throttle::*;
+pub use totp::*;
 pub use user::*;
+
+use crate::error;
+
+/// Maximum number of rows in a page, larger limits are lowered to it.
+const LIST_LIMIT_MAX: u64 = 1000;
+
+/// Build query listing rows sorted by a unique name column.
+/// Rows are searched by name and only paginated if a limit is given.
+fn list_query<T>(
+    column: shared_core::database::Column<T>,
+    search: Option<String>,
+    cursor: Option<String>,
+    limit: Option<u64>,
+) -> shared_core::database::ListQuery {
+    let mut query =
+        shared_core::database::ListQuery::new().order(shared_core::database::Order::asc(column));
+
+    if let Some(search) = search {
+        query = query.filter(shared_core::database::Filter::contains(column, search));
+    }
+
+    if let Some(limit) = limit {
+        query = query.cursor(cursor, limit.min(LIST_LIMIT_MAX));
+    }
+
+    query
+}
+
+/// Map errors from listing rows, invalid cursors are given by the caller.
+fn list_error(error: shared_core::error::Error) -> error::ServiceError {
+    match error {
+        shared_core::error::Error::InvalidCursor(x) => {
+            error::ServiceError::InvalidArgument(format!("invalid cursor {}", x))
+        }
+        e => error::ServiceError::Internal(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::schema;
+
+    use shared_core::database::{ListQuery, Order, Pagination};
+
+    #[tokio::test]
+    async fn list_query() {
+        let result_1 = super::list_query(schema::User::COLUMN_USERNAME, None, None, None);
+        let result_2 = super::list_query(schema::User::COLUMN_USERNAME, None, None, Some(u64::MAX));
+
+        assert_eq!(
+            result_1,
+            ListQuery::new().order(Order::asc(schema::User::COLUMN_USERNAME))
+        );
+        assert_eq!(
+            result_2.pagination,
+            Pagination::Cursor {
+                cursor: None,
+                limit: super::LIST_LIMIT_MAX
+            }
+        );
+    }
+}
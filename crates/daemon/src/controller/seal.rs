@@ -0,0 +1,562 @@
+use crate::client::DaemonClient;
+use crate::config::{ConfigManager, DatabaseKeySource};
+use crate::error;
+
+use shared_core::crypt;
+#[cfg(test)]
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Delay after an incorrect passphrase or shares, attempts are made one at a time so this
+/// slows down guessing the key.
+const UNSEAL_FAILURE_DELAY: Duration = Duration::from_secs(1);
+
+/// Unseal status
+#[derive(Debug, Clone)]
+pub struct UnsealStatus {
+    pub sealed: bool,
+    pub key_source: DatabaseKeySource,
+    /// Number of shares unsealing needs, one for passphrases.
+    pub threshold: u8,
+    /// Number of shares given so far.
+    pub progress: u8,
+}
+
+/// State of sealed daemon.
+#[derive(Debug, Default)]
+struct SealState {
+    client: Option<Arc<DaemonClient>>,
+    shares: Vec<crypt::SecretShare>,
+}
+
+/// Seal controller
+///
+/// Daemons whose database key is a passphrase or split into shares start sealed, only serving
+/// health and unseal until the database was opened with the key. Shares are kept in memory
+/// until enough were given, they are combined into the key once the threshold is reached.
+/// Each share is checked against the hashes saved when the key was split as it's given.
+#[derive(Debug, Clone)]
+pub struct ControllerSeal {
+    pub(crate) config: Arc<ConfigManager>,
+    state: Arc<tokio::sync::Mutex<SealState>>,
+    /// Held for the whole of an unseal attempt including the delay after a failure.
+    attempt: Arc<tokio::sync::Mutex<()>>,
+    unsealed: CancellationToken,
+    /// Database mocked clients are started with.
+    #[cfg(test)]
+    database_file: Option<PathBuf>,
+}
+
+impl ControllerSeal {
+    pub fn new(config: Arc<ConfigManager>) -> Self {
+        Self {
+            config,
+            state: Arc::default(),
+            attempt: Arc::default(),
+            unsealed: CancellationToken::new(),
+            #[cfg(test)]
+            database_file: None,
+        }
+    }
+
+    /// Mocked controller starting clients with the database stored in the given file.
+    #[cfg(test)]
+    pub fn mocked(config: Arc<ConfigManager>, database_file: PathBuf) -> Self {
+        Self {
+            database_file: Some(database_file),
+            ..Self::new(config)
+        }
+    }
+
+    /// Get unseal status.
+    pub async fn status(&self) -> UnsealStatus {
+        let state = self.state.lock().await;
+
+        self.status_of(&state).await
+    }
+
+    /// Open the database with passphrase and start the client.
+    /// A new database is encrypted with the first passphrase given.
+    pub async fn unseal(&self, passphrase: String) -> Result<UnsealStatus, error::ServiceError> {
+        let _attempt = self.attempt.lock().await;
+        let mut state = self.state.lock().await;
+
+        self.check_sealed(&state, DatabaseKeySource::Passphrase)
+            .await?;
+
+        if passphrase.is_empty() {
+            return Err(error::ServiceError::InvalidArgument(
+                "passphrase must not be empty".into(),
+            ));
+        }
+
+        if let Err(e) = self
+            .start(&mut state, passphrase, "incorrect passphrase")
+            .await
+        {
+            return Self::fail(state, e).await;
+        }
+
+        Ok(self.status_of(&state).await)
+    }
+
+    /// Add share of the database key, once enough shares were given they are combined into the
+    /// key and the client is started. A share not matching its hash is refused and the shares
+    /// given before are kept. Every share is discarded if they don't open the database, which
+    /// only happens if the config has no hashes of the shares.
+    pub async fn unseal_share(&self, share: String) -> Result<UnsealStatus, error::ServiceError> {
+        let _attempt = self.attempt.lock().await;
+        let mut state = self.state.lock().await;
+
+        self.check_sealed(&state, DatabaseKeySource::Shamir).await?;
+
+        let share = crypt::SecretShare::parse(&share)
+            .ok_or(error::ServiceError::InvalidArgument("invalid share".into()))?;
+
+        if state.shares.iter().any(|x| x.index() == share.index()) {
+            return Err(error::ServiceError::InvalidArgument(format!(
+                "share {} was already given",
+                share.index()
+            )));
+        }
+
+        let hashes = self
+            .config
+            .config
+            .read()
+            .await
+            .database
+            .shamir_share_hashes
+            .clone();
+
+        if !hashes.is_empty() && !Self::check_share(&hashes, &share) {
+            let message = format!(
+                "share {} is incorrect, the shares given before are kept",
+                share.index()
+            );
+
+            return Self::fail(state, error::ServiceError::Unauthorized(message)).await;
+        }
+
+        state.shares.push(share);
+
+        let threshold = self.config.config.read().await.database.shamir_threshold;
+
+        if state.shares.len() < threshold as usize {
+            return Ok(self.status_of(&state).await);
+        }
+
+        let shares = std::mem::take(&mut state.shares);
+        let message = "shares don't open the database, every share has to be given again";
+
+        let encryption_key = match crypt::combine_shares(&shares)
+            .ok()
+            .and_then(|x| String::from_utf8(x.to_vec()).ok())
+        {
+            Some(x) => x,
+            None => {
+                return Self::fail(state, error::ServiceError::Unauthorized(message.into())).await;
+            }
+        };
+
+        if let Err(e) = self.start(&mut state, encryption_key, message).await {
+            return Self::fail(state, e).await;
+        }
+
+        Ok(self.status_of(&state).await)
+    }
+
+    /// Discard the shares given so far, which needs one of the shares to prove it's asked for
+    /// by an operator. Only available if the config has hashes of the shares to check it with.
+    pub async fn reset(&self, share: String) -> Result<UnsealStatus, error::ServiceError> {
+        let _attempt = self.attempt.lock().await;
+        let mut state = self.state.lock().await;
+
+        self.check_sealed(&state, DatabaseKeySource::Shamir).await?;
+
+        let share = crypt::SecretShare::parse(&share)
+            .ok_or(error::ServiceError::InvalidArgument("invalid share".into()))?;
+
+        let hashes = self
+            .config
+            .config
+            .read()
+            .await
+            .database
+            .shamir_share_hashes
+            .clone();
+
+        if hashes.is_empty() {
+            return Err(error::ServiceError::Conflict(
+                "shares can't be checked without their hashes, give every share again instead"
+                    .into(),
+            ));
+        }
+
+        if !Self::check_share(&hashes, &share) {
+            return Self::fail(
+                state,
+                error::ServiceError::Unauthorized("incorrect share".into()),
+            )
+            .await;
+        }
+
+        state.shares.clear();
+
+        tracing::info!("unseal progress was reset with share {}", share.index());
+
+        Ok(self.status_of(&state).await)
+    }
+
+    /// Wait until daemon is unsealed.
+    pub async fn unsealed(&self) {
+        self.unsealed.cancelled().await
+    }
+
+    /// Get client started by unsealing.
+    pub async fn get_client(&self) -> Option<Arc<DaemonClient>> {
+        self.state.lock().await.client.clone()
+    }
+
+    async fn status_of(&self, state: &SealState) -> UnsealStatus {
+        let config = self.config.config.read().await;
+
+        let threshold = match config.database.key_source {
+            DatabaseKeySource::Shamir => config.database.shamir_threshold,
+            _ => 1,
+        };
+
+        UnsealStatus {
+            sealed: state.client.is_none(),
+            key_source: config.database.key_source,
+            threshold,
+            progress: state.shares.len() as u8,
+        }
+    }
+
+    /// Checks that daemon is still sealed and unsealed with the given kind of key.
+    async fn check_sealed(
+        &self,
+        state: &SealState,
+        key_source: DatabaseKeySource,
+    ) -> Result<(), error::ServiceError> {
+        if state.client.is_some() {
+            return Err(error::ServiceError::Conflict(
+                "daemon is already unsealed".into(),
+            ));
+        }
+
+        let key_source_config = self.config.config.read().await.database.key_source;
+
+        if key_source_config != key_source {
+            return Err(error::ServiceError::InvalidArgument(format!(
+                "database key is from {}, not {}",
+                key_source_config, key_source
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Checks share against the hash saved for its index.
+    fn check_share(hashes: &[String], share: &crypt::SecretShare) -> bool {
+        hashes
+            .get(share.index() as usize - 1)
+            .is_some_and(|x| *x == share.hash())
+    }
+
+    /// Release the state and wait before returning error. Status requests aren't blocked while
+    /// waiting, other attempts are as the caller still holds the attempt lock.
+    async fn fail<T>(
+        state: tokio::sync::MutexGuard<'_, SealState>,
+        error: error::ServiceError,
+    ) -> Result<T, error::ServiceError> {
+        drop(state);
+
+        tokio::time::sleep(UNSEAL_FAILURE_DELAY).await;
+
+        Err(error)
+    }
+
+    /// Start client with database key.
+    async fn start(
+        &self,
+        state: &mut SealState,
+        encryption_key: String,
+        message: &str,
+    ) -> Result<(), error::ServiceError> {
+        #[cfg(test)]
+        let result = match &self.database_file {
+            Some(x) => DaemonClient::mocked_file(x, encryption_key).await,
+            None => DaemonClient::start(self.config.clone(), encryption_key).await,
+        };
+        #[cfg(not(test))]
+        let result = DaemonClient::start(self.config.clone(), encryption_key).await;
+
+        match result {
+            Ok(x) => {
+                state.client = Some(Arc::new(x));
+                self.unsealed.cancel();
+
+                tracing::info!("daemon unsealed");
+
+                Ok(())
+            }
+            Err(e) => match e.downcast_ref::<shared_core::error::Error>() {
+                Some(shared_core::error::Error::DatabaseKey) => {
+                    Err(error::ServiceError::Unauthorized(message.into()))
+                }
+                _ => Err(error::ServiceError::Internal(e.to_string())),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UNSEAL_FAILURE_DELAY;
+    use crate::config::{ConfigManager, DatabaseKeySource};
+    use crate::controller::ControllerSeal;
+    use crate::{client, error};
+
+    use shared_core::{crypt, rng};
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    /// Create database encrypted with key in the temp test data.
+    async fn database_file(encryption_key: &str) -> PathBuf {
+        let path = PathBuf::from(env!("WORKSPACE_DIR"))
+            .join("test-data")
+            .join("temp")
+            .join(format!("{}.sqlite3", rng::random_bytes_str(10)));
+
+        client::DaemonClient::mocked_file(&path, encryption_key.into())
+            .await
+            .unwrap()
+            .get_database()
+            .close()
+            .await;
+
+        path
+    }
+
+    async fn controller_with(key_source: DatabaseKeySource, path: PathBuf) -> ControllerSeal {
+        let config = ConfigManager::mocked();
+        config.config.write().await.database.key_source = key_source;
+
+        ControllerSeal::mocked(Arc::new(config), path)
+    }
+
+    #[tokio::test]
+    async fn unseal() {
+        let path = database_file("passphrase").await;
+        let controller = controller_with(DatabaseKeySource::Passphrase, path.clone()).await;
+
+        let status = controller.status().await;
+
+        assert!(status.sealed);
+        assert_eq!(status.threshold, 1);
+
+        let result_1 = controller.unseal("".into()).await;
+        let result_2 = controller.unseal_share("mvs_1_00".into()).await;
+
+        assert!(matches!(
+            result_1,
+            Err(error::ServiceError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            result_2,
+            Err(error::ServiceError::InvalidArgument(_))
+        ));
+
+        // Status isn't blocked while waiting after an incorrect passphrase.
+        let (result_3, result_4) = tokio::join!(controller.unseal("incorrect".into()), async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            tokio::time::timeout(Duration::from_millis(500), controller.status()).await
+        });
+
+        assert!(matches!(
+            result_3,
+            Err(error::ServiceError::Unauthorized(_))
+        ));
+        assert!(result_4.unwrap().sealed);
+        assert!(controller.get_client().await.is_none());
+
+        // Concurrent attempts wait for each other.
+        let started = Instant::now();
+        let (result_5, result_6) = tokio::join!(
+            controller.unseal("incorrect".into()),
+            controller.unseal("incorrect".into())
+        );
+
+        assert!(matches!(
+            result_5,
+            Err(error::ServiceError::Unauthorized(_))
+        ));
+        assert!(matches!(
+            result_6,
+            Err(error::ServiceError::Unauthorized(_))
+        ));
+        assert!(started.elapsed() >= UNSEAL_FAILURE_DELAY * 2);
+
+        let result_7 = controller.unseal("passphrase".into()).await;
+
+        assert!(!result_7.unwrap().sealed);
+
+        // Unsealing only works once.
+        let result_8 = controller.unseal("passphrase".into()).await;
+
+        assert!(matches!(result_8, Err(error::ServiceError::Conflict(_))));
+
+        let client = controller.get_client().await;
+
+        assert!(client.is_some());
+
+        client.unwrap().get_database().close().await;
+
+        assert!(tokio::fs::remove_file(&path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn unseal_share() {
+        let encryption_key = rng::random_bytes_str(32);
+        let shares = crypt::split_secret(encryption_key.as_bytes(), 3, 5).unwrap();
+        let shares_other = crypt::split_secret(b"other-key", 3, 5).unwrap();
+
+        let path = database_file(&encryption_key).await;
+        let controller = controller_with(DatabaseKeySource::Shamir, path.clone()).await;
+        controller
+            .config
+            .config
+            .write()
+            .await
+            .database
+            .shamir_share_hashes = shares.iter().map(|x| x.hash()).collect();
+
+        assert_eq!(controller.status().await.threshold, 3);
+
+        let result_1 = controller.unseal_share("mvs_invalid".into()).await;
+        let result_2 = controller.unseal("passphrase".into()).await;
+
+        assert!(matches!(
+            result_1,
+            Err(error::ServiceError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            result_2,
+            Err(error::ServiceError::InvalidArgument(_))
+        ));
+
+        // Shares are collected until the threshold is reached, each one only once.
+        let result_3 = controller.unseal_share(shares[0].to_string()).await;
+        let result_4 = controller.unseal_share(shares[0].to_string()).await;
+        let result_5 = controller.unseal_share(shares[1].to_string()).await;
+
+        assert_eq!(result_3.unwrap().progress, 1);
+        assert!(matches!(
+            result_4,
+            Err(error::ServiceError::InvalidArgument(_))
+        ));
+
+        let result_5 = result_5.unwrap();
+
+        assert!(result_5.sealed);
+        assert_eq!(result_5.progress, 2);
+
+        // Incorrect shares are refused, the shares given before are kept.
+        let result_6 = controller.unseal_share(shares_other[2].to_string()).await;
+
+        assert!(matches!(
+            result_6,
+            Err(error::ServiceError::Unauthorized(_))
+        ));
+        assert_eq!(controller.status().await.progress, 2);
+
+        // Resetting needs a correct share.
+        let result_7 = controller.reset(shares_other[3].to_string()).await;
+
+        assert!(matches!(
+            result_7,
+            Err(error::ServiceError::Unauthorized(_))
+        ));
+        assert_eq!(controller.status().await.progress, 2);
+
+        let result_8 = controller.reset(shares[3].to_string()).await;
+
+        assert_eq!(result_8.unwrap().progress, 0);
+
+        for share in &shares[2..4] {
+            let result_9 = controller.unseal_share(share.to_string()).await;
+
+            assert!(result_9.unwrap().sealed);
+        }
+
+        let result_10 = controller.unseal_share(shares[4].to_string()).await;
+
+        assert!(!result_10.unwrap().sealed);
+
+        let client = controller.get_client().await;
+
+        assert!(client.is_some());
+
+        client.unwrap().get_database().close().await;
+
+        assert!(tokio::fs::remove_file(&path).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn unseal_share_without_hashes() {
+        let encryption_key = rng::random_bytes_str(32);
+        let shares = crypt::split_secret(encryption_key.as_bytes(), 3, 5).unwrap();
+        let shares_other = crypt::split_secret(b"other-key", 3, 5).unwrap();
+
+        let path = database_file(&encryption_key).await;
+        let controller = controller_with(DatabaseKeySource::Shamir, path.clone()).await;
+
+        // Progress can't be reset as the share can't be checked.
+        let result_1 = controller.unseal_share(shares[0].to_string()).await;
+        let result_2 = controller.reset(shares[1].to_string()).await;
+
+        assert_eq!(result_1.unwrap().progress, 1);
+        assert!(matches!(result_2, Err(error::ServiceError::Conflict(_))));
+        assert_eq!(controller.status().await.progress, 1);
+
+        // Shares which don't combine into the key are all discarded.
+        controller
+            .unseal_share(shares[1].to_string())
+            .await
+            .unwrap();
+
+        let result_3 = controller.unseal_share(shares_other[2].to_string()).await;
+
+        assert!(matches!(
+            result_3,
+            Err(error::ServiceError::Unauthorized(_))
+        ));
+        assert_eq!(controller.status().await.progress, 0);
+        assert!(controller.get_client().await.is_none());
+
+        for share in &shares[2..4] {
+            let result_4 = controller.unseal_share(share.to_string()).await;
+
+            assert!(result_4.unwrap().sealed);
+        }
+
+        let result_5 = controller.unseal_share(shares[4].to_string()).await;
+
+        assert!(!result_5.unwrap().sealed);
+
+        controller
+            .get_client()
+            .await
+            .unwrap()
+            .get_database()
+            .close()
+            .await;
+
+        assert!(tokio::fs::remove_file(&path).await.is_ok());
+    }
+}
@@ -0,0 +1,417 @@
+use crate::client::DaemonClient;
+use crate::{error, model, schema};
+
+use shared_core::database;
+use std::sync::Arc;
+use validator::Validate;
+
+/// Secret controller
+#[derive(Debug, Clone)]
+pub struct ControllerSecret {
+    pub(crate) client: Arc<DaemonClient>,
+    pub(crate) encryption: super::ControllerEncryption,
+}
+
+impl ControllerSecret {
+    pub fn new(client: Arc<DaemonClient>) -> Self {
+        let encryption = super::ControllerEncryption::new(client.clone());
+
+        Self { client, encryption }
+    }
+
+    /// Checks if secret with name exists
+    pub async fn exists(&self, name: String) -> Result<bool, error::ServiceError> {
+        let result =
+            model::ModelSecret::does_secret_exist(self.client.get_database().get_pool(), name)
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    /// Get secret from uuid.
+    pub async fn get(&self, uuid: uuid::Uuid) -> Result<schema::Secret, error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+
+        if !model::ModelSecret::does_secret_exist_uuid(pool, uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(error::ServiceError::NotFound(format!(
+                "could not find secret with uuid {}",
+                &uuid
+            )));
+        }
+
+        let secret = model::ModelSecret::get_secret_from_uuid(pool, uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        self.encryption.open(secret).await
+    }
+
+    /// Get secret from name.
+    pub async fn get_by_name(&self, name: String) -> Result<schema::Secret, error::ServiceError> {
+        if !self.exists(name.clone()).await? {
+            return Err(error::ServiceError::NotFound(format!(
+                "could not find secret with name {}",
+                &name
+            )));
+        }
+
+        let secret =
+            model::ModelSecret::get_secret_from_name(self.client.get_database().get_pool(), name)
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        self.encryption.open(secret).await
+    }
+
+    /// List secrets sorted by name.
+    /// Only secrets whose name contains `search` are listed and pages are only used if `limit` is set.
+    /// If `uuids` is set only those secrets are listed.
+    pub async fn list(
+        &self,
+        uuids: Option<Vec<uuid::Uuid>>,
+        search: Option<String>,
+        cursor: Option<String>,
+        limit: Option<u64>,
+    ) -> Result<database::Page<schema::Secret>, error::ServiceError> {
+        let mut query = super::list_query(schema::Secret::COLUMN_NAME, search, cursor, limit);
+
+        if let Some(uuids) = uuids {
+            query = query.filter(database::Filter::is_in(
+                schema::Secret::COLUMN_UUID,
+                uuids.iter().map(|x| x.as_hyphenated().to_string()),
+            ));
+        }
+
+        let result = model::ModelSecret::get_secrets(self.client.get_database().get_pool(), query)
+            .await
+            .map_err(super::list_error)?;
+
+        Ok(database::Page {
+            items: self.encryption.open_all(result.items).await?,
+            ..result
+        })
+    }
+
+    /// Add a new secret.
+    /// The value is encrypted with the data key of the collection, or the default data key if no
+    /// collection is given.
+    pub async fn add(
+        &self,
+        name: String,
+        key: Option<String>,
+        description: Option<String>,
+        secret: String,
+        secret_type: u32,
+        uuid_collection: Option<uuid::Uuid>,
+    ) -> Result<schema::Secret, error::ServiceError> {
+        // Secret names are unique so we can look them up by name.
+        if self.exists(name.clone()).await? {
+            return Err(error::ServiceError::AlreadyExists(format!(
+                "secret with name {} already exists",
+                &name
+            )));
+        }
+
+        if let Some(uuid_collection) = uuid_collection
+            && !model::ModelCollection::does_collection_exist_uuid(
+                self.client.get_database().get_pool(),
+                uuid_collection,
+            )
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(error::ServiceError::NotFound(format!(
+                "could not find collection with uuid {}",
+                &uuid_collection
+            )));
+        }
+
+        let data = schema::Secret::new(name, key, description, secret, secret_type)
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+        let data = self
+            .encryption
+            .data_key(uuid_collection)
+            .await?
+            .seal(data)?;
+
+        let secret = model::ModelSecret::add_secret(self.client.get_database().get_pool(), data)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        self.encryption.open(secret).await
+    }
+
+    /// Update an existing secret.
+    /// Only fields which are given are updated, everything else is left as is.
+    pub async fn update(
+        &self,
+        uuid: uuid::Uuid,
+        name: Option<String>,
+        key: Option<String>,
+        description: Option<String>,
+        secret: Option<String>,
+        secret_type: Option<u32>,
+    ) -> Result<schema::Secret, error::ServiceError> {
+        let current = self.get(uuid).await?;
+
+        // Renaming must not collide with another secret.
+        if let Some(name) = &name
+            && name != &current.name
+            && self.exists(name.clone()).await?
+        {
+            return Err(error::ServiceError::AlreadyExists(format!(
+                "secret with name {} already exists",
+                name
+            )));
+        }
+
+        let data = schema::Secret {
+            name: name.unwrap_or(current.name),
+            key: key.or(current.key),
+            description: description.or(current.description),
+            secret: secret.unwrap_or(current.secret),
+            secret_type: secret_type.unwrap_or(current.secret_type),
+            ..current
+        };
+
+        data.validate()
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        // The value stays encrypted with the data key it was created with.
+        let data_key = match data.uuid_data_key {
+            Some(x) => self.encryption.data_key_from_uuid(x.into_uuid()).await?,
+            None => self.encryption.data_key(None).await?,
+        };
+
+        let secret = model::ModelSecret::update_secret(
+            self.client.get_database().get_pool(),
+            data_key.seal(data)?,
+        )
+        .await
+        .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        self.encryption.open(secret).await
+    }
+
+    /// Delete an existing secret.
+    pub async fn delete(&self, uuid: uuid::Uuid) -> Result<(), error::ServiceError> {
+        // Make sure we give a sensible error if the secret doesn't exist.
+        self.get(uuid).await?;
+
+        model::ModelSecret::delete_secret(self.client.get_database().get_pool(), uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client;
+    use crate::controller::ControllerSecret;
+    use crate::schema::SecretType;
+
+    use sqlx::sqlite;
+    use std::sync::Arc;
+
+    #[sqlx::test]
+    async fn add(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let client = client::DaemonClient::mocked(pool)
+            .await
+            .expect("could not create mocked client");
+
+        let controller = ControllerSecret::new(Arc::new(client));
+
+        let result_ok = controller
+            .add(
+                "github-token".into(),
+                Some("GITHUB_TOKEN".into()),
+                None,
+                "ghp_123".into(),
+                SecretType::Key as u32,
+                None,
+            )
+            .await;
+
+        // Duplicate entry should error
+        let result_err_1 = controller
+            .add(
+                "github-token".into(),
+                None,
+                None,
+                "ghp_456".into(),
+                SecretType::Key as u32,
+                None,
+            )
+            .await;
+
+        // Name too short
+        let result_err_2 = controller
+            .add(
+                "gh".into(),
+                None,
+                None,
+                "ghp_789".into(),
+                SecretType::Key as u32,
+                None,
+            )
+            .await;
+
+        assert!(result_ok.is_ok());
+        assert!(result_err_1.is_err());
+        assert!(result_err_2.is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn get(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let client = client::DaemonClient::mocked(pool)
+            .await
+            .expect("could not create mocked client");
+
+        let controller = ControllerSecret::new(Arc::new(client));
+
+        let result_1 = controller.get(uuid::Uuid::new_v4()).await;
+        let result_2 = controller.get_by_name("github-token".into()).await;
+
+        assert!(result_1.is_err());
+        assert!(result_2.is_err());
+
+        let result_add = controller
+            .add(
+                "github-token".into(),
+                None,
+                None,
+                "ghp_123".into(),
+                SecretType::Key as u32,
+                None,
+            )
+            .await;
+
+        assert!(result_add.is_ok());
+
+        let result_add = result_add.unwrap();
+
+        let result_3 = controller.get(result_add.uuid.into_uuid()).await;
+        let result_4 = controller.get_by_name("github-token".into()).await;
+        let result_5 = controller.list(None, None, None, None).await;
+        let result_6 = controller
+            .list(Some(vec![result_add.uuid.into_uuid()]), None, None, None)
+            .await;
+        let result_7 = controller.list(Some(vec![]), None, None, None).await;
+
+        assert!(result_3.is_ok());
+        assert!(result_4.is_ok());
+        assert!(result_5.is_ok());
+
+        assert_eq!(result_3.unwrap().secret, "ghp_123");
+        assert_eq!(result_4.unwrap().uuid, result_add.uuid);
+        assert_eq!(result_5.unwrap().items.len(), 1);
+        assert_eq!(result_6.unwrap().items.len(), 1);
+        assert!(result_7.unwrap().items.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn update(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let client = client::DaemonClient::mocked(pool)
+            .await
+            .expect("could not create mocked client");
+
+        let controller = ControllerSecret::new(Arc::new(client));
+
+        let result_add_1 = controller
+            .add(
+                "github-token".into(),
+                None,
+                Some("token for ci".into()),
+                "ghp_123".into(),
+                SecretType::Key as u32,
+                None,
+            )
+            .await;
+
+        let result_add_2 = controller
+            .add(
+                "gitlab-token".into(),
+                None,
+                None,
+                "glpat_123".into(),
+                SecretType::Key as u32,
+                None,
+            )
+            .await;
+
+        assert!(result_add_1.is_ok());
+        assert!(result_add_2.is_ok());
+
+        let uuid = result_add_1.unwrap().uuid.into_uuid();
+
+        // Only the secret value should change
+        let result_1 = controller
+            .update(uuid, None, None, None, Some("ghp_456".into()), None)
+            .await;
+
+        assert!(result_1.is_ok());
+
+        let result_1 = result_1.unwrap();
+
+        assert_eq!(result_1.name, "github-token");
+        assert_eq!(result_1.description, Some("token for ci".into()));
+        assert_eq!(result_1.secret, "ghp_456");
+
+        // Renaming to an existing name should error
+        let result_2 = controller
+            .update(uuid, Some("gitlab-token".into()), None, None, None, None)
+            .await;
+
+        // Updating a secret which doesn't exist should error
+        let result_3 = controller
+            .update(uuid::Uuid::new_v4(), None, None, None, None, None)
+            .await;
+
+        assert!(result_2.is_err());
+        assert!(result_3.is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn delete(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let client = client::DaemonClient::mocked(pool)
+            .await
+            .expect("could not create mocked client");
+
+        let controller = ControllerSecret::new(Arc::new(client));
+
+        let result_add = controller
+            .add(
+                "github-token".into(),
+                None,
+                None,
+                "ghp_123".into(),
+                SecretType::Key as u32,
+                None,
+            )
+            .await;
+
+        assert!(result_add.is_ok());
+
+        let uuid = result_add.unwrap().uuid.into_uuid();
+
+        let result_1 = controller.delete(uuid).await;
+        let result_2 = controller.delete(uuid).await;
+
+        assert!(result_1.is_ok());
+        assert!(result_2.is_err());
+
+        Ok(())
+    }
+}
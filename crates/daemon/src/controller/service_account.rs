@@ -0,0 +1,539 @@
+use crate::client::DaemonClient;
+use crate::{error, model, schema};
+
+use shared_core::{crypt, database};
+use std::sync::Arc;
+
+/// Service account controller
+///
+/// Service accounts are users which can't login, they authenticate with api keys instead.
+/// Each key is scoped by its own grants, so a key only has the permissions given to it.
+#[derive(Debug, Clone)]
+pub struct ControllerServiceAccount {
+    pub(crate) client: Arc<DaemonClient>,
+}
+
+impl ControllerServiceAccount {
+    pub fn new(client: Arc<DaemonClient>) -> Self {
+        Self { client }
+    }
+
+    /// Add a new service account.
+    pub async fn add(&self, name: String) -> Result<schema::User, error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+
+        // Service accounts share usernames with users.
+        if model::ModelUser::does_user_exist(pool, name.clone())
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(error::ServiceError::AlreadyExists(format!(
+                "user with username {} already exists",
+                &name
+            )));
+        }
+
+        // Password is never checked, it is left empty so no password can match.
+        let mut data = schema::User::new(name, "")
+            .map_err(|e| error::ServiceError::InvalidArgument(e.to_string()))?;
+
+        data.service = Some(true);
+
+        let user = model::ModelUser::add_user(pool, data)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        tracing::info!("added service account {}", &user.username);
+
+        Ok(user)
+    }
+
+    /// List active service accounts sorted by name.
+    pub async fn list(&self) -> Result<Vec<schema::User>, error::ServiceError> {
+        let query = database::ListQuery::new()
+            .filter(database::Filter::eq(schema::User::COLUMN_SERVICE, true))
+            .order(database::Order::asc(schema::User::COLUMN_USERNAME));
+
+        let result = model::ModelUser::get_users(self.client.get_database().get_pool(), query)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(result.items)
+    }
+
+    /// Soft delete service account with name, its api keys can no longer be used.
+    pub async fn delete(&self, name: String) -> Result<(), error::ServiceError> {
+        let user = self.get(name).await?;
+
+        model::ModelUser::delete_user(self.client.get_database().get_pool(), user.uuid.into_uuid())
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        tracing::info!("soft deleted service account {}", &user.username);
+
+        Ok(())
+    }
+
+    /// Add api key to service account, the key gets role on every scope.
+    /// Returns the key together with its token, the token can't be retrieved later on.
+    pub async fn add_key(
+        &self,
+        name: String,
+        key_name: String,
+        role: String,
+        scopes: Vec<(u32, Option<uuid::Uuid>)>,
+        expires_in_days: Option<u32>,
+    ) -> Result<(schema::ApiKey, String), error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+        let user = self.get(name).await?;
+        let uuid_user = user.uuid.into_uuid();
+
+        if scopes.is_empty() {
+            return Err(error::ServiceError::InvalidArgument(
+                "api keys require at least one scope".into(),
+            ));
+        }
+
+        if model::ModelApiKey::does_api_key_exist(pool, uuid_user, key_name.clone())
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(error::ServiceError::AlreadyExists(format!(
+                "service account {} already has api key {}",
+                &user.username, &key_name
+            )));
+        }
+
+        let controller_grant = super::ControllerGrant::new(self.client.clone());
+        controller_grant.check_role(&role).await?;
+
+        let key = crypt::ApiKey::generate(uuid::Uuid::new_v4());
+        let mut grants: Vec<schema::Grant> = Vec::with_capacity(scopes.len());
+
+        for (scope_type, uuid_scope) in scopes {
+            let scope_type = controller_grant.check_scope(scope_type, uuid_scope).await?;
+            let grant = schema::Grant {
+                uuid_api_key: Some(key.id.into()),
+                ..schema::Grant::new(uuid_user, &role, scope_type, uuid_scope)
+            };
+
+            // Skip scopes given twice.
+            if !grants
+                .iter()
+                .any(|x| x.scope_type == grant.scope_type && x.uuid_scope == grant.uuid_scope)
+            {
+                grants.push(grant);
+            }
+        }
+
+        let expires_at = expires_in_days
+            .map(|x| chrono::Utc::now().naive_utc() + chrono::Duration::days(x.into()));
+
+        let data = schema::ApiKey::new(&key, uuid_user, key_name, expires_at)
+            .map_err(|e| error::ServiceError::InvalidArgument(e.to_string()))?;
+
+        let api_key = model::ModelApiKey::add_api_key(pool, data, grants)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        tracing::info!(
+            "added api key {} to service account {}",
+            &api_key.name,
+            &user.username
+        );
+
+        Ok((api_key, key.to_string()))
+    }
+
+    /// List api keys of service account together with the grants scoping them.
+    pub async fn list_keys(
+        &self,
+        name: String,
+    ) -> Result<Vec<(schema::ApiKey, Vec<schema::Grant>)>, error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+        let user = self.get(name).await?;
+
+        let api_keys = model::ModelApiKey::get_user_api_keys(pool, user.uuid.into_uuid())
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        let mut result = Vec::with_capacity(api_keys.len());
+
+        for api_key in api_keys {
+            let grants = model::ModelGrant::get_api_key_grants(pool, api_key.uuid.into_uuid())
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+            result.push((api_key, grants));
+        }
+
+        Ok(result)
+    }
+
+    /// Generate a new token for api key, the previous token stops working immediately.
+    /// Returns the key together with its new token.
+    pub async fn rotate_key(
+        &self,
+        name: String,
+        uuid: uuid::Uuid,
+    ) -> Result<(schema::ApiKey, String), error::ServiceError> {
+        let api_key = self.get_key(name, uuid).await?;
+        let key = crypt::ApiKey::generate(uuid);
+
+        model::ModelApiKey::rotate_api_key(self.client.get_database().get_pool(), uuid, key.hash())
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        tracing::info!("rotated api key {}", &api_key.name);
+
+        Ok((api_key, key.to_string()))
+    }
+
+    /// Revoke api key with uuid.
+    pub async fn delete_key(
+        &self,
+        name: String,
+        uuid: uuid::Uuid,
+    ) -> Result<(), error::ServiceError> {
+        let api_key = self.get_key(name, uuid).await?;
+
+        model::ModelApiKey::delete_api_key(self.client.get_database().get_pool(), uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        tracing::info!("revoked api key {}", &api_key.name);
+
+        Ok(())
+    }
+
+    /// Authenticate request with api key.
+    /// Returns the service account owning the key if the key is valid and not expired.
+    pub async fn authenticate(
+        &self,
+        key: &crypt::ApiKey,
+    ) -> Result<schema::User, error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+        let denied = || error::ServiceError::PermissionDenied("invalid api key".into());
+
+        if !model::ModelApiKey::does_api_key_exist_uuid(pool, key.id)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(denied());
+        }
+
+        let api_key = model::ModelApiKey::get_api_key_from_uuid(pool, key.id)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        if !key.verify(&api_key.key_hash) || api_key.is_expired() {
+            return Err(denied());
+        }
+
+        // Keys of deleted service accounts are no longer valid.
+        let uuid_user = api_key.uuid_user.into_uuid();
+
+        if !model::ModelUser::does_user_exist_uuid(pool, uuid_user)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(denied());
+        }
+
+        let user = model::ModelUser::get_user_from_uuid(pool, uuid_user)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        model::ModelApiKey::touch_api_key(pool, key.id)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(user)
+    }
+
+    /// Get active service account with name.
+    async fn get(&self, name: String) -> Result<schema::User, error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+        let not_found =
+            || error::ServiceError::NotFound(format!("could not find service account {}", &name));
+
+        if !model::ModelUser::does_user_exist(pool, name.clone())
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(not_found());
+        }
+
+        let user = model::ModelUser::get_user_from_username(pool, name.clone())
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        if !user.is_service() {
+            return Err(not_found());
+        }
+
+        Ok(user)
+    }
+
+    /// Get api key with uuid owned by service account with name.
+    async fn get_key(
+        &self,
+        name: String,
+        uuid: uuid::Uuid,
+    ) -> Result<schema::ApiKey, error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+        let user = self.get(name).await?;
+        let not_found =
+            || error::ServiceError::NotFound(format!("could not find api key with uuid {}", &uuid));
+
+        if !model::ModelApiKey::does_api_key_exist_uuid(pool, uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(not_found());
+        }
+
+        let api_key = model::ModelApiKey::get_api_key_from_uuid(pool, uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        if api_key.uuid_user != user.uuid {
+            return Err(not_found());
+        }
+
+        Ok(api_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::controller::{ControllerCollection, ControllerServiceAccount, ControllerUser};
+    use crate::schema::ScopeType;
+    use crate::{client, config, error};
+
+    use shared_core::crypt;
+    use sqlx::sqlite;
+    use std::sync::Arc;
+
+    #[sqlx::test]
+    async fn add(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let config = Arc::new(config::ConfigManager::mocked());
+        let client = Arc::new(
+            client::DaemonClient::mocked(pool)
+                .await
+                .expect("could not create mocked client"),
+        );
+
+        let controller = ControllerServiceAccount::new(client.clone());
+        let controller_user = ControllerUser::new(config, client);
+
+        controller_user
+            .add("carl".into(), "carl-loves-cars1234".into())
+            .await
+            .unwrap();
+
+        let result_ok = controller.add("ci-runner".into()).await;
+
+        // Names are shared with users
+        let result_err_1 = controller.add("ci-runner".into()).await;
+        let result_err_2 = controller.add("carl".into()).await;
+
+        assert!(result_ok.unwrap().is_service());
+        assert!(matches!(
+            result_err_1,
+            Err(error::ServiceError::AlreadyExists(_))
+        ));
+        assert!(matches!(
+            result_err_2,
+            Err(error::ServiceError::AlreadyExists(_))
+        ));
+
+        // Service accounts can't login
+        let result_1 = controller_user
+            .auth("ci-runner".into(), "".into(), None)
+            .await;
+        let result_2 = controller.list().await.unwrap();
+
+        assert!(matches!(result_1, Err(error::ServiceError::NotFound(_))));
+        assert_eq!(result_2.len(), 1);
+        assert_eq!(result_2[0].username, "ci-runner");
+
+        // Users are not service accounts
+        let result_3 = controller.delete("carl".into()).await;
+        let result_4 = controller.delete("ci-runner".into()).await;
+
+        assert!(matches!(result_3, Err(error::ServiceError::NotFound(_))));
+        assert!(result_4.is_ok());
+        assert_eq!(controller.list().await.unwrap().len(), 0);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn api_key(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let client = Arc::new(
+            client::DaemonClient::mocked(pool)
+                .await
+                .expect("could not create mocked client"),
+        );
+
+        let controller = ControllerServiceAccount::new(client.clone());
+        let controller_collection = ControllerCollection::new(client);
+
+        controller.add("ci-runner".into()).await.unwrap();
+
+        let collection = controller_collection
+            .add("project".into())
+            .await
+            .unwrap()
+            .uuid
+            .into_uuid();
+
+        let scopes = vec![(ScopeType::Collection as u32, Some(collection))];
+
+        let result_ok = controller
+            .add_key(
+                "ci-runner".into(),
+                "deploy".into(),
+                "reader".into(),
+                scopes.clone(),
+                Some(30),
+            )
+            .await;
+
+        // Duplicate name, missing scopes, unknown role and unknown collection
+        let result_err_1 = controller
+            .add_key(
+                "ci-runner".into(),
+                "deploy".into(),
+                "reader".into(),
+                scopes.clone(),
+                None,
+            )
+            .await;
+        let result_err_2 = controller
+            .add_key(
+                "ci-runner".into(),
+                "build".into(),
+                "reader".into(),
+                vec![],
+                None,
+            )
+            .await;
+        let result_err_3 = controller
+            .add_key(
+                "ci-runner".into(),
+                "build".into(),
+                "owner".into(),
+                scopes.clone(),
+                None,
+            )
+            .await;
+        let result_err_4 = controller
+            .add_key(
+                "ci-runner".into(),
+                "build".into(),
+                "reader".into(),
+                vec![(ScopeType::Collection as u32, Some(uuid::Uuid::new_v4()))],
+                None,
+            )
+            .await;
+
+        assert!(matches!(
+            result_err_1,
+            Err(error::ServiceError::AlreadyExists(_))
+        ));
+        assert!(matches!(
+            result_err_2,
+            Err(error::ServiceError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            result_err_3,
+            Err(error::ServiceError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            result_err_4,
+            Err(error::ServiceError::NotFound(_))
+        ));
+
+        let (api_key, token) = result_ok.unwrap();
+        let uuid = api_key.uuid.into_uuid();
+        let key = crypt::ApiKey::parse(&token).unwrap();
+
+        assert!(api_key.expires_at.is_some());
+
+        let keys = controller.list_keys("ci-runner".into()).await.unwrap();
+
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].1.len(), 1);
+        assert_eq!(keys[0].1[0].role, "reader");
+
+        // Only the current token of a key authenticates
+        let forged = crypt::ApiKey::generate(uuid);
+
+        assert_eq!(
+            controller.authenticate(&key).await.unwrap().username,
+            "ci-runner"
+        );
+        assert!(controller.authenticate(&forged).await.is_err());
+
+        let (_, token_rotated) = controller
+            .rotate_key("ci-runner".into(), uuid)
+            .await
+            .unwrap();
+        let key_rotated = crypt::ApiKey::parse(&token_rotated).unwrap();
+
+        assert!(controller.authenticate(&key).await.is_err());
+        assert!(controller.authenticate(&key_rotated).await.is_ok());
+
+        // Expired keys are rejected
+        let (_, token_expired) = controller
+            .add_key(
+                "ci-runner".into(),
+                "expired".into(),
+                "reader".into(),
+                scopes.clone(),
+                Some(0),
+            )
+            .await
+            .unwrap();
+        let key_expired = crypt::ApiKey::parse(&token_expired).unwrap();
+
+        assert!(matches!(
+            controller.authenticate(&key_expired).await,
+            Err(error::ServiceError::PermissionDenied(_))
+        ));
+
+        // Revoked keys and keys of deleted accounts are rejected
+        let result_1 = controller
+            .delete_key("ci-runner".into(), uuid::Uuid::new_v4())
+            .await;
+        let result_2 = controller.delete_key("ci-runner".into(), uuid).await;
+
+        assert!(matches!(result_1, Err(error::ServiceError::NotFound(_))));
+        assert!(result_2.is_ok());
+        assert!(controller.authenticate(&key_rotated).await.is_err());
+
+        let (_, token) = controller
+            .add_key(
+                "ci-runner".into(),
+                "deploy".into(),
+                "reader".into(),
+                scopes,
+                None,
+            )
+            .await
+            .unwrap();
+
+        controller.delete("ci-runner".into()).await.unwrap();
+
+        assert!(
+            controller
+                .authenticate(&crypt::ApiKey::parse(&token).unwrap())
+                .await
+                .is_err()
+        );
+
+        Ok(())
+    }
+}
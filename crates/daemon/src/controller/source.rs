@@ -0,0 +1,838 @@
+use crate::client::DaemonClient;
+use crate::{error, importer, model, schema};
+
+use shared_core::database;
+use std::sync::Arc;
+use validator::Validate;
+
+/// Source controller
+#[derive(Debug, Clone)]
+pub struct ControllerSource {
+    pub(crate) client: Arc<DaemonClient>,
+    pub(crate) encryption: super::ControllerEncryption,
+}
+
+impl ControllerSource {
+    pub fn new(client: Arc<DaemonClient>) -> Self {
+        let encryption = super::ControllerEncryption::new(client.clone());
+
+        Self { client, encryption }
+    }
+
+    /// Checks if source with name exists
+    pub async fn exists(&self, name: String) -> Result<bool, error::ServiceError> {
+        let result =
+            model::ModelSource::does_source_exist(self.client.get_database().get_pool(), name)
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    /// Get source from uuid.
+    pub async fn get(&self, uuid: uuid::Uuid) -> Result<schema::Source, error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+
+        if !model::ModelSource::does_source_exist_uuid(pool, uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(error::ServiceError::NotFound(format!(
+                "could not find source with uuid {}",
+                &uuid
+            )));
+        }
+
+        let source = model::ModelSource::get_source_from_uuid(pool, uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(source)
+    }
+
+    /// List sources sorted by name.
+    /// Only sources whose name contains `search` are listed and pages are only used if `limit` is set.
+    pub async fn list(
+        &self,
+        search: Option<String>,
+        cursor: Option<String>,
+        limit: Option<u64>,
+    ) -> Result<database::Page<schema::Source>, error::ServiceError> {
+        let query = super::list_query(schema::Source::COLUMN_NAME, search, cursor, limit);
+
+        let result = model::ModelSource::get_sources(self.client.get_database().get_pool(), query)
+            .await
+            .map_err(super::list_error)?;
+
+        Ok(result)
+    }
+
+    /// Register a new source.
+    pub async fn add(
+        &self,
+        name: String,
+        description: Option<String>,
+        source_type: u32,
+        source_config: Option<String>,
+        source_auth: Option<String>,
+        source_auth_type: u32,
+    ) -> Result<schema::Source, error::ServiceError> {
+        if self.exists(name.clone()).await? {
+            return Err(error::ServiceError::AlreadyExists(format!(
+                "source with name {} already exists",
+                &name
+            )));
+        }
+
+        if schema::SourceType::from(source_type) == schema::SourceType::Unknown {
+            return Err(error::ServiceError::InvalidArgument(format!(
+                "unknown source type {}",
+                source_type
+            )));
+        }
+
+        if let Some(source_config) = &source_config {
+            Self::validate_config(source_type.into(), source_config)?;
+        }
+
+        let data = schema::Source::new(
+            name,
+            description,
+            source_type,
+            source_config,
+            source_auth,
+            schema::SourceAuthType::from(source_auth_type) as u32,
+        )
+        .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        let source = model::ModelSource::add_source(self.client.get_database().get_pool(), data)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(source)
+    }
+
+    /// Update description, config or credentials of an existing source.
+    /// Only fields which are given are updated, everything else is left as is.
+    pub async fn update(
+        &self,
+        uuid: uuid::Uuid,
+        description: Option<String>,
+        source_config: Option<String>,
+        source_auth: Option<String>,
+        source_auth_type: Option<u32>,
+    ) -> Result<schema::Source, error::ServiceError> {
+        let current = self.get(uuid).await?;
+
+        if let Some(source_config) = &source_config {
+            Self::validate_config(current.source_type.into(), source_config)?;
+        }
+
+        let data = schema::Source {
+            description: description.or(current.description),
+            source_config: source_config.or(current.source_config),
+            source_auth: source_auth.or(current.source_auth),
+            source_auth_type: source_auth_type
+                .map(|x| schema::SourceAuthType::from(x) as u32)
+                .unwrap_or(current.source_auth_type),
+            ..current
+        };
+
+        data.validate()
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        let source = model::ModelSource::update_source(self.client.get_database().get_pool(), data)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(source)
+    }
+
+    /// Import secrets from an existing source.
+    ///
+    /// Secrets which are new are created, changed secrets are updated and secrets
+    /// which are no longer part of the source are removed. Nothing is written when
+    /// `dry_run` is set and the returned plan only reports what would change.
+    pub async fn import(
+        &self,
+        uuid: uuid::Uuid,
+        dry_run: bool,
+    ) -> Result<importer::ImportPlan, error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+        let source = self.get(uuid).await?;
+
+        let source_config = source
+            .source_config
+            .ok_or(error::ServiceError::InvalidArgument(format!(
+                "source {} has no config",
+                &uuid
+            )))?;
+
+        let entries = match schema::SourceType::from(source.source_type) {
+            schema::SourceType::Csv => {
+                let config = importer::CsvSourceConfig::from_json(&source_config)
+                    .map_err(|e| error::ServiceError::InvalidArgument(e.to_string()))?;
+
+                importer::CsvImporter::new(config)
+                    .read(self.client.get_import_directory())
+                    .await
+                    .map_err(|e| error::ServiceError::InvalidArgument(e.to_string()))?
+            }
+            schema::SourceType::Unknown => {
+                return Err(error::ServiceError::InvalidArgument(format!(
+                    "source {} has an unknown source type",
+                    &uuid
+                )));
+            }
+        };
+
+        let existing = model::ModelSource::get_source_secrets(pool, uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+        let existing = self.encryption.open_all(existing).await?;
+
+        let plan = importer::ImportPlan::new(existing, entries);
+
+        // Secret names are unique across all sources so new entries must not
+        // collide with secrets which belong to something else.
+        for entry in &plan.create {
+            if model::ModelSecret::does_secret_exist(pool, entry.name.clone())
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+            {
+                return Err(error::ServiceError::Conflict(format!(
+                    "secret with name {} already exists outside of source {}",
+                    &entry.name, &uuid
+                )));
+            }
+        }
+
+        if dry_run {
+            return Ok(plan);
+        }
+
+        // Imported values are encrypted with the data key of the collection the source is
+        // attached to. Secrets of sources attached to several collections or none don't belong
+        // to one collection, they use the default data key. It's looked up before the
+        // transaction as creating it on first use needs its own write.
+        let collections = model::ModelSource::get_source_collections(pool, uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        let uuid_collection = match collections.as_slice() {
+            [x] => Some(x.uuid_collection.into_uuid()),
+            _ => None,
+        };
+
+        let data_key = self.encryption.data_key(uuid_collection).await?;
+
+        // Everything is written in one transaction so a failing entry leaves the
+        // source as it was before the import.
+        let mut tx = self
+            .client
+            .get_database()
+            .begin()
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        for entry in &plan.create {
+            let data = schema::Secret::new(
+                entry.name.clone(),
+                entry.key.clone(),
+                entry.description.clone(),
+                entry.secret.clone(),
+                entry.secret_type,
+            )
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+            let secret = model::ModelSecret::add_secret(&mut *tx, data_key.seal(data)?)
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+            model::ModelSource::link_secret(&mut *tx, uuid, secret.uuid.into_uuid())
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+        }
+
+        for (uuid_secret, entry) in &plan.update {
+            let data = schema::Secret {
+                uuid: (*uuid_secret).into(),
+                name: entry.name.clone(),
+                key: entry.key.clone(),
+                description: entry.description.clone(),
+                secret: entry.secret.clone(),
+                secret_type: entry.secret_type,
+                uuid_data_key: None,
+            };
+
+            model::ModelSecret::update_secret(&mut *tx, data_key.seal(data)?)
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+        }
+
+        for secret in &plan.delete {
+            model::ModelSource::unlink_secret(&mut *tx, uuid, secret.uuid.into_uuid())
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        tracing::info!(
+            "imported source {}: {} created, {} updated, {} deleted, {} unchanged",
+            &uuid,
+            plan.create.len(),
+            plan.update.len(),
+            plan.delete.len(),
+            plan.unchanged
+        );
+
+        Ok(plan)
+    }
+
+    /// Make sure source config can be used with the source type.
+    fn validate_config(
+        source_type: schema::SourceType,
+        source_config: &str,
+    ) -> Result<(), error::ServiceError> {
+        match source_type {
+            schema::SourceType::Csv => {
+                importer::CsvSourceConfig::from_json(source_config)
+                    .map_err(|e| error::ServiceError::InvalidArgument(e.to_string()))?;
+            }
+            schema::SourceType::Unknown => {}
+        }
+
+        Ok(())
+    }
+
+    /// Delete an existing source.
+    ///
+    /// Without cascade, a source which is attached to a collection or still owns secrets
+    /// is refused. With cascade, the source is detached from all collections and every
+    /// secret only owned by the source is deleted with it.
+    pub async fn delete(&self, uuid: uuid::Uuid, cascade: bool) -> Result<(), error::ServiceError> {
+        self.get(uuid).await?;
+
+        let mut tx = self
+            .client
+            .get_database()
+            .begin()
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        if cascade {
+            model::ModelSource::detach_collections(&mut *tx, uuid)
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+            model::ModelSource::delete_source_secrets(&mut *tx, uuid)
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+        } else {
+            let collections = model::ModelSource::get_collection_count(&mut *tx, uuid)
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+            let secrets = model::ModelSource::get_source_secrets(&mut *tx, uuid)
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+            if collections > 0 || !secrets.is_empty() {
+                return Err(error::ServiceError::Conflict(format!(
+                    "source {} is attached to {} collection(s) and owns {} secret(s)",
+                    &uuid,
+                    collections,
+                    secrets.len()
+                )));
+            }
+        }
+
+        model::ModelSource::delete_source(&mut *tx, uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::controller::{ControllerCollection, ControllerSource};
+    use crate::model::{ModelDataKey, ModelSecret, ModelSource};
+    use crate::schema::{Secret, SecretType, SourceAuthType, SourceType};
+    use crate::{client, error};
+
+    use shared_core::rng;
+    use sqlx::sqlite;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    #[sqlx::test]
+    async fn add(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let client = client::DaemonClient::mocked(pool)
+            .await
+            .expect("could not create mocked client");
+
+        let controller = ControllerSource::new(Arc::new(client));
+
+        let result_ok = controller
+            .add(
+                "passwords".into(),
+                None,
+                SourceType::Csv as u32,
+                None,
+                None,
+                SourceAuthType::None as u32,
+            )
+            .await;
+
+        // Duplicate entry should error
+        let result_err_1 = controller
+            .add(
+                "passwords".into(),
+                None,
+                SourceType::Csv as u32,
+                None,
+                None,
+                SourceAuthType::None as u32,
+            )
+            .await;
+
+        // Unknown source type
+        let result_err_2 = controller
+            .add(
+                "passwords-2".into(),
+                None,
+                SourceType::Unknown as u32,
+                None,
+                None,
+                SourceAuthType::None as u32,
+            )
+            .await;
+
+        assert!(result_ok.is_ok());
+        assert!(result_err_1.is_err());
+        assert!(result_err_2.is_err());
+
+        let result_list = controller.list(None, None, None).await;
+
+        assert!(result_list.is_ok());
+        assert_eq!(result_list.unwrap().items.len(), 1);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn update(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let client = client::DaemonClient::mocked(pool)
+            .await
+            .expect("could not create mocked client");
+
+        let controller = ControllerSource::new(Arc::new(client));
+
+        let result_add = controller
+            .add(
+                "passwords".into(),
+                Some("exported passwords".into()),
+                SourceType::Csv as u32,
+                None,
+                None,
+                SourceAuthType::None as u32,
+            )
+            .await;
+
+        assert!(result_add.is_ok());
+
+        let uuid = result_add.unwrap().uuid.into_uuid();
+
+        let result_1 = controller
+            .update(
+                uuid,
+                None,
+                None,
+                Some("hunter2".into()),
+                Some(SourceAuthType::Cipher as u32),
+            )
+            .await;
+
+        assert!(result_1.is_ok());
+
+        let result_1 = result_1.unwrap();
+
+        assert_eq!(result_1.description, Some("exported passwords".into()));
+        assert_eq!(result_1.source_auth, Some("hunter2".into()));
+        assert_eq!(result_1.source_auth_type, SourceAuthType::Cipher as u32);
+
+        let result_2 = controller
+            .update(uuid::Uuid::new_v4(), None, None, None, None)
+            .await;
+
+        assert!(result_2.is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn delete(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let client = client::DaemonClient::mocked(pool.clone())
+            .await
+            .expect("could not create mocked client");
+
+        let client = Arc::new(client);
+        let controller = ControllerSource::new(client.clone());
+        let controller_collection = ControllerCollection::new(client);
+
+        let result_add = controller
+            .add(
+                "passwords".into(),
+                None,
+                SourceType::Csv as u32,
+                None,
+                None,
+                SourceAuthType::None as u32,
+            )
+            .await;
+
+        assert!(result_add.is_ok());
+
+        let uuid = result_add.unwrap().uuid.into_uuid();
+
+        let secret = Secret::new("secret", None, None, "123", SecretType::Cipher as u32).unwrap();
+        let secret = ModelSecret::add_secret(&pool, secret).await.unwrap();
+
+        ModelSource::link_secret(&pool, uuid, secret.uuid.into_uuid())
+            .await
+            .unwrap();
+
+        let collection = controller_collection.add("project".into()).await.unwrap();
+
+        controller_collection
+            .attach(collection.uuid.into_uuid(), uuid)
+            .await
+            .unwrap();
+
+        // Source is referenced so delete without cascade is refused
+        let result_1 = controller.delete(uuid, false).await;
+
+        assert!(result_1.is_err());
+
+        let result_2 = controller.delete(uuid, true).await;
+        let result_3 = controller.delete(uuid, true).await;
+
+        assert!(result_2.is_ok());
+        assert!(result_3.is_err());
+
+        let result_4 = ModelSecret::does_secret_exist(&pool, "secret".into()).await;
+        let result_5 = controller_collection
+            .sources(collection.uuid.into_uuid())
+            .await;
+
+        assert!(!result_4.unwrap());
+        assert!(result_5.unwrap().is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn import(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let client = client::DaemonClient::mocked(pool)
+            .await
+            .expect("could not create mocked client");
+
+        let controller = ControllerSource::new(Arc::new(client));
+
+        // Create random file in test data folder
+        let csv_path = PathBuf::from(env!("WORKSPACE_DIR"))
+            .join("test-data")
+            .join("temp")
+            .join(format!("{}.csv", rng::random_bytes_str(10)));
+
+        let data_1 = "name,secret,type
+github-token,ghp_123,key
+database,hunter2,cipher
+legacy,abc,cipher";
+
+        assert!(tokio::fs::write(&csv_path, data_1).await.is_ok());
+
+        let source_config = format!(r#"{{"path": {:?}}}"#, csv_path);
+
+        // Config must be valid for the source type
+        let result_err_1 = controller
+            .add(
+                "passwords".into(),
+                None,
+                SourceType::Csv as u32,
+                Some(r#"{"delimiter": ";"}"#.into()),
+                None,
+                SourceAuthType::None as u32,
+            )
+            .await;
+
+        assert!(result_err_1.is_err());
+
+        let source = controller
+            .add(
+                "passwords".into(),
+                None,
+                SourceType::Csv as u32,
+                Some(source_config),
+                None,
+                SourceAuthType::None as u32,
+            )
+            .await
+            .unwrap();
+
+        let uuid = source.uuid.into_uuid();
+
+        // Dry run should not write anything
+        let result_1 = controller.import(uuid, true).await;
+
+        assert!(result_1.is_ok());
+        assert_eq!(result_1.unwrap().create.len(), 3);
+
+        let result_2 =
+            ModelSource::get_source_secrets(controller.client.get_database().get_pool(), uuid)
+                .await;
+
+        assert!(result_2.unwrap().is_empty());
+
+        let result_3 = controller.import(uuid, false).await;
+
+        assert!(result_3.is_ok());
+        assert_eq!(result_3.unwrap().create.len(), 3);
+
+        // Change one entry and remove another
+        let data_2 = "name,secret,type
+github-token,ghp_456,key
+database,hunter2,cipher";
+
+        assert!(tokio::fs::write(&csv_path, data_2).await.is_ok());
+
+        let result_4 = controller.import(uuid, false).await;
+
+        assert!(tokio::fs::remove_file(&csv_path).await.is_ok());
+        assert!(result_4.is_ok());
+
+        let result_4 = result_4.unwrap();
+
+        assert_eq!(result_4.create.len(), 0);
+        assert_eq!(result_4.update.len(), 1);
+        assert_eq!(result_4.delete.len(), 1);
+        assert_eq!(result_4.unchanged, 1);
+
+        let pool = controller.client.get_database().get_pool();
+
+        let result_5 = ModelSecret::get_secret_from_name(pool, "github-token".into())
+            .await
+            .unwrap();
+        let result_6 = ModelSecret::does_secret_exist(pool, "legacy".into()).await;
+
+        // Imported values are stored encrypted
+        assert_ne!(result_5.secret, "ghp_456");
+        assert_eq!(
+            controller.encryption.open(result_5).await.unwrap().secret,
+            "ghp_456"
+        );
+        assert!(!result_6.unwrap());
+
+        // Missing file should error
+        let result_err_2 = controller.import(uuid, false).await;
+
+        assert!(result_err_2.is_err());
+
+        // Valid files outside of the import directory are never read
+        let csv_path_outside = PathBuf::from(env!("WORKSPACE_DIR"))
+            .join("test-data")
+            .join(format!("{}.csv", rng::random_bytes_str(10)));
+
+        assert!(tokio::fs::write(&csv_path_outside, data_2).await.is_ok());
+
+        let source = controller
+            .add(
+                "outside".into(),
+                None,
+                SourceType::Csv as u32,
+                Some(format!(r#"{{"path": {:?}}}"#, csv_path_outside)),
+                None,
+                SourceAuthType::None as u32,
+            )
+            .await
+            .unwrap();
+
+        let result_err_3 = controller.import(source.uuid.into_uuid(), true).await;
+
+        assert!(tokio::fs::remove_file(&csv_path_outside).await.is_ok());
+
+        assert!(matches!(
+            result_err_3,
+            Err(error::ServiceError::InvalidArgument(_))
+        ));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn import_data_key(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let client = Arc::new(
+            client::DaemonClient::mocked(pool)
+                .await
+                .expect("could not create mocked client"),
+        );
+
+        let controller = ControllerSource::new(client.clone());
+        let controller_collection = ControllerCollection::new(client);
+
+        let uuid_collection_1 = controller_collection
+            .add("project-1".into())
+            .await
+            .unwrap()
+            .uuid
+            .into_uuid();
+        let uuid_collection_2 = controller_collection
+            .add("project-2".into())
+            .await
+            .unwrap()
+            .uuid
+            .into_uuid();
+
+        let mut csv_paths = Vec::new();
+        let mut uuid_sources = Vec::new();
+
+        for name in ["project", "shared"] {
+            let csv_path = PathBuf::from(env!("WORKSPACE_DIR"))
+                .join("test-data")
+                .join("temp")
+                .join(format!("{}.csv", rng::random_bytes_str(10)));
+
+            let data = format!("name,secret\n{}-token,123", name);
+
+            assert!(tokio::fs::write(&csv_path, data).await.is_ok());
+
+            let source = controller
+                .add(
+                    name.into(),
+                    None,
+                    SourceType::Csv as u32,
+                    Some(format!(r#"{{"path": {:?}}}"#, csv_path)),
+                    None,
+                    SourceAuthType::None as u32,
+                )
+                .await
+                .unwrap();
+
+            csv_paths.push(csv_path);
+            uuid_sources.push(source.uuid.into_uuid());
+        }
+
+        // First source belongs to one collection, the second one is shared by both
+        for (uuid_collection, uuid_source) in [
+            (uuid_collection_1, uuid_sources[0]),
+            (uuid_collection_1, uuid_sources[1]),
+            (uuid_collection_2, uuid_sources[1]),
+        ] {
+            controller_collection
+                .attach(uuid_collection, uuid_source)
+                .await
+                .unwrap();
+        }
+
+        let result_1 = controller.import(uuid_sources[0], false).await;
+        let result_2 = controller.import(uuid_sources[1], false).await;
+
+        for csv_path in csv_paths {
+            assert!(tokio::fs::remove_file(&csv_path).await.is_ok());
+        }
+
+        assert!(result_1.is_ok());
+        assert!(result_2.is_ok());
+
+        let pool = controller.client.get_database().get_pool();
+
+        let secret_1 = ModelSecret::get_secret_from_name(pool, "project-token".into())
+            .await
+            .unwrap();
+        let secret_2 = ModelSecret::get_secret_from_name(pool, "shared-token".into())
+            .await
+            .unwrap();
+
+        let data_key_1 = ModelDataKey::get_collection_data_key(pool, Some(uuid_collection_1))
+            .await
+            .unwrap();
+        let data_key_2 = ModelDataKey::get_collection_data_key(pool, None)
+            .await
+            .unwrap();
+
+        assert_eq!(secret_1.uuid_data_key, data_key_1.map(|x| x.uuid));
+        assert_eq!(secret_2.uuid_data_key, data_key_2.map(|x| x.uuid));
+        assert_ne!(secret_1.uuid_data_key, secret_2.uuid_data_key);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn import_rollback(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        // Make linking one of the secrets fail halfway through the import
+        sqlx::query(
+            "CREATE TRIGGER fail_link BEFORE INSERT ON source_secrets
+                WHEN (SELECT name FROM secrets WHERE uuid = NEW.uuid_secret) = 'database'
+                BEGIN SELECT RAISE(ABORT, 'link failed'); END",
+        )
+        .execute(&pool)
+        .await?;
+
+        let client = client::DaemonClient::mocked(pool)
+            .await
+            .expect("could not create mocked client");
+
+        let controller = ControllerSource::new(Arc::new(client));
+
+        // Create random file in test data folder
+        let csv_path = PathBuf::from(env!("WORKSPACE_DIR"))
+            .join("test-data")
+            .join("temp")
+            .join(format!("{}.csv", rng::random_bytes_str(10)));
+
+        let data = "name,secret,type
+github-token,ghp_123,key
+database,hunter2,cipher";
+
+        assert!(tokio::fs::write(&csv_path, data).await.is_ok());
+
+        let source = controller
+            .add(
+                "passwords".into(),
+                None,
+                SourceType::Csv as u32,
+                Some(format!(r#"{{"path": {:?}}}"#, csv_path)),
+                None,
+                SourceAuthType::None as u32,
+            )
+            .await
+            .unwrap();
+
+        let uuid = source.uuid.into_uuid();
+
+        let result_err_1 = controller.import(uuid, false).await;
+
+        assert!(tokio::fs::remove_file(&csv_path).await.is_ok());
+        assert!(result_err_1.is_err());
+
+        // Secret created before the failure should be rolled back
+        let pool = controller.client.get_database().get_pool();
+
+        let result_1 = ModelSecret::does_secret_exist(pool, "github-token".into()).await;
+        let result_2 = ModelSource::get_source_secrets(pool, uuid).await;
+
+        assert!(!result_1.unwrap());
+        assert!(result_2.unwrap().is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,237 @@
+use crate::client::DaemonClient;
+use crate::config::{ConfigManager, LoginConfig};
+use crate::{error, model, schema};
+
+use std::sync::Arc;
+
+/// Login throttle controller
+///
+/// Tracks failed logins so brute forcing passwords gets slower with every failure until the
+/// username or peer address is locked.
+#[derive(Debug, Clone)]
+pub struct ControllerThrottle {
+    pub(crate) config: Arc<ConfigManager>,
+    pub(crate) client: Arc<DaemonClient>,
+}
+
+impl ControllerThrottle {
+    pub fn new(config: Arc<ConfigManager>, client: Arc<DaemonClient>) -> Self {
+        Self { config, client }
+    }
+
+    /// Count a login attempt of subjects as failed until a successful login resets them.
+    ///
+    /// Locked subjects get `Locked`, subjects still waiting for their backoff get
+    /// `TooManyRequests`, refused attempts aren't counted. Subjects are checked and counted in one
+    /// transaction so concurrent attempts can't get past the limits.
+    pub async fn attempt(
+        &self,
+        subjects: &[schema::LoginSubject],
+    ) -> Result<(), error::ServiceError> {
+        let config = self.config.config.read().await.login.clone();
+        let now = chrono::Utc::now().naive_utc();
+
+        let mut tx = self
+            .client
+            .get_database()
+            .begin()
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        for subject in subjects {
+            let limits = Self::limits(&config, subject);
+
+            let failure =
+                model::ModelLoginFailure::add_login_attempt(&mut *tx, subject.clone(), now, limits)
+                    .await
+                    .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+            match failure {
+                Some(x) if x.failures == limits.max_failures => {
+                    tracing::warn!(
+                        "locked {} {} after {} login attempts",
+                        subject.kind(),
+                        subject.name(),
+                        x.failures
+                    );
+                }
+                Some(_) => {}
+                None => {
+                    let failure =
+                        model::ModelLoginFailure::get_login_failure(&mut *tx, subject.clone())
+                            .await
+                            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+                    return Err(Self::refused(&config, subject, failure, now));
+                }
+            }
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        // Forget old failures so unknown usernames don't pile up.
+        model::ModelLoginFailure::purge_login_failures(
+            self.client.get_database().get_pool(),
+            now,
+            now - Self::duration(config.reset_secs),
+        )
+        .await
+        .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Forget failed logins of subject after a successful login or when unlocked.
+    /// Returns true if the subject had failed logins.
+    pub async fn reset(&self, subject: schema::LoginSubject) -> Result<bool, error::ServiceError> {
+        let result = model::ModelLoginFailure::delete_login_failure(
+            self.client.get_database().get_pool(),
+            subject,
+        )
+        .await
+        .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    /// Error of an attempt refused because the subject is locked or has to wait for its backoff.
+    fn refused(
+        config: &LoginConfig,
+        subject: &schema::LoginSubject,
+        failure: schema::LoginFailure,
+        now: chrono::NaiveDateTime,
+    ) -> error::ServiceError {
+        if let Some(locked_for) = failure.locked_for(now) {
+            return error::ServiceError::Locked(format!(
+                "too many failed logins, {} is locked for {} seconds",
+                subject.kind(),
+                locked_for.num_seconds() + 1
+            ));
+        }
+
+        let retry_at = failure.last_failed_at + Self::backoff(config, failure.failures);
+
+        error::ServiceError::TooManyRequests(format!(
+            "too many failed logins, retry in {} seconds",
+            (retry_at - now).num_seconds().max(0) + 1
+        ))
+    }
+
+    /// Limits of subject from config.
+    fn limits(config: &LoginConfig, subject: &schema::LoginSubject) -> schema::LoginLimits {
+        schema::LoginLimits {
+            max_failures: match subject {
+                schema::LoginSubject::Username(_) => config.max_failures_username,
+                schema::LoginSubject::Peer(_) => config.max_failures_peer,
+            },
+            backoff: Self::duration(config.backoff_secs),
+            backoff_max: Self::duration(config.backoff_max_secs),
+            lockout: Self::duration(config.lockout_secs),
+            reset: Self::duration(config.reset_secs),
+        }
+    }
+
+    /// Delay after failed logins, doubled for every failure.
+    fn backoff(config: &LoginConfig, failures: u32) -> chrono::Duration {
+        let factor = 1u64
+            .checked_shl(failures.saturating_sub(1))
+            .unwrap_or(u64::MAX);
+
+        Self::duration(
+            config
+                .backoff_secs
+                .saturating_mul(factor)
+                .min(config.backoff_max_secs),
+        )
+    }
+
+    fn duration(secs: u64) -> chrono::Duration {
+        chrono::Duration::seconds(secs.try_into().unwrap_or(i64::MAX))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::LoginConfig;
+    use crate::controller::ControllerThrottle;
+    use crate::model::ModelLoginFailure;
+    use crate::schema::LoginSubject;
+    use crate::{client, config, error};
+
+    use sqlx::sqlite;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn backoff() {
+        let config = LoginConfig {
+            backoff_secs: 2,
+            backoff_max_secs: 30,
+            ..Default::default()
+        };
+
+        let result =
+            [0, 1, 2, 3, 4, 5, 100].map(|x| ControllerThrottle::backoff(&config, x).num_seconds());
+
+        assert_eq!(result, [2, 2, 4, 8, 16, 30, 30]);
+    }
+
+    #[sqlx::test]
+    async fn attempt(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let config = Arc::new(config::ConfigManager::mocked());
+        let client = Arc::new(
+            client::DaemonClient::mocked(pool)
+                .await
+                .expect("could not create mocked client"),
+        );
+
+        let controller = ControllerThrottle::new(config.clone(), client.clone());
+        let pool = client.get_database().get_pool();
+
+        let carl = LoginSubject::Username("carl".into());
+        let peer = LoginSubject::Peer("10.0.0.1".parse().unwrap());
+        let subjects = [carl.clone(), peer.clone()];
+
+        assert!(controller.attempt(&subjects).await.is_ok());
+
+        // Attempts have to wait for the backoff, refused attempts aren't counted
+        assert!(matches!(
+            controller.attempt(&subjects).await,
+            Err(error::ServiceError::TooManyRequests(_))
+        ));
+
+        let failure = ModelLoginFailure::get_login_failure(pool, peer.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(failure.failures, 1);
+
+        // Without backoff attempts are only refused once locked
+        config.config.write().await.login.backoff_secs = 0;
+
+        for _ in 1..5 {
+            assert!(controller.attempt(&subjects).await.is_ok());
+        }
+
+        let result_1 = controller.attempt(&subjects).await;
+        let result_2 = controller.attempt(std::slice::from_ref(&peer)).await;
+
+        assert!(matches!(result_1, Err(error::ServiceError::Locked(_))));
+        assert!(result_2.is_ok());
+
+        // Attempts refused for one subject aren't counted for the others
+        let failure = ModelLoginFailure::get_login_failure(pool, peer.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(failure.failures, 6);
+
+        // Unlocking forgets the failures of the subject only
+        assert!(controller.reset(carl.clone()).await.unwrap());
+        assert!(!controller.reset(carl.clone()).await.unwrap());
+        assert!(controller.attempt(&subjects).await.is_ok());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,378 @@
+use crate::client::DaemonClient;
+use crate::{error, model, schema};
+
+use shared_core::crypt;
+use std::sync::Arc;
+
+/// Issuer shown by authenticator apps.
+const TOTP_ISSUER: &str = "my-vault";
+
+/// Number of recovery codes created when TOTP is enabled.
+const RECOVERY_CODE_COUNT: usize = 10;
+
+/// TOTP controller
+///
+/// Users enroll a TOTP secret and confirm it with a code, from then on logins need a TOTP code
+/// or one of the recovery codes returned when confirming.
+#[derive(Debug, Clone)]
+pub struct ControllerTotp {
+    pub(crate) client: Arc<DaemonClient>,
+}
+
+impl ControllerTotp {
+    pub fn new(client: Arc<DaemonClient>) -> Self {
+        Self { client }
+    }
+
+    /// Checks if TOTP of caller is enabled and counts unused recovery codes.
+    pub async fn status(&self, caller: uuid::Uuid) -> Result<(bool, u64), error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+
+        let enabled = self.get(caller).await?.is_some_and(|x| x.enabled);
+        let recovery_codes = model::ModelTotp::count_recovery_codes(pool, caller)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok((enabled, recovery_codes))
+    }
+
+    /// Create new TOTP secret of caller, returns the base32 secret and its `otpauth://` URI.
+    /// TOTP is only enabled once confirmed with a code, enrolling again replaces the secret.
+    pub async fn enroll(
+        &self,
+        caller: uuid::Uuid,
+    ) -> Result<(String, String), error::ServiceError> {
+        let user = self.get_user(caller).await?;
+
+        if self.get(caller).await?.is_some_and(|x| x.enabled) {
+            return Err(error::ServiceError::Conflict(
+                "totp is already enabled, disable it first".into(),
+            ));
+        }
+
+        let totp = crypt::Totp::generate();
+
+        model::ModelTotp::add_totp(
+            self.client.get_database().get_pool(),
+            schema::Totp::new(caller, &totp),
+        )
+        .await
+        .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok((totp.to_base32(), totp.uri(TOTP_ISSUER, &user.username)))
+    }
+
+    /// Enable TOTP of caller with a code of the enrolled secret.
+    /// Returns the recovery codes, they are only shown once.
+    pub async fn confirm(
+        &self,
+        caller: uuid::Uuid,
+        code: String,
+    ) -> Result<Vec<String>, error::ServiceError> {
+        let user = self.get_user(caller).await?;
+
+        let totp = match self.get(caller).await? {
+            Some(x) if x.enabled => {
+                return Err(error::ServiceError::Conflict(
+                    "totp is already enabled".into(),
+                ));
+            }
+            Some(x) => x,
+            None => {
+                return Err(error::ServiceError::NotFound(
+                    "no totp enrollment to confirm, enroll first".into(),
+                ));
+            }
+        };
+
+        let step = Self::secret(&totp)?
+            .verify(&code, Self::timestamp())
+            .ok_or(error::ServiceError::InvalidArgument(
+                "incorrect totp code".into(),
+            ))?;
+
+        let codes: Vec<_> = (0..RECOVERY_CODE_COUNT)
+            .map(|_| crypt::RecoveryCode::generate())
+            .collect();
+
+        let rows = codes
+            .iter()
+            .map(|x| schema::RecoveryCode::new(caller, x))
+            .collect();
+
+        if !model::ModelTotp::enable_totp(self.client.get_database().get_pool(), caller, step, rows)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(error::ServiceError::Conflict(
+                "totp is already enabled".into(),
+            ));
+        }
+
+        tracing::info!("enabled totp of user {}", &user.username);
+
+        Ok(codes.iter().map(|x| x.to_string()).collect())
+    }
+
+    /// Disable TOTP of caller, a TOTP or recovery code must be given.
+    pub async fn disable(
+        &self,
+        caller: uuid::Uuid,
+        code: String,
+    ) -> Result<(), error::ServiceError> {
+        let user = self.get_user(caller).await?;
+
+        if !self.get(caller).await?.is_some_and(|x| x.enabled) {
+            return Err(error::ServiceError::NotFound("totp is not enabled".into()));
+        }
+
+        self.verify(caller, Some(code)).await?;
+        self.reset(caller).await?;
+
+        tracing::info!("disabled totp of user {}", &user.username);
+
+        Ok(())
+    }
+
+    /// Remove TOTP secret and recovery codes of user without a code.
+    /// Returns true if the user had a TOTP secret.
+    pub async fn reset(&self, uuid_user: uuid::Uuid) -> Result<bool, error::ServiceError> {
+        let result =
+            model::ModelTotp::delete_totp(self.client.get_database().get_pool(), uuid_user)
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    /// Make sure a valid code is given if user has TOTP enabled.
+    ///
+    /// Codes are either the current TOTP code or a recovery code, both can only be used once.
+    /// A missing code is `Unauthorized`, an incorrect code `PermissionDenied`.
+    pub async fn verify(
+        &self,
+        uuid_user: uuid::Uuid,
+        code: Option<String>,
+    ) -> Result<(), error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+
+        let Some(totp) = self.get(uuid_user).await?.filter(|x| x.enabled) else {
+            return Ok(());
+        };
+
+        let Some(code) = code.filter(|x| !x.trim().is_empty()) else {
+            return Err(error::ServiceError::Unauthorized(
+                "totp code or recovery code required".into(),
+            ));
+        };
+
+        let step = Self::secret(&totp)?.verify(&code, Self::timestamp());
+
+        let result = match step {
+            Some(step) => model::ModelTotp::use_totp_step(pool, uuid_user, step).await,
+            None => {
+                let code_hash = crypt::RecoveryCode::parse(&code).hash();
+
+                model::ModelTotp::use_recovery_code(pool, uuid_user, code_hash)
+                    .await
+                    .inspect(|x| {
+                        if *x {
+                            tracing::warn!("user {} used a recovery code", uuid_user);
+                        }
+                    })
+            }
+        }
+        .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        if !result {
+            return Err(error::ServiceError::PermissionDenied(
+                "incorrect totp code or recovery code".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    async fn get(
+        &self,
+        uuid_user: uuid::Uuid,
+    ) -> Result<Option<schema::Totp>, error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+
+        if !model::ModelTotp::does_totp_exist(pool, uuid_user)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Ok(None);
+        }
+
+        let result = model::ModelTotp::get_totp(pool, uuid_user)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(Some(result))
+    }
+
+    /// Get user which can use TOTP, service accounts only authenticate with api keys.
+    async fn get_user(&self, uuid_user: uuid::Uuid) -> Result<schema::User, error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+
+        if !model::ModelUser::does_user_exist_uuid(pool, uuid_user)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(error::ServiceError::NotFound(format!(
+                "could not find user with uuid {}",
+                &uuid_user
+            )));
+        }
+
+        let user = model::ModelUser::get_user_from_uuid(pool, uuid_user)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        if user.is_service() {
+            return Err(error::ServiceError::InvalidArgument(
+                "service accounts can't use totp".into(),
+            ));
+        }
+
+        Ok(user)
+    }
+
+    fn secret(totp: &schema::Totp) -> Result<crypt::Totp, error::ServiceError> {
+        crypt::Totp::from_base32(&totp.secret)
+            .ok_or(error::ServiceError::Internal("invalid totp secret".into()))
+    }
+
+    fn timestamp() -> u64 {
+        chrono::Utc::now()
+            .timestamp()
+            .try_into()
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::controller::{ControllerTotp, ControllerUser};
+    use crate::{client, config, error, model};
+
+    use shared_core::crypt;
+    use sqlx::sqlite;
+    use std::sync::Arc;
+
+    /// Current code of base32 secret.
+    fn code(secret: &str, offset: i64) -> String {
+        let totp = crypt::Totp::from_base32(secret).unwrap();
+        let timestamp = chrono::Utc::now().timestamp() + offset;
+
+        totp.code(crypt::Totp::step(timestamp as u64))
+    }
+
+    #[sqlx::test]
+    async fn enroll(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let config = Arc::new(config::ConfigManager::mocked());
+        let client = Arc::new(
+            client::DaemonClient::mocked(pool)
+                .await
+                .expect("could not create mocked client"),
+        );
+
+        let controller = ControllerTotp::new(client.clone());
+        let controller_user = ControllerUser::new(config, client);
+
+        controller_user
+            .add("carl".into(), "carl-loves-cars1234".into())
+            .await
+            .unwrap();
+
+        let caller = model::ModelUser::get_user_from_username(
+            controller.client.get_database().get_pool(),
+            "carl".into(),
+        )
+        .await
+        .unwrap()
+        .uuid
+        .into_uuid();
+
+        // Users without TOTP don't need a code
+        assert!(controller.verify(caller, None).await.is_ok());
+
+        let (secret, uri) = controller.enroll(caller).await.unwrap();
+
+        assert!(uri.starts_with("otpauth://totp/my-vault:carl?secret="));
+        assert!(uri.contains(&secret));
+
+        // Not enabled until confirmed
+        assert!(controller.verify(caller, None).await.is_ok());
+        assert_eq!(controller.status(caller).await.unwrap(), (false, 0));
+
+        let result_err = controller.confirm(caller, "000000".into()).await;
+        let result_ok = controller.confirm(caller, code(&secret, -30)).await;
+
+        assert!(matches!(
+            result_err,
+            Err(error::ServiceError::InvalidArgument(_))
+        ));
+
+        let recovery_codes = result_ok.unwrap();
+
+        assert_eq!(recovery_codes.len(), 10);
+        assert_eq!(controller.status(caller).await.unwrap(), (true, 10));
+        assert!(matches!(
+            controller.enroll(caller).await,
+            Err(error::ServiceError::Conflict(_))
+        ));
+
+        // Codes are required and can't be replayed
+        let result_1 = controller.verify(caller, None).await;
+        let result_2 = controller.verify(caller, Some(code(&secret, -30))).await;
+        let result_3 = controller.verify(caller, Some(code(&secret, 0))).await;
+        let result_4 = controller.verify(caller, Some(code(&secret, 0))).await;
+
+        assert!(matches!(
+            result_1,
+            Err(error::ServiceError::Unauthorized(_))
+        ));
+        assert!(matches!(
+            result_2,
+            Err(error::ServiceError::PermissionDenied(_))
+        ));
+        assert!(result_3.is_ok());
+        assert!(matches!(
+            result_4,
+            Err(error::ServiceError::PermissionDenied(_))
+        ));
+
+        // Recovery codes are used once
+        let recovery_code = recovery_codes[0].to_uppercase();
+
+        assert!(
+            controller
+                .verify(caller, Some(recovery_code.clone()))
+                .await
+                .is_ok()
+        );
+        assert!(
+            controller
+                .verify(caller, Some(recovery_code))
+                .await
+                .is_err()
+        );
+        assert_eq!(controller.status(caller).await.unwrap(), (true, 9));
+
+        // Disabling needs a code
+        assert!(controller.disable(caller, "".into()).await.is_err());
+        assert!(
+            controller
+                .disable(caller, recovery_codes[1].clone())
+                .await
+                .is_ok()
+        );
+        assert!(controller.verify(caller, None).await.is_ok());
+        assert_eq!(controller.status(caller).await.unwrap(), (false, 0));
+
+        Ok(())
+    }
+}
@@ -1,9 +1,10 @@
 use crate::client::DaemonClient;
-use crate::config::ConfigManager;
-use crate::{error, model, schema};
+use crate::config::{ConfigManager, RegistrationPolicy};
+use crate::{error, middleware, model, schema};
 
 use shared_core::crypt::JwtFactoryMetadata;
-use shared_core::{crypt, rng};
+use shared_core::{crypt, database, rng};
+use std::net::IpAddr;
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -12,11 +13,24 @@ use std::sync::Arc;
 pub struct ControllerUser {
     pub(crate) config: Arc<ConfigManager>,
     pub(crate) client: Arc<DaemonClient>,
+    pub(crate) throttle: super::ControllerThrottle,
+    pub(crate) totp: super::ControllerTotp,
+    /// Hash unknown users are verified against, made with the configured argon2 parameters.
+    dummy_hash: Arc<tokio::sync::Mutex<Option<String>>>,
 }
 
 impl ControllerUser {
     pub fn new(config: Arc<ConfigManager>, client: Arc<DaemonClient>) -> Self {
-        Self { config, client }
+        let throttle = super::ControllerThrottle::new(config.clone(), client.clone());
+        let totp = super::ControllerTotp::new(client.clone());
+
+        Self {
+            config,
+            client,
+            throttle,
+            totp,
+            dummy_hash: Arc::default(),
+        }
     }
 
     /// Checks if user with username exists
@@ -31,87 +45,382 @@ impl ControllerUser {
     }
 
     /// Authenticate a given user.
+    /// Users with TOTP enabled also need a TOTP code or recovery code.
     pub async fn auth(
         &self,
         username: String,
         password: String,
+        code: Option<String>,
+    ) -> Result<(String, String), error::ServiceError> {
+        // Fetch user to get the password hash, service accounts only authenticate with api keys.
+        let user = match self.exists(username.clone()).await? {
+            true => Some(
+                model::ModelUser::get_user_from_username(
+                    self.client.get_database().get_pool(),
+                    username,
+                )
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?,
+            ),
+            false => None,
+        }
+        .filter(|x| !x.is_service());
+
+        // Check if passwords match, unknown users are checked against a dummy hash so the time
+        // taken doesn't tell which usernames exist.
+        let argon = self.argon2().await?;
+        let password_hash = match &user {
+            Some(x) => x.password_hash.clone(),
+            None => self.dummy_hash(&argon).await?,
+        };
+
+        let verified = argon
+            .verify(&password_hash, password.as_bytes())
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        let user = match user {
+            Some(x) if verified => x,
+            _ => {
+                return Err(error::ServiceError::NotFound(
+                    "could not find user, make sure username and password are correct".to_string(),
+                ));
+            }
+        };
+
+        // Second factor is only checked once the password is correct.
+        self.totp.verify(user.uuid.into_uuid(), code).await?;
+
+        // Upgrade hashes made with weaker parameters or another pepper than currently configured,
+        // the password stays the same so sessions are kept.
+        if argon.needs_rehash(&user.password_hash) {
+            let password_hash = self.hash_password(&password).await?;
+
+            if model::ModelUser::rehash_password(
+                self.client.get_database().get_pool(),
+                user.uuid.into_uuid(),
+                user.password_hash.clone(),
+                password_hash,
+            )
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+            {
+                tracing::info!("re-hashed password of user {}", &user.username);
+            }
+        }
+
+        self.issue_tokens(&user, None).await
+    }
+
+    /// Authenticate a given user, throttling failed logins of the username and the peer address.
+    ///
+    /// Throttled logins are refused before the password is checked. Every attempt counts as
+    /// failed until it succeeds, which forgets the failures of the username and the peer address.
+    pub async fn login(
+        &self,
+        username: String,
+        password: String,
+        code: Option<String>,
+        peer: Option<IpAddr>,
     ) -> Result<(String, String), error::ServiceError> {
-        // Make sure that user with given username exists.
+        let subjects: Vec<_> = std::iter::once(schema::LoginSubject::Username(username.clone()))
+            .chain(peer.map(schema::LoginSubject::Peer))
+            .collect();
+
+        self.throttle.attempt(&subjects).await?;
+
+        let result = self.auth(username, password, code).await?;
+
+        for subject in subjects {
+            self.throttle.reset(subject).await?;
+        }
+
+        Ok(result)
+    }
+
+    /// Unlock user locked after failed logins, admin only.
+    /// Returns true if the user had failed logins.
+    pub async fn unlock(
+        &self,
+        caller: &middleware::User,
+        username: String,
+    ) -> Result<bool, error::ServiceError> {
+        self.check_admin(caller).await?;
+
+        let user = self.get(username).await?;
+        let result = self
+            .throttle
+            .reset(schema::LoginSubject::Username(user.username.clone()))
+            .await?;
+
+        tracing::info!("unlocked user {}", &user.username);
+
+        Ok(result)
+    }
+
+    /// Disable TOTP of user who lost their authenticator and recovery codes, admin only.
+    /// Returns true if the user had TOTP enrolled.
+    pub async fn reset_totp(
+        &self,
+        caller: &middleware::User,
+        username: String,
+    ) -> Result<bool, error::ServiceError> {
+        self.check_admin(caller).await?;
+
+        let user = self.get(username).await?;
+        let result = self.totp.reset(user.uuid.into_uuid()).await?;
+
+        tracing::info!("reset totp of user {}", &user.username);
+
+        Ok(result)
+    }
+
+    /// Get user mapped to the peer credentials of a local process.
+    /// Peers without a mapping or mapped to an unknown user are denied.
+    pub async fn authenticate_peer(
+        &self,
+        uid: u32,
+        gid: u32,
+    ) -> Result<schema::User, error::ServiceError> {
+        let username = self
+            .config
+            .config
+            .read()
+            .await
+            .peer_auth
+            .username(uid, gid)
+            .map(|x| x.to_string())
+            .ok_or(error::ServiceError::PermissionDenied(
+                "no user mapped to peer".into(),
+            ))?;
+
         if !self.exists(username.clone()).await? {
-            return Err(error::ServiceError::NotFound(
-                "could not find user, make sure username and password are correct".to_string(),
+            return Err(error::ServiceError::PermissionDenied(format!(
+                "peer is mapped to unknown user {}",
+                &username
+            )));
+        }
+
+        self.get(username).await
+    }
+
+    /// Change password of caller, the current password must be given.
+    /// Every session of the caller is revoked and new tokens are returned.
+    pub async fn change_password(
+        &self,
+        caller: uuid::Uuid,
+        password_old: String,
+        password_new: String,
+    ) -> Result<(String, String), error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+
+        if !model::ModelUser::does_user_exist_uuid(pool, caller)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(error::ServiceError::NotFound(format!(
+                "could not find user with uuid {}",
+                &caller
+            )));
+        }
+
+        let user = model::ModelUser::get_user_from_uuid(pool, caller)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        if user.is_service() {
+            return Err(error::ServiceError::InvalidArgument(
+                "service accounts have no password".into(),
+            ));
+        }
+
+        if !self
+            .argon2()
+            .await?
+            .verify(&user.password_hash, password_old.as_bytes())
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(error::ServiceError::PermissionDenied(
+                "incorrect password".into(),
+            ));
+        }
+
+        let (user, revoked) = self.set_password(user, &password_new).await?;
+
+        tracing::info!(
+            "changed password of user {}, revoked {} sessions",
+            &user.username,
+            revoked
+        );
+
+        self.issue_tokens(&user, None).await
+    }
+
+    /// Generate new auth and refresh token from refresh token.
+    ///
+    /// Refresh tokens can only be used once, each refresh rotates the token. Using a token
+    /// again revokes every token of its family since the token has likely been stolen.
+    pub async fn refresh(
+        &self,
+        token_refresh: String,
+    ) -> Result<(String, String), error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+        let session = self.get_session(&token_refresh).await?;
+        let uuid = session.uuid.into_uuid();
+
+        if session.revoked {
+            return Err(error::ServiceError::PermissionDenied(
+                "invalid refresh token".into(),
+            ));
+        }
+
+        // Rotating fails if the token was already rotated, concurrent refreshes included.
+        if !model::ModelSession::rotate_session(pool, uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            model::ModelSession::revoke_family(pool, session.uuid_family.into_uuid())
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+            tracing::warn!(
+                "refresh token {} reused, revoked token family {}",
+                &uuid,
+                &session.uuid_family
+            );
+
+            return Err(error::ServiceError::PermissionDenied(
+                "refresh token reuse detected, please login again".into(),
             ));
         }
 
-        // Fetch user but only to get argon 2 parameters.
-        let user = model::ModelUser::get_user_from_username(
+        // Refresh tokens of deleted users are no longer valid.
+        let uuid_user = session.uuid_user.into_uuid();
+
+        if !model::ModelUser::does_user_exist_uuid(pool, uuid_user)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(error::ServiceError::PermissionDenied(
+                "invalid refresh token".into(),
+            ));
+        }
+
+        let user = model::ModelUser::get_user_from_uuid(pool, uuid_user)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        self.issue_tokens(&user, Some(session.uuid_family.into_uuid()))
+            .await
+    }
+
+    /// Logout session of refresh token, every token of its family is revoked.
+    pub async fn logout(&self, token_refresh: String) -> Result<(), error::ServiceError> {
+        let session = self.get_session(&token_refresh).await?;
+
+        model::ModelSession::revoke_family(
             self.client.get_database().get_pool(),
-            username,
+            session.uuid_family.into_uuid(),
         )
         .await
         .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
 
-        // Generate password hash based on current password.
-        let salt = user.salt;
-        let password_hash = crypt::Argon2Factory::new(
-            user.argon2_iters,
-            user.argon2_memory_mb,
-            user.argon2_parallelism,
+        Ok(())
+    }
+
+    /// Logout every session of user.
+    /// Returns the number of revoked refresh tokens.
+    pub async fn logout_all(&self, caller: uuid::Uuid) -> Result<u64, error::ServiceError> {
+        let result = model::ModelSession::revoke_user_sessions(
+            self.client.get_database().get_pool(),
+            caller,
         )
-        .map_err(|e| error::ServiceError::Internal(e.to_string()))?
-        .encode(password.as_bytes(), salt.as_bytes())
         .await
         .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
 
-        // Check if passwords match.
-        if password_hash != user.password_hash {
-            return Err(error::ServiceError::NotFound(
-                "could not find user, make sure username and password are correct".to_string(),
-            ));
+        tracing::info!("revoked {} sessions of user {}", result, &caller);
+
+        Ok(result)
+    }
+
+    /// Register a new user following the registration policy.
+    ///
+    /// While there is no admin, users only register with the setup token and become admin.
+    /// After that users register freely, with an invite code or not at all depending on the
+    /// policy.
+    pub async fn register(
+        &self,
+        username: String,
+        password: String,
+        invite: Option<String>,
+    ) -> Result<(String, String), error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+
+        let admin_count = model::ModelUser::get_admin_count(pool)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        let policy = self.config.config.read().await.registration.policy;
+
+        if admin_count > 0 {
+            match policy {
+                RegistrationPolicy::Open => return self.add(username, password).await,
+                RegistrationPolicy::Invite => {}
+                RegistrationPolicy::Admin => {
+                    return Err(error::ServiceError::PermissionDenied(
+                        "registration is closed, an admin has to create the user".into(),
+                    ));
+                }
+            }
         }
 
-        let jwt_factory = self.client.get_jwt_factory();
+        let Some(invite) = invite.filter(|x| !x.trim().is_empty()) else {
+            return Err(error::ServiceError::PermissionDenied(match admin_count {
+                0 => "the first admin registers with the setup token printed at startup".into(),
+                _ => "registration needs an invite code".into(),
+            }));
+        };
 
-        let token_auth = jwt_factory.encode(crypt::JwtClaimAccess::new(
-            DaemonClient::ISSUER,
-            user.uuid.into_uuid(),
-            user.username,
-        ));
+        let data = self.new_user(username, password).await?;
 
-        let token_refresh = jwt_factory.encode(crypt::JwtClaimRefresh::new(
-            DaemonClient::ISSUER,
-            user.uuid.into_uuid(),
-        ));
+        let user =
+            model::ModelInvite::redeem_invite(pool, crypt::InviteCode::parse(&invite).hash(), data)
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+                .ok_or(error::ServiceError::PermissionDenied(
+                    "invalid or expired invite code".into(),
+                ))?;
+
+        if user.is_admin() {
+            tracing::info!("registered admin {} with the setup token", &user.username);
+        } else {
+            tracing::info!("registered user {} with an invite", &user.username);
+        }
 
-        Ok((token_auth, token_refresh))
+        self.issue_tokens(&user, None).await
     }
 
-    /// Generate new auth token from refresh token.
-    pub async fn refresh(&self, token_refresh: String) -> Result<String, error::ServiceError> {
-        let jwt_factory = self.client.get_jwt_factory();
+    /// Create a new user which isn't an admin, admin only.
+    /// Unlike registering no tokens are issued, the user logs in with the given password.
+    pub async fn create(
+        &self,
+        caller: &middleware::User,
+        username: String,
+        password: String,
+    ) -> Result<schema::User, error::ServiceError> {
+        self.check_admin(caller).await?;
 
-        // Make sure that current token is valid
-        let claim_refresh = jwt_factory
-            .decode::<crypt::JwtClaimRefresh>(&token_refresh)
-            .map_err(|_| error::ServiceError::PermissionDenied("invalid refresh token".into()))?;
+        let mut data = self.new_user(username, password).await?;
+        data.admin = Some(false);
 
-        // Fetch user based on uuid
-        let uuid = uuid::Uuid::from_str(&claim_refresh.sub)
+        let user = model::ModelUser::add_user(self.client.get_database().get_pool(), data)
+            .await
             .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
 
-        let user =
-            model::ModelUser::get_user_from_uuid(self.client.get_database().get_pool(), uuid)
-                .await
-                .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
-
-        // Generate new auth token
-        let token_auth = jwt_factory.encode(crypt::JwtClaimAccess::new(
-            DaemonClient::ISSUER,
-            user.uuid.into_uuid(),
-            user.username,
-        ));
+        tracing::info!("admin {} created user {}", &caller.username, &user.username);
 
-        Ok(token_auth)
+        Ok(user)
     }
 
     /// Add a new user.
@@ -120,6 +429,103 @@ impl ControllerUser {
         username: String,
         password: String,
     ) -> Result<(String, String), error::ServiceError> {
+        let mut data = self.new_user(username, password).await?;
+
+        // First user to register while there is no admin administers the vault.
+        let admin_count = model::ModelUser::get_admin_count(self.client.get_database().get_pool())
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        data.admin = Some(admin_count == 0);
+
+        // Try insert user into database and return auth tokens if successful.
+        let user = model::ModelUser::add_user(self.client.get_database().get_pool(), data)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        self.issue_tokens(&user, None).await
+    }
+
+    /// Get info of user with username.
+    /// Only the user themself or an admin can view user info.
+    pub async fn info(
+        &self,
+        caller: &middleware::User,
+        username: String,
+    ) -> Result<schema::User, error::ServiceError> {
+        let user = self.get(username).await?;
+
+        self.check_self_or_admin(caller, &user).await?;
+
+        Ok(user)
+    }
+
+    /// Soft delete user with username.
+    /// Only the user themself or an admin can delete a user.
+    pub async fn delete(
+        &self,
+        caller: &middleware::User,
+        username: String,
+    ) -> Result<(), error::ServiceError> {
+        let user = self.get(username).await?;
+
+        self.check_self_or_admin(caller, &user).await?;
+
+        // Soft deleted users are no longer active so their refresh tokens are rejected.
+        model::ModelUser::delete_user(self.client.get_database().get_pool(), user.uuid.into_uuid())
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        self.logout_all(user.uuid.into_uuid()).await?;
+
+        tracing::info!("soft deleted user {}", &user.username);
+
+        Ok(())
+    }
+
+    /// Hard delete users which were soft deleted longer than the retention period ago.
+    /// Only admins can purge users.
+    pub async fn purge(&self, caller: &middleware::User) -> Result<u64, error::ServiceError> {
+        self.check_admin(caller).await?;
+
+        let retention_days = self.config.config.read().await.user.deleted_retention_days;
+
+        let result =
+            model::ModelUser::purge_users(self.client.get_database().get_pool(), retention_days)
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        tracing::info!("purged {} deleted users", result);
+
+        Ok(result)
+    }
+
+    /// List active users sorted by username, admin only.
+    /// Only users whose username contains `search` are listed and pages are only used if `limit` is set.
+    pub async fn list(
+        &self,
+        caller: &middleware::User,
+        search: Option<String>,
+        cursor: Option<String>,
+        limit: Option<u64>,
+    ) -> Result<database::Page<schema::User>, error::ServiceError> {
+        self.check_admin(caller).await?;
+
+        let query = super::list_query(schema::User::COLUMN_USERNAME, search, cursor, limit);
+
+        let result = model::ModelUser::get_users(self.client.get_database().get_pool(), query)
+            .await
+            .map_err(super::list_error)?;
+
+        Ok(result)
+    }
+
+    /// Create user row with a new password hash, the username must not be taken.
+    async fn new_user(
+        &self,
+        username: String,
+        password: String,
+    ) -> Result<schema::User, error::ServiceError> {
         // Make sure that user doesn't exist.
         // If there is a user with the same identifier error as each username is assumed to be unique.
         if self.exists(username.clone()).await? {
@@ -130,59 +536,239 @@ impl ControllerUser {
         }
 
         // Generate new user and password hash.
+        let password_hash = self.hash_password(&password).await?;
+
+        schema::User::new(username, password_hash)
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))
+    }
+
+    /// Get argon2 factory with the currently configured parameters and pepper.
+    async fn argon2(&self) -> Result<crypt::Argon2Factory, error::ServiceError> {
         let config = self.config.config.read().await.encryption.clone();
 
-        let salt = rng::random_bytes_str(16);
-        let password_hash = crypt::Argon2Factory::new(
+        let argon = crypt::Argon2Factory::new(
             config.argon2_iters,
             config.argon2_memory_mb,
             config.argon2_parallelism,
         )
-        .map_err(|e| error::ServiceError::Internal(e.to_string()))?
-        .encode(password.as_bytes(), salt.as_bytes())
-        .await
         .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
 
-        let data = schema::User::new(
-            username,
-            password_hash,
-            salt,
-            config.argon2_iters,
-            config.argon2_memory_mb,
-            config.argon2_parallelism,
-        )
-        .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+        match self.client.get_pepper() {
+            Some(x) => argon
+                .with_pepper(x)
+                .map_err(|e| error::ServiceError::Internal(e.to_string())),
+            None => Ok(argon),
+        }
+    }
 
-        // Try insert user into database and return auth tokens if successful.
-        let user = model::ModelUser::add_user(self.client.get_database().get_pool(), data)
+    /// Hash password with a new salt and the currently configured argon2 parameters.
+    async fn hash_password(&self, password: &str) -> Result<String, error::ServiceError> {
+        let password_hash = self
+            .argon2()
+            .await?
+            .hash(password.as_bytes())
             .await
             .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
 
+        Ok(password_hash)
+    }
+
+    /// Get hash of a random password, made again once the argon2 parameters or pepper changed.
+    async fn dummy_hash(
+        &self,
+        argon: &crypt::Argon2Factory,
+    ) -> Result<String, error::ServiceError> {
+        let mut dummy_hash = self.dummy_hash.lock().await;
+
+        match dummy_hash.as_ref() {
+            Some(x) if !argon.needs_rehash(x) => Ok(x.clone()),
+            _ => {
+                let password_hash = argon
+                    .hash(rng::random_bytes_str(32).as_bytes())
+                    .await
+                    .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+                *dummy_hash = Some(password_hash.clone());
+
+                Ok(password_hash)
+            }
+        }
+    }
+
+    /// Hash and store new password of user, every session of the user is revoked.
+    /// Returns the updated user and the number of sessions revoked.
+    async fn set_password(
+        &self,
+        user: schema::User,
+        password: &str,
+    ) -> Result<(schema::User, u64), error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+        let uuid = user.uuid.into_uuid();
+        let password_hash = self.hash_password(password).await?;
+
+        let revoked = model::ModelUser::update_password(pool, uuid, password_hash)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        let user = model::ModelUser::get_user_from_uuid(pool, uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok((user, revoked))
+    }
+
+    /// Create auth and refresh token for user.
+    /// The refresh token is tracked as a session in the given token family.
+    async fn issue_tokens(
+        &self,
+        user: &schema::User,
+        uuid_family: Option<uuid::Uuid>,
+    ) -> Result<(String, String), error::ServiceError> {
         let jwt_factory = self.client.get_jwt_factory();
 
-        let token_auth = jwt_factory.encode(crypt::JwtClaimAccess::new(
+        let claim_refresh = crypt::JwtClaimRefresh::new(
             DaemonClient::ISSUER,
             user.uuid.into_uuid(),
-            user.username,
-        ));
+            uuid::Uuid::new_v4(),
+        );
+
+        let session = schema::Session::new(
+            uuid::Uuid::from_str(&claim_refresh.jti)
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?,
+            user.uuid.into_uuid(),
+            uuid_family,
+            claim_refresh
+                .expires_at()
+                .ok_or(error::ServiceError::Internal("invalid expiration".into()))?,
+        );
+
+        model::ModelSession::add_session(self.client.get_database().get_pool(), session)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
 
-        let token_refresh = jwt_factory.encode(crypt::JwtClaimRefresh::new(
+        let token_auth = jwt_factory.encode(crypt::JwtClaimAccess::new(
             DaemonClient::ISSUER,
             user.uuid.into_uuid(),
+            &user.username,
         ));
 
+        let token_refresh = jwt_factory.encode(claim_refresh);
+
         Ok((token_auth, token_refresh))
     }
+
+    /// Get session of refresh token.
+    async fn get_session(
+        &self,
+        token_refresh: &str,
+    ) -> Result<schema::Session, error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+        let denied = || error::ServiceError::PermissionDenied("invalid refresh token".into());
+
+        // Make sure that current token is valid
+        let claim_refresh = self
+            .client
+            .get_jwt_factory()
+            .decode::<crypt::JwtClaimRefresh>(token_refresh)
+            .map_err(|_| denied())?;
+
+        let uuid = uuid::Uuid::from_str(&claim_refresh.jti).map_err(|_| denied())?;
+
+        if !model::ModelSession::does_session_exist_uuid(pool, uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+        {
+            return Err(denied());
+        }
+
+        let session = model::ModelSession::get_session_from_uuid(pool, uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(session)
+    }
+
+    /// Get active user with username.
+    async fn get(&self, username: String) -> Result<schema::User, error::ServiceError> {
+        if !self.exists(username.clone()).await? {
+            return Err(error::ServiceError::NotFound(format!(
+                "could not find user with username {}",
+                &username
+            )));
+        }
+
+        let result = model::ModelUser::get_user_from_username(
+            self.client.get_database().get_pool(),
+            username,
+        )
+        .await
+        .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    /// Make sure caller is an admin.
+    /// Requests authorized with an api key only get the grants of the key, never admin rights.
+    async fn check_admin(&self, caller: &middleware::User) -> Result<(), error::ServiceError> {
+        if caller.api_key.is_some() {
+            return Err(error::ServiceError::PermissionDenied(
+                "api keys can't be used for admin requests".into(),
+            ));
+        }
+
+        let pool = self.client.get_database().get_pool();
+
+        let is_admin = model::ModelUser::does_user_exist_uuid(pool, caller.uuid)
+            .await
+            .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+            && model::ModelUser::get_user_from_uuid(pool, caller.uuid)
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?
+                .is_admin();
+
+        if !is_admin {
+            return Err(error::ServiceError::PermissionDenied(
+                "user is not an admin".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Make sure caller is either the given user or an admin.
+    async fn check_self_or_admin(
+        &self,
+        caller: &middleware::User,
+        user: &schema::User,
+    ) -> Result<(), error::ServiceError> {
+        if user.uuid.into_uuid() == caller.uuid {
+            return Ok(());
+        }
+
+        self.check_admin(caller).await
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::config::PeerAuthRule;
     use crate::controller::ControllerUser;
-    use crate::{client, config};
+    use crate::model::ModelLoginFailure;
+    use crate::schema::LoginSubject;
+    use crate::{client, config, error, middleware};
 
     use sqlx::sqlite;
     use std::sync::Arc;
 
+    /// User authorized with a session of the user with uuid.
+    fn user(uuid: uuid::Uuid) -> middleware::User {
+        middleware::User {
+            uuid,
+            username: String::new(),
+            api_key: None,
+        }
+    }
+
     #[sqlx::test]
     async fn exists(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
         let config = config::ConfigManager::mocked();
@@ -216,12 +802,12 @@ mod tests {
 
     #[sqlx::test]
     async fn auth(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
-        let config = config::ConfigManager::mocked();
+        let config = Arc::new(config::ConfigManager::mocked());
         let client = client::DaemonClient::mocked(pool)
             .await
             .expect("could not create mocked client");
 
-        let controller = ControllerUser::new(Arc::new(config), Arc::new(client));
+        let controller = ControllerUser::new(config.clone(), Arc::new(client));
 
         // Add a new user to get tokens
         let result_add = controller
@@ -232,23 +818,432 @@ mod tests {
 
         // Try auth with incorrect user
         let result_1 = controller
-            .auth("bob".into(), "carl-loves-cars1234".to_string())
+            .auth("bob".into(), "carl-loves-cars1234".to_string(), None)
             .await;
 
         // Try auth with incorrect password
         let result_2 = controller
-            .auth("carl".into(), "carl-loves-boats".to_string())
+            .auth("carl".into(), "carl-loves-boats".to_string(), None)
             .await;
 
         // Correct auth
         let result_ok = controller
-            .auth("carl".into(), "carl-loves-cars1234".to_string())
+            .auth("carl".into(), "carl-loves-cars1234".to_string(), None)
             .await;
 
         assert!(result_1.is_err());
         assert!(result_2.is_err());
         assert!(result_ok.is_ok());
 
+        // Unknown users are verified against a dummy hash with the configured parameters.
+        let argon = controller.argon2().await.unwrap();
+        let dummy_hash = controller.dummy_hash.lock().await.clone();
+
+        assert!(dummy_hash.is_some());
+        assert!(!argon.needs_rehash(&dummy_hash.unwrap()));
+
+        config.config.write().await.encryption.argon2_iters += 1;
+
+        let result_3 = controller
+            .auth("bob".into(), "carl-loves-cars1234".to_string(), None)
+            .await;
+        let argon = controller.argon2().await.unwrap();
+        let dummy_hash = controller.dummy_hash.lock().await.clone();
+
+        assert!(matches!(result_3, Err(error::ServiceError::NotFound(_))));
+        assert!(!argon.needs_rehash(&dummy_hash.unwrap()));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn login(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let config = Arc::new(config::ConfigManager::mocked());
+        let client = client::DaemonClient::mocked(pool)
+            .await
+            .expect("could not create mocked client");
+
+        config.config.write().await.login.backoff_secs = 0;
+
+        let controller = ControllerUser::new(config.clone(), Arc::new(client));
+        let peer_ip = "10.0.0.1".parse().unwrap();
+        let peer = Some(peer_ip);
+
+        controller
+            .add("carl".to_string(), "carl-loves-cars1234".to_string())
+            .await
+            .unwrap();
+
+        let caller = controller
+            .get("carl".into())
+            .await
+            .unwrap()
+            .uuid
+            .into_uuid();
+
+        // Username is locked after too many failed logins, even with the correct password
+        for _ in 0..5 {
+            let result = controller
+                .login("carl".into(), "carl-loves-boats".into(), None, peer)
+                .await;
+
+            assert!(matches!(result, Err(error::ServiceError::NotFound(_))));
+        }
+
+        let result_locked = controller
+            .login("carl".into(), "carl-loves-cars1234".into(), None, peer)
+            .await;
+
+        assert!(matches!(result_locked, Err(error::ServiceError::Locked(_))));
+
+        // Only admins can unlock existing users
+        let result_unknown = controller.unlock(&user(caller), "bob".into()).await;
+        let result_unlock = controller.unlock(&user(caller), "carl".into()).await;
+
+        assert!(matches!(
+            result_unknown,
+            Err(error::ServiceError::NotFound(_))
+        ));
+        assert!(result_unlock.unwrap());
+
+        let result_ok = controller
+            .login("carl".into(), "carl-loves-cars1234".into(), None, peer)
+            .await;
+
+        assert!(result_ok.is_ok());
+
+        // Failed logins of the username and the peer are forgotten after a successful login
+        let pool = controller.client.get_database().get_pool();
+        let peer = LoginSubject::Peer(peer_ip);
+        let carl = LoginSubject::Username("carl".into());
+
+        assert!(
+            ModelLoginFailure::get_login_failure(pool, peer)
+                .await
+                .is_err()
+        );
+        assert!(
+            ModelLoginFailure::get_login_failure(pool, carl)
+                .await
+                .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn auth_totp(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let config = config::ConfigManager::mocked();
+        let client = client::DaemonClient::mocked(pool)
+            .await
+            .expect("could not create mocked client");
+
+        let controller = ControllerUser::new(Arc::new(config), Arc::new(client));
+
+        controller
+            .add("carl".into(), "carl-loves-cars1234".into())
+            .await
+            .unwrap();
+
+        let caller = controller
+            .get("carl".into())
+            .await
+            .unwrap()
+            .uuid
+            .into_uuid();
+
+        let (secret, _) = controller.totp.enroll(caller).await.unwrap();
+        let totp = shared_core::crypt::Totp::from_base32(&secret).unwrap();
+        let step = shared_core::crypt::Totp::step(chrono::Utc::now().timestamp() as u64);
+
+        let recovery_codes = controller
+            .totp
+            .confirm(caller, totp.code(step - 1))
+            .await
+            .unwrap();
+
+        // Password alone is no longer enough
+        let result_1 = controller
+            .auth("carl".into(), "carl-loves-cars1234".into(), None)
+            .await;
+        let result_2 = controller
+            .auth(
+                "carl".into(),
+                "carl-loves-boats".into(),
+                Some(totp.code(step)),
+            )
+            .await;
+        let result_3 = controller
+            .auth(
+                "carl".into(),
+                "carl-loves-cars1234".into(),
+                Some("000000".into()),
+            )
+            .await;
+        let result_ok = controller
+            .auth(
+                "carl".into(),
+                "carl-loves-cars1234".into(),
+                Some(recovery_codes[0].clone()),
+            )
+            .await;
+
+        assert!(matches!(
+            result_1,
+            Err(error::ServiceError::Unauthorized(_))
+        ));
+        assert!(matches!(result_2, Err(error::ServiceError::NotFound(_))));
+        assert!(matches!(
+            result_3,
+            Err(error::ServiceError::PermissionDenied(_))
+        ));
+        assert!(result_ok.is_ok());
+
+        // Admins can remove TOTP of users who lost their codes
+        assert!(
+            controller
+                .reset_totp(&user(caller), "carl".into())
+                .await
+                .unwrap()
+        );
+
+        let result_reset = controller
+            .auth("carl".into(), "carl-loves-cars1234".into(), None)
+            .await;
+
+        assert!(result_reset.is_ok());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn authenticate_peer(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let config = Arc::new(config::ConfigManager::mocked());
+        let client = client::DaemonClient::mocked(pool)
+            .await
+            .expect("could not create mocked client");
+
+        let controller = ControllerUser::new(config.clone(), Arc::new(client));
+
+        controller
+            .add("carl".into(), "carl-loves-cars1234".into())
+            .await
+            .unwrap();
+
+        config.config.write().await.peer_auth.rules = vec![
+            PeerAuthRule {
+                uid: Some(1000),
+                gid: None,
+                username: "carl".into(),
+            },
+            PeerAuthRule {
+                uid: Some(1001),
+                gid: Some(1001),
+                username: "bob".into(),
+            },
+            PeerAuthRule {
+                uid: None,
+                gid: None,
+                username: "carl".into(),
+            },
+        ];
+
+        // Rules match on every id they set
+        let result_1 = controller.authenticate_peer(1000, 1000).await;
+        let result_2 = controller.authenticate_peer(1000, 0).await;
+        let result_3 = controller.authenticate_peer(1001, 1000).await;
+
+        assert_eq!(result_1.unwrap().username, "carl");
+        assert_eq!(result_2.unwrap().username, "carl");
+        assert!(matches!(
+            result_3,
+            Err(error::ServiceError::PermissionDenied(_))
+        ));
+
+        // Mapped users must exist
+        let result_4 = controller.authenticate_peer(1001, 1001).await;
+
+        assert!(matches!(
+            result_4,
+            Err(error::ServiceError::PermissionDenied(_))
+        ));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn change_password(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let config = config::ConfigManager::mocked();
+        let client = client::DaemonClient::mocked(pool)
+            .await
+            .expect("could not create mocked client");
+
+        let controller = ControllerUser::new(Arc::new(config), Arc::new(client));
+
+        let (_, token_refresh) = controller
+            .add("carl".into(), "carl-loves-cars1234".into())
+            .await
+            .unwrap();
+
+        let uuid_carl = controller
+            .get("carl".into())
+            .await
+            .unwrap()
+            .uuid
+            .into_uuid();
+
+        // Current password must be correct
+        let result_err_1 = controller
+            .change_password(uuid_carl, "carl-loves-boats".into(), "new-password".into())
+            .await;
+        let result_err_2 = controller
+            .change_password(
+                uuid::Uuid::new_v4(),
+                "carl-loves-cars1234".into(),
+                "new-password".into(),
+            )
+            .await;
+
+        assert!(matches!(
+            result_err_1,
+            Err(error::ServiceError::PermissionDenied(_))
+        ));
+        assert!(matches!(
+            result_err_2,
+            Err(error::ServiceError::NotFound(_))
+        ));
+
+        let result_ok = controller
+            .change_password(
+                uuid_carl,
+                "carl-loves-cars1234".into(),
+                "carl-loves-trains1234".into(),
+            )
+            .await;
+
+        assert!(result_ok.is_ok());
+
+        // Sessions of the old password are revoked, new tokens are valid
+        let (_, token_refresh_2) = result_ok.unwrap();
+
+        assert!(controller.refresh(token_refresh).await.is_err());
+        assert!(controller.refresh(token_refresh_2).await.is_ok());
+
+        let result_1 = controller
+            .auth("carl".into(), "carl-loves-cars1234".into(), None)
+            .await;
+        let result_2 = controller
+            .auth("carl".into(), "carl-loves-trains1234".into(), None)
+            .await;
+
+        assert!(result_1.is_err());
+        assert!(result_2.is_ok());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn rehash(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let config = Arc::new(config::ConfigManager::mocked());
+        let client = client::DaemonClient::mocked(pool)
+            .await
+            .expect("could not create mocked client");
+
+        let controller = ControllerUser::new(config.clone(), Arc::new(client));
+
+        let (_, token_refresh) = controller
+            .add("carl".into(), "carl-loves-cars1234".into())
+            .await
+            .unwrap();
+
+        // Hash is kept while parameters are up to date
+        let (_, token_refresh_2) = controller
+            .auth("carl".into(), "carl-loves-cars1234".into(), None)
+            .await
+            .unwrap();
+        let user_1 = controller.get("carl".into()).await.unwrap();
+
+        config.config.write().await.encryption.argon2_iters += 1;
+
+        let result_ok = controller
+            .auth("carl".into(), "carl-loves-cars1234".into(), None)
+            .await;
+        let user_2 = controller.get("carl".into()).await.unwrap();
+
+        assert!(result_ok.is_ok());
+        assert!(user_1.password_hash.contains(",t=2,"));
+        assert!(user_2.password_hash.contains(",t=3,"));
+
+        // Re-hashing keeps previous sessions
+        let (_, token_refresh_3) = result_ok.unwrap();
+
+        assert!(controller.refresh(token_refresh).await.is_ok());
+        assert!(controller.refresh(token_refresh_2).await.is_ok());
+        assert!(controller.refresh(token_refresh_3).await.is_ok());
+
+        // Password still works with the new hash
+        let result_1 = controller
+            .auth("carl".into(), "carl-loves-cars1234".into(), None)
+            .await;
+        let user_3 = controller.get("carl".into()).await.unwrap();
+
+        assert!(result_1.is_ok());
+        assert_eq!(user_3.password_hash, user_2.password_hash);
+
+        Ok(())
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn auth_legacy(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        // Migrate to before hashes were stored as PHC string only
+        let mut migrator = sqlx::migrate!();
+
+        migrator.migrations = migrator
+            .migrations
+            .iter()
+            .filter(|x| x.version < 20251114090000)
+            .cloned()
+            .collect();
+
+        migrator.run(&pool).await.unwrap();
+
+        // Legacy users kept the salt and argon2 parameters next to the hash
+        sqlx::query(
+            "INSERT INTO users_active (uuid, username, password_hash, salt, argon2_iters, argon2_memory_mb, argon2_parallelism)
+                VALUES (?, 'carl', ?, '1111111111111111', 2, 32, 2)",
+        )
+        .bind(uuid::Uuid::new_v4().as_hyphenated().to_string())
+        .bind("$argon2id$v=19$m=256,t=2,p=2$MTExMTExMTExMTExMTExMQ$5QbtwztLhtk7tK/VeFqbfGNTZh2GmhCglWIt5JJcNLE")
+        .execute(&pool)
+        .await?;
+
+        sqlx::migrate!().run(&pool).await.unwrap();
+
+        let config = config::ConfigManager::mocked();
+        let client = client::DaemonClient::mocked(pool)
+            .await
+            .expect("could not create mocked client");
+
+        let controller = ControllerUser::new(Arc::new(config), Arc::new(client));
+
+        let result_err = controller
+            .auth("carl".into(), "carl-loves-boats".into(), None)
+            .await;
+        let result_ok = controller
+            .auth("carl".into(), "carl-loves-cars1234".into(), None)
+            .await;
+
+        assert!(result_err.is_err());
+        assert!(result_ok.is_ok());
+
+        // Legacy hash is upgraded to the configured parameters
+        let user = controller.get("carl".into()).await.unwrap();
+
+        assert!(
+            user.password_hash
+                .starts_with("$argon2id$v=19$m=32768,t=2,p=2$")
+        );
+
         Ok(())
     }
 
@@ -306,10 +1301,226 @@ mod tests {
 
         // Only refresh token can be used
         let result_1 = controller.refresh(token_auth).await;
-        let result_2 = controller.refresh(token_refresh).await;
+        let result_2 = controller.refresh(token_refresh.clone()).await;
+
+        assert!(result_1.is_err());
+        assert!(result_2.is_ok());
+
+        // Refreshing rotates the token, reusing the old one revokes the whole family
+        let (_, token_refresh_2) = result_2.unwrap();
+
+        let result_3 = controller.refresh(token_refresh).await;
+        let result_4 = controller.refresh(token_refresh_2).await;
+
+        assert!(matches!(
+            result_3,
+            Err(error::ServiceError::PermissionDenied(_))
+        ));
+        assert!(result_4.is_err());
+
+        // Logout revokes the session of the token only
+        let (_, token_refresh_3) = controller
+            .auth("carl".into(), "carl-loves-cars1234".into(), None)
+            .await
+            .unwrap();
+        let (_, token_refresh_4) = controller
+            .auth("carl".into(), "carl-loves-cars1234".into(), None)
+            .await
+            .unwrap();
+
+        assert!(controller.logout(token_refresh_3.clone()).await.is_ok());
+        assert!(controller.refresh(token_refresh_3).await.is_err());
+
+        let (_, token_refresh_4) = controller.refresh(token_refresh_4).await.unwrap();
+        let (_, token_refresh_5) = controller
+            .auth("carl".into(), "carl-loves-cars1234".into(), None)
+            .await
+            .unwrap();
+
+        // Logging out everywhere revokes every session
+        let uuid_carl = controller
+            .get("carl".into())
+            .await
+            .unwrap()
+            .uuid
+            .into_uuid();
+
+        assert_eq!(controller.logout_all(uuid_carl).await.unwrap(), 2);
+        assert!(controller.refresh(token_refresh_4).await.is_err());
+        assert!(controller.refresh(token_refresh_5).await.is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn info(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let config = config::ConfigManager::mocked();
+        let client = client::DaemonClient::mocked(pool)
+            .await
+            .expect("could not create mocked client");
+
+        let controller = ControllerUser::new(Arc::new(config), Arc::new(client));
+
+        // First user is admin
+        controller
+            .add("admin".into(), "admin-password".into())
+            .await
+            .unwrap();
+        controller
+            .add("carl".into(), "carl-loves-cars1234".into())
+            .await
+            .unwrap();
+        controller
+            .add("bob".into(), "bob-loves-boats1234".into())
+            .await
+            .unwrap();
+
+        let admin = controller.get("admin".into()).await.unwrap();
+        let carl = controller.get("carl".into()).await.unwrap();
+
+        assert!(admin.is_admin());
+        assert!(!carl.is_admin());
+
+        let uuid_admin = admin.uuid.into_uuid();
+        let uuid_carl = carl.uuid.into_uuid();
+
+        let result_1 = controller.info(&user(uuid_carl), "carl".into()).await;
+        let result_2 = controller.info(&user(uuid_admin), "carl".into()).await;
+
+        // Other users can't be viewed by non admins
+        let result_3 = controller.info(&user(uuid_carl), "bob".into()).await;
+        let result_4 = controller.info(&user(uuid_admin), "jeff".into()).await;
+
+        assert!(result_1.is_ok());
+        assert!(result_2.is_ok());
+        assert!(matches!(
+            result_3,
+            Err(error::ServiceError::PermissionDenied(_))
+        ));
+        assert!(matches!(result_4, Err(error::ServiceError::NotFound(_))));
+
+        assert_eq!(result_1.unwrap().username, "carl");
+
+        // Listing users is admin only
+        let result_5 = controller.list(&user(uuid_admin), None, None, None).await;
+        let result_6 = controller
+            .list(&user(uuid_admin), Some("ar".into()), None, None)
+            .await;
+        let result_7 = controller.list(&user(uuid_carl), None, None, None).await;
+        let result_8 = controller
+            .list(
+                &user(uuid_admin),
+                None,
+                Some("not-a-cursor".into()),
+                Some(1),
+            )
+            .await;
+
+        assert_eq!(result_5.unwrap().items.len(), 3);
+        assert_eq!(result_6.unwrap().items[0].username, "carl");
+        assert!(matches!(
+            result_7,
+            Err(error::ServiceError::PermissionDenied(_))
+        ));
+        assert!(matches!(
+            result_8,
+            Err(error::ServiceError::InvalidArgument(_))
+        ));
+
+        // Api keys of admins don't carry admin rights
+        let admin_key = middleware::User {
+            api_key: Some(uuid::Uuid::new_v4()),
+            ..user(uuid_admin)
+        };
+        let result_9 = controller.list(&admin_key, None, None, None).await;
+        let result_10 = controller.info(&admin_key, "carl".into()).await;
+
+        assert!(matches!(
+            result_9,
+            Err(error::ServiceError::PermissionDenied(_))
+        ));
+        assert!(matches!(
+            result_10,
+            Err(error::ServiceError::PermissionDenied(_))
+        ));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn delete(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let config = config::ConfigManager::mocked();
+        let client = client::DaemonClient::mocked(pool)
+            .await
+            .expect("could not create mocked client");
+
+        let controller = ControllerUser::new(Arc::new(config), Arc::new(client));
+
+        controller
+            .add("admin".into(), "admin-password".into())
+            .await
+            .unwrap();
+
+        let (_, token_refresh) = controller
+            .add("carl".into(), "carl-loves-cars1234".into())
+            .await
+            .unwrap();
+        controller
+            .add("bob".into(), "bob-loves-boats1234".into())
+            .await
+            .unwrap();
+
+        let uuid_admin = controller
+            .get("admin".into())
+            .await
+            .unwrap()
+            .uuid
+            .into_uuid();
+        let uuid_carl = controller
+            .get("carl".into())
+            .await
+            .unwrap()
+            .uuid
+            .into_uuid();
+
+        // Non admins can only delete themselves
+        let result_1 = controller.delete(&user(uuid_carl), "bob".into()).await;
+        let result_2 = controller.delete(&user(uuid_carl), "carl".into()).await;
+        let result_3 = controller.delete(&user(uuid_admin), "bob".into()).await;
 
         assert!(result_1.is_err());
         assert!(result_2.is_ok());
+        assert!(result_3.is_ok());
+
+        // Deleted users can no longer login or refresh
+        let result_4 = controller
+            .auth("carl".into(), "carl-loves-cars1234".into(), None)
+            .await;
+        let result_5 = controller.refresh(token_refresh).await;
+        let result_6 = controller.delete(&user(uuid_admin), "carl".into()).await;
+
+        assert!(result_4.is_err());
+        assert!(result_5.is_err());
+        assert!(matches!(result_6, Err(error::ServiceError::NotFound(_))));
+
+        // Purging is admin only and deleted users are still within retention period
+        let result_7 = controller.purge(&user(uuid_carl)).await;
+        let result_8 = controller.purge(&user(uuid_admin)).await;
+
+        assert!(result_7.is_err());
+        assert_eq!(result_8.unwrap(), 0);
+
+        controller
+            .config
+            .config
+            .write()
+            .await
+            .user
+            .deleted_retention_days = 0;
+
+        let result_9 = controller.purge(&user(uuid_admin)).await;
+
+        assert_eq!(result_9.unwrap(), 2);
 
         Ok(())
     }
@@ -4,6 +4,12 @@ pub enum ServiceError {
     #[error("{0}")]
     AlreadyExists(String),
 
+    #[error("{0}")]
+    Conflict(String),
+
+    #[error("{0}")]
+    InvalidArgument(String),
+
     #[error("{0}")]
     PermissionDenied(String),
 
@@ -12,4 +18,13 @@ pub enum ServiceError {
 
     #[error("{0}")]
     NotFound(String),
+
+    #[error("{0}")]
+    Unauthorized(String),
+
+    #[error("{0}")]
+    TooManyRequests(String),
+
+    #[error("{0}")]
+    Locked(String),
 }
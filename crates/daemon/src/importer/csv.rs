@@ -0,0 +1,318 @@
+use crate::importer::{ImportEntry, ImportError};
+use crate::schema;
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// CSV source config.
+///
+/// This is stored as JSON in the `source_config` column of the source.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CsvSourceConfig {
+    /// Path to CSV file, relative to the import directory unless absolute.
+    pub path: PathBuf,
+    /// Field delimiter.
+    #[serde(default = "CsvSourceConfig::default_delimiter")]
+    pub delimiter: char,
+    /// Mapping of secret fields to CSV header names.
+    #[serde(default)]
+    pub columns: CsvColumnMapping,
+}
+
+impl CsvSourceConfig {
+    fn default_delimiter() -> char {
+        ','
+    }
+
+    /// Parse config from JSON.
+    pub fn from_json(config: &str) -> Result<Self, ImportError> {
+        let config: Self =
+            serde_json::from_str(config).map_err(|e| ImportError::Config(e.to_string()))?;
+
+        if !config.delimiter.is_ascii() {
+            return Err(ImportError::Config("delimiter must be ascii".into()));
+        }
+
+        Ok(config)
+    }
+}
+
+/// Mapping of secret fields to CSV header names.
+///
+/// Optional columns which are `None`, or missing from the file, are left empty.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct CsvColumnMapping {
+    pub name: String,
+    pub key: Option<String>,
+    pub description: Option<String>,
+    pub secret: String,
+    pub secret_type: Option<String>,
+}
+
+impl Default for CsvColumnMapping {
+    fn default() -> Self {
+        Self {
+            name: "name".into(),
+            key: Some("key".into()),
+            description: Some("description".into()),
+            secret: "secret".into(),
+            secret_type: Some("type".into()),
+        }
+    }
+}
+
+/// CSV source importer
+#[derive(Debug)]
+pub struct CsvImporter {
+    config: CsvSourceConfig,
+}
+
+impl CsvImporter {
+    pub fn new(config: CsvSourceConfig) -> Self {
+        Self { config }
+    }
+
+    /// Read all entries from the configured file, which must be in the import directory.
+    pub async fn read(&self, directory: &Path) -> Result<Vec<ImportEntry>, ImportError> {
+        let path = super::resolve_path(directory, &self.config.path).await?;
+
+        tracing::info!("importing csv source: {}", path.display());
+
+        let data = tokio::fs::read(&path).await?;
+
+        self.parse(&data)
+    }
+
+    /// Parse entries from CSV data.
+    pub fn parse(&self, data: &[u8]) -> Result<Vec<ImportEntry>, ImportError> {
+        let mut reader = ::csv::ReaderBuilder::new()
+            .delimiter(self.config.delimiter as u8)
+            .trim(::csv::Trim::All)
+            .from_reader(data);
+
+        let headers = reader
+            .headers()
+            .map_err(|e| ImportError::Config(e.to_string()))?
+            .clone();
+
+        let find_column = |column: &str| headers.iter().position(|x| x == column);
+
+        let columns = &self.config.columns;
+
+        let column_name = find_column(&columns.name).ok_or(ImportError::Config(format!(
+            "missing name column {}",
+            &columns.name
+        )))?;
+
+        let column_secret = find_column(&columns.secret).ok_or(ImportError::Config(format!(
+            "missing secret column {}",
+            &columns.secret
+        )))?;
+
+        let column_key = columns.key.as_deref().and_then(find_column);
+        let column_description = columns.description.as_deref().and_then(find_column);
+        let column_secret_type = columns.secret_type.as_deref().and_then(find_column);
+
+        let mut names = HashSet::new();
+        let mut entries = Vec::new();
+
+        for record in reader.records() {
+            let record = record.map_err(|e| {
+                let line = e.position().map(|x| x.line()).unwrap_or_default();
+                ImportError::Entry(line, e.to_string())
+            })?;
+
+            let line = record.position().map(|x| x.line()).unwrap_or_default();
+
+            // Empty optional fields are treated as missing.
+            let field = |column: Option<usize>| {
+                column
+                    .and_then(|x| record.get(x))
+                    .filter(|x| !x.is_empty())
+                    .map(|x| x.to_string())
+            };
+
+            let name =
+                field(Some(column_name)).ok_or(ImportError::Entry(line, "missing name".into()))?;
+            let secret = field(Some(column_secret))
+                .ok_or(ImportError::Entry(line, "missing secret".into()))?;
+
+            let secret_type = match field(column_secret_type) {
+                Some(x) => parse_secret_type(&x).ok_or(ImportError::Entry(
+                    line,
+                    format!("unknown secret type {}", x),
+                ))?,
+                None => schema::SecretType::Unknown,
+            };
+
+            // Make sure entry would be a valid secret.
+            let secret = schema::Secret::new(
+                name,
+                field(column_key),
+                field(column_description),
+                secret,
+                secret_type as u32,
+            )
+            .map_err(|e| ImportError::Entry(line, e.to_string()))?;
+
+            if !names.insert(secret.name.clone()) {
+                return Err(ImportError::Entry(
+                    line,
+                    format!("duplicate name {}", &secret.name),
+                ));
+            }
+
+            entries.push(ImportEntry {
+                name: secret.name,
+                key: secret.key,
+                description: secret.description,
+                secret: secret.secret,
+                secret_type: secret.secret_type,
+            });
+        }
+
+        Ok(entries)
+    }
+}
+
+/// Parse secret type from either its name or its numeric value.
+fn parse_secret_type(value: &str) -> Option<schema::SecretType> {
+    if let Ok(value) = value.parse::<u32>() {
+        return Some(value.into());
+    }
+
+    match value.to_lowercase().as_str() {
+        "unknown" => Some(schema::SecretType::Unknown),
+        "cipher" => Some(schema::SecretType::Cipher),
+        "key" => Some(schema::SecretType::Key),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CsvColumnMapping, CsvImporter, CsvSourceConfig};
+    use crate::schema::SecretType;
+
+    use std::path::PathBuf;
+
+    fn importer(columns: CsvColumnMapping) -> CsvImporter {
+        CsvImporter::new(CsvSourceConfig {
+            path: PathBuf::new(),
+            delimiter: ',',
+            columns,
+        })
+    }
+
+    #[tokio::test]
+    async fn from_json() {
+        let result_1 = CsvSourceConfig::from_json(r#"{"path": "/tmp/secrets.csv"}"#);
+
+        assert!(result_1.is_ok());
+
+        let result_1 = result_1.unwrap();
+
+        assert_eq!(result_1.delimiter, ',');
+        assert_eq!(result_1.columns, CsvColumnMapping::default());
+
+        let result_2 = CsvSourceConfig::from_json(
+            r#"{"path": "/tmp/secrets.csv", "delimiter": ";", "columns": {"name": "title"}}"#,
+        );
+
+        assert!(result_2.is_ok());
+
+        let result_2 = result_2.unwrap();
+
+        assert_eq!(result_2.delimiter, ';');
+        assert_eq!(result_2.columns.name, "title");
+        assert_eq!(result_2.columns.secret, "secret");
+
+        let result_3 = CsvSourceConfig::from_json(r#"{"delimiter": ";"}"#);
+        let result_4 = CsvSourceConfig::from_json(r#"{"path": "/tmp/a.csv", "delimiter": "ä"}"#);
+
+        assert!(result_3.is_err());
+        assert!(result_4.is_err());
+    }
+
+    #[tokio::test]
+    async fn parse() {
+        let data = "name,key,description,secret,type
+github-token,GITHUB_TOKEN,token for ci,ghp_123,key
+database, , ,hunter2,1
+legacy,,,abc,";
+
+        let result = importer(CsvColumnMapping::default()).parse(data.as_bytes());
+
+        assert!(result.is_ok());
+
+        let result = result.unwrap();
+
+        assert_eq!(result.len(), 3);
+
+        assert_eq!(result[0].name, "github-token");
+        assert_eq!(result[0].key, Some("GITHUB_TOKEN".into()));
+        assert_eq!(result[0].description, Some("token for ci".into()));
+        assert_eq!(result[0].secret, "ghp_123");
+        assert_eq!(result[0].secret_type, SecretType::Key as u32);
+
+        assert_eq!(result[1].key, None);
+        assert_eq!(result[1].description, None);
+        assert_eq!(result[1].secret_type, SecretType::Cipher as u32);
+
+        assert_eq!(result[2].secret_type, SecretType::Unknown as u32);
+    }
+
+    #[tokio::test]
+    async fn parse_mapping() {
+        let data = "title,password,notes
+github-token,ghp_123,token for ci";
+
+        let columns = CsvColumnMapping {
+            name: "title".into(),
+            key: None,
+            description: Some("notes".into()),
+            secret: "password".into(),
+            secret_type: None,
+        };
+
+        let result = importer(columns).parse(data.as_bytes());
+
+        assert!(result.is_ok());
+
+        let result = result.unwrap();
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].name, "github-token");
+        assert_eq!(result[0].description, Some("token for ci".into()));
+        assert_eq!(result[0].secret, "ghp_123");
+
+        // Default mapping doesn't match any of the headers.
+        let result_err = importer(CsvColumnMapping::default()).parse(data.as_bytes());
+
+        assert!(result_err.is_err());
+    }
+
+    #[tokio::test]
+    async fn parse_invalid() {
+        let importer = importer(CsvColumnMapping::default());
+
+        let data_duplicate = "name,secret
+github-token,123
+github-token,456";
+
+        let data_missing_secret = "name,secret
+github-token,";
+
+        let data_short_name = "name,secret
+gh,123";
+
+        let data_invalid_type = "name,secret,type
+github-token,123,password";
+
+        assert!(importer.parse(data_duplicate.as_bytes()).is_err());
+        assert!(importer.parse(data_missing_secret.as_bytes()).is_err());
+        assert!(importer.parse(data_short_name.as_bytes()).is_err());
+        assert!(importer.parse(data_invalid_type.as_bytes()).is_err());
+    }
+}
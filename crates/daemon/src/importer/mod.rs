@@ -0,0 +1,192 @@
+mod csv;
+
+use crate::schema;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub use csv::*;
+
+/// Import errors
+#[derive(thiserror::Error, Debug)]
+pub enum ImportError {
+    #[error("io error - {0}")]
+    IO(#[from] std::io::Error),
+
+    #[error("invalid source config - {0}")]
+    Config(String),
+
+    #[error("invalid entry on line {0} - {1}")]
+    Entry(u64, String),
+}
+
+/// Resolve path of a source file, relative paths start in the import directory.
+///
+/// Sources are configured by users which may not read every file of the daemon user, paths
+/// outside of the import directory are refused once symlinks are resolved. Missing files give
+/// the same error so nothing outside is revealed.
+pub async fn resolve_path(directory: &Path, path: &Path) -> Result<PathBuf, ImportError> {
+    let directory = tokio::fs::canonicalize(directory).await.map_err(|e| {
+        ImportError::Config(format!(
+            "import directory {} can't be read - {e}",
+            directory.display()
+        ))
+    })?;
+
+    match tokio::fs::canonicalize(directory.join(path)).await {
+        Ok(x) if x.starts_with(&directory) => Ok(x),
+        _ => Err(ImportError::Config(format!(
+            "{} isn't a file in the import directory",
+            path.display()
+        ))),
+    }
+}
+
+/// Secret read from a source which has not been written to the database yet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportEntry {
+    pub name: String,
+    pub key: Option<String>,
+    pub description: Option<String>,
+    pub secret: String,
+    pub secret_type: u32,
+}
+
+impl ImportEntry {
+    /// Checks if the entry differs from the stored secret.
+    fn is_changed(&self, secret: &schema::Secret) -> bool {
+        self.key != secret.key
+            || self.description != secret.description
+            || self.secret != secret.secret
+            || self.secret_type != secret.secret_type
+    }
+}
+
+/// Changes needed to bring the secrets of a source in line with what was imported.
+#[derive(Debug, Default)]
+pub struct ImportPlan {
+    /// Entries which don't exist yet.
+    pub create: Vec<ImportEntry>,
+    /// Entries which exist but have changed, with the uuid of the existing secret.
+    pub update: Vec<(uuid::Uuid, ImportEntry)>,
+    /// Existing secrets which are no longer part of the source.
+    pub delete: Vec<schema::Secret>,
+    /// Number of entries which are already up to date.
+    pub unchanged: usize,
+}
+
+impl ImportPlan {
+    /// Compare secrets currently linked to a source with freshly imported entries.
+    /// Entries are matched by name.
+    pub fn new(existing: Vec<schema::Secret>, entries: Vec<ImportEntry>) -> Self {
+        let mut existing: HashMap<String, schema::Secret> =
+            existing.into_iter().map(|x| (x.name.clone(), x)).collect();
+
+        let mut plan = Self::default();
+
+        for entry in entries {
+            match existing.remove(&entry.name) {
+                Some(secret) if entry.is_changed(&secret) => {
+                    plan.update.push((secret.uuid.into_uuid(), entry));
+                }
+                Some(_) => {
+                    plan.unchanged += 1;
+                }
+                None => {
+                    plan.create.push(entry);
+                }
+            }
+        }
+
+        plan.delete = existing.into_values().collect();
+        plan.delete.sort_by(|a, b| a.name.cmp(&b.name));
+
+        plan
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ImportEntry, ImportPlan, resolve_path};
+    use crate::schema::{Secret, SecretType};
+
+    use shared_core::rng;
+    use std::path::{Path, PathBuf};
+
+    fn entry(name: &str, secret: &str) -> ImportEntry {
+        ImportEntry {
+            name: name.into(),
+            key: None,
+            description: None,
+            secret: secret.into(),
+            secret_type: SecretType::Cipher as u32,
+        }
+    }
+
+    #[tokio::test]
+    async fn plan() {
+        let existing = ["unchanged", "changed", "removed"]
+            .iter()
+            .map(|x| Secret::new(*x, None, None, "123", SecretType::Cipher as u32).unwrap())
+            .collect::<Vec<_>>();
+
+        let entries = vec![
+            entry("unchanged", "123"),
+            entry("changed", "456"),
+            entry("created", "789"),
+        ];
+
+        let plan = ImportPlan::new(existing, entries);
+
+        assert_eq!(plan.unchanged, 1);
+        assert_eq!(plan.create.len(), 1);
+        assert_eq!(plan.update.len(), 1);
+        assert_eq!(plan.delete.len(), 1);
+
+        assert_eq!(plan.create[0].name, "created");
+        assert_eq!(plan.update[0].1.name, "changed");
+        assert_eq!(plan.update[0].1.secret, "456");
+        assert_eq!(plan.delete[0].name, "removed");
+    }
+
+    #[tokio::test]
+    async fn resolve() {
+        let directory = PathBuf::from(env!("WORKSPACE_DIR"))
+            .join("test-data")
+            .join("temp");
+
+        let name = format!("{}.csv", rng::random_bytes_str(10));
+        let path = directory.join(&name);
+
+        assert!(tokio::fs::write(&path, "name,secret").await.is_ok());
+
+        let result_1 = resolve_path(&directory, Path::new(&name)).await;
+        let result_2 = resolve_path(&directory, &path).await;
+
+        // Files outside of the directory are refused, whether they exist or not
+        let result_3 = resolve_path(&directory, Path::new("/etc/passwd")).await;
+        let result_4 = resolve_path(&directory, Path::new("../config/empty.toml")).await;
+        let result_5 = resolve_path(&directory, Path::new("missing.csv")).await;
+
+        assert_eq!(result_1.unwrap(), path.canonicalize().unwrap());
+        assert_eq!(result_2.unwrap(), path.canonicalize().unwrap());
+        assert!(result_3.is_err());
+        assert!(result_4.is_err());
+        assert!(result_5.is_err());
+
+        // Symlinks can't point outside of the directory
+        #[cfg(unix)]
+        {
+            let link = directory.join(format!("{}.csv", rng::random_bytes_str(10)));
+
+            assert!(tokio::fs::symlink("/etc/passwd", &link).await.is_ok());
+
+            let result_6 = resolve_path(&directory, &link).await;
+
+            assert!(tokio::fs::remove_file(&link).await.is_ok());
+            assert!(result_6.is_err());
+        }
+
+        assert!(tokio::fs::remove_file(&path).await.is_ok());
+    }
+}
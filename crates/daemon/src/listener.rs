@@ -0,0 +1,158 @@
+use poem::web::RemoteAddr;
+
+/// Name of remote addresses carrying peer credentials.
+const PEER_CREDENTIALS_ADDR: &str = "unix+peercred";
+
+/// Credentials of the process on the other end of a unix socket, read with `SO_PEERCRED`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerCredentials {
+    pub uid: u32,
+    pub gid: u32,
+    pub pid: Option<i32>,
+}
+
+impl PeerCredentials {
+    /// Get credentials of request made through a [`PeerCredentialsListener`].
+    pub fn from_remote_addr(addr: &RemoteAddr) -> Option<Self> {
+        let poem::Addr::Custom(PEER_CREDENTIALS_ADDR, value) = &addr.0 else {
+            return None;
+        };
+
+        let mut parts = value.split(':');
+
+        let uid = parts.next()?.parse().ok()?;
+        let gid = parts.next()?.parse().ok()?;
+        let pid = parts.next()?.parse().ok();
+
+        Some(Self { uid, gid, pid })
+    }
+
+    fn to_remote_addr(self) -> RemoteAddr {
+        let pid = self.pid.map(|x| x.to_string()).unwrap_or_default();
+
+        RemoteAddr(poem::Addr::Custom(
+            PEER_CREDENTIALS_ADDR,
+            format!("{}:{}:{}", self.uid, self.gid, pid).into(),
+        ))
+    }
+}
+
+impl std::fmt::Display for PeerCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "uid={} gid={}", self.uid, self.gid)?;
+
+        if let Some(pid) = self.pid {
+            write!(f, " pid={}", pid)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Unix socket listener which sets the remote address of requests to the peer credentials of
+/// the connecting process.
+#[cfg(unix)]
+pub struct PeerCredentialsListener<T> {
+    listener: poem::listener::UnixListener<T>,
+}
+
+#[cfg(unix)]
+impl<T> PeerCredentialsListener<T> {
+    pub fn bind(path: T) -> Self {
+        Self {
+            listener: poem::listener::UnixListener::bind(path),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl<T: AsRef<std::path::Path> + Send + Clone> poem::listener::Listener
+    for PeerCredentialsListener<T>
+{
+    type Acceptor = PeerCredentialsAcceptor;
+
+    async fn into_acceptor(self) -> std::io::Result<Self::Acceptor> {
+        Ok(PeerCredentialsAcceptor {
+            acceptor: self.listener.into_acceptor().await?,
+        })
+    }
+}
+
+/// Acceptor of [`PeerCredentialsListener`].
+#[cfg(unix)]
+pub struct PeerCredentialsAcceptor {
+    acceptor: poem::listener::UnixAcceptor,
+}
+
+#[cfg(unix)]
+impl poem::listener::Acceptor for PeerCredentialsAcceptor {
+    type Io = tokio::net::UnixStream;
+
+    fn local_addr(&self) -> Vec<poem::web::LocalAddr> {
+        self.acceptor.local_addr()
+    }
+
+    async fn accept(
+        &mut self,
+    ) -> std::io::Result<(
+        Self::Io,
+        poem::web::LocalAddr,
+        RemoteAddr,
+        poem::http::uri::Scheme,
+    )> {
+        let (stream, local_addr, _, scheme) = self.acceptor.accept().await?;
+
+        // Connections are refused if the credentials can't be read.
+        let credentials = stream.peer_cred()?;
+
+        let peer = PeerCredentials {
+            uid: credentials.uid(),
+            gid: credentials.gid(),
+            pid: credentials.pid(),
+        };
+
+        Ok((stream, local_addr, peer.to_remote_addr(), scheme))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::{PeerCredentials, PeerCredentialsListener};
+
+    use poem::listener::{Acceptor, Listener};
+    use shared_core::rng;
+    use std::os::unix::fs::MetadataExt;
+    use std::path::PathBuf;
+
+    #[tokio::test]
+    async fn accept() {
+        let path = PathBuf::from(env!("WORKSPACE_DIR"))
+            .join("test-data")
+            .join("temp")
+            .join(format!("{}.sock", rng::random_bytes_str(10)));
+
+        let mut acceptor = PeerCredentialsListener::bind(path.clone())
+            .into_acceptor()
+            .await
+            .unwrap();
+
+        let (_stream, result) = tokio::join!(tokio::net::UnixStream::connect(&path), async {
+            acceptor.accept().await.unwrap().2
+        });
+
+        let owner = std::fs::metadata(&path).unwrap().uid();
+
+        tokio::fs::remove_file(&path).await.unwrap();
+
+        let peer = PeerCredentials::from_remote_addr(&result).unwrap();
+
+        // Both ends of the socket are this process.
+        assert_eq!(peer.uid, owner);
+        assert_eq!(peer.pid, Some(std::process::id() as i32));
+
+        // Other addresses have no credentials
+        let addr = poem::web::RemoteAddr(poem::Addr::Custom("unknown", "1:2:3".into()));
+
+        assert_eq!(PeerCredentials::from_remote_addr(&addr), None);
+    }
+}
@@ -3,20 +3,27 @@ mod config;
 mod constants;
 mod controller;
 mod error;
+mod importer;
+mod listener;
 mod middleware;
 mod model;
 mod schema;
 mod service;
 
-use crate::client::DaemonClient;
+use crate::client::{DaemonClient, DatabaseKey};
 use crate::config::ConfigManager;
 
 use clap::Parser;
+use shared_core::crypt;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio_util::sync::CancellationToken;
 use tokio_util::task::TaskTracker;
 
+/// Time connections to a sealed daemon get to finish once it's unsealed.
+const SEALED_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// My Vault daemon service.
 #[derive(clap::Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -38,12 +45,32 @@ struct Args {
     /// Disable OpenAPI UI
     #[arg(short, long, env = "MY_VAULT_DISABLE_UI")]
     disable_ui: bool,
+
+    /// Setup token registering the first admin, a random token is printed if not given.
+    /// Only used while no user is registered.
+    #[arg(long, env = "MY_VAULT_SETUP_TOKEN", hide_env_values = true)]
+    setup_token: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Maintenance commands, these exit instead of serving.
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Encrypt the database with a new random key and save it to the config.
+    /// Stop a running daemon first, or use the rekey endpoint while it's running.
+    Rekey {
+        /// Encrypt the backup with the new key instead of the previous one.
+        #[arg(long)]
+        backup_new_key: bool,
+    },
 }
 
 //noinspection DuplicatedCode
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    shared_core::sys::init_tracing_subscriber(&constants::GLOBAL_CACHE_PATH.join("daemon"))?;
+    shared_core::sys::init_tracing_subscriber(&constants::GLOBAL_CACHE_PATH.join("daemon"), true)?;
     constants::create_global_paths().await?;
 
     let args = Args::parse();
@@ -59,22 +86,133 @@ async fn main() -> anyhow::Result<()> {
     )?;
 
     let config = Arc::new(ConfigManager::load().await?);
-    let client = Arc::new(DaemonClient::start(config.clone()).await?);
 
-    let close_fn;
+    run(args, config.clone(), cancellation_token).await?;
 
-    // Start serving our service
-    if args.tcp_socket {
-        let tcp_address = args.tcp_address;
+    // Close signal stream
+    signal_handle.close();
+
+    // Wait for everything to finish before exiting
+    task_tracker.close();
+    task_tracker.wait().await;
+
+    //
+    // IMPORTANT: only do cleanup after task tracker has yield otherwise we might be
+    //            removing resources still being used.
+    //
+
+    if let Err(e) = config.save().await {
+        tracing::warn!("error saving config: {e}")
+    }
+
+    Ok(())
+}
 
-        // Tcp requires no cleanup
-        close_fn = None;
+/// Start the client, unsealing the daemon first if needed, and serve until cancelled.
+async fn run(
+    args: Args,
+    config: Arc<ConfigManager>,
+    cancellation_token: CancellationToken,
+) -> anyhow::Result<()> {
+    // Open api ui is only served over tcp.
+    let enable_ui = args.tcp_socket && !args.disable_ui;
 
-        let tcp = poem::listener::TcpListener::bind(&tcp_address);
-        let app = service::create_services(!args.disable_ui, config.clone(), client).await?;
+    let client = match DaemonClient::load_database_key(&config).await? {
+        DatabaseKey::Key(encryption_key) => {
+            Arc::new(DaemonClient::start(config.clone(), encryption_key).await?)
+        }
+        DatabaseKey::Split(encryption_key, shares) => {
+            let client = DaemonClient::start(config.clone(), encryption_key).await?;
+
+            // Only printed once the database was created with the key.
+            print_shares(&shares);
+
+            Arc::new(client)
+        }
+        DatabaseKey::Sealed if args.command.is_some() => {
+            anyhow::bail!("daemon is sealed, use the endpoints of the unsealed daemon")
+        }
+        DatabaseKey::Sealed => {
+            tracing::warn!("daemon is sealed, unseal it with the database passphrase or shares");
+
+            let controller_seal = controller::ControllerSeal::new(config.clone());
+            let app = service::create_sealed_services(enable_ui, controller_seal.clone()).await?;
+
+            // Connections kept alive must not hold up serving every endpoint.
+            serve(
+                &args,
+                app,
+                async {
+                    tokio::select! {
+                        _ = cancellation_token.cancelled() => {}
+                        _ = controller_seal.unsealed() => {}
+                    }
+                },
+                Some(SEALED_SHUTDOWN_TIMEOUT),
+            )
+            .await?;
+
+            match controller_seal.get_client().await {
+                Some(x) => x,
+                None => return Ok(()),
+            }
+        }
+    };
+
+    if let Some(Command::Rekey { backup_new_key }) = args.command {
+        let rekey = controller::ControllerDatabase::new(config.clone(), client.clone())
+            .rekey(None, backup_new_key)
+            .await?;
+
+        println!("rekeyed database, backup: {}", rekey.backup_file.display());
+
+        // Shares of the previous key no longer open the database.
+        if !rekey.shares.is_empty() {
+            print_shares(&rekey.shares);
+        }
+
+        return Ok(());
+    }
+
+    // Secrets stored before values were encrypted are encrypted before anything reads them.
+    controller::ControllerEncryption::new(client.clone())
+        .seal_plaintext_secrets()
+        .await?;
+
+    // Printed instead of logged so the token doesn't end up in log files.
+    if let Some(setup_token) = controller::ControllerInvite::new(config.clone(), client.clone())
+        .bootstrap(args.setup_token.clone())
+        .await?
+    {
+        println!("setup token: {}", setup_token);
+    }
+
+    let app = service::create_services(enable_ui, config, client).await?;
+
+    serve(&args, app, cancellation_token.cancelled(), None).await
+}
+
+/// Print shares of the database key, printed instead of logged as they are only shown once.
+fn print_shares(shares: &[crypt::SecretShare]) {
+    println!("database key shares, hand each one to a different operator:");
+
+    for share in shares {
+        println!("{}", share);
+    }
+}
+
+/// Serve app over tcp or the unix socket until shutdown completes.
+async fn serve(
+    args: &Args,
+    app: impl poem::Endpoint + 'static,
+    shutdown: impl Future<Output = ()> + Send,
+    shutdown_timeout: Option<Duration>,
+) -> anyhow::Result<()> {
+    if args.tcp_socket {
+        let tcp = poem::listener::TcpListener::bind(&args.tcp_address);
 
         poem::Server::new(tcp)
-            .run_with_graceful_shutdown(app, cancellation_token.cancelled(), None)
+            .run_with_graceful_shutdown(app, shutdown, shutdown_timeout)
             .await?;
     } else {
         #[cfg(unix)]
@@ -85,35 +223,31 @@ async fn main() -> anyhow::Result<()> {
 
             tokio::fs::create_dir_all(&uds_socket_path.parent().unwrap()).await?;
 
+            // Local processes without a token are authenticated by their uid and gid.
+            let uds = listener::PeerCredentialsListener::bind(&uds_socket_path);
+
+            let result = poem::Server::new(uds)
+                .run_with_graceful_shutdown(app, shutdown, shutdown_timeout)
+                .await;
+
             // Remove socket after using it otherwise we will error on startup
             // next time run the daemon.
-            close_fn = Some({
-                let uds_socket_path = uds_socket_path.clone();
-
-                async move {
-                    match tokio::fs::try_exists(&uds_socket_path).await {
-                        Ok(exists) => {
-                            tracing::debug!("does uds socket exist: {exists}");
-                            if let Err(e) = tokio::fs::remove_file(&uds_socket_path).await {
-                                tracing::warn!("could delete socket: {e}");
-                            }
-                        }
-                        Err(e) => {
-                            tracing::warn!(
-                                "io error for socket path {} - {e}",
-                                &uds_socket_path.display(),
-                            )
-                        }
-                    };
+            match tokio::fs::try_exists(&uds_socket_path).await {
+                Ok(exists) => {
+                    tracing::debug!("does uds socket exist: {exists}");
+                    if let Err(e) = tokio::fs::remove_file(&uds_socket_path).await {
+                        tracing::warn!("could delete socket: {e}");
+                    }
                 }
-            });
-
-            let uds = poem::listener::UnixListener::bind(&uds_socket_path);
-            let app = service::create_services(false, config.clone(), client).await?;
+                Err(e) => {
+                    tracing::warn!(
+                        "io error for socket path {} - {e}",
+                        &uds_socket_path.display(),
+                    )
+                }
+            };
 
-            poem::Server::new(uds)
-                .run_with_graceful_shutdown(app, cancellation_token.cancelled(), None)
-                .await?;
+            result?;
         }
 
         #[cfg(not(unix))]
@@ -122,25 +256,5 @@ async fn main() -> anyhow::Result<()> {
         }
     }
 
-    // Close signal stream
-    signal_handle.close();
-
-    // Wait for everything to finish before exiting
-    task_tracker.close();
-    task_tracker.wait().await;
-
-    //
-    // IMPORTANT: only do cleanup after task tracker has yield otherwise we might be
-    //            removing resources still being used.
-    //
-
-    if let Err(e) = config.save().await {
-        tracing::warn!("error saving config: {e}")
-    }
-
-    if let Some(close_fn) = close_fn {
-        close_fn.await;
-    }
-
     Ok(())
 }
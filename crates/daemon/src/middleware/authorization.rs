@@ -1,3 +1,5 @@
+use crate::controller;
+
 use shared_core::crypt;
 use std::str::FromStr;
 
@@ -6,9 +8,11 @@ use std::str::FromStr;
 pub struct User {
     pub uuid: uuid::Uuid,
     pub username: String,
+    /// Api key the request was authorized with, permissions are limited to the key's grants.
+    pub api_key: Option<uuid::Uuid>,
 }
 
-/// Jwt authorization scheme.
+/// Jwt authorization scheme, service accounts send an api key instead of a JWT.
 #[derive(poem_openapi::SecurityScheme)]
 #[oai(
     ty = "bearer",
@@ -28,6 +32,30 @@ async fn check_jwt(
         poem::http::StatusCode::INTERNAL_SERVER_ERROR,
     ))?;
 
+    // Local processes are authenticated by the peer credentials of their unix socket.
+    if bearer.token == super::PEER_CREDENTIALS_TOKEN {
+        return check_peer(request, data).await;
+    }
+
+    // Api keys are told apart from JWTs by their prefix.
+    if let Some(key) = crypt::ApiKey::parse(&bearer.token) {
+        let user = controller::ControllerServiceAccount::new(data.client.clone())
+            .authenticate(&key)
+            .await
+            .map_err(|e| match e {
+                crate::error::ServiceError::PermissionDenied(_) => {
+                    poem::Error::from_status(poem::http::StatusCode::UNAUTHORIZED)
+                }
+                e => e.into(),
+            })?;
+
+        return Ok(User {
+            uuid: user.uuid.into_uuid(),
+            username: user.username,
+            api_key: Some(key.id),
+        });
+    }
+
     // Make sure JWT is valid
     let jwt_decode = data
         .client
@@ -40,5 +68,40 @@ async fn check_jwt(
             .unwrap()
             .into_uuid(),
         username: jwt_decode.username,
+        api_key: None,
+    })
+}
+
+/// Authenticate request by the peer credentials of its unix socket.
+async fn check_peer(request: &poem::Request, data: &super::MiddlewareData) -> poem::Result<User> {
+    let peer = crate::listener::PeerCredentials::from_remote_addr(request.remote_addr()).ok_or(
+        poem::Error::from_status(poem::http::StatusCode::UNAUTHORIZED),
+    )?;
+
+    let user = controller::ControllerUser::new(data.config.clone(), data.client.clone())
+        .authenticate_peer(peer.uid, peer.gid)
+        .await
+        .map_err(|e| match e {
+            crate::error::ServiceError::PermissionDenied(_) => {
+                tracing::warn!(target: "audit", "peer {} denied: {}", peer, e);
+
+                poem::Error::from_status(poem::http::StatusCode::UNAUTHORIZED)
+            }
+            e => e.into(),
+        })?;
+
+    tracing::info!(
+        target: "audit",
+        "peer {} authenticated as {}: {} {}",
+        peer,
+        &user.username,
+        request.method(),
+        request.uri().path()
+    );
+
+    Ok(User {
+        uuid: user.uuid.into_uuid(),
+        username: user.username,
+        api_key: None,
     })
 }
@@ -1,9 +1,13 @@
 mod authorization;
+mod peer_auth;
+mod policy;
 mod set_header;
 
 use std::sync::Arc;
 
 pub use authorization::*;
+pub use peer_auth::*;
+pub use policy::*;
 pub use set_header::*;
 
 /// Data being passed to all middleware
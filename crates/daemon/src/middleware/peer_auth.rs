@@ -0,0 +1,52 @@
+use crate::listener::PeerCredentials;
+
+/// Bearer token standing in for the peer credentials of requests without a token.
+pub const PEER_CREDENTIALS_TOKEN: &str = "peer-credentials";
+
+/// Authorization header of [`PEER_CREDENTIALS_TOKEN`].
+const PEER_CREDENTIALS_HEADER: &str = "Bearer peer-credentials";
+
+/// Authenticate unix socket requests without a token by their peer credentials.
+///
+/// The security scheme needs an authorization header, so requests carrying peer credentials
+/// get [`PEER_CREDENTIALS_TOKEN`] as bearer token which [`super::JwtAuthorization`] resolves
+/// to the user mapped to the peer.
+pub struct PeerAuthentication;
+
+impl PeerAuthentication {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl<E: poem::Endpoint> poem::Middleware<E> for PeerAuthentication {
+    type Output = PeerAuthenticationEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        PeerAuthenticationEndpoint { ep }
+    }
+}
+
+/// Endpoint of [`PeerAuthentication`].
+pub struct PeerAuthenticationEndpoint<E> {
+    ep: E,
+}
+
+impl<E: poem::Endpoint> poem::Endpoint for PeerAuthenticationEndpoint<E> {
+    type Output = E::Output;
+
+    async fn call(&self, mut req: poem::Request) -> poem::Result<Self::Output> {
+        if !req
+            .headers()
+            .contains_key(poem::http::header::AUTHORIZATION)
+            && PeerCredentials::from_remote_addr(req.remote_addr()).is_some()
+        {
+            req.headers_mut().insert(
+                poem::http::header::AUTHORIZATION,
+                poem::http::HeaderValue::from_static(PEER_CREDENTIALS_HEADER),
+            );
+        }
+
+        self.ep.call(req).await
+    }
+}
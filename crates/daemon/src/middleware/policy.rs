@@ -0,0 +1,229 @@
+use crate::client::DaemonClient;
+use crate::{error, model, schema};
+
+use std::sync::Arc;
+
+/// Policy checking permissions of authorized users.
+///
+/// Services check the policy with the user from [`super::JwtAuthorization`] before calling
+/// into a controller.
+#[derive(Debug, Clone)]
+pub struct Policy {
+    client: Arc<DaemonClient>,
+}
+
+impl Policy {
+    pub fn new(client: Arc<DaemonClient>) -> Self {
+        Self { client }
+    }
+
+    /// Make sure user has permission on resource.
+    pub async fn check(
+        &self,
+        user: &super::User,
+        permission: schema::Permission,
+        resource: schema::Resource,
+    ) -> Result<(), error::ServiceError> {
+        let allowed = model::ModelGrant::has_permission(
+            self.client.get_database().get_pool(),
+            user.uuid,
+            user.api_key,
+            permission,
+            resource,
+        )
+        .await
+        .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        if !allowed {
+            return Err(error::ServiceError::PermissionDenied(format!(
+                "user {} is missing {} permission",
+                &user.username, permission
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Get uuids of the secrets user has permission on, `None` if user has it on every secret.
+    pub async fn permitted_secrets(
+        &self,
+        user: &super::User,
+        permission: schema::Permission,
+    ) -> Result<Option<Vec<uuid::Uuid>>, error::ServiceError> {
+        let pool = self.client.get_database().get_pool();
+
+        let global = model::ModelGrant::has_permission(
+            pool,
+            user.uuid,
+            user.api_key,
+            permission,
+            schema::Resource::Global,
+        )
+        .await
+        .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        if global {
+            return Ok(None);
+        }
+
+        let result =
+            model::ModelGrant::get_permitted_secrets(pool, user.uuid, user.api_key, permission)
+                .await
+                .map_err(|e| error::ServiceError::Internal(e.to_string()))?;
+
+        Ok(Some(result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::controller::{
+        ControllerCollection, ControllerGrant, ControllerServiceAccount, ControllerUser,
+    };
+    use crate::middleware::{Policy, User};
+    use crate::schema::{Permission, Resource, ScopeType};
+    use crate::{client, config, error, model};
+
+    use sqlx::sqlite;
+    use std::sync::Arc;
+
+    #[sqlx::test]
+    async fn check(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let config = Arc::new(config::ConfigManager::mocked());
+        let client = Arc::new(
+            client::DaemonClient::mocked(pool)
+                .await
+                .expect("could not create mocked client"),
+        );
+
+        let policy = Policy::new(client.clone());
+        let controller_user = ControllerUser::new(config, client.clone());
+        let controller_grant = ControllerGrant::new(client.clone());
+        let controller_collection = ControllerCollection::new(client.clone());
+
+        // First user is admin
+        controller_user
+            .add("admin".into(), "admin-password".into())
+            .await
+            .unwrap();
+        controller_user
+            .add("carl".into(), "carl-loves-cars1234".into())
+            .await
+            .unwrap();
+
+        let pool = client.get_database().get_pool();
+        let user = async |username: &str| User {
+            uuid: model::ModelUser::get_user_from_username(pool, username.into())
+                .await
+                .unwrap()
+                .uuid
+                .into_uuid(),
+            username: username.into(),
+            api_key: None,
+        };
+
+        let admin = user("admin").await;
+        let carl = user("carl").await;
+
+        let collection = controller_collection
+            .add("project".into())
+            .await
+            .unwrap()
+            .uuid
+            .into_uuid();
+
+        let result_1 = policy
+            .check(&admin, Permission::Manage, Resource::Global)
+            .await;
+        let result_2 = policy
+            .check(&carl, Permission::Read, Resource::Collection(collection))
+            .await;
+
+        assert!(result_1.is_ok());
+        assert!(matches!(
+            result_2,
+            Err(error::ServiceError::PermissionDenied(_))
+        ));
+
+        controller_grant
+            .add(
+                "carl".into(),
+                "reader".into(),
+                ScopeType::Collection as u32,
+                Some(collection),
+            )
+            .await
+            .unwrap();
+
+        let result_3 = policy
+            .check(&carl, Permission::Read, Resource::Collection(collection))
+            .await;
+        let result_4 = policy
+            .check(&carl, Permission::Write, Resource::Collection(collection))
+            .await;
+
+        assert!(result_3.is_ok());
+        assert!(matches!(
+            result_4,
+            Err(error::ServiceError::PermissionDenied(_))
+        ));
+
+        // Users without global grants only get the secrets their scopes cover.
+        let result_5 = policy.permitted_secrets(&admin, Permission::Read).await;
+        let result_6 = policy.permitted_secrets(&carl, Permission::Read).await;
+
+        assert_eq!(result_5.unwrap(), None);
+        assert_eq!(result_6.unwrap(), Some(vec![]));
+
+        // Api keys only get the grants scoping them.
+        let controller_service_account = ControllerServiceAccount::new(client.clone());
+
+        controller_service_account
+            .add("ci-runner".into())
+            .await
+            .unwrap();
+
+        let (api_key, _) = controller_service_account
+            .add_key(
+                "ci-runner".into(),
+                "deploy".into(),
+                "reader".into(),
+                vec![(ScopeType::Collection as u32, Some(collection))],
+                None,
+            )
+            .await
+            .unwrap();
+
+        let ci_runner = User {
+            api_key: Some(api_key.uuid.into_uuid()),
+            ..user("ci-runner").await
+        };
+
+        let result_7 = policy
+            .check(
+                &ci_runner,
+                Permission::Read,
+                Resource::Collection(collection),
+            )
+            .await;
+        let result_8 = policy
+            .check(&ci_runner, Permission::Read, Resource::Global)
+            .await;
+        let result_9 = policy
+            .check(
+                &User {
+                    api_key: None,
+                    ..ci_runner.clone()
+                },
+                Permission::Read,
+                Resource::Collection(collection),
+            )
+            .await;
+
+        assert!(result_7.is_ok());
+        assert!(result_8.is_err());
+        assert!(result_9.is_err());
+
+        Ok(())
+    }
+}
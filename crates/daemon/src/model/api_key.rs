@@ -0,0 +1,254 @@
+use crate::schema;
+use shared_core::database::{self, Table};
+
+pub struct ModelApiKey;
+
+impl ModelApiKey {
+    /// Checks if api key with uuid exists.
+    pub fn does_api_key_exist_uuid<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, bool> {
+        // Database stores uuid in hyphenated form.
+        let filter = vec![(
+            schema::ApiKey::COLUMN_UUID,
+            uuid.as_hyphenated().to_string(),
+        )];
+        schema::ApiKey::exists(executor, filter)
+    }
+
+    /// Checks if user already has an api key with name.
+    pub fn does_api_key_exist<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid_user: uuid::Uuid,
+        name: String,
+    ) -> database::DatabaseFuture<'c, bool> {
+        let filter = vec![
+            (
+                schema::ApiKey::COLUMN_UUID_USER,
+                uuid_user.as_hyphenated().to_string(),
+            ),
+            (schema::ApiKey::COLUMN_NAME, name),
+        ];
+        schema::ApiKey::exists(executor, filter)
+    }
+
+    /// Get api key from uuid.
+    pub fn get_api_key_from_uuid<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, schema::ApiKey> {
+        let filter = vec![(
+            schema::ApiKey::COLUMN_UUID,
+            uuid.as_hyphenated().to_string(),
+        )];
+        schema::ApiKey::read(executor, filter)
+    }
+
+    /// Get every api key of user sorted by name.
+    pub fn get_user_api_keys<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid_user: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, Vec<schema::ApiKey>> {
+        Box::pin(async move {
+            let query = database::ListQuery::new()
+                .filter(database::Filter::eq(
+                    schema::ApiKey::COLUMN_UUID_USER,
+                    uuid_user.as_hyphenated().to_string(),
+                ))
+                .order(database::Order::asc(schema::ApiKey::COLUMN_NAME));
+
+            let result = schema::ApiKey::list(executor, query).await?;
+
+            Ok(result.items)
+        })
+    }
+
+    /// Add a new api key together with the grants scoping it.
+    pub fn add_api_key<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        api_key: schema::ApiKey,
+        grants: Vec<schema::Grant>,
+    ) -> database::DatabaseFuture<'c, schema::ApiKey> {
+        Box::pin(async move {
+            let mut tx = executor.begin().await?;
+
+            let result = schema::ApiKey::create(&mut *tx, api_key).await?;
+
+            for grant in grants {
+                super::ModelGrant::add_grant(&mut *tx, grant).await?;
+            }
+
+            tx.commit().await?;
+
+            Ok(result)
+        })
+    }
+
+    /// Replace hash of api key, keys issued before are no longer valid.
+    pub fn rotate_api_key<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+        key_hash: String,
+    ) -> database::DatabaseFuture<'c, ()> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let rows_affected = sqlx::query(
+                "UPDATE api_keys
+                    SET key_hash = ?, updated_at = DATETIME('NOW')
+                    WHERE uuid = ?",
+            )
+            .bind(key_hash)
+            .bind(uuid.as_hyphenated().to_string())
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+
+            if rows_affected == 0 {
+                return Err(sqlx::error::Error::RowNotFound.into());
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Record that api key was used.
+    pub fn touch_api_key<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, ()> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            sqlx::query("UPDATE api_keys SET last_used_at = DATETIME('NOW') WHERE uuid = ?")
+                .bind(uuid.as_hyphenated().to_string())
+                .execute(&mut *conn)
+                .await?;
+
+            Ok(())
+        })
+    }
+
+    /// Delete api key with uuid and the grants scoping it.
+    pub fn delete_api_key<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, ()> {
+        Box::pin(async move {
+            let mut tx = executor.begin().await?;
+
+            sqlx::query("DELETE FROM grants WHERE uuid_api_key = ?")
+                .bind(uuid.as_hyphenated().to_string())
+                .execute(&mut *tx)
+                .await?;
+
+            let filter = vec![(
+                schema::ApiKey::COLUMN_UUID,
+                uuid.as_hyphenated().to_string(),
+            )];
+            schema::ApiKey::delete(&mut *tx, filter).await?;
+
+            tx.commit().await?;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{ModelApiKey, ModelGrant, ModelUser};
+    use crate::schema::{ApiKey, Grant, Permission, Resource, ScopeType, User};
+
+    use shared_core::crypt;
+    use sqlx::sqlite;
+
+    #[sqlx::test]
+    async fn add_api_key(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let user = User {
+            uuid: uuid::Uuid::new_v4().into(),
+            username: "ci-runner".into(),
+            service: Some(true),
+            ..User::default()
+        };
+        let uuid_user = ModelUser::add_user(&pool, user)
+            .await
+            .unwrap()
+            .uuid
+            .into_uuid();
+
+        let key = crypt::ApiKey::generate(uuid::Uuid::new_v4());
+        let api_key = ApiKey::new(&key, uuid_user, "deploy", None).unwrap();
+        let grant = Grant {
+            uuid_api_key: Some(key.id.into()),
+            ..Grant::new(uuid_user, "reader", ScopeType::Global, None)
+        };
+
+        let result_add = ModelApiKey::add_api_key(&pool, api_key, vec![grant]).await;
+
+        assert!(result_add.is_ok());
+        assert!(
+            ModelApiKey::does_api_key_exist(&pool, uuid_user, "deploy".into())
+                .await
+                .unwrap()
+        );
+
+        // Key grants are not grants of the user itself.
+        let result_1 = ModelGrant::get_user_grants(&pool, uuid_user).await;
+        let result_2 = ModelGrant::get_api_key_grants(&pool, key.id).await;
+
+        assert_eq!(result_1.unwrap().len(), 0);
+        assert_eq!(result_2.unwrap().len(), 1);
+
+        let check = async |uuid_api_key| {
+            ModelGrant::has_permission(
+                &pool,
+                uuid_user,
+                uuid_api_key,
+                Permission::Read,
+                Resource::Global,
+            )
+            .await
+            .unwrap()
+        };
+
+        assert!(check(Some(key.id)).await);
+        assert!(!check(None).await);
+        assert!(!check(Some(uuid::Uuid::new_v4())).await);
+
+        // Rotating replaces the hash.
+        let key_rotated = crypt::ApiKey::generate(key.id);
+
+        ModelApiKey::rotate_api_key(&pool, key.id, key_rotated.hash())
+            .await
+            .unwrap();
+        ModelApiKey::touch_api_key(&pool, key.id).await.unwrap();
+
+        let result_3 = ModelApiKey::get_api_key_from_uuid(&pool, key.id)
+            .await
+            .unwrap();
+
+        assert!(key_rotated.verify(&result_3.key_hash));
+        assert!(!key.verify(&result_3.key_hash));
+        assert!(result_3.last_used_at.is_some());
+
+        // Deleting removes the grants as well.
+        assert!(ModelApiKey::delete_api_key(&pool, key.id).await.is_ok());
+        assert!(
+            !ModelApiKey::does_api_key_exist_uuid(&pool, key.id)
+                .await
+                .unwrap()
+        );
+        assert!(!check(Some(key.id)).await);
+        assert_eq!(
+            ModelApiKey::get_user_api_keys(&pool, uuid_user)
+                .await
+                .unwrap()
+                .len(),
+            0
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,365 @@
+use crate::schema;
+use shared_core::database::{self, Table};
+
+pub struct ModelCollection;
+
+impl ModelCollection {
+    /// Checks if collection with name exists.
+    pub fn does_collection_exist<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        name: String,
+    ) -> database::DatabaseFuture<'c, bool> {
+        let filter = vec![(schema::Collection::COLUMN_NAME, name)];
+        schema::Collection::exists(executor, filter)
+    }
+
+    /// Checks if collection with uuid exists.
+    pub fn does_collection_exist_uuid<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, bool> {
+        // Database stores uuid in hyphenated form.
+        let filter = vec![(
+            schema::Collection::COLUMN_UUID,
+            uuid.as_hyphenated().to_string(),
+        )];
+        schema::Collection::exists(executor, filter)
+    }
+
+    /// Get collection from uuid.
+    pub fn get_collection_from_uuid<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, schema::Collection> {
+        let filter = vec![(
+            schema::Collection::COLUMN_UUID,
+            uuid.as_hyphenated().to_string(),
+        )];
+        schema::Collection::read(executor, filter)
+    }
+
+    /// List collections.
+    pub fn get_collections<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        query: database::ListQuery,
+    ) -> database::DatabaseFuture<'c, database::Page<schema::Collection>> {
+        schema::Collection::list(executor, query)
+    }
+
+    /// Add a new collection.
+    pub fn add_collection<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        collection: schema::Collection,
+    ) -> database::DatabaseFuture<'c, schema::Collection> {
+        schema::Collection::create(executor, collection)
+    }
+
+    /// Update an existing collection.
+    /// The collection being updated is identified by the uuid of the given collection.
+    pub fn update_collection<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        collection: schema::Collection,
+    ) -> database::DatabaseFuture<'c, schema::Collection> {
+        schema::Collection::update(executor, collection)
+    }
+
+    /// Delete collection with uuid.
+    /// Any sources attached to the collection are detached and grants on it removed first.
+    pub fn delete_collection<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, ()> {
+        Box::pin(async move {
+            let mut tx = executor.begin().await?;
+
+            sqlx::query("DELETE FROM collection_source WHERE uuid_collection = ?")
+                .bind(uuid.as_hyphenated().to_string())
+                .execute(&mut *tx)
+                .await?;
+
+            super::ModelGrant::delete_scope_grants(&mut *tx, schema::ScopeType::Collection, uuid)
+                .await?;
+
+            let filter = vec![(
+                schema::Collection::COLUMN_UUID,
+                uuid.as_hyphenated().to_string(),
+            )];
+            schema::Collection::delete(&mut *tx, filter).await?;
+
+            tx.commit().await?;
+
+            Ok(())
+        })
+    }
+
+    /// Checks if source is attached to collection.
+    pub fn is_source_attached<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid_collection: uuid::Uuid,
+        uuid_source: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, bool> {
+        let filter = vec![
+            (
+                schema::CollectionSource::COLUMN_UUID_COLLECTION,
+                uuid_collection.as_hyphenated().to_string(),
+            ),
+            (
+                schema::CollectionSource::COLUMN_UUID_SOURCE,
+                uuid_source.as_hyphenated().to_string(),
+            ),
+        ];
+        schema::CollectionSource::exists(executor, filter)
+    }
+
+    /// Attach source to collection.
+    pub fn attach_source<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid_collection: uuid::Uuid,
+        uuid_source: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, schema::CollectionSource> {
+        let data = schema::CollectionSource::new(uuid_collection, uuid_source);
+        schema::CollectionSource::create(executor, data)
+    }
+
+    /// Detach source from collection.
+    pub fn detach_source<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid_collection: uuid::Uuid,
+        uuid_source: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, ()> {
+        let filter = vec![
+            (
+                schema::CollectionSource::COLUMN_UUID_COLLECTION,
+                uuid_collection.as_hyphenated().to_string(),
+            ),
+            (
+                schema::CollectionSource::COLUMN_UUID_SOURCE,
+                uuid_source.as_hyphenated().to_string(),
+            ),
+        ];
+        schema::CollectionSource::delete(executor, filter)
+    }
+
+    /// Get uuid of every source attached to collection.
+    pub fn get_collection_sources<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, Vec<schema::CollectionSource>> {
+        Box::pin(async move {
+            let query = database::ListQuery::new().filter(database::Filter::eq(
+                schema::CollectionSource::COLUMN_UUID_COLLECTION,
+                uuid.as_hyphenated().to_string(),
+            ));
+
+            let result = schema::CollectionSource::list(executor, query).await?;
+
+            Ok(result.items)
+        })
+    }
+
+    /// Get every secret reachable from collection through its sources.
+    pub fn get_collection_secrets<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, Vec<schema::Secret>> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let result = sqlx::query_as(
+                "SELECT DISTINCT s.uuid, s.name, s.key, s.description, s.secret, s.secret_type,
+                    s.uuid_data_key
+                    FROM secrets s
+                    INNER JOIN source_secrets ss ON ss.uuid_secret = s.uuid
+                    INNER JOIN collection_source cs ON cs.uuid_source = ss.uuid_source
+                    WHERE cs.uuid_collection = ?
+                    ORDER BY s.name",
+            )
+            .bind(uuid.as_hyphenated().to_string())
+            .fetch_all(&mut *conn)
+            .await?;
+
+            Ok(result)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{ModelCollection, ModelSecret, ModelSource};
+    use crate::schema::{Collection, Secret, SecretType, Source, SourceAuthType, SourceType};
+
+    use shared_core::database;
+    use sqlx::sqlite;
+
+    /// Add source with secrets linked to it.
+    async fn add_source(pool: &sqlite::SqlitePool, name: &str, secrets: &[&str]) -> uuid::Uuid {
+        let source = Source::new(
+            name,
+            None,
+            SourceType::Csv as u32,
+            None,
+            None,
+            SourceAuthType::None as u32,
+        )
+        .unwrap();
+        let source = ModelSource::add_source(pool, source).await.unwrap();
+
+        for secret in secrets {
+            let secret =
+                Secret::new(*secret, None, None, "secret", SecretType::Cipher as u32).unwrap();
+            let secret = ModelSecret::add_secret(pool, secret).await.unwrap();
+
+            ModelSource::link_secret(pool, source.uuid.into_uuid(), secret.uuid.into_uuid())
+                .await
+                .unwrap();
+        }
+
+        source.uuid.into_uuid()
+    }
+
+    #[sqlx::test]
+    async fn does_collection_exist(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let result_1 = ModelCollection::does_collection_exist(&pool, "project".into()).await;
+
+        assert!(result_1.is_ok());
+        assert!(!result_1.unwrap());
+
+        let collection = Collection::new("project").unwrap();
+        let result_add = ModelCollection::add_collection(&pool, collection).await;
+
+        assert!(result_add.is_ok());
+
+        let result_2 = ModelCollection::does_collection_exist(&pool, "project".into()).await;
+        let result_3 = ModelCollection::does_collection_exist_uuid(
+            &pool,
+            result_add.unwrap().uuid.into_uuid(),
+        )
+        .await;
+
+        assert!(result_2.is_ok());
+        assert!(result_3.is_ok());
+        assert!(result_2.unwrap());
+        assert!(result_3.unwrap());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn update_collection(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let collection = Collection::new("project").unwrap();
+        let result_add = ModelCollection::add_collection(&pool, collection).await;
+
+        assert!(result_add.is_ok());
+
+        let collection = Collection {
+            name: "renamed-project".into(),
+            ..result_add.unwrap()
+        };
+        let result_1 = ModelCollection::update_collection(&pool, collection).await;
+
+        assert!(result_1.is_ok());
+
+        let result_1 = result_1.unwrap();
+        let result_2 =
+            ModelCollection::get_collection_from_uuid(&pool, result_1.uuid.into_uuid()).await;
+
+        assert!(result_2.is_ok());
+        assert_eq!(result_2.unwrap().name, "renamed-project");
+
+        let result_3 = ModelCollection::get_collections(&pool, database::ListQuery::new()).await;
+
+        assert!(result_3.is_ok());
+        assert_eq!(result_3.unwrap().items.len(), 1);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn attach_source(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let collection = Collection::new("project").unwrap();
+        let collection = ModelCollection::add_collection(&pool, collection)
+            .await
+            .unwrap();
+
+        let uuid_collection = collection.uuid.into_uuid();
+        let uuid_source = add_source(&pool, "source", &[]).await;
+
+        let result_1 =
+            ModelCollection::is_source_attached(&pool, uuid_collection, uuid_source).await;
+
+        assert!(result_1.is_ok());
+        assert!(!result_1.unwrap());
+
+        let result_attach =
+            ModelCollection::attach_source(&pool, uuid_collection, uuid_source).await;
+
+        assert!(result_attach.is_ok());
+
+        let result_2 =
+            ModelCollection::is_source_attached(&pool, uuid_collection, uuid_source).await;
+        let result_3 = ModelCollection::get_collection_sources(&pool, uuid_collection).await;
+
+        assert!(result_2.is_ok());
+        assert!(result_3.is_ok());
+        assert!(result_2.unwrap());
+        assert_eq!(result_3.unwrap().len(), 1);
+
+        let result_detach_1 =
+            ModelCollection::detach_source(&pool, uuid_collection, uuid_source).await;
+        let result_detach_2 =
+            ModelCollection::detach_source(&pool, uuid_collection, uuid_source).await;
+
+        assert!(result_detach_1.is_ok());
+        assert!(result_detach_2.is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn get_collection_secrets(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let collection = Collection::new("project").unwrap();
+        let collection = ModelCollection::add_collection(&pool, collection)
+            .await
+            .unwrap();
+
+        let uuid_collection = collection.uuid.into_uuid();
+        let uuid_source_1 = add_source(&pool, "source-1", &["secret-b", "secret-a"]).await;
+        let uuid_source_2 = add_source(&pool, "source-2", &["secret-c"]).await;
+
+        // Source which is never attached to the collection.
+        add_source(&pool, "source-3", &["secret-d"]).await;
+
+        let result_1 = ModelCollection::get_collection_secrets(&pool, uuid_collection).await;
+
+        assert!(result_1.is_ok());
+        assert!(result_1.unwrap().is_empty());
+
+        ModelCollection::attach_source(&pool, uuid_collection, uuid_source_1)
+            .await
+            .unwrap();
+        ModelCollection::attach_source(&pool, uuid_collection, uuid_source_2)
+            .await
+            .unwrap();
+
+        let result_2 = ModelCollection::get_collection_secrets(&pool, uuid_collection).await;
+
+        assert!(result_2.is_ok());
+
+        let result_2 = result_2.unwrap();
+
+        assert_eq!(result_2.len(), 3);
+        assert_eq!(result_2[0].name, "secret-a");
+        assert_eq!(result_2[1].name, "secret-b");
+        assert_eq!(result_2[2].name, "secret-c");
+
+        // Deleting the collection also detaches its sources.
+        let result_delete = ModelCollection::delete_collection(&pool, uuid_collection).await;
+        let result_3 = ModelCollection::get_collection_sources(&pool, uuid_collection).await;
+
+        assert!(result_delete.is_ok());
+        assert!(result_3.is_ok());
+        assert!(result_3.unwrap().is_empty());
+
+        Ok(())
+    }
+}
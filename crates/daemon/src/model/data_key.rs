@@ -0,0 +1,206 @@
+use crate::schema;
+use shared_core::database::{self, Table};
+
+pub struct ModelDataKey;
+
+impl ModelDataKey {
+    /// Get data key from uuid.
+    pub fn get_data_key_from_uuid<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, schema::DataKey> {
+        // Database stores uuid in hyphenated form.
+        let filter = vec![(
+            schema::DataKey::COLUMN_UUID,
+            uuid.as_hyphenated().to_string(),
+        )];
+        schema::DataKey::read(executor, filter)
+    }
+
+    /// Get data key of collection, or the default data key if no collection is given.
+    /// Returns `None` if the data key wasn't created yet.
+    pub fn get_collection_data_key<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid_collection: Option<uuid::Uuid>,
+    ) -> database::DatabaseFuture<'c, Option<schema::DataKey>> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let result = sqlx::query_as(
+                "SELECT * FROM data_keys WHERE uuid_collection IS ? AND retired = FALSE",
+            )
+            .bind(uuid_collection.map(|x| x.as_hyphenated().to_string()))
+            .fetch_optional(&mut *conn)
+            .await?;
+
+            Ok(result)
+        })
+    }
+
+    /// Add data key unless its collection already has one, returns the data key the collection
+    /// ends up with. Concurrent first use of a collection therefore agrees on one data key.
+    pub fn get_or_add_collection_data_key<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        data_key: schema::DataKey,
+    ) -> database::DatabaseFuture<'c, schema::DataKey> {
+        Box::pin(async move {
+            let mut tx = executor.begin().await?;
+
+            sqlx::query(
+                "INSERT INTO data_keys (uuid, uuid_collection, master_key_id, wrapped_key)
+                    VALUES (?, ?, ?, ?)
+                    ON CONFLICT DO NOTHING",
+            )
+            .bind(data_key.uuid.to_string())
+            .bind(data_key.uuid_collection.map(|x| x.to_string()))
+            .bind(data_key.master_key_id)
+            .bind(data_key.wrapped_key)
+            .execute(&mut *tx)
+            .await?;
+
+            let result = sqlx::query_as(
+                "SELECT * FROM data_keys WHERE uuid_collection IS ? AND retired = FALSE",
+            )
+            .bind(data_key.uuid_collection.map(|x| x.to_string()))
+            .fetch_one(&mut *tx)
+            .await?;
+
+            tx.commit().await?;
+
+            Ok(result)
+        })
+    }
+
+    /// Get every data key.
+    pub fn get_data_keys<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+    ) -> database::DatabaseFuture<'c, Vec<schema::DataKey>> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let result = sqlx::query_as("SELECT * FROM data_keys ORDER BY created_at, ROWID")
+                .fetch_all(&mut *conn)
+                .await?;
+
+            Ok(result)
+        })
+    }
+
+    /// Update wrapped keys of data keys in one transaction, used when the master key rotates.
+    pub fn update_data_keys<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        data_keys: Vec<schema::DataKey>,
+    ) -> database::DatabaseFuture<'c, ()> {
+        Box::pin(async move {
+            let mut tx = executor.begin().await?;
+
+            for data_key in data_keys {
+                sqlx::query(
+                    "UPDATE data_keys SET master_key_id = ?, wrapped_key = ? WHERE uuid = ?",
+                )
+                .bind(data_key.master_key_id)
+                .bind(data_key.wrapped_key)
+                .bind(data_key.uuid.to_string())
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            tx.commit().await?;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{ModelCollection, ModelDataKey};
+    use crate::schema::{Collection, DataKey};
+
+    use shared_core::database::Table;
+    use sqlx::sqlite;
+
+    #[sqlx::test]
+    async fn get_collection_data_key(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let collection = Collection::new("project").unwrap();
+        let collection = ModelCollection::add_collection(&pool, collection)
+            .await
+            .unwrap();
+        let uuid_collection = collection.uuid.into_uuid();
+
+        let result_1 = ModelDataKey::get_collection_data_key(&pool, None).await;
+        let result_2 = ModelDataKey::get_collection_data_key(&pool, Some(uuid_collection)).await;
+
+        assert!(result_1.unwrap().is_none());
+        assert!(result_2.unwrap().is_none());
+
+        let data_key_default = DataKey::new(None, "id-1".into(), "key-1".into());
+        let data_key_collection =
+            DataKey::new(Some(uuid_collection), "id-1".into(), "key-2".into());
+
+        ModelDataKey::get_or_add_collection_data_key(&pool, data_key_default)
+            .await
+            .unwrap();
+        ModelDataKey::get_or_add_collection_data_key(&pool, data_key_collection)
+            .await
+            .unwrap();
+
+        // Collections and the default only have one data key
+        let result_3 = DataKey::create(
+            &pool,
+            DataKey::new(Some(uuid_collection), "id-1".into(), "key-3".into()),
+        )
+        .await;
+        let result_4 =
+            DataKey::create(&pool, DataKey::new(None, "id-1".into(), "key-3".into())).await;
+
+        assert!(result_3.is_err());
+        assert!(result_4.is_err());
+
+        // Adding when a data key exists keeps the existing one
+        let result_5 = ModelDataKey::get_or_add_collection_data_key(
+            &pool,
+            DataKey::new(None, "id-1".into(), "key-3".into()),
+        )
+        .await;
+
+        assert_eq!(result_5.unwrap().wrapped_key, "key-1");
+
+        let result_6 = ModelDataKey::get_collection_data_key(&pool, None)
+            .await
+            .unwrap()
+            .unwrap();
+        let result_7 = ModelDataKey::get_collection_data_key(&pool, Some(uuid_collection))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(result_6.wrapped_key, "key-1");
+        assert_eq!(result_7.wrapped_key, "key-2");
+
+        // Rewrapping replaces the wrapped key of every data key
+        let data_keys = ModelDataKey::get_data_keys(&pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|x| DataKey {
+                master_key_id: "id-2".into(),
+                wrapped_key: format!("{}-rewrapped", x.wrapped_key),
+                ..x
+            })
+            .collect();
+
+        ModelDataKey::update_data_keys(&pool, data_keys)
+            .await
+            .unwrap();
+
+        let result_8 = ModelDataKey::get_data_key_from_uuid(&pool, result_7.uuid.into_uuid())
+            .await
+            .unwrap();
+
+        assert_eq!(result_8.master_key_id, "id-2");
+        assert_eq!(result_8.wrapped_key, "key-2-rewrapped");
+
+        Ok(())
+    }
+}
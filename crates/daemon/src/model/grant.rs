@@ -0,0 +1,469 @@
+use crate::schema;
+use shared_core::database::{self, Table};
+
+pub struct ModelGrant;
+
+impl ModelGrant {
+    /// Checks if role with name exists.
+    pub fn does_role_exist<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        role: String,
+    ) -> database::DatabaseFuture<'c, bool> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let result = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM roles WHERE name = ?)")
+                .bind(role)
+                .fetch_one(&mut *conn)
+                .await?;
+
+            Ok(result)
+        })
+    }
+
+    /// Checks if grant with uuid exists.
+    pub fn does_grant_exist_uuid<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, bool> {
+        // Database stores uuid in hyphenated form.
+        let filter = vec![(schema::Grant::COLUMN_UUID, uuid.as_hyphenated().to_string())];
+        schema::Grant::exists(executor, filter)
+    }
+
+    /// Checks if user was already granted role on scope.
+    pub fn does_grant_exist<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        grant: &schema::Grant,
+    ) -> database::DatabaseFuture<'c, bool> {
+        let uuid_user = grant.uuid_user.to_string();
+        let role = grant.role.clone();
+        let scope_type = grant.scope_type;
+        let uuid_scope = grant.uuid_scope.map(|x| x.to_string());
+        let uuid_api_key = grant.uuid_api_key.map(|x| x.to_string());
+
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            // Global grants have no scope, `IS` also matches null.
+            let result = sqlx::query_scalar(
+                "SELECT EXISTS (
+                    SELECT 1 FROM grants
+                        WHERE uuid_user = ? AND role = ? AND scope_type = ? AND uuid_scope IS ?
+                        AND uuid_api_key IS ?
+                )",
+            )
+            .bind(uuid_user)
+            .bind(role)
+            .bind(scope_type)
+            .bind(uuid_scope)
+            .bind(uuid_api_key)
+            .fetch_one(&mut *conn)
+            .await?;
+
+            Ok(result)
+        })
+    }
+
+    /// Get grant from uuid.
+    pub fn get_grant_from_uuid<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, schema::Grant> {
+        let filter = vec![(schema::Grant::COLUMN_UUID, uuid.as_hyphenated().to_string())];
+        schema::Grant::read(executor, filter)
+    }
+
+    /// Get every grant of user, grants scoping api keys are not included.
+    pub fn get_user_grants<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid_user: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, Vec<schema::Grant>> {
+        Box::pin(async move {
+            let query = database::ListQuery::new()
+                .filter(database::Filter::eq(
+                    schema::Grant::COLUMN_UUID_USER,
+                    uuid_user.as_hyphenated().to_string(),
+                ))
+                .filter(database::Filter::is_null(
+                    schema::Grant::COLUMN_UUID_API_KEY,
+                ))
+                .order(database::Order::asc(schema::Grant::COLUMN_SCOPE_TYPE))
+                .order(database::Order::asc(schema::Grant::COLUMN_ROLE));
+
+            let result = schema::Grant::list(executor, query).await?;
+
+            Ok(result.items)
+        })
+    }
+
+    /// Get every grant scoping api key.
+    pub fn get_api_key_grants<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid_api_key: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, Vec<schema::Grant>> {
+        Box::pin(async move {
+            let query = database::ListQuery::new()
+                .filter(database::Filter::eq(
+                    schema::Grant::COLUMN_UUID_API_KEY,
+                    uuid_api_key.as_hyphenated().to_string(),
+                ))
+                .order(database::Order::asc(schema::Grant::COLUMN_SCOPE_TYPE))
+                .order(database::Order::asc(schema::Grant::COLUMN_ROLE));
+
+            let result = schema::Grant::list(executor, query).await?;
+
+            Ok(result.items)
+        })
+    }
+
+    /// Add a new grant.
+    pub fn add_grant<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        grant: schema::Grant,
+    ) -> database::DatabaseFuture<'c, schema::Grant> {
+        schema::Grant::create(executor, grant)
+    }
+
+    /// Delete grant with uuid.
+    pub fn delete_grant<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, ()> {
+        let filter = vec![(schema::Grant::COLUMN_UUID, uuid.as_hyphenated().to_string())];
+        schema::Grant::delete(executor, filter)
+    }
+
+    /// Delete every grant scoped to a collection or source.
+    pub fn delete_scope_grants<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        scope_type: schema::ScopeType,
+        uuid_scope: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, ()> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            sqlx::query("DELETE FROM grants WHERE scope_type = ? AND uuid_scope = ?")
+                .bind(scope_type as u32)
+                .bind(uuid_scope.as_hyphenated().to_string())
+                .execute(&mut *conn)
+                .await?;
+
+            Ok(())
+        })
+    }
+
+    /// Checks if active user has permission on resource.
+    ///
+    /// Admin users have every permission, other users need a role with the permission granted
+    /// globally or on a scope covering the resource. Requests authorized with an api key only
+    /// get the grants scoping that key.
+    pub fn has_permission<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid_user: uuid::Uuid,
+        uuid_api_key: Option<uuid::Uuid>,
+        permission: schema::Permission,
+        resource: schema::Resource,
+    ) -> database::DatabaseFuture<'c, bool> {
+        let (collection, source, secret) = match resource {
+            schema::Resource::Global => (None, None, None),
+            schema::Resource::Collection(x) => (Some(x), None, None),
+            schema::Resource::Source(x) => (None, Some(x), None),
+            schema::Resource::Secret(x) => (None, None, Some(x)),
+        };
+
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            // Collect every source and collection covering the resource, grants on any of
+            // them apply to the resource as well.
+            let result = sqlx::query_scalar(
+                "WITH
+                    scope_sources AS (
+                        SELECT ?3 AS uuid WHERE ?3 IS NOT NULL
+                        UNION
+                        SELECT uuid_source FROM source_secrets WHERE uuid_secret = ?4
+                    ),
+                    scope_collections AS (
+                        SELECT ?2 AS uuid WHERE ?2 IS NOT NULL
+                        UNION
+                        SELECT uuid_collection FROM collection_source
+                            WHERE uuid_source IN (SELECT uuid FROM scope_sources)
+                    )
+                SELECT
+                    (
+                        ?6 IS NULL
+                        AND EXISTS (SELECT 1 FROM users_active WHERE uuid = ?1 AND admin = TRUE)
+                    )
+                    OR EXISTS (
+                        SELECT 1 FROM grants g
+                            INNER JOIN role_permissions rp ON rp.role = g.role
+                            INNER JOIN users_active u ON u.uuid = g.uuid_user
+                            WHERE g.uuid_user = ?1
+                            AND rp.permission = ?5
+                            AND g.uuid_api_key IS ?6
+                            AND (
+                                g.scope_type = 0
+                                OR (g.scope_type = 1 AND g.uuid_scope IN (SELECT uuid FROM scope_collections))
+                                OR (g.scope_type = 2 AND g.uuid_scope IN (SELECT uuid FROM scope_sources))
+                            )
+                    )",
+            )
+            .bind(uuid_user.as_hyphenated().to_string())
+            .bind(collection.map(|x| x.as_hyphenated().to_string()))
+            .bind(source.map(|x| x.as_hyphenated().to_string()))
+            .bind(secret.map(|x| x.as_hyphenated().to_string()))
+            .bind(permission.as_str())
+            .bind(uuid_api_key.map(|x| x.as_hyphenated().to_string()))
+            .fetch_one(&mut *conn)
+            .await?;
+
+            Ok(result)
+        })
+    }
+
+    /// Get uuids of secrets the active user has permission on through collection or source
+    /// grants, global grants and admin rights aren't considered.
+    pub fn get_permitted_secrets<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid_user: uuid::Uuid,
+        uuid_api_key: Option<uuid::Uuid>,
+        permission: schema::Permission,
+    ) -> database::DatabaseFuture<'c, Vec<uuid::Uuid>> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let result: Vec<uuid::fmt::Hyphenated> = sqlx::query_scalar(
+                "WITH
+                    user_grants AS (
+                        SELECT g.scope_type, g.uuid_scope FROM grants g
+                            INNER JOIN role_permissions rp ON rp.role = g.role
+                            INNER JOIN users_active u ON u.uuid = g.uuid_user
+                            WHERE g.uuid_user = ?1
+                            AND rp.permission = ?2
+                            AND g.uuid_api_key IS ?3
+                    ),
+                    scope_sources AS (
+                        SELECT uuid_scope AS uuid FROM user_grants WHERE scope_type = 2
+                        UNION
+                        SELECT uuid_source FROM collection_source
+                            WHERE uuid_collection IN (
+                                SELECT uuid_scope FROM user_grants WHERE scope_type = 1
+                            )
+                    )
+                SELECT DISTINCT uuid_secret FROM source_secrets
+                    WHERE uuid_source IN (SELECT uuid FROM scope_sources)",
+            )
+            .bind(uuid_user.as_hyphenated().to_string())
+            .bind(permission.as_str())
+            .bind(uuid_api_key.map(|x| x.as_hyphenated().to_string()))
+            .fetch_all(&mut *conn)
+            .await?;
+
+            Ok(result.into_iter().map(|x| x.into_uuid()).collect())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{ModelCollection, ModelGrant, ModelSecret, ModelSource, ModelUser};
+    use crate::schema::{
+        Collection, Grant, Permission, Resource, ScopeType, Secret, SecretType, Source,
+        SourceAuthType, SourceType, User,
+    };
+
+    use sqlx::sqlite;
+
+    async fn add_user(pool: &sqlite::SqlitePool, username: &str, admin: bool) -> uuid::Uuid {
+        let user = User {
+            uuid: uuid::Uuid::new_v4().into(),
+            username: username.into(),
+            admin: Some(admin),
+            ..User::default()
+        };
+
+        ModelUser::add_user(pool, user)
+            .await
+            .unwrap()
+            .uuid
+            .into_uuid()
+    }
+
+    #[sqlx::test]
+    async fn add_grant(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let uuid_user = add_user(&pool, "carl", false).await;
+
+        let result_1 = ModelGrant::does_role_exist(&pool, "reader".into()).await;
+        let result_2 = ModelGrant::does_role_exist(&pool, "owner".into()).await;
+
+        assert!(result_1.unwrap());
+        assert!(!result_2.unwrap());
+
+        let grant = Grant::new(uuid_user, "reader", ScopeType::Global, None);
+
+        assert!(!ModelGrant::does_grant_exist(&pool, &grant).await.unwrap());
+
+        let result_add = ModelGrant::add_grant(&pool, grant).await;
+
+        assert!(result_add.is_ok());
+
+        let result_add = result_add.unwrap();
+
+        assert!(
+            ModelGrant::does_grant_exist(&pool, &result_add)
+                .await
+                .unwrap()
+        );
+
+        // Unknown roles are refused by the database.
+        let grant = Grant::new(uuid_user, "owner", ScopeType::Global, None);
+
+        assert!(ModelGrant::add_grant(&pool, grant).await.is_err());
+
+        let result_3 = ModelGrant::get_user_grants(&pool, uuid_user).await;
+
+        assert_eq!(result_3.unwrap().len(), 1);
+
+        let uuid = result_add.uuid.into_uuid();
+
+        assert!(ModelGrant::delete_grant(&pool, uuid).await.is_ok());
+        assert!(
+            !ModelGrant::does_grant_exist_uuid(&pool, uuid)
+                .await
+                .unwrap()
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn has_permission(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let uuid_admin = add_user(&pool, "admin", true).await;
+        let uuid_user = add_user(&pool, "ci-runner", false).await;
+
+        let collection_1 =
+            ModelCollection::add_collection(&pool, Collection::new("project-1").unwrap())
+                .await
+                .unwrap()
+                .uuid
+                .into_uuid();
+        let collection_2 =
+            ModelCollection::add_collection(&pool, Collection::new("project-2").unwrap())
+                .await
+                .unwrap()
+                .uuid
+                .into_uuid();
+
+        let source = Source::new(
+            "source",
+            None,
+            SourceType::Csv as u32,
+            None,
+            None,
+            SourceAuthType::None as u32,
+        )
+        .unwrap();
+        let source = ModelSource::add_source(&pool, source)
+            .await
+            .unwrap()
+            .uuid
+            .into_uuid();
+
+        let secret =
+            Secret::new("database", None, None, "secret", SecretType::Cipher as u32).unwrap();
+        let secret = ModelSecret::add_secret(&pool, secret)
+            .await
+            .unwrap()
+            .uuid
+            .into_uuid();
+
+        ModelSource::link_secret(&pool, source, secret)
+            .await
+            .unwrap();
+        ModelCollection::attach_source(&pool, collection_1, source)
+            .await
+            .unwrap();
+
+        let check = async |uuid_user, permission, resource| {
+            ModelGrant::has_permission(&pool, uuid_user, None, permission, resource)
+                .await
+                .unwrap()
+        };
+
+        // Admins can do everything, users without grants nothing.
+        assert!(check(uuid_admin, Permission::Manage, Resource::Global).await);
+        assert!(!check(uuid_user, Permission::Read, Resource::Global).await);
+        assert!(!check(uuid_user, Permission::Read, Resource::Secret(secret)).await);
+
+        let grant = Grant::new(
+            uuid_user,
+            "reader",
+            ScopeType::Collection,
+            Some(collection_1),
+        );
+        ModelGrant::add_grant(&pool, grant).await.unwrap();
+
+        // Collection grants cover its sources and their secrets but nothing else.
+        assert!(
+            check(
+                uuid_user,
+                Permission::Read,
+                Resource::Collection(collection_1)
+            )
+            .await
+        );
+        assert!(check(uuid_user, Permission::Read, Resource::Source(source)).await);
+        assert!(check(uuid_user, Permission::Read, Resource::Secret(secret)).await);
+        assert!(!check(uuid_user, Permission::Write, Resource::Secret(secret)).await);
+        assert!(
+            !check(
+                uuid_user,
+                Permission::Read,
+                Resource::Collection(collection_2)
+            )
+            .await
+        );
+        assert!(!check(uuid_user, Permission::Read, Resource::Global).await);
+
+        let result_1 =
+            ModelGrant::get_permitted_secrets(&pool, uuid_user, None, Permission::Read).await;
+        let result_2 =
+            ModelGrant::get_permitted_secrets(&pool, uuid_user, None, Permission::Write).await;
+
+        assert_eq!(result_1.unwrap(), vec![secret]);
+        assert!(result_2.unwrap().is_empty());
+
+        // Detaching the source also removes access through the collection.
+        ModelCollection::detach_source(&pool, collection_1, source)
+            .await
+            .unwrap();
+
+        assert!(!check(uuid_user, Permission::Read, Resource::Secret(secret)).await);
+        assert!(
+            ModelGrant::get_permitted_secrets(&pool, uuid_user, None, Permission::Read)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let grant = Grant::new(uuid_user, "writer", ScopeType::Source, Some(source));
+        ModelGrant::add_grant(&pool, grant).await.unwrap();
+
+        assert!(check(uuid_user, Permission::Write, Resource::Secret(secret)).await);
+        assert!(!check(uuid_user, Permission::Manage, Resource::Source(source)).await);
+        assert_eq!(
+            ModelGrant::get_permitted_secrets(&pool, uuid_user, None, Permission::Write)
+                .await
+                .unwrap(),
+            vec![secret]
+        );
+
+        // Deleted users lose their grants.
+        ModelUser::delete_user(&pool, uuid_user).await.unwrap();
+
+        assert!(!check(uuid_user, Permission::Read, Resource::Source(source)).await);
+
+        Ok(())
+    }
+}
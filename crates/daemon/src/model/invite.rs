@@ -0,0 +1,210 @@
+use crate::schema;
+use shared_core::database::{self, Table};
+
+pub struct ModelInvite;
+
+impl ModelInvite {
+    /// Add invite.
+    pub fn add_invite<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        invite: schema::Invite,
+    ) -> database::DatabaseFuture<'c, schema::Invite> {
+        schema::Invite::create(executor, invite)
+    }
+
+    /// Add setup invite, replacing previous setup invites.
+    pub fn set_setup_invite<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        invite: schema::Invite,
+    ) -> database::DatabaseFuture<'c, schema::Invite> {
+        Box::pin(async move {
+            let mut tx = executor.begin().await?;
+
+            sqlx::query("DELETE FROM invites WHERE admin = TRUE")
+                .execute(&mut *tx)
+                .await?;
+
+            let result = schema::Invite::create(&mut *tx, invite).await?;
+
+            tx.commit().await?;
+
+            Ok(result)
+        })
+    }
+
+    /// Get unexpired invites created by admins, oldest first.
+    pub fn get_invites<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+    ) -> database::DatabaseFuture<'c, Vec<schema::Invite>> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let result = sqlx::query_as(
+                "SELECT * FROM invites
+                    WHERE admin = FALSE
+                    AND (expires_at IS NULL OR expires_at > DATETIME('NOW'))
+                    ORDER BY created_at, ROWID",
+            )
+            .fetch_all(&mut *conn)
+            .await?;
+
+            Ok(result)
+        })
+    }
+
+    /// Remove invite created by an admin. Returns false if there is no such invite.
+    pub fn delete_invite<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, bool> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let rows_affected = sqlx::query("DELETE FROM invites WHERE uuid = ? AND admin = FALSE")
+                .bind(uuid.as_hyphenated().to_string())
+                .execute(&mut *conn)
+                .await?
+                .rows_affected();
+
+            Ok(rows_affected == 1)
+        })
+    }
+
+    /// Register user with invite code hash, the invite is removed in the same transaction.
+    /// The user is an admin if registered with the setup invite. Returns `None` if there is no
+    /// unexpired invite with the hash.
+    pub fn redeem_invite<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        code_hash: String,
+        mut user: schema::User,
+    ) -> database::DatabaseFuture<'c, Option<schema::User>> {
+        Box::pin(async move {
+            let mut tx = executor.begin().await?;
+
+            let admin: Option<bool> = sqlx::query_scalar(
+                "DELETE FROM invites
+                    WHERE code_hash = ?
+                    AND (expires_at IS NULL OR expires_at > DATETIME('NOW'))
+                    RETURNING admin",
+            )
+            .bind(code_hash)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            let Some(admin) = admin else {
+                return Ok(None);
+            };
+
+            user.admin = Some(admin);
+
+            let result = schema::User::create(&mut *tx, user).await?;
+
+            tx.commit().await?;
+
+            Ok(Some(result))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{ModelInvite, ModelUser};
+    use crate::schema;
+
+    use shared_core::crypt;
+    use sqlx::sqlite;
+
+    #[sqlx::test]
+    async fn redeem_invite(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let code_setup_1 = crypt::InviteCode::generate();
+        let code_setup_2 = crypt::InviteCode::generate();
+
+        ModelInvite::set_setup_invite(&pool, schema::Invite::setup(&code_setup_1))
+            .await
+            .unwrap();
+        ModelInvite::set_setup_invite(&pool, schema::Invite::setup(&code_setup_2))
+            .await
+            .unwrap();
+
+        // Only the last setup invite is kept
+        let result_1 = ModelInvite::redeem_invite(
+            &pool,
+            code_setup_1.hash(),
+            schema::User::new("allan", "").unwrap(),
+        )
+        .await
+        .unwrap();
+        let result_2 = ModelInvite::redeem_invite(
+            &pool,
+            code_setup_2.hash(),
+            schema::User::new("allan", "").unwrap(),
+        )
+        .await
+        .unwrap();
+
+        assert!(result_1.is_none());
+
+        let admin = result_2.unwrap();
+
+        assert!(admin.is_admin());
+
+        // Invites of admins expire and are used once
+        let now = chrono::Utc::now().naive_utc();
+        let code_1 = crypt::InviteCode::generate();
+        let code_2 = crypt::InviteCode::generate();
+        let code_3 = crypt::InviteCode::generate();
+
+        for (code, expires_at) in [
+            (&code_1, now + chrono::Duration::days(1)),
+            (&code_2, now - chrono::Duration::days(1)),
+            (&code_3, now + chrono::Duration::days(1)),
+        ] {
+            ModelInvite::add_invite(
+                &pool,
+                schema::Invite::new(code, admin.uuid.into_uuid(), expires_at),
+            )
+            .await
+            .unwrap();
+        }
+
+        let invites = ModelInvite::get_invites(&pool).await.unwrap();
+
+        assert_eq!(invites.len(), 2);
+        assert_eq!(invites[0].code_hash, code_1.hash());
+
+        let result_3 = ModelInvite::redeem_invite(
+            &pool,
+            code_1.hash(),
+            schema::User::new("carl", "").unwrap(),
+        )
+        .await
+        .unwrap();
+        let result_4 =
+            ModelInvite::redeem_invite(&pool, code_1.hash(), schema::User::new("bob", "").unwrap())
+                .await
+                .unwrap();
+        let result_5 =
+            ModelInvite::redeem_invite(&pool, code_2.hash(), schema::User::new("bob", "").unwrap())
+                .await
+                .unwrap();
+
+        assert!(!result_3.unwrap().is_admin());
+        assert!(result_4.is_none());
+        assert!(result_5.is_none());
+        assert!(
+            !ModelUser::does_user_exist(&pool, "bob".into())
+                .await
+                .unwrap()
+        );
+
+        // Revoked invites can't be used
+        assert!(
+            ModelInvite::delete_invite(&pool, invites[1].uuid.into_uuid())
+                .await
+                .unwrap()
+        );
+        assert_eq!(ModelInvite::get_invites(&pool).await.unwrap().len(), 0);
+
+        Ok(())
+    }
+}
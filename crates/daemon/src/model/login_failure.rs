@@ -0,0 +1,237 @@
+use crate::schema;
+use shared_core::database::{self, Table};
+
+pub struct ModelLoginFailure;
+
+impl ModelLoginFailure {
+    /// Get failed logins of subject.
+    pub fn get_login_failure<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        subject: schema::LoginSubject,
+    ) -> database::DatabaseFuture<'c, schema::LoginFailure> {
+        let filter = vec![
+            (
+                schema::LoginFailure::COLUMN_KIND,
+                subject.kind().to_string(),
+            ),
+            (schema::LoginFailure::COLUMN_NAME, subject.name()),
+        ];
+        schema::LoginFailure::read(executor, filter)
+    }
+
+    /// Count a login attempt of subject at `now` as failed, unless the subject is locked or
+    /// still has to wait for its backoff. Checking and counting is one statement so concurrent
+    /// attempts can't get past the limits. Returns `None` if the attempt is refused.
+    ///
+    /// Failures older than the reset time are forgotten. Once the subject has the maximum of
+    /// failures it is locked for the lockout time.
+    pub fn add_login_attempt<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        subject: schema::LoginSubject,
+        now: chrono::NaiveDateTime,
+        limits: schema::LoginLimits,
+    ) -> database::DatabaseFuture<'c, Option<schema::LoginFailure>> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            // Every expression of the update reads the failures before the update. The backoff
+            // doubles with every failure, overflowing products become reals and are capped.
+            let result = sqlx::query_as(
+                "INSERT INTO login_failures (kind, name, failures, locked_until, last_failed_at)
+                    VALUES (?1, ?2, 1, CASE WHEN 1 >= ?3 THEN ?4 END, ?5)
+                    ON CONFLICT (kind, name) DO UPDATE SET
+                        failures = CASE
+                            WHEN last_failed_at < ?6 THEN 1
+                            ELSE failures + 1
+                        END,
+                        locked_until = CASE
+                            WHEN (CASE WHEN last_failed_at < ?6 THEN 1 ELSE failures + 1 END) >= ?3
+                                THEN ?4
+                            ELSE locked_until
+                        END,
+                        last_failed_at = ?5
+                    WHERE (locked_until IS NULL OR locked_until <= ?5)
+                        AND (
+                            last_failed_at < ?6
+                            OR ROUND((JULIANDAY(?5) - JULIANDAY(last_failed_at)) * 86400, 3)
+                                >= MIN(?8, ?7 * (1 << MIN(failures - 1, 32)))
+                        )
+                    RETURNING *",
+            )
+            .bind(subject.kind())
+            .bind(subject.name())
+            .bind(limits.max_failures)
+            .bind(now + limits.lockout)
+            .bind(now)
+            .bind(now - limits.reset)
+            .bind(limits.backoff.num_seconds())
+            .bind(limits.backoff_max.num_seconds())
+            .fetch_optional(&mut *conn)
+            .await?;
+
+            Ok(result)
+        })
+    }
+
+    /// Forget failed logins of subject, this also unlocks the subject.
+    /// Returns true if the subject had failed logins.
+    pub fn delete_login_failure<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        subject: schema::LoginSubject,
+    ) -> database::DatabaseFuture<'c, bool> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let rows_affected =
+                sqlx::query("DELETE FROM login_failures WHERE kind = ? AND name = ?")
+                    .bind(subject.kind())
+                    .bind(subject.name())
+                    .execute(&mut *conn)
+                    .await?
+                    .rows_affected();
+
+            Ok(rows_affected > 0)
+        })
+    }
+
+    /// Remove failed logins before `reset_before` of subjects which are no longer locked.
+    /// Returns the number of rows removed.
+    pub fn purge_login_failures<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        now: chrono::NaiveDateTime,
+        reset_before: chrono::NaiveDateTime,
+    ) -> database::DatabaseFuture<'c, u64> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let rows_affected = sqlx::query(
+                "DELETE FROM login_failures
+                    WHERE last_failed_at < ?
+                    AND (locked_until IS NULL OR locked_until <= ?)",
+            )
+            .bind(reset_before)
+            .bind(now)
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+
+            Ok(rows_affected)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::ModelLoginFailure;
+    use crate::schema::{LoginLimits, LoginSubject};
+
+    use sqlx::sqlite;
+
+    #[sqlx::test]
+    async fn add_login_attempt(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let subject = LoginSubject::Username("carl".into());
+        let now = chrono::Utc::now().naive_utc();
+        let limits = LoginLimits {
+            max_failures: 3,
+            backoff: chrono::Duration::zero(),
+            backoff_max: chrono::Duration::zero(),
+            lockout: chrono::Duration::minutes(15),
+            reset: chrono::Duration::hours(1),
+        };
+        let locked_until = now + limits.lockout;
+
+        assert!(
+            ModelLoginFailure::get_login_failure(&pool, subject.clone())
+                .await
+                .is_err()
+        );
+
+        // Subject is locked on the third failure
+        for failures in 1..=3 {
+            let result = ModelLoginFailure::add_login_attempt(&pool, subject.clone(), now, limits)
+                .await
+                .unwrap()
+                .unwrap();
+
+            assert_eq!(result.failures, failures);
+            assert_eq!(result.locked_until, (failures == 3).then_some(locked_until));
+        }
+
+        // Locked subjects are refused without counting the attempt
+        let result_1 = ModelLoginFailure::add_login_attempt(
+            &pool,
+            subject.clone(),
+            now + chrono::Duration::minutes(1),
+            limits,
+        )
+        .await;
+
+        assert!(result_1.unwrap().is_none());
+
+        // Once unlocked old failures are forgotten
+        let result_2 = ModelLoginFailure::add_login_attempt(
+            &pool,
+            subject.clone(),
+            now + chrono::Duration::hours(2),
+            limits,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(result_2.failures, 1);
+        assert!(
+            result_2
+                .locked_for(now + chrono::Duration::hours(2))
+                .is_none()
+        );
+
+        // Attempts during the backoff are refused, it doubles with every failure up to the maximum
+        let peer = LoginSubject::Peer("127.0.0.1".parse().unwrap());
+        let limits_backoff = LoginLimits {
+            max_failures: 20,
+            backoff: chrono::Duration::seconds(10),
+            backoff_max: chrono::Duration::seconds(15),
+            ..limits
+        };
+
+        for (secs, expected) in [(0, true), (9, false), (10, true), (24, false), (25, true)] {
+            let result = ModelLoginFailure::add_login_attempt(
+                &pool,
+                peer.clone(),
+                now + chrono::Duration::seconds(secs),
+                limits_backoff,
+            )
+            .await;
+
+            assert_eq!(result.unwrap().is_some(), expected, "{}", secs);
+        }
+
+        // Other subjects are tracked separately
+        let result_peer = ModelLoginFailure::get_login_failure(&pool, peer.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(result_peer.failures, 3);
+        assert_eq!(result_peer.name, "127.0.0.1");
+
+        // Only unlocked subjects are purged
+        let result_purge =
+            ModelLoginFailure::purge_login_failures(&pool, now, now + chrono::Duration::hours(3))
+                .await;
+
+        assert_eq!(result_purge.unwrap(), 1);
+        assert!(
+            ModelLoginFailure::delete_login_failure(&pool, subject.clone())
+                .await
+                .unwrap()
+        );
+        assert!(
+            !ModelLoginFailure::delete_login_failure(&pool, subject)
+                .await
+                .unwrap()
+        );
+
+        Ok(())
+    }
+}
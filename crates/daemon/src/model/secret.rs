@@ -0,0 +1,287 @@
+use crate::schema;
+use shared_core::database::{self, Table};
+
+pub struct ModelSecret;
+
+impl ModelSecret {
+    /// Checks if secret with name exists.
+    pub fn does_secret_exist<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        name: String,
+    ) -> database::DatabaseFuture<'c, bool> {
+        let filter = vec![(schema::Secret::COLUMN_NAME, name)];
+        schema::Secret::exists(executor, filter)
+    }
+
+    /// Checks if secret with uuid exists.
+    pub fn does_secret_exist_uuid<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, bool> {
+        // Database stores uuid in hyphenated form.
+        let filter = vec![(
+            schema::Secret::COLUMN_UUID,
+            uuid.as_hyphenated().to_string(),
+        )];
+        schema::Secret::exists(executor, filter)
+    }
+
+    /// Get secret from name.
+    pub fn get_secret_from_name<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        name: String,
+    ) -> database::DatabaseFuture<'c, schema::Secret> {
+        let filter = vec![(schema::Secret::COLUMN_NAME, name)];
+        schema::Secret::read(executor, filter)
+    }
+
+    /// Get secret from uuid.
+    pub fn get_secret_from_uuid<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, schema::Secret> {
+        // Database stores uuid in hyphenated form.
+        let filter = vec![(
+            schema::Secret::COLUMN_UUID,
+            uuid.as_hyphenated().to_string(),
+        )];
+        schema::Secret::read(executor, filter)
+    }
+
+    /// List secrets.
+    pub fn get_secrets<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        query: database::ListQuery,
+    ) -> database::DatabaseFuture<'c, database::Page<schema::Secret>> {
+        schema::Secret::list(executor, query)
+    }
+
+    /// Get secrets whose value isn't encrypted with a data key yet.
+    pub fn get_unsealed_secrets<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+    ) -> database::DatabaseFuture<'c, Vec<schema::Secret>> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let result = sqlx::query_as("SELECT * FROM secrets WHERE uuid_data_key IS NULL")
+                .fetch_all(&mut *conn)
+                .await?;
+
+            Ok(result)
+        })
+    }
+
+    /// Add a new secret.
+    pub fn add_secret<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        secret: schema::Secret,
+    ) -> database::DatabaseFuture<'c, schema::Secret> {
+        schema::Secret::create(executor, secret)
+    }
+
+    /// Update an existing secret.
+    /// The secret being updated is identified by the uuid of the given secret.
+    pub fn update_secret<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        secret: schema::Secret,
+    ) -> database::DatabaseFuture<'c, schema::Secret> {
+        schema::Secret::update(executor, secret)
+    }
+
+    /// Delete secret with uuid.
+    /// Any links to sources are removed first.
+    pub fn delete_secret<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, ()> {
+        Box::pin(async move {
+            let uuid = uuid.as_hyphenated().to_string();
+            let mut tx = executor.begin().await?;
+
+            sqlx::query("DELETE FROM source_secrets WHERE uuid_secret = ?")
+                .bind(&uuid)
+                .execute(&mut *tx)
+                .await?;
+
+            let filter = vec![(schema::Secret::COLUMN_UUID, uuid)];
+            schema::Secret::delete(&mut *tx, filter).await?;
+
+            tx.commit().await?;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::ModelSecret;
+    use crate::schema::{Secret, SecretType};
+
+    use shared_core::database;
+    use sqlx::sqlite;
+
+    #[sqlx::test]
+    async fn does_secret_exist(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let result_1 = ModelSecret::does_secret_exist(&pool, "api-key".into()).await;
+
+        assert!(result_1.is_ok());
+        assert!(!result_1.unwrap());
+
+        let secret = Secret {
+            name: "api-key".into(),
+            ..Secret::default()
+        };
+        let result_add = ModelSecret::add_secret(&pool, secret).await;
+
+        assert!(result_add.is_ok());
+
+        let result_2 = ModelSecret::does_secret_exist(&pool, "api-key".into()).await;
+
+        assert!(result_2.is_ok());
+        assert!(result_2.unwrap());
+
+        let result_3 =
+            ModelSecret::does_secret_exist_uuid(&pool, result_add.unwrap().uuid.into_uuid()).await;
+
+        assert!(result_3.is_ok());
+        assert!(result_3.unwrap());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn get_secret(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let result_1 = ModelSecret::get_secret_from_name(&pool, "api-key".into()).await;
+
+        assert!(result_1.is_err());
+
+        let secret = Secret::new(
+            "api-key",
+            Some("API_KEY".into()),
+            None,
+            "super-secret",
+            SecretType::Key as u32,
+        )
+        .unwrap();
+        let result_add = ModelSecret::add_secret(&pool, secret).await;
+
+        assert!(result_add.is_ok());
+
+        let result_add = result_add.unwrap();
+
+        let result_2 = ModelSecret::get_secret_from_name(&pool, "api-key".into()).await;
+        let result_3 = ModelSecret::get_secret_from_uuid(&pool, result_add.uuid.into_uuid()).await;
+
+        assert!(result_2.is_ok());
+        assert!(result_3.is_ok());
+
+        let result_3 = result_3.unwrap();
+
+        assert_eq!(result_3.name, "api-key");
+        assert_eq!(result_3.key, Some("API_KEY".into()));
+        assert_eq!(result_3.secret, "super-secret");
+        assert_eq!(result_3.secret_type, SecretType::Key as u32);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn get_secrets(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let query = database::ListQuery::new().order(database::Order::asc("name"));
+        let result_1 = ModelSecret::get_secrets(&pool, query.clone()).await;
+
+        assert!(result_1.is_ok());
+        assert!(result_1.unwrap().items.is_empty());
+
+        for name in ["zebra", "apple", "mango"] {
+            let secret =
+                Secret::new(name, None, None, "secret", SecretType::Cipher as u32).unwrap();
+
+            assert!(ModelSecret::add_secret(&pool, secret).await.is_ok());
+        }
+
+        let result_2 = ModelSecret::get_secrets(&pool, query.clone()).await;
+
+        assert!(result_2.is_ok());
+
+        let result_2 = result_2.unwrap().items;
+
+        assert_eq!(result_2.len(), 3);
+        assert_eq!(result_2[0].name, "apple");
+        assert_eq!(result_2[1].name, "mango");
+        assert_eq!(result_2[2].name, "zebra");
+
+        // Page through secrets
+        let result_3 = ModelSecret::get_secrets(&pool, query.clone().cursor(None, 2)).await;
+
+        assert!(result_3.is_ok());
+
+        let result_3 = result_3.unwrap();
+
+        assert_eq!(result_3.items.len(), 2);
+        assert!(result_3.next_cursor.is_some());
+
+        let result_4 = ModelSecret::get_secrets(&pool, query.cursor(result_3.next_cursor, 2)).await;
+
+        assert!(result_4.is_ok());
+
+        let result_4 = result_4.unwrap();
+
+        assert_eq!(result_4.items.len(), 1);
+        assert_eq!(result_4.items[0].name, "zebra");
+        assert_eq!(result_4.next_cursor, None);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn update_secret(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let secret = Secret::default();
+        let result_1 = ModelSecret::update_secret(&pool, secret).await;
+
+        assert!(result_1.is_err());
+
+        let result_add = ModelSecret::add_secret(&pool, Secret::default()).await;
+
+        assert!(result_add.is_ok());
+
+        let secret = Secret {
+            secret: "new-secret".into(),
+            description: None,
+            ..result_add.unwrap()
+        };
+        let result_2 = ModelSecret::update_secret(&pool, secret).await;
+
+        assert!(result_2.is_ok());
+
+        let result_2 = result_2.unwrap();
+
+        assert_eq!(result_2.secret, "new-secret");
+        assert_eq!(result_2.description, None);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn delete_secret(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let result_add = ModelSecret::add_secret(&pool, Secret::default()).await;
+
+        assert!(result_add.is_ok());
+
+        let uuid = result_add.unwrap().uuid.into_uuid();
+
+        let result_1 = ModelSecret::delete_secret(&pool, uuid).await;
+        let result_2 = ModelSecret::delete_secret(&pool, uuid).await;
+
+        assert!(result_1.is_ok());
+        assert!(result_2.is_err());
+
+        let result_3 = ModelSecret::does_secret_exist_uuid(&pool, uuid).await;
+
+        assert!(result_3.is_ok());
+        assert!(!result_3.unwrap());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,196 @@
+use crate::schema;
+use shared_core::database::{self, Table};
+
+pub struct ModelSession;
+
+impl ModelSession {
+    /// Checks if session with uuid exists.
+    pub fn does_session_exist_uuid<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, bool> {
+        // Database stores uuid in hyphenated form.
+        let filter = vec![(
+            schema::Session::COLUMN_UUID,
+            uuid.as_hyphenated().to_string(),
+        )];
+        schema::Session::exists(executor, filter)
+    }
+
+    /// Get session from uuid.
+    pub fn get_session_from_uuid<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, schema::Session> {
+        let filter = vec![(
+            schema::Session::COLUMN_UUID,
+            uuid.as_hyphenated().to_string(),
+        )];
+        schema::Session::read(executor, filter)
+    }
+
+    /// Add a new session.
+    /// Expired sessions of the user are removed, they can't be reused anyway.
+    pub fn add_session<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        session: schema::Session,
+    ) -> database::DatabaseFuture<'c, schema::Session> {
+        Box::pin(async move {
+            let mut tx = executor.begin().await?;
+
+            sqlx::query(
+                "DELETE FROM sessions WHERE uuid_user = ? AND expires_at <= DATETIME('NOW')",
+            )
+            .bind(session.uuid_user.to_string())
+            .execute(&mut *tx)
+            .await?;
+
+            let result = schema::Session::create(&mut *tx, session).await?;
+
+            tx.commit().await?;
+
+            Ok(result)
+        })
+    }
+
+    /// Mark session as rotated so its refresh token can't be used again.
+    /// Returns false if the session was already rotated or revoked.
+    pub fn rotate_session<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, bool> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let rows_affected = sqlx::query(
+                "UPDATE sessions
+                    SET rotated = TRUE
+                    WHERE uuid = ? AND rotated = FALSE AND revoked = FALSE",
+            )
+            .bind(uuid.as_hyphenated().to_string())
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+
+            Ok(rows_affected == 1)
+        })
+    }
+
+    /// Revoke every session of token family.
+    /// Returns the number of sessions revoked.
+    pub fn revoke_family<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid_family: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, u64> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let rows_affected = sqlx::query(
+                "UPDATE sessions SET revoked = TRUE WHERE uuid_family = ? AND revoked = FALSE",
+            )
+            .bind(uuid_family.as_hyphenated().to_string())
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+
+            Ok(rows_affected)
+        })
+    }
+
+    /// Revoke every session of user which can still be refreshed.
+    /// Returns the number of sessions revoked.
+    pub fn revoke_user_sessions<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid_user: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, u64> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let rows_affected = sqlx::query(
+                "UPDATE sessions
+                    SET revoked = TRUE
+                    WHERE uuid_user = ? AND rotated = FALSE AND revoked = FALSE",
+            )
+            .bind(uuid_user.as_hyphenated().to_string())
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+
+            Ok(rows_affected)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{ModelSession, ModelUser};
+    use crate::schema::{Session, User};
+
+    use sqlx::sqlite;
+
+    #[sqlx::test]
+    async fn rotate_session(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let uuid_user = ModelUser::add_user(&pool, User::default())
+            .await
+            .unwrap()
+            .uuid
+            .into_uuid();
+
+        let expires_at = chrono::Utc::now().naive_utc() + chrono::Duration::days(1);
+        let uuid_1 = uuid::Uuid::new_v4();
+        let uuid_2 = uuid::Uuid::new_v4();
+
+        let session_1 = Session::new(uuid_1, uuid_user, None, expires_at);
+        let session_2 = Session::new(uuid_2, uuid_user, Some(uuid_1), expires_at);
+
+        assert!(ModelSession::add_session(&pool, session_1).await.is_ok());
+        assert!(ModelSession::add_session(&pool, session_2).await.is_ok());
+
+        // Sessions can only be rotated once.
+        assert!(ModelSession::rotate_session(&pool, uuid_1).await.unwrap());
+        assert!(!ModelSession::rotate_session(&pool, uuid_1).await.unwrap());
+
+        let result_1 = ModelSession::get_session_from_uuid(&pool, uuid_1)
+            .await
+            .unwrap();
+        let result_2 = ModelSession::get_session_from_uuid(&pool, uuid_2)
+            .await
+            .unwrap();
+
+        assert!(result_1.rotated);
+        assert_eq!(result_2.uuid_family, result_1.uuid);
+
+        // Revoking the family covers every token in it.
+        assert_eq!(ModelSession::revoke_family(&pool, uuid_1).await.unwrap(), 2);
+        assert!(!ModelSession::rotate_session(&pool, uuid_2).await.unwrap());
+
+        // Expired sessions are cleaned up when adding new ones.
+        let expired = Session::new(
+            uuid::Uuid::new_v4(),
+            uuid_user,
+            None,
+            chrono::Utc::now().naive_utc() - chrono::Duration::days(1),
+        );
+        let uuid_expired = expired.uuid.into_uuid();
+
+        ModelSession::add_session(&pool, expired).await.unwrap();
+
+        let session_3 = Session::new(uuid::Uuid::new_v4(), uuid_user, None, expires_at);
+
+        ModelSession::add_session(&pool, session_3).await.unwrap();
+
+        assert!(
+            !ModelSession::does_session_exist_uuid(&pool, uuid_expired)
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            ModelSession::revoke_user_sessions(&pool, uuid_user)
+                .await
+                .unwrap(),
+            1
+        );
+
+        Ok(())
+    }
+}
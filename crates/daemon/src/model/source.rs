@@ -0,0 +1,427 @@
+use crate::schema;
+use shared_core::database::{self, Table};
+
+pub struct ModelSource;
+
+impl ModelSource {
+    /// Checks if source with name exists.
+    pub fn does_source_exist<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        name: String,
+    ) -> database::DatabaseFuture<'c, bool> {
+        let filter = vec![(schema::Source::COLUMN_NAME, name)];
+        schema::Source::exists(executor, filter)
+    }
+
+    /// Checks if source with uuid exists.
+    pub fn does_source_exist_uuid<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, bool> {
+        // Database stores uuid in hyphenated form.
+        let filter = vec![(
+            schema::Source::COLUMN_UUID,
+            uuid.as_hyphenated().to_string(),
+        )];
+        schema::Source::exists(executor, filter)
+    }
+
+    /// Get source from uuid.
+    pub fn get_source_from_uuid<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, schema::Source> {
+        let filter = vec![(
+            schema::Source::COLUMN_UUID,
+            uuid.as_hyphenated().to_string(),
+        )];
+        schema::Source::read(executor, filter)
+    }
+
+    /// List sources.
+    pub fn get_sources<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        query: database::ListQuery,
+    ) -> database::DatabaseFuture<'c, database::Page<schema::Source>> {
+        schema::Source::list(executor, query)
+    }
+
+    /// Add a new source.
+    pub fn add_source<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        source: schema::Source,
+    ) -> database::DatabaseFuture<'c, schema::Source> {
+        Box::pin(async move {
+            let uuid = source.uuid.into_uuid();
+            let mut conn = executor.acquire().await?;
+
+            schema::Source::create(&mut *conn, source).await?;
+
+            // Timestamps are written by a trigger after the insert so they are not
+            // part of the returned row, read it back to get them.
+            Self::get_source_from_uuid(&mut *conn, uuid).await
+        })
+    }
+
+    /// Update an existing source.
+    /// The source being updated is identified by the uuid of the given source.
+    pub fn update_source<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        source: schema::Source,
+    ) -> database::DatabaseFuture<'c, schema::Source> {
+        Box::pin(async move {
+            let uuid = source.uuid.into_uuid();
+            let mut conn = executor.acquire().await?;
+
+            schema::Source::update(&mut *conn, source).await?;
+
+            Self::get_source_from_uuid(&mut *conn, uuid).await
+        })
+    }
+
+    /// Delete source with uuid.
+    /// Source must not be referenced by any collection or secret, grants on it are removed.
+    pub fn delete_source<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, ()> {
+        Box::pin(async move {
+            let mut tx = executor.begin().await?;
+
+            super::ModelGrant::delete_scope_grants(&mut *tx, schema::ScopeType::Source, uuid)
+                .await?;
+
+            let filter = vec![(
+                schema::Source::COLUMN_UUID,
+                uuid.as_hyphenated().to_string(),
+            )];
+            schema::Source::delete(&mut *tx, filter).await?;
+
+            tx.commit().await?;
+
+            Ok(())
+        })
+    }
+
+    /// Number of collections source is attached to.
+    pub fn get_collection_count<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, i64> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let result =
+                sqlx::query_scalar("SELECT COUNT(*) FROM collection_source WHERE uuid_source = ?")
+                    .bind(uuid.as_hyphenated().to_string())
+                    .fetch_one(&mut *conn)
+                    .await?;
+
+            Ok(result)
+        })
+    }
+
+    /// Get uuid of every collection source is attached to.
+    pub fn get_source_collections<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, Vec<schema::CollectionSource>> {
+        Box::pin(async move {
+            let query = database::ListQuery::new().filter(database::Filter::eq(
+                schema::CollectionSource::COLUMN_UUID_SOURCE,
+                uuid.as_hyphenated().to_string(),
+            ));
+
+            let result = schema::CollectionSource::list(executor, query).await?;
+
+            Ok(result.items)
+        })
+    }
+
+    /// Detach source from every collection.
+    pub fn detach_collections<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, ()> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            sqlx::query("DELETE FROM collection_source WHERE uuid_source = ?")
+                .bind(uuid.as_hyphenated().to_string())
+                .execute(&mut *conn)
+                .await?;
+
+            Ok(())
+        })
+    }
+
+    /// Link secret to source.
+    pub fn link_secret<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid_source: uuid::Uuid,
+        uuid_secret: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, schema::SourceSecret> {
+        let data = schema::SourceSecret::new(uuid_source, uuid_secret);
+        schema::SourceSecret::create(executor, data)
+    }
+
+    /// Get every secret linked to source.
+    pub fn get_source_secrets<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, Vec<schema::Secret>> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let result = sqlx::query_as(
+                "SELECT s.uuid, s.name, s.key, s.description, s.secret, s.secret_type,
+                    s.uuid_data_key
+                    FROM secrets s
+                    INNER JOIN source_secrets ss ON ss.uuid_secret = s.uuid
+                    WHERE ss.uuid_source = ?
+                    ORDER BY s.name",
+            )
+            .bind(uuid.as_hyphenated().to_string())
+            .fetch_all(&mut *conn)
+            .await?;
+
+            Ok(result)
+        })
+    }
+
+    /// Unlink secret from source.
+    /// The secret itself is deleted if no other source links to it.
+    pub fn unlink_secret<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid_source: uuid::Uuid,
+        uuid_secret: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, ()> {
+        Box::pin(async move {
+            let uuid_secret = uuid_secret.as_hyphenated().to_string();
+            let mut tx = executor.begin().await?;
+
+            let filter = vec![
+                (
+                    schema::SourceSecret::COLUMN_UUID_SOURCE,
+                    uuid_source.as_hyphenated().to_string(),
+                ),
+                (
+                    schema::SourceSecret::COLUMN_UUID_SECRET,
+                    uuid_secret.clone(),
+                ),
+            ];
+            schema::SourceSecret::delete(&mut *tx, filter).await?;
+
+            sqlx::query(
+                "DELETE FROM secrets
+                    WHERE uuid = ?
+                    AND NOT EXISTS(SELECT 1 FROM source_secrets WHERE uuid_secret = ?)",
+            )
+            .bind(&uuid_secret)
+            .bind(&uuid_secret)
+            .execute(&mut *tx)
+            .await?;
+
+            tx.commit().await?;
+
+            Ok(())
+        })
+    }
+
+    /// Delete every secret linked to source.
+    /// Secrets which are also linked to another source are only unlinked.
+    pub fn delete_source_secrets<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, ()> {
+        Box::pin(async move {
+            let mut tx = executor.begin().await?;
+            let secrets = Self::get_source_secrets(&mut *tx, uuid).await?;
+
+            for secret in secrets {
+                Self::unlink_secret(&mut *tx, uuid, secret.uuid.into_uuid()).await?;
+            }
+
+            tx.commit().await?;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{ModelCollection, ModelSecret, ModelSource};
+    use crate::schema::{Collection, Secret, SecretType, Source, SourceAuthType, SourceType};
+
+    use shared_core::database;
+    use sqlx::sqlite;
+
+    fn new_source(name: &str) -> Source {
+        Source::new(
+            name,
+            Some("exported passwords".into()),
+            SourceType::Csv as u32,
+            None,
+            None,
+            SourceAuthType::None as u32,
+        )
+        .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn does_source_exist(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let result_1 = ModelSource::does_source_exist(&pool, "passwords".into()).await;
+
+        assert!(result_1.is_ok());
+        assert!(!result_1.unwrap());
+
+        let result_add = ModelSource::add_source(&pool, new_source("passwords")).await;
+
+        assert!(result_add.is_ok());
+
+        let result_2 = ModelSource::does_source_exist(&pool, "passwords".into()).await;
+        let result_3 =
+            ModelSource::does_source_exist_uuid(&pool, result_add.unwrap().uuid.into_uuid()).await;
+
+        assert!(result_2.is_ok());
+        assert!(result_3.is_ok());
+        assert!(result_2.unwrap());
+        assert!(result_3.unwrap());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn add_source(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let result_1 = ModelSource::add_source(&pool, new_source("passwords")).await;
+        let result_2 = ModelSource::add_source(&pool, new_source("passwords")).await;
+
+        assert!(result_1.is_ok());
+        assert!(result_2.is_err());
+
+        let result_1 = result_1.unwrap();
+
+        assert!(result_1.created_at.is_some());
+        assert!(result_1.updated_at.is_some());
+        assert_eq!(result_1.source_type, SourceType::Csv as u32);
+
+        let result_3 = ModelSource::get_sources(&pool, database::ListQuery::new()).await;
+
+        assert!(result_3.is_ok());
+        assert_eq!(result_3.unwrap().items.len(), 1);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn update_source(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let result_add = ModelSource::add_source(&pool, new_source("passwords")).await;
+
+        assert!(result_add.is_ok());
+
+        let result_add = result_add.unwrap();
+        let created_at = result_add.created_at;
+
+        let source = Source {
+            description: None,
+            source_auth: Some("hunter2".into()),
+            source_auth_type: SourceAuthType::Cipher as u32,
+            ..result_add
+        };
+        let result_1 = ModelSource::update_source(&pool, source).await;
+
+        assert!(result_1.is_ok());
+
+        let result_1 = result_1.unwrap();
+
+        assert_eq!(result_1.description, None);
+        assert_eq!(result_1.source_auth, Some("hunter2".into()));
+        assert_eq!(result_1.source_auth_type, SourceAuthType::Cipher as u32);
+        assert_eq!(result_1.created_at, created_at);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn delete_source(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let source_1 = ModelSource::add_source(&pool, new_source("passwords-1"))
+            .await
+            .unwrap();
+        let source_2 = ModelSource::add_source(&pool, new_source("passwords-2"))
+            .await
+            .unwrap();
+
+        let uuid_source_1 = source_1.uuid.into_uuid();
+        let uuid_source_2 = source_2.uuid.into_uuid();
+
+        // One secret only in the first source and one shared by both sources.
+        let secret_1 = Secret::new("secret-1", None, None, "123", SecretType::Cipher as u32);
+        let secret_2 = Secret::new("secret-2", None, None, "456", SecretType::Cipher as u32);
+
+        let secret_1 = ModelSecret::add_secret(&pool, secret_1.unwrap())
+            .await
+            .unwrap();
+        let secret_2 = ModelSecret::add_secret(&pool, secret_2.unwrap())
+            .await
+            .unwrap();
+
+        for (uuid_source, uuid_secret) in [
+            (uuid_source_1, secret_1.uuid.into_uuid()),
+            (uuid_source_1, secret_2.uuid.into_uuid()),
+            (uuid_source_2, secret_2.uuid.into_uuid()),
+        ] {
+            assert!(
+                ModelSource::link_secret(&pool, uuid_source, uuid_secret)
+                    .await
+                    .is_ok()
+            );
+        }
+
+        let collection =
+            ModelCollection::add_collection(&pool, Collection::new("project").unwrap())
+                .await
+                .unwrap();
+
+        ModelCollection::attach_source(&pool, collection.uuid.into_uuid(), uuid_source_1)
+            .await
+            .unwrap();
+
+        let result_secrets = ModelSource::get_source_secrets(&pool, uuid_source_1).await;
+        let result_count = ModelSource::get_collection_count(&pool, uuid_source_1).await;
+
+        assert!(result_secrets.is_ok());
+        assert!(result_count.is_ok());
+        assert_eq!(result_secrets.unwrap().len(), 2);
+        assert_eq!(result_count.unwrap(), 1);
+
+        // Source is still referenced so deleting must fail.
+        let result_1 = ModelSource::delete_source(&pool, uuid_source_1).await;
+
+        assert!(result_1.is_err());
+
+        assert!(
+            ModelSource::detach_collections(&pool, uuid_source_1)
+                .await
+                .is_ok()
+        );
+        assert!(
+            ModelSource::delete_source_secrets(&pool, uuid_source_1)
+                .await
+                .is_ok()
+        );
+
+        let result_2 = ModelSource::delete_source(&pool, uuid_source_1).await;
+
+        assert!(result_2.is_ok());
+
+        // Shared secret must still exist for the other source.
+        let result_3 = ModelSecret::does_secret_exist(&pool, "secret-1".into()).await;
+        let result_4 = ModelSecret::does_secret_exist(&pool, "secret-2".into()).await;
+
+        assert!(!result_3.unwrap());
+        assert!(result_4.unwrap());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,279 @@
+use crate::schema;
+use shared_core::database::{self, Table};
+
+pub struct ModelTotp;
+
+impl ModelTotp {
+    /// Checks if user has a TOTP secret, enabled or not.
+    pub fn does_totp_exist<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid_user: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, bool> {
+        let filter = vec![(
+            schema::Totp::COLUMN_UUID_USER,
+            uuid_user.as_hyphenated().to_string(),
+        )];
+        schema::Totp::exists(executor, filter)
+    }
+
+    /// Get TOTP secret of user.
+    pub fn get_totp<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid_user: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, schema::Totp> {
+        let filter = vec![(
+            schema::Totp::COLUMN_UUID_USER,
+            uuid_user.as_hyphenated().to_string(),
+        )];
+        schema::Totp::read(executor, filter)
+    }
+
+    /// Add TOTP secret of user, replacing a previous secret.
+    pub fn add_totp<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        totp: schema::Totp,
+    ) -> database::DatabaseFuture<'c, schema::Totp> {
+        Box::pin(async move {
+            let mut tx = executor.begin().await?;
+
+            sqlx::query("DELETE FROM totp WHERE uuid_user = ?")
+                .bind(totp.uuid_user.to_string())
+                .execute(&mut *tx)
+                .await?;
+
+            let result = schema::Totp::create(&mut *tx, totp).await?;
+
+            tx.commit().await?;
+
+            Ok(result)
+        })
+    }
+
+    /// Enable TOTP of user after a code of `step` was confirmed, previous recovery codes are
+    /// replaced by `recovery_codes`. Returns false if TOTP was already enabled.
+    pub fn enable_totp<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid_user: uuid::Uuid,
+        step: u64,
+        recovery_codes: Vec<schema::RecoveryCode>,
+    ) -> database::DatabaseFuture<'c, bool> {
+        Box::pin(async move {
+            let mut tx = executor.begin().await?;
+
+            let rows_affected = sqlx::query(
+                "UPDATE totp
+                    SET enabled = TRUE, last_step = ?
+                    WHERE uuid_user = ? AND enabled = FALSE",
+            )
+            .bind(step as i64)
+            .bind(uuid_user.as_hyphenated().to_string())
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+            if rows_affected != 1 {
+                return Ok(false);
+            }
+
+            sqlx::query("DELETE FROM recovery_codes WHERE uuid_user = ?")
+                .bind(uuid_user.as_hyphenated().to_string())
+                .execute(&mut *tx)
+                .await?;
+
+            for code in recovery_codes {
+                schema::RecoveryCode::create(&mut *tx, code).await?;
+            }
+
+            tx.commit().await?;
+
+            Ok(true)
+        })
+    }
+
+    /// Accept a code of `step` for user. Returns false if a code of this or a later step was
+    /// already accepted, so every code can only be used once.
+    pub fn use_totp_step<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid_user: uuid::Uuid,
+        step: u64,
+    ) -> database::DatabaseFuture<'c, bool> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let rows_affected = sqlx::query(
+                "UPDATE totp
+                    SET last_step = ?1
+                    WHERE uuid_user = ?2 AND enabled = TRUE
+                    AND (last_step IS NULL OR last_step < ?1)",
+            )
+            .bind(step as i64)
+            .bind(uuid_user.as_hyphenated().to_string())
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+
+            Ok(rows_affected == 1)
+        })
+    }
+
+    /// Use recovery code of user with hash, the code is removed.
+    /// Returns false if the user has no such code.
+    pub fn use_recovery_code<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid_user: uuid::Uuid,
+        code_hash: String,
+    ) -> database::DatabaseFuture<'c, bool> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let rows_affected =
+                sqlx::query("DELETE FROM recovery_codes WHERE uuid_user = ? AND code_hash = ?")
+                    .bind(uuid_user.as_hyphenated().to_string())
+                    .bind(code_hash)
+                    .execute(&mut *conn)
+                    .await?
+                    .rows_affected();
+
+            Ok(rows_affected == 1)
+        })
+    }
+
+    /// Count unused recovery codes of user.
+    pub fn count_recovery_codes<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid_user: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, u64> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let result: i64 =
+                sqlx::query_scalar("SELECT COUNT(*) FROM recovery_codes WHERE uuid_user = ?")
+                    .bind(uuid_user.as_hyphenated().to_string())
+                    .fetch_one(&mut *conn)
+                    .await?;
+
+            Ok(result as u64)
+        })
+    }
+
+    /// Remove TOTP secret and recovery codes of user.
+    /// Returns false if the user had no TOTP secret.
+    pub fn delete_totp<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid_user: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, bool> {
+        Box::pin(async move {
+            let mut tx = executor.begin().await?;
+
+            sqlx::query("DELETE FROM recovery_codes WHERE uuid_user = ?")
+                .bind(uuid_user.as_hyphenated().to_string())
+                .execute(&mut *tx)
+                .await?;
+
+            let rows_affected = sqlx::query("DELETE FROM totp WHERE uuid_user = ?")
+                .bind(uuid_user.as_hyphenated().to_string())
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+
+            tx.commit().await?;
+
+            Ok(rows_affected == 1)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{ModelTotp, ModelUser};
+    use crate::schema;
+
+    use shared_core::crypt;
+    use sqlx::sqlite;
+
+    #[sqlx::test]
+    async fn enable_totp(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let user = ModelUser::add_user(&pool, schema::User::new("carl", "").unwrap())
+            .await
+            .unwrap();
+        let uuid_user = user.uuid.into_uuid();
+
+        let totp = crypt::Totp::generate();
+
+        ModelTotp::add_totp(&pool, schema::Totp::new(uuid_user, &totp))
+            .await
+            .unwrap();
+
+        let result = ModelTotp::get_totp(&pool, uuid_user).await.unwrap();
+
+        assert_eq!(result.secret, totp.to_base32());
+        assert!(!result.enabled);
+
+        // Codes are only accepted once enabled
+        assert!(
+            !ModelTotp::use_totp_step(&pool, uuid_user, 10)
+                .await
+                .unwrap()
+        );
+
+        let codes: Vec<_> = (0..3).map(|_| crypt::RecoveryCode::generate()).collect();
+        let rows = codes
+            .iter()
+            .map(|x| schema::RecoveryCode::new(uuid_user, x))
+            .collect();
+
+        assert!(
+            ModelTotp::enable_totp(&pool, uuid_user, 10, rows)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !ModelTotp::enable_totp(&pool, uuid_user, 10, vec![])
+                .await
+                .unwrap()
+        );
+
+        // Steps can't be used twice
+        assert!(
+            !ModelTotp::use_totp_step(&pool, uuid_user, 10)
+                .await
+                .unwrap()
+        );
+        assert!(
+            ModelTotp::use_totp_step(&pool, uuid_user, 11)
+                .await
+                .unwrap()
+        );
+
+        // Recovery codes can't be used twice
+        let hash = codes[0].hash();
+
+        assert!(
+            ModelTotp::use_recovery_code(&pool, uuid_user, hash.clone())
+                .await
+                .unwrap()
+        );
+        assert!(
+            !ModelTotp::use_recovery_code(&pool, uuid_user, hash)
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            ModelTotp::count_recovery_codes(&pool, uuid_user)
+                .await
+                .unwrap(),
+            2
+        );
+
+        assert!(ModelTotp::delete_totp(&pool, uuid_user).await.unwrap());
+        assert_eq!(
+            ModelTotp::count_recovery_codes(&pool, uuid_user)
+                .await
+                .unwrap(),
+            0
+        );
+        assert!(!ModelTotp::does_totp_exist(&pool, uuid_user).await.unwrap());
+
+        Ok(())
+    }
+}
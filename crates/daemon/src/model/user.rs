@@ -1,57 +1,211 @@
 use crate::schema;
-use shared_core::{database, error};
-use sqlx::sqlite;
+use shared_core::database::{self, Table};
 
 pub struct ModelUser;
 
-impl database::TableName for ModelUser {
-    const NAME: &'static str = "users_active";
-}
-
 impl ModelUser {
     /// Checks if user exists.
-    pub async fn does_user_exist(
-        pool: &sqlite::SqlitePool,
+    pub fn does_user_exist<'c>(
+        executor: impl database::Acquire<'c> + 'c,
         username: String,
-    ) -> Result<bool, error::Error> {
-        let filter = vec![("username", username)];
-        database::exists::<Self>(pool, filter).await
+    ) -> database::DatabaseFuture<'c, bool> {
+        let filter = vec![(schema::User::COLUMN_USERNAME, username)];
+        schema::User::exists(executor, filter)
+    }
+
+    /// Checks if user with uuid exists.
+    pub fn does_user_exist_uuid<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, bool> {
+        // Database stores uuid in hyphenated form.
+        let filter = vec![(schema::User::COLUMN_UUID, uuid.as_hyphenated().to_string())];
+        schema::User::exists(executor, filter)
     }
 
     /// Get user from username.
-    pub async fn get_user_from_username(
-        pool: &sqlite::SqlitePool,
+    pub fn get_user_from_username<'c>(
+        executor: impl database::Acquire<'c> + 'c,
         username: String,
-    ) -> Result<schema::User, error::Error> {
-        let filter = vec![("username", username)];
-        database::read::<Self, schema::User>(pool, filter).await
+    ) -> database::DatabaseFuture<'c, schema::User> {
+        let filter = vec![(schema::User::COLUMN_USERNAME, username)];
+        schema::User::read(executor, filter)
     }
 
     /// Get user from uuid.
-    pub async fn get_user_from_uuid(
-        pool: &sqlite::SqlitePool,
+    pub fn get_user_from_uuid<'c>(
+        executor: impl database::Acquire<'c> + 'c,
         uuid: uuid::Uuid,
-    ) -> Result<schema::User, error::Error> {
+    ) -> database::DatabaseFuture<'c, schema::User> {
         // Database stores uuid in hyphenated form.
-        let filter = vec![("uuid", uuid.as_hyphenated().to_string())];
-        database::read::<Self, schema::User>(pool, filter).await
+        let filter = vec![(schema::User::COLUMN_UUID, uuid.as_hyphenated().to_string())];
+        schema::User::read(executor, filter)
+    }
+
+    /// Get active users.
+    pub fn get_users<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        query: database::ListQuery,
+    ) -> database::DatabaseFuture<'c, database::Page<schema::User>> {
+        schema::User::list(executor, query)
     }
 
     /// Add a new user.
-    pub async fn add_user(
-        pool: &sqlite::SqlitePool,
+    pub fn add_user<'c>(
+        executor: impl database::Acquire<'c> + 'c,
         user: schema::User,
-    ) -> Result<schema::User, error::Error> {
+    ) -> database::DatabaseFuture<'c, schema::User> {
         // We get return data from database since on insert, things
         // like timestamps and other default values will be written.
-        database::create::<Self, schema::User>(pool, user).await
+        schema::User::create(executor, user)
+    }
+
+    /// Number of users, including users which have been soft deleted.
+    pub fn get_user_count<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+    ) -> database::DatabaseFuture<'c, i64> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let result = sqlx::query_scalar("SELECT COUNT(*) FROM users")
+                .fetch_one(&mut *conn)
+                .await?;
+
+            Ok(result)
+        })
+    }
+
+    /// Number of admins which haven't been soft deleted.
+    pub fn get_admin_count<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+    ) -> database::DatabaseFuture<'c, i64> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let result = sqlx::query_scalar("SELECT COUNT(*) FROM users_active WHERE admin = TRUE")
+                .fetch_one(&mut *conn)
+                .await?;
+
+            Ok(result)
+        })
+    }
+
+    /// Update password hash of user.
+    /// Every session of the user is revoked in the same transaction, returns the number of
+    /// sessions revoked.
+    pub fn update_password<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+        password_hash: String,
+    ) -> database::DatabaseFuture<'c, u64> {
+        Box::pin(async move {
+            let mut tx = executor.begin().await?;
+
+            let rows_affected = sqlx::query(
+                "UPDATE users
+                    SET password_hash = ?
+                    WHERE uuid = ? AND deleted = FALSE",
+            )
+            .bind(password_hash)
+            .bind(uuid.as_hyphenated().to_string())
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+            if rows_affected == 0 {
+                return Err(sqlx::error::Error::RowNotFound.into());
+            }
+
+            let result = super::ModelSession::revoke_user_sessions(&mut *tx, uuid).await?;
+
+            tx.commit().await?;
+
+            Ok(result)
+        })
+    }
+
+    /// Replace password hash of user with a new hash of the same password, sessions are kept.
+    /// Returns false if the hash changed in the meantime, e.g. by a password change.
+    pub fn rehash_password<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+        password_hash_old: String,
+        password_hash_new: String,
+    ) -> database::DatabaseFuture<'c, bool> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let rows_affected = sqlx::query(
+                "UPDATE users
+                    SET password_hash = ?
+                    WHERE uuid = ? AND password_hash = ? AND deleted = FALSE",
+            )
+            .bind(password_hash_new)
+            .bind(uuid.as_hyphenated().to_string())
+            .bind(password_hash_old)
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+
+            Ok(rows_affected > 0)
+        })
+    }
+
+    /// Soft delete user with uuid.
+    /// The user is kept in the users table until purged but is no longer active.
+    pub fn delete_user<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        uuid: uuid::Uuid,
+    ) -> database::DatabaseFuture<'c, ()> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let rows_affected = sqlx::query(
+                "UPDATE users
+                    SET deleted = TRUE, deleted_at = DATETIME('NOW')
+                    WHERE uuid = ? AND deleted = FALSE",
+            )
+            .bind(uuid.as_hyphenated().to_string())
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+
+            if rows_affected == 0 {
+                return Err(sqlx::error::Error::RowNotFound.into());
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Hard delete users which were soft deleted more than `retention_days` ago.
+    /// Returns the number of users removed.
+    pub fn purge_users<'c>(
+        executor: impl database::Acquire<'c> + 'c,
+        retention_days: u32,
+    ) -> database::DatabaseFuture<'c, u64> {
+        Box::pin(async move {
+            let mut conn = executor.acquire().await?;
+
+            let rows_affected = sqlx::query(
+                "DELETE FROM users
+                    WHERE deleted = TRUE
+                    AND deleted_at <= DATETIME('NOW', ?)",
+            )
+            .bind(format!("-{} days", retention_days))
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+
+            Ok(rows_affected)
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::model::ModelUser;
-    use crate::schema::User;
+    use crate::model::{ModelSession, ModelUser};
+    use crate::schema::{Session, User};
 
     use sqlx::sqlite;
 
@@ -129,4 +283,193 @@ mod tests {
 
         Ok(())
     }
+
+    #[sqlx::test]
+    async fn delete_user(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let user = User {
+            username: "jeff".into(),
+            ..User::default()
+        };
+        let result_add = ModelUser::add_user(&pool, user).await;
+
+        assert!(result_add.is_ok());
+
+        let uuid = result_add.unwrap().uuid.into_uuid();
+
+        let result_1 = ModelUser::delete_user(&pool, uuid).await;
+        let result_2 = ModelUser::delete_user(&pool, uuid).await;
+
+        assert!(result_1.is_ok());
+        assert!(result_2.is_err());
+
+        // User is no longer active but still counted until purged
+        let result_3 = ModelUser::does_user_exist(&pool, "jeff".into()).await;
+        let result_4 = ModelUser::get_user_count(&pool).await;
+
+        assert!(!result_3.unwrap());
+        assert_eq!(result_4.unwrap(), 1);
+
+        // User was deleted just now so it is within the retention period
+        let result_5 = ModelUser::purge_users(&pool, 1).await;
+        let result_6 = ModelUser::purge_users(&pool, 0).await;
+
+        assert_eq!(result_5.unwrap(), 0);
+        assert_eq!(result_6.unwrap(), 1);
+        assert_eq!(ModelUser::get_user_count(&pool).await.unwrap(), 0);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn update_password(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let user = User {
+            username: "jeff".into(),
+            ..User::default()
+        };
+        let user = ModelUser::add_user(&pool, user).await.unwrap();
+        let uuid = user.uuid.into_uuid();
+
+        let session = Session::new(
+            uuid::Uuid::new_v4(),
+            uuid,
+            None,
+            chrono::Utc::now().naive_utc() + chrono::Duration::days(1),
+        );
+
+        ModelSession::add_session(&pool, session).await.unwrap();
+
+        let result_1 = ModelUser::update_password(&pool, uuid, "new-hash".into()).await;
+
+        // Sessions of the old password are revoked
+        assert_eq!(result_1.unwrap(), 1);
+
+        let user = ModelUser::get_user_from_uuid(&pool, uuid).await.unwrap();
+
+        assert_eq!(user.password_hash, "new-hash");
+
+        // Deleted users can't change their password
+        ModelUser::delete_user(&pool, uuid).await.unwrap();
+
+        let result_2 = ModelUser::update_password(&pool, uuid, "other-hash".into()).await;
+
+        assert!(result_2.is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn rehash_password(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let user = User {
+            username: "jeff".into(),
+            password_hash: "old-hash".into(),
+            ..User::default()
+        };
+        let user = ModelUser::add_user(&pool, user).await.unwrap();
+        let uuid = user.uuid.into_uuid();
+
+        let session = Session::new(
+            uuid::Uuid::new_v4(),
+            uuid,
+            None,
+            chrono::Utc::now().naive_utc() + chrono::Duration::days(1),
+        );
+
+        ModelSession::add_session(&pool, session).await.unwrap();
+
+        let result_1 =
+            ModelUser::rehash_password(&pool, uuid, "old-hash".into(), "new-hash".into()).await;
+
+        // Hashes are only replaced if they didn't change in the meantime
+        let result_2 =
+            ModelUser::rehash_password(&pool, uuid, "old-hash".into(), "other-hash".into()).await;
+
+        assert!(result_1.unwrap());
+        assert!(!result_2.unwrap());
+
+        let user = ModelUser::get_user_from_uuid(&pool, uuid).await.unwrap();
+
+        assert_eq!(user.password_hash, "new-hash");
+
+        // Sessions are kept
+        let result_3 = ModelSession::revoke_user_sessions(&pool, uuid).await;
+
+        assert_eq!(result_3.unwrap(), 1);
+
+        Ok(())
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn migrate_admin(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        // Migrate to before users could be admins
+        let mut migrator = sqlx::migrate!();
+
+        migrator.migrations = migrator
+            .migrations
+            .iter()
+            .filter(|x| x.version < 20251103021540)
+            .cloned()
+            .collect();
+
+        migrator.run(&pool).await.unwrap();
+
+        for (username, deleted) in [("jeff", true), ("bob", false), ("carl", false)] {
+            sqlx::query(
+                "INSERT INTO users (uuid, username, password_hash, salt, argon2_iters, argon2_memory_mb, argon2_parallelism, deleted)
+                    VALUES (?, ?, 'hash', '1111111111111111', 2, 32, 2, ?)",
+            )
+            .bind(uuid::Uuid::new_v4().as_hyphenated().to_string())
+            .bind(username)
+            .bind(deleted)
+            .execute(&pool)
+            .await?;
+        }
+
+        sqlx::migrate!().run(&pool).await.unwrap();
+
+        // Earliest user which isn't deleted becomes admin
+        let user_1 = ModelUser::get_user_from_username(&pool, "bob".into()).await;
+        let user_2 = ModelUser::get_user_from_username(&pool, "carl".into()).await;
+
+        assert!(user_1.unwrap().is_admin());
+        assert!(!user_2.unwrap().is_admin());
+
+        Ok(())
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn migrate_admin_existing(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        // Migrate to before the earliest user got promoted
+        let mut migrator = sqlx::migrate!();
+
+        migrator.migrations = migrator
+            .migrations
+            .iter()
+            .filter(|x| x.version < 20251124090000)
+            .cloned()
+            .collect();
+
+        migrator.run(&pool).await.unwrap();
+
+        for (username, admin) in [("bob", false), ("carl", true)] {
+            sqlx::query(
+                "INSERT INTO users (uuid, username, password_hash, admin) VALUES (?, ?, 'hash', ?)",
+            )
+            .bind(uuid::Uuid::new_v4().as_hyphenated().to_string())
+            .bind(username)
+            .bind(admin)
+            .execute(&pool)
+            .await?;
+        }
+
+        sqlx::migrate!().run(&pool).await.unwrap();
+
+        // Installs which already have an admin keep their admins
+        let user_1 = ModelUser::get_user_from_username(&pool, "bob".into()).await;
+        let user_2 = ModelUser::get_user_from_username(&pool, "carl".into()).await;
+
+        assert!(!user_1.unwrap().is_admin());
+        assert!(user_2.unwrap().is_admin());
+
+        Ok(())
+    }
 }
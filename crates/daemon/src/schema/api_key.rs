@@ -0,0 +1,95 @@
+use shared_core::database;
+use std::str::FromStr;
+use validator::Validate;
+
+/// Api key row entry, keys belong to service accounts.
+#[derive(
+    Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow, database::Table, validator::Validate,
+)]
+#[table(name = "api_keys")]
+pub struct ApiKey {
+    #[table(primary_key)]
+    #[serde(deserialize_with = "shared_core::serde::uuid::Hyphenated::deserialize")]
+    pub uuid: uuid::fmt::Hyphenated,
+    #[serde(deserialize_with = "shared_core::serde::uuid::Hyphenated::deserialize")]
+    pub uuid_user: uuid::fmt::Hyphenated,
+    #[validate(length(min = 3, max = 255))]
+    pub name: String,
+    pub key_hash: String,
+    #[serde(with = "shared_core::serde::datetime::option")]
+    pub expires_at: Option<chrono::NaiveDateTime>,
+    #[serde(with = "shared_core::serde::datetime::option")]
+    pub last_used_at: Option<chrono::NaiveDateTime>,
+    #[serde(with = "shared_core::serde::datetime::option")]
+    pub created_at: Option<chrono::NaiveDateTime>,
+    #[serde(with = "shared_core::serde::datetime::option")]
+    pub updated_at: Option<chrono::NaiveDateTime>,
+}
+
+impl Default for ApiKey {
+    fn default() -> Self {
+        Self {
+            uuid: uuid::fmt::Hyphenated::from_str("9a4b1f02-3c7e-4d8a-b5f6-2e1d0c9b8a7f").unwrap(),
+            uuid_user: uuid::fmt::Hyphenated::from_str("56d7f689-68b0-4473-8336-6678a5d4788d")
+                .unwrap(),
+            name: "example-key".to_string(),
+            key_hash: String::new(),
+            expires_at: None,
+            last_used_at: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+}
+
+impl ApiKey {
+    /// Create api key row from key, only the hash of the secret is kept.
+    pub fn new<A>(
+        key: &shared_core::crypt::ApiKey,
+        uuid_user: uuid::Uuid,
+        name: A,
+        expires_at: Option<chrono::NaiveDateTime>,
+    ) -> Result<Self, validator::ValidationErrors>
+    where
+        A: ToString,
+    {
+        let res = ApiKey {
+            uuid: key.id.into(),
+            uuid_user: uuid_user.into(),
+            name: name.to_string(),
+            key_hash: key.hash(),
+            expires_at,
+            ..Self::default()
+        };
+
+        res.validate()?;
+
+        Ok(res)
+    }
+
+    /// Checks if key expired.
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|x| x <= chrono::Utc::now().naive_utc())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ApiKey;
+
+    #[tokio::test]
+    async fn new_api_key() {
+        let key = shared_core::crypt::ApiKey::generate(uuid::Uuid::new_v4());
+        let past = chrono::Utc::now().naive_utc() - chrono::Duration::days(1);
+
+        let api_key_1 = ApiKey::new(&key, uuid::Uuid::new_v4(), "deploy", None).unwrap();
+        let api_key_2 = ApiKey::new(&key, uuid::Uuid::new_v4(), "deploy", Some(past)).unwrap();
+        let api_key_3 = ApiKey::new(&key, uuid::Uuid::new_v4(), "k", None);
+
+        assert!(key.verify(&api_key_1.key_hash));
+        assert!(!api_key_1.is_expired());
+        assert!(api_key_2.is_expired());
+        assert!(api_key_3.is_err());
+    }
+}
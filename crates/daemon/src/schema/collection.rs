@@ -1,9 +1,14 @@
+use shared_core::database;
 use std::str::FromStr;
 use validator::Validate;
 
 /// Collection row entry
-#[derive(Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow, validator::Validate)]
+#[derive(
+    Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow, database::Table, validator::Validate,
+)]
+#[table(name = "collections")]
 pub struct Collection {
+    #[table(primary_key)]
     #[serde(deserialize_with = "shared_core::serde::uuid::Hyphenated::deserialize")]
     pub uuid: uuid::fmt::Hyphenated,
     #[validate(length(min = 3, max = 255))]
@@ -35,6 +40,27 @@ impl Collection {
     }
 }
 
+/// Collection source row entry
+#[derive(Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow, database::Table)]
+#[table(name = "collection_source")]
+pub struct CollectionSource {
+    #[table(primary_key)]
+    #[serde(deserialize_with = "shared_core::serde::uuid::Hyphenated::deserialize")]
+    pub uuid_collection: uuid::fmt::Hyphenated,
+    #[table(primary_key)]
+    #[serde(deserialize_with = "shared_core::serde::uuid::Hyphenated::deserialize")]
+    pub uuid_source: uuid::fmt::Hyphenated,
+}
+
+impl CollectionSource {
+    pub fn new(uuid_collection: uuid::Uuid, uuid_source: uuid::Uuid) -> Self {
+        Self {
+            uuid_collection: uuid_collection.into(),
+            uuid_source: uuid_source.into(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::Collection;
@@ -0,0 +1,46 @@
+use shared_core::database;
+use std::str::FromStr;
+
+/// Data key row entry, a key encrypting secret values wrapped by the master key.
+#[derive(Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow, database::Table)]
+#[table(name = "data_keys")]
+pub struct DataKey {
+    #[table(primary_key)]
+    #[serde(deserialize_with = "shared_core::serde::uuid::Hyphenated::deserialize")]
+    pub uuid: uuid::fmt::Hyphenated,
+    pub uuid_collection: Option<uuid::fmt::Hyphenated>,
+    /// Id of the master key the data key is wrapped with.
+    pub master_key_id: String,
+    pub wrapped_key: String,
+    #[serde(with = "shared_core::serde::datetime::option")]
+    pub created_at: Option<chrono::NaiveDateTime>,
+}
+
+impl Default for DataKey {
+    fn default() -> Self {
+        Self {
+            uuid: uuid::fmt::Hyphenated::from_str("5d8e2f4a-9b1c-4e7d-a3f6-2c8b0e9d7a41").unwrap(),
+            uuid_collection: None,
+            master_key_id: String::new(),
+            wrapped_key: String::new(),
+            created_at: None,
+        }
+    }
+}
+
+impl DataKey {
+    /// Create data key of collection, the default data key has no collection.
+    pub fn new(
+        uuid_collection: Option<uuid::Uuid>,
+        master_key_id: String,
+        wrapped_key: String,
+    ) -> Self {
+        Self {
+            uuid: uuid::Uuid::new_v4().into(),
+            uuid_collection: uuid_collection.map(Into::into),
+            master_key_id,
+            wrapped_key,
+            created_at: None,
+        }
+    }
+}
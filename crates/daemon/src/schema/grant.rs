@@ -0,0 +1,138 @@
+use shared_core::database;
+use std::str::FromStr;
+
+/// Permission checked before accessing a resource, values match the permissions table.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
+pub enum Permission {
+    Read,
+    Write,
+    Manage,
+}
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::Manage => "manage",
+        }
+    }
+}
+
+impl std::fmt::Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Scope type of grant
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
+pub enum ScopeType {
+    Global = 0,
+    Collection = 1,
+    Source = 2,
+}
+
+impl TryFrom<u32> for ScopeType {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Global),
+            1 => Ok(Self::Collection),
+            2 => Ok(Self::Source),
+            x => Err(x),
+        }
+    }
+}
+
+/// Resource a permission is checked against.
+///
+/// Grants on a collection also cover the sources attached to it and grants on a source
+/// also cover the secrets it owns.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Resource {
+    Global,
+    Collection(uuid::Uuid),
+    Source(uuid::Uuid),
+    Secret(uuid::Uuid),
+}
+
+/// Grant row entry
+#[derive(Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow, database::Table)]
+#[table(name = "grants")]
+pub struct Grant {
+    #[table(primary_key)]
+    #[serde(deserialize_with = "shared_core::serde::uuid::Hyphenated::deserialize")]
+    pub uuid: uuid::fmt::Hyphenated,
+    #[serde(deserialize_with = "shared_core::serde::uuid::Hyphenated::deserialize")]
+    pub uuid_user: uuid::fmt::Hyphenated,
+    pub role: String,
+    pub scope_type: u32,
+    pub uuid_scope: Option<uuid::fmt::Hyphenated>,
+    pub uuid_api_key: Option<uuid::fmt::Hyphenated>,
+    #[serde(with = "shared_core::serde::datetime::option")]
+    pub created_at: Option<chrono::NaiveDateTime>,
+}
+
+impl Default for Grant {
+    fn default() -> Self {
+        Self {
+            uuid: uuid::fmt::Hyphenated::from_str("1c0d8f46-54b4-4d2c-8a3e-0f3f61c2b7a9").unwrap(),
+            uuid_user: uuid::fmt::Hyphenated::from_str("56d7f689-68b0-4473-8336-6678a5d4788d")
+                .unwrap(),
+            role: "reader".to_string(),
+            scope_type: ScopeType::Global as u32,
+            uuid_scope: None,
+            uuid_api_key: None,
+            created_at: None,
+        }
+    }
+}
+
+impl Grant {
+    /// Create grant of role to user, `uuid_scope` is ignored for global grants.
+    pub fn new<A>(
+        uuid_user: uuid::Uuid,
+        role: A,
+        scope_type: ScopeType,
+        uuid_scope: Option<uuid::Uuid>,
+    ) -> Self
+    where
+        A: ToString,
+    {
+        let uuid_scope = match scope_type {
+            ScopeType::Global => None,
+            _ => uuid_scope.map(|x| x.into()),
+        };
+
+        Self {
+            uuid: uuid::Uuid::new_v4().into(),
+            uuid_user: uuid_user.into(),
+            role: role.to_string(),
+            scope_type: scope_type as u32,
+            uuid_scope,
+            ..Self::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Grant, ScopeType};
+
+    #[tokio::test]
+    async fn new_grant() {
+        let uuid_user = uuid::Uuid::new_v4();
+        let uuid_scope = uuid::Uuid::new_v4();
+
+        let grant_1 = Grant::new(uuid_user, "reader", ScopeType::Collection, Some(uuid_scope));
+        let grant_2 = Grant::new(uuid_user, "reader", ScopeType::Global, Some(uuid_scope));
+
+        assert_eq!(grant_1.uuid_scope.unwrap().into_uuid(), uuid_scope);
+        assert!(grant_2.uuid_scope.is_none());
+
+        assert_eq!(ScopeType::try_from(2), Ok(ScopeType::Source));
+        assert_eq!(ScopeType::try_from(3), Err(3));
+    }
+}
@@ -0,0 +1,58 @@
+use shared_core::database;
+use std::str::FromStr;
+
+/// Invite row entry, a one-time code allowing to register a user.
+#[derive(Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow, database::Table)]
+#[table(name = "invites")]
+pub struct Invite {
+    #[table(primary_key)]
+    #[serde(deserialize_with = "shared_core::serde::uuid::Hyphenated::deserialize")]
+    pub uuid: uuid::fmt::Hyphenated,
+    pub uuid_creator: Option<uuid::fmt::Hyphenated>,
+    pub code_hash: String,
+    pub admin: bool,
+    #[serde(with = "shared_core::serde::datetime::option")]
+    pub expires_at: Option<chrono::NaiveDateTime>,
+    #[serde(with = "shared_core::serde::datetime::option")]
+    pub created_at: Option<chrono::NaiveDateTime>,
+}
+
+impl Default for Invite {
+    fn default() -> Self {
+        Self {
+            uuid: uuid::fmt::Hyphenated::from_str("c2e7a9d4-5f1b-4c3a-8e6d-0b9f8a7c6d5e").unwrap(),
+            uuid_creator: None,
+            code_hash: String::new(),
+            admin: false,
+            expires_at: None,
+            created_at: None,
+        }
+    }
+}
+
+impl Invite {
+    /// Create invite created by an admin, users registered with it aren't admins.
+    pub fn new(
+        code: &shared_core::crypt::InviteCode,
+        uuid_creator: uuid::Uuid,
+        expires_at: chrono::NaiveDateTime,
+    ) -> Self {
+        Self {
+            uuid: uuid::Uuid::new_v4().into(),
+            uuid_creator: Some(uuid_creator.into()),
+            code_hash: code.hash(),
+            expires_at: Some(expires_at),
+            ..Self::default()
+        }
+    }
+
+    /// Create setup invite, the user registered with it is the first admin.
+    pub fn setup(code: &shared_core::crypt::InviteCode) -> Self {
+        Self {
+            uuid: uuid::Uuid::new_v4().into(),
+            code_hash: code.hash(),
+            admin: true,
+            ..Self::default()
+        }
+    }
+}
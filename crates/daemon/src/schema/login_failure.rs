@@ -0,0 +1,63 @@
+use shared_core::database;
+
+/// Subject failed logins are tracked for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoginSubject {
+    Username(String),
+    Peer(std::net::IpAddr),
+}
+
+impl LoginSubject {
+    /// Kind of subject as stored in the database.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Username(_) => "username",
+            Self::Peer(_) => "peer",
+        }
+    }
+
+    /// Name of subject as stored in the database.
+    pub fn name(&self) -> String {
+        match self {
+            Self::Username(x) => x.clone(),
+            Self::Peer(x) => x.to_string(),
+        }
+    }
+}
+
+/// Limits login attempts of a subject are checked against.
+#[derive(Debug, Clone, Copy)]
+pub struct LoginLimits {
+    /// Failed logins before the subject is locked.
+    pub max_failures: u32,
+    /// Delay after the first failed login, doubled for every further failure.
+    pub backoff: chrono::Duration,
+    /// Maximum delay between failed logins.
+    pub backoff_max: chrono::Duration,
+    /// Time the subject stays locked.
+    pub lockout: chrono::Duration,
+    /// Time without failed logins after which failures are forgotten.
+    pub reset: chrono::Duration,
+}
+
+/// Failed login row entry
+#[derive(Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow, database::Table)]
+#[table(name = "login_failures")]
+pub struct LoginFailure {
+    #[table(primary_key)]
+    pub kind: String,
+    #[table(primary_key)]
+    pub name: String,
+    pub failures: u32,
+    #[serde(with = "shared_core::serde::datetime::option")]
+    pub locked_until: Option<chrono::NaiveDateTime>,
+    #[serde(with = "shared_core::serde::datetime")]
+    pub last_failed_at: chrono::NaiveDateTime,
+}
+
+impl LoginFailure {
+    /// Time left until subject is unlocked, none if subject isn't locked at time.
+    pub fn locked_for(&self, now: chrono::NaiveDateTime) -> Option<chrono::Duration> {
+        self.locked_until.filter(|x| *x > now).map(|x| x - now)
+    }
+}
@@ -1,10 +1,25 @@
 #![allow(clippy::too_many_arguments)]
 
+mod api_key;
 mod collection;
+mod data_key;
+mod grant;
+mod invite;
+mod login_failure;
 mod secret;
+mod session;
 mod source;
+mod totp;
 mod user;
 
+pub use api_key::*;
 pub use collection::*;
+pub use data_key::*;
+pub use grant::*;
+pub use invite::*;
+pub use login_failure::*;
 pub use secret::*;
+pub use session::*;
+pub use source::*;
+pub use totp::*;
 pub use user::*;
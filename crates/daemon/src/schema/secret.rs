@@ -1,3 +1,4 @@
+use shared_core::database;
 use std::str::FromStr;
 use validator::Validate;
 
@@ -20,16 +21,22 @@ impl From<u32> for SecretType {
 }
 
 /// Secret row entry
-#[derive(Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow, validator::Validate)]
+#[derive(
+    Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow, database::Table, validator::Validate,
+)]
+#[table(name = "secrets")]
 pub struct Secret {
+    #[table(primary_key)]
     #[serde(deserialize_with = "shared_core::serde::uuid::Hyphenated::deserialize")]
     pub uuid: uuid::fmt::Hyphenated,
     #[validate(length(min = 3, max = 255))]
     pub name: String,
     pub key: Option<String>,
     pub description: Option<String>,
+    /// Value encrypted with the data key, or the plaintext value if there is no data key.
     pub secret: String,
     pub secret_type: u32,
+    pub uuid_data_key: Option<uuid::fmt::Hyphenated>,
 }
 
 impl Default for Secret {
@@ -41,6 +48,7 @@ impl Default for Secret {
             description: Some("some example secret".to_string()),
             secret: "my_secret".to_string(),
             secret_type: SecretType::Unknown as u32,
+            uuid_data_key: None,
         }
     }
 }
@@ -64,6 +72,7 @@ impl Secret {
             description,
             secret: secret.to_string(),
             secret_type,
+            uuid_data_key: None,
         };
 
         res.validate()?;
@@ -0,0 +1,56 @@
+use shared_core::database;
+use std::str::FromStr;
+
+/// Session row entry, each row tracks one refresh token.
+#[derive(Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow, database::Table)]
+#[table(name = "sessions")]
+pub struct Session {
+    #[table(primary_key)]
+    #[serde(deserialize_with = "shared_core::serde::uuid::Hyphenated::deserialize")]
+    pub uuid: uuid::fmt::Hyphenated,
+    #[serde(deserialize_with = "shared_core::serde::uuid::Hyphenated::deserialize")]
+    pub uuid_user: uuid::fmt::Hyphenated,
+    #[serde(deserialize_with = "shared_core::serde::uuid::Hyphenated::deserialize")]
+    pub uuid_family: uuid::fmt::Hyphenated,
+    pub rotated: bool,
+    pub revoked: bool,
+    #[serde(with = "shared_core::serde::datetime")]
+    pub expires_at: chrono::NaiveDateTime,
+    #[serde(with = "shared_core::serde::datetime::option")]
+    pub created_at: Option<chrono::NaiveDateTime>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            uuid: uuid::fmt::Hyphenated::from_str("3f9e6c1a-7b2d-4e8f-9a0b-5c4d3e2f1a0b").unwrap(),
+            uuid_user: uuid::fmt::Hyphenated::from_str("56d7f689-68b0-4473-8336-6678a5d4788d")
+                .unwrap(),
+            uuid_family: uuid::fmt::Hyphenated::from_str("3f9e6c1a-7b2d-4e8f-9a0b-5c4d3e2f1a0b")
+                .unwrap(),
+            rotated: false,
+            revoked: false,
+            expires_at: chrono::NaiveDateTime::default(),
+            created_at: None,
+        }
+    }
+}
+
+impl Session {
+    /// Create session for refresh token with id.
+    /// Tokens from a new login start a new family, refreshed tokens stay in their family.
+    pub fn new(
+        uuid: uuid::Uuid,
+        uuid_user: uuid::Uuid,
+        uuid_family: Option<uuid::Uuid>,
+        expires_at: chrono::NaiveDateTime,
+    ) -> Self {
+        Self {
+            uuid: uuid.into(),
+            uuid_user: uuid_user.into(),
+            uuid_family: uuid_family.unwrap_or(uuid).into(),
+            expires_at,
+            ..Self::default()
+        }
+    }
+}
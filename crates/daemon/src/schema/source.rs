@@ -1,3 +1,4 @@
+use shared_core::database;
 use std::str::FromStr;
 use validator::Validate;
 
@@ -34,17 +35,24 @@ impl From<u32> for SourceAuthType {
 }
 
 /// Secret source row entry
-#[derive(Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow, validator::Validate)]
+#[derive(
+    Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow, database::Table, validator::Validate,
+)]
+#[table(name = "sources")]
 pub struct Source {
+    #[table(primary_key)]
     #[serde(deserialize_with = "shared_core::serde::uuid::Hyphenated::deserialize")]
     pub uuid: uuid::fmt::Hyphenated,
     #[validate(length(min = 3, max = 255))]
     pub name: String,
     pub description: Option<String>,
     pub source_type: u32,
+    pub source_config: Option<String>,
     pub source_auth: Option<String>,
     pub source_auth_type: u32,
+    #[serde(with = "shared_core::serde::datetime::option")]
     pub created_at: Option<chrono::NaiveDateTime>,
+    #[serde(with = "shared_core::serde::datetime::option")]
     pub updated_at: Option<chrono::NaiveDateTime>,
 }
 
@@ -55,6 +63,7 @@ impl Default for Source {
             name: "example-source".to_string(),
             description: Some("default example source".to_string()),
             source_type: SourceType::Unknown as u32,
+            source_config: None,
             source_auth: None,
             source_auth_type: SourceAuthType::None as u32,
             created_at: None,
@@ -68,6 +77,7 @@ impl Source {
         name: A,
         description: Option<String>,
         source_type: u32,
+        source_config: Option<String>,
         source_auth: Option<String>,
         source_auth_type: u32,
     ) -> Result<Self, validator::ValidationErrors>
@@ -79,6 +89,7 @@ impl Source {
             name: name.to_string(),
             description,
             source_type,
+            source_config,
             source_auth,
             source_auth_type,
             ..Self::default()
@@ -90,6 +101,27 @@ impl Source {
     }
 }
 
+/// Source secret row entry
+#[derive(Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow, database::Table)]
+#[table(name = "source_secrets")]
+pub struct SourceSecret {
+    #[table(primary_key)]
+    #[serde(deserialize_with = "shared_core::serde::uuid::Hyphenated::deserialize")]
+    pub uuid_source: uuid::fmt::Hyphenated,
+    #[table(primary_key)]
+    #[serde(deserialize_with = "shared_core::serde::uuid::Hyphenated::deserialize")]
+    pub uuid_secret: uuid::fmt::Hyphenated,
+}
+
+impl SourceSecret {
+    pub fn new(uuid_source: uuid::Uuid, uuid_secret: uuid::Uuid) -> Self {
+        Self {
+            uuid_source: uuid_source.into(),
+            uuid_secret: uuid_secret.into(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{Source, SourceAuthType, SourceType};
@@ -101,6 +133,7 @@ mod tests {
             Some("information from bob".to_string()),
             SourceType::Csv as u32,
             None,
+            None,
             SourceAuthType::None as u32,
         );
 
@@ -111,6 +144,7 @@ mod tests {
             Some("information from bob".to_string()),
             SourceType::Csv as u32,
             None,
+            None,
             SourceAuthType::None as u32,
         );
 
@@ -126,6 +160,7 @@ mod tests {
             Some("information from bob".to_string()),
             SourceType::Csv as u32,
             None,
+            None,
             SourceAuthType::None as u32,
         );
 
@@ -0,0 +1,61 @@
+use shared_core::database;
+use std::str::FromStr;
+
+/// TOTP row entry, the second factor of a user.
+#[derive(Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow, database::Table)]
+#[table(name = "totp")]
+pub struct Totp {
+    #[table(primary_key)]
+    #[serde(deserialize_with = "shared_core::serde::uuid::Hyphenated::deserialize")]
+    pub uuid_user: uuid::fmt::Hyphenated,
+    pub secret: String,
+    pub enabled: bool,
+    pub last_step: Option<i64>,
+    #[serde(with = "shared_core::serde::datetime::option")]
+    pub created_at: Option<chrono::NaiveDateTime>,
+}
+
+impl Default for Totp {
+    fn default() -> Self {
+        Self {
+            uuid_user: uuid::fmt::Hyphenated::from_str("56d7f689-68b0-4473-8336-6678a5d4788d")
+                .unwrap(),
+            secret: String::new(),
+            enabled: false,
+            last_step: None,
+            created_at: None,
+        }
+    }
+}
+
+impl Totp {
+    /// Create TOTP row of user, it's disabled until enrollment is confirmed.
+    pub fn new(uuid_user: uuid::Uuid, totp: &shared_core::crypt::Totp) -> Self {
+        Self {
+            uuid_user: uuid_user.into(),
+            secret: totp.to_base32(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Recovery code row entry, only the hash of the code is kept.
+#[derive(Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow, database::Table)]
+#[table(name = "recovery_codes")]
+pub struct RecoveryCode {
+    #[table(primary_key)]
+    #[serde(deserialize_with = "shared_core::serde::uuid::Hyphenated::deserialize")]
+    pub uuid_user: uuid::fmt::Hyphenated,
+    #[table(primary_key)]
+    pub code_hash: String,
+}
+
+impl RecoveryCode {
+    /// Create recovery code row of user.
+    pub fn new(uuid_user: uuid::Uuid, code: &shared_core::crypt::RecoveryCode) -> Self {
+        Self {
+            uuid_user: uuid_user.into(),
+            code_hash: code.hash(),
+        }
+    }
+}
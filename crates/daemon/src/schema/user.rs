@@ -1,21 +1,29 @@
+use shared_core::database;
 use std::str::FromStr;
 use validator::Validate;
 
 /// User row entry
-#[derive(Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow, validator::Validate)]
+#[derive(
+    Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow, database::Table, validator::Validate,
+)]
+#[table(name = "users_active")]
 pub struct User {
+    #[table(primary_key)]
     #[serde(deserialize_with = "shared_core::serde::uuid::Hyphenated::deserialize")]
     pub uuid: uuid::fmt::Hyphenated,
     #[validate(length(min = 3, max = 255))]
     pub username: String,
+    /// PHC string of the password hash, includes salt and argon2 parameters.
     pub password_hash: String,
-    pub salt: String,
-    pub argon2_iters: u32,
-    pub argon2_memory_mb: u32,
-    pub argon2_parallelism: u32,
+    pub admin: Option<bool>,
+    pub service: Option<bool>,
+    #[serde(with = "shared_core::serde::datetime::option")]
     pub created_at: Option<chrono::NaiveDateTime>,
+    #[serde(with = "shared_core::serde::datetime::option")]
     pub updated_at: Option<chrono::NaiveDateTime>,
     pub deleted: Option<bool>,
+    #[serde(with = "shared_core::serde::datetime::option")]
+    pub deleted_at: Option<chrono::NaiveDateTime>,
 }
 
 impl Default for User {
@@ -24,39 +32,26 @@ impl Default for User {
             uuid: uuid::fmt::Hyphenated::from_str("56d7f689-68b0-4473-8336-6678a5d4788d").unwrap(),
             username: "demo".to_string(),
             password_hash: "test123".to_string(),
-            salt: "1".repeat(16),
-            argon2_iters: 2,
-            argon2_memory_mb: 32,
-            argon2_parallelism: 2,
+            admin: None,
+            service: None,
             created_at: None,
             updated_at: None,
             deleted: None,
+            deleted_at: None,
         }
     }
 }
 
 impl User {
-    pub fn new<A, B, C>(
-        username: A,
-        password_hash: B,
-        salt: C,
-        argon2_iters: u32,
-        argon2_memory_mb: u32,
-        argon2_parallelism: u32,
-    ) -> Result<Self, validator::ValidationErrors>
+    pub fn new<A, B>(username: A, password_hash: B) -> Result<Self, validator::ValidationErrors>
     where
         A: ToString,
         B: ToString,
-        C: ToString,
     {
         let res = User {
             uuid: uuid::Uuid::new_v4().into(),
             username: username.to_string(),
             password_hash: password_hash.to_string(),
-            salt: salt.to_string(),
-            argon2_iters,
-            argon2_memory_mb,
-            argon2_parallelism,
             ..Self::default()
         };
 
@@ -64,27 +59,29 @@ impl User {
 
         Ok(res)
     }
+
+    /// Checks if user is an admin.
+    pub fn is_admin(&self) -> bool {
+        self.admin.unwrap_or(false)
+    }
+
+    /// Checks if user is a service account.
+    pub fn is_service(&self) -> bool {
+        self.service.unwrap_or(false)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::User;
-    use shared_core::rng;
 
     #[tokio::test]
     async fn new_user() {
-        let res_ok = User::new(
-            "hello@mail.com",
-            "123456",
-            rng::random_bytes_str(16),
-            2,
-            32,
-            2,
-        );
+        let res_ok = User::new("hello@mail.com", "123456");
 
         assert!(res_ok.is_ok());
 
-        let res_username_to_short = User::new("h", "123456", rng::random_bytes_str(16), 2, 32, 2);
+        let res_username_to_short = User::new("h", "123456");
 
         assert!(res_username_to_short.is_err());
 
@@ -93,14 +90,7 @@ mod tests {
 
         assert!(res_username_to_short.contains_key("username"));
 
-        let res_username_to_long = User::new(
-            "a".repeat(256),
-            "123456",
-            rng::random_bytes_str(16),
-            2,
-            32,
-            2,
-        );
+        let res_username_to_long = User::new("a".repeat(256), "123456");
 
         assert!(res_username_to_long.is_err());
 
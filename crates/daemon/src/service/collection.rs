@@ -0,0 +1,247 @@
+use crate::service::secret::SecretResponseGet;
+use crate::{controller, middleware, schema};
+
+use poem_openapi::param::{Path, Query};
+use poem_openapi::payload::Json;
+use poem_openapi::{Object, OpenApi};
+
+#[derive(Debug, Clone)]
+pub struct CollectionService {
+    controller: controller::ControllerCollection,
+    policy: middleware::Policy,
+}
+
+impl CollectionService {
+    pub fn new(controller: controller::ControllerCollection, policy: middleware::Policy) -> Self {
+        Self { controller, policy }
+    }
+}
+
+/// Collection request - POST
+#[derive(Debug, Clone, Object)]
+struct CollectionRequestPost {
+    name: String,
+}
+
+/// Collection request - PUT
+#[derive(Debug, Clone, Object)]
+struct CollectionRequestPut {
+    name: String,
+}
+
+/// Collection response - GET
+#[derive(Debug, Clone, Object)]
+struct CollectionResponseGet {
+    uuid: uuid::Uuid,
+    name: String,
+}
+
+impl From<schema::Collection> for CollectionResponseGet {
+    fn from(value: schema::Collection) -> Self {
+        Self {
+            uuid: value.uuid.into_uuid(),
+            name: value.name,
+        }
+    }
+}
+
+/// Collection list response - GET
+#[derive(Debug, Clone, Object)]
+struct CollectionListResponseGet {
+    items: Vec<CollectionResponseGet>,
+    next_cursor: Option<String>,
+}
+
+#[OpenApi(prefix_path = "/collection")]
+impl CollectionService {
+    /// Create Collection
+    #[oai(path = "/", method = "post")]
+    async fn collection_create(
+        &self,
+        user: middleware::JwtAuthorization,
+        request: Json<CollectionRequestPost>,
+    ) -> poem::Result<Json<CollectionResponseGet>> {
+        self.policy
+            .check(&user.0, schema::Permission::Write, schema::Resource::Global)
+            .await?;
+
+        let collection = self.controller.add(request.0.name).await?;
+
+        Ok(Json(collection.into()))
+    }
+
+    /// List Collections
+    ///
+    /// Only collections whose name contains `search` are listed. Set `limit` to page through
+    /// collections, passing the returned `next_cursor` as `cursor` to get the next page.
+    /// Pages hold at most 1000 entries.
+    #[oai(path = "/", method = "get")]
+    async fn collection_list(
+        &self,
+        user: middleware::JwtAuthorization,
+        search: Query<Option<String>>,
+        cursor: Query<Option<String>>,
+        limit: Query<Option<u64>>,
+    ) -> poem::Result<Json<CollectionListResponseGet>> {
+        self.policy
+            .check(&user.0, schema::Permission::Read, schema::Resource::Global)
+            .await?;
+
+        let collections = self.controller.list(search.0, cursor.0, limit.0).await?;
+
+        let res = CollectionListResponseGet {
+            items: collections
+                .items
+                .into_iter()
+                .map(CollectionResponseGet::from)
+                .collect(),
+            next_cursor: collections.next_cursor,
+        };
+
+        Ok(Json(res))
+    }
+
+    /// Collection Info
+    #[oai(path = "/:uuid", method = "get")]
+    async fn collection_info(
+        &self,
+        user: middleware::JwtAuthorization,
+        uuid: Path<uuid::Uuid>,
+    ) -> poem::Result<Json<CollectionResponseGet>> {
+        self.policy
+            .check(
+                &user.0,
+                schema::Permission::Read,
+                schema::Resource::Collection(uuid.0),
+            )
+            .await?;
+
+        let collection = self.controller.get(uuid.0).await?;
+
+        Ok(Json(collection.into()))
+    }
+
+    /// Rename Collection
+    #[oai(path = "/:uuid", method = "put")]
+    async fn collection_update(
+        &self,
+        user: middleware::JwtAuthorization,
+        uuid: Path<uuid::Uuid>,
+        request: Json<CollectionRequestPut>,
+    ) -> poem::Result<Json<CollectionResponseGet>> {
+        self.policy
+            .check(
+                &user.0,
+                schema::Permission::Write,
+                schema::Resource::Collection(uuid.0),
+            )
+            .await?;
+
+        let collection = self.controller.rename(uuid.0, request.0.name).await?;
+
+        Ok(Json(collection.into()))
+    }
+
+    /// Delete Collection
+    #[oai(path = "/:uuid", method = "delete")]
+    async fn collection_delete(
+        &self,
+        user: middleware::JwtAuthorization,
+        uuid: Path<uuid::Uuid>,
+    ) -> poem::Result<()> {
+        self.policy
+            .check(
+                &user.0,
+                schema::Permission::Manage,
+                schema::Resource::Collection(uuid.0),
+            )
+            .await?;
+
+        self.controller.delete(uuid.0).await?;
+
+        Ok(())
+    }
+
+    /// List Collection Sources
+    #[oai(path = "/:uuid/source", method = "get")]
+    async fn collection_source_list(
+        &self,
+        user: middleware::JwtAuthorization,
+        uuid: Path<uuid::Uuid>,
+    ) -> poem::Result<Json<Vec<uuid::Uuid>>> {
+        self.policy
+            .check(
+                &user.0,
+                schema::Permission::Read,
+                schema::Resource::Collection(uuid.0),
+            )
+            .await?;
+
+        let sources = self.controller.sources(uuid.0).await?;
+
+        Ok(Json(sources))
+    }
+
+    /// Attach Source
+    #[oai(path = "/:uuid/source/:uuid_source", method = "put")]
+    async fn collection_source_attach(
+        &self,
+        user: middleware::JwtAuthorization,
+        uuid: Path<uuid::Uuid>,
+        uuid_source: Path<uuid::Uuid>,
+    ) -> poem::Result<()> {
+        self.policy
+            .check(
+                &user.0,
+                schema::Permission::Manage,
+                schema::Resource::Collection(uuid.0),
+            )
+            .await?;
+
+        self.controller.attach(uuid.0, uuid_source.0).await?;
+
+        Ok(())
+    }
+
+    /// Detach Source
+    #[oai(path = "/:uuid/source/:uuid_source", method = "delete")]
+    async fn collection_source_detach(
+        &self,
+        user: middleware::JwtAuthorization,
+        uuid: Path<uuid::Uuid>,
+        uuid_source: Path<uuid::Uuid>,
+    ) -> poem::Result<()> {
+        self.policy
+            .check(
+                &user.0,
+                schema::Permission::Manage,
+                schema::Resource::Collection(uuid.0),
+            )
+            .await?;
+
+        self.controller.detach(uuid.0, uuid_source.0).await?;
+
+        Ok(())
+    }
+
+    /// List Collection Secrets
+    #[oai(path = "/:uuid/secret", method = "get")]
+    async fn collection_secret_list(
+        &self,
+        user: middleware::JwtAuthorization,
+        uuid: Path<uuid::Uuid>,
+    ) -> poem::Result<Json<Vec<SecretResponseGet>>> {
+        self.policy
+            .check(
+                &user.0,
+                schema::Permission::Read,
+                schema::Resource::Collection(uuid.0),
+            )
+            .await?;
+
+        let secrets = self.controller.secrets(uuid.0).await?;
+        let res = secrets.into_iter().map(SecretResponseGet::from).collect();
+
+        Ok(Json(res))
+    }
+}
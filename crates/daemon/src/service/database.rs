@@ -0,0 +1,82 @@
+use crate::{controller, middleware, schema};
+
+use poem_openapi::payload::Json;
+use poem_openapi::{Object, OpenApi};
+
+#[derive(Debug, Clone)]
+pub struct DatabaseService {
+    controller: controller::ControllerDatabase,
+    policy: middleware::Policy,
+}
+
+impl DatabaseService {
+    pub fn new(controller: controller::ControllerDatabase, policy: middleware::Policy) -> Self {
+        Self { controller, policy }
+    }
+}
+
+/// Database rekey request - POST
+#[derive(Debug, Clone, Object)]
+struct DatabaseRekeyRequestPost {
+    /// New database key, a random key is created if not set. Required if the key source is
+    /// `env`, `systemd` or `passphrase`, the new key has to be put in place of the previous one.
+    /// Keys are split into shares if the key source is `shamir`.
+    encryption_key: Option<String>,
+    /// Encrypt the backup with the new key instead of the previous one. Only set it if the new
+    /// key is kept, keys from `env`, `systemd`, `passphrase` and `shamir` aren't saved by the
+    /// daemon.
+    backup_new_key: Option<bool>,
+}
+
+/// Database rekey response - POST
+#[derive(Debug, Clone, Object)]
+struct DatabaseRekeyResponsePost {
+    /// Backup of the database taken before the key changed, encrypted with the previous key
+    /// unless `backup_new_key` was set.
+    backup_file: String,
+    /// Shares of the new key if the key source is `shamir`, they are only shown once.
+    shares: Vec<String>,
+}
+
+impl From<controller::DatabaseRekey> for DatabaseRekeyResponsePost {
+    fn from(value: controller::DatabaseRekey) -> Self {
+        Self {
+            backup_file: value.backup_file.display().to_string(),
+            shares: value.shares.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+}
+
+#[OpenApi(prefix_path = "/database")]
+impl DatabaseService {
+    /// Rekey Database
+    ///
+    /// Encrypts the database with a new key and saves it to the config or key file, a backup of
+    /// the database is taken first. Requires the global manage permission.
+    #[oai(path = "/rekey", method = "post")]
+    async fn database_rekey(
+        &self,
+        user: middleware::JwtAuthorization,
+        request: Json<DatabaseRekeyRequestPost>,
+    ) -> poem::Result<Json<DatabaseRekeyResponsePost>> {
+        self.policy
+            .check(
+                &user.0,
+                schema::Permission::Manage,
+                schema::Resource::Global,
+            )
+            .await?;
+
+        let request = request.0;
+
+        let rekey = self
+            .controller
+            .rekey(
+                request.encryption_key,
+                request.backup_new_key.unwrap_or_default(),
+            )
+            .await?;
+
+        Ok(Json(rekey.into()))
+    }
+}
@@ -0,0 +1,59 @@
+use crate::{controller, middleware, schema};
+
+use poem_openapi::payload::Json;
+use poem_openapi::{Object, OpenApi};
+
+#[derive(Debug, Clone)]
+pub struct EncryptionService {
+    controller: controller::ControllerEncryption,
+    policy: middleware::Policy,
+}
+
+impl EncryptionService {
+    pub fn new(controller: controller::ControllerEncryption, policy: middleware::Policy) -> Self {
+        Self { controller, policy }
+    }
+}
+
+/// Master key rotation response - POST
+#[derive(Debug, Clone, Object)]
+struct MasterKeyRotateResponsePost {
+    /// Id of the new master key.
+    master_key_id: String,
+    /// Number of data keys wrapped with the new master key.
+    data_keys: u64,
+}
+
+impl From<controller::MasterKeyRotation> for MasterKeyRotateResponsePost {
+    fn from(value: controller::MasterKeyRotation) -> Self {
+        Self {
+            master_key_id: value.master_key_id,
+            data_keys: value.data_keys as u64,
+        }
+    }
+}
+
+#[OpenApi(prefix_path = "/encryption")]
+impl EncryptionService {
+    /// Rotate Master Key
+    ///
+    /// Replaces the master key and wraps every data key with it, secret values are not
+    /// re-encrypted. Requires the global manage permission.
+    #[oai(path = "/master-key/rotate", method = "post")]
+    async fn encryption_master_key_rotate(
+        &self,
+        user: middleware::JwtAuthorization,
+    ) -> poem::Result<Json<MasterKeyRotateResponsePost>> {
+        self.policy
+            .check(
+                &user.0,
+                schema::Permission::Manage,
+                schema::Resource::Global,
+            )
+            .await?;
+
+        let rotation = self.controller.rotate_master_key().await?;
+
+        Ok(Json(rotation.into()))
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{controller, middleware, schema};
+
+use poem_openapi::param::{Path, Query};
+use poem_openapi::payload::Json;
+use poem_openapi::{Object, OpenApi};
+
+#[derive(Debug, Clone)]
+pub struct GrantService {
+    controller: controller::ControllerGrant,
+    policy: middleware::Policy,
+}
+
+impl GrantService {
+    pub fn new(controller: controller::ControllerGrant, policy: middleware::Policy) -> Self {
+        Self { controller, policy }
+    }
+}
+
+/// Grant request - POST
+#[derive(Debug, Clone, Object)]
+struct GrantRequestPost {
+    username: String,
+    /// Name of role, one of `admin`, `writer` or `reader`.
+    role: String,
+    /// Scope of grant, `0` for global, `1` for a collection and `2` for a source.
+    scope_type: u32,
+    /// Uuid of collection or source, required unless the grant is global.
+    uuid_scope: Option<uuid::Uuid>,
+}
+
+/// Grant response - GET
+#[derive(Debug, Clone, Object)]
+struct GrantResponseGet {
+    uuid: uuid::Uuid,
+    uuid_user: uuid::Uuid,
+    role: String,
+    scope_type: u32,
+    uuid_scope: Option<uuid::Uuid>,
+    created_at: Option<chrono::NaiveDateTime>,
+}
+
+impl From<schema::Grant> for GrantResponseGet {
+    fn from(value: schema::Grant) -> Self {
+        Self {
+            uuid: value.uuid.into_uuid(),
+            uuid_user: value.uuid_user.into_uuid(),
+            role: value.role,
+            scope_type: value.scope_type,
+            uuid_scope: value.uuid_scope.map(|x| x.into_uuid()),
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[OpenApi(prefix_path = "/grant")]
+impl GrantService {
+    /// Grant Role
+    ///
+    /// Granting a role requires the manage permission on the scope of the grant.
+    #[oai(path = "/", method = "post")]
+    async fn grant_create(
+        &self,
+        user: middleware::JwtAuthorization,
+        request: Json<GrantRequestPost>,
+    ) -> poem::Result<Json<GrantResponseGet>> {
+        let request = request.0;
+        let resource =
+            controller::ControllerGrant::scope_resource(request.scope_type, request.uuid_scope)?;
+
+        self.policy
+            .check(&user.0, schema::Permission::Manage, resource)
+            .await?;
+
+        let grant = self
+            .controller
+            .add(
+                request.username,
+                request.role,
+                request.scope_type,
+                request.uuid_scope,
+            )
+            .await?;
+
+        Ok(Json(grant.into()))
+    }
+
+    /// List Grants
+    ///
+    /// Lists grants of the current user unless `username` is given, listing grants of other
+    /// users requires the global manage permission.
+    #[oai(path = "/", method = "get")]
+    async fn grant_list(
+        &self,
+        user: middleware::JwtAuthorization,
+        username: Query<Option<String>>,
+    ) -> poem::Result<Json<Vec<GrantResponseGet>>> {
+        let username = username.0.unwrap_or(user.0.username.clone());
+
+        if username != user.0.username {
+            self.policy
+                .check(
+                    &user.0,
+                    schema::Permission::Manage,
+                    schema::Resource::Global,
+                )
+                .await?;
+        }
+
+        let grants = self.controller.list(username).await?;
+        let res = grants.into_iter().map(GrantResponseGet::from).collect();
+
+        Ok(Json(res))
+    }
+
+    /// Revoke Grant
+    ///
+    /// Revoking a grant requires the manage permission on the scope of the grant.
+    #[oai(path = "/:uuid", method = "delete")]
+    async fn grant_delete(
+        &self,
+        user: middleware::JwtAuthorization,
+        uuid: Path<uuid::Uuid>,
+    ) -> poem::Result<()> {
+        let grant = self.controller.get(uuid.0).await?;
+        let resource = controller::ControllerGrant::scope_resource(
+            grant.scope_type,
+            grant.uuid_scope.map(|x| x.into_uuid()),
+        )?;
+
+        self.policy
+            .check(&user.0, schema::Permission::Manage, resource)
+            .await?;
+
+        self.controller.delete(uuid.0).await?;
+
+        Ok(())
+    }
+}
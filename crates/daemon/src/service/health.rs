@@ -2,11 +2,13 @@ use poem_openapi::payload::Json;
 use poem_openapi::{Object, OpenApi};
 
 #[derive(Debug, Clone)]
-pub struct HealthService;
+pub struct HealthService {
+    sealed: bool,
+}
 
 impl HealthService {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(sealed: bool) -> Self {
+        Self { sealed }
     }
 }
 
@@ -14,6 +16,8 @@ impl HealthService {
 #[derive(Debug, Clone, Object)]
 struct HealthResponseGet {
     healthy: bool,
+    /// Sealed daemons only serve health until they are unsealed.
+    sealed: bool,
 }
 
 #[OpenApi]
@@ -21,7 +25,10 @@ impl HealthService {
     /// Health check
     #[oai(path = "/health", method = "get")]
     async fn health_check(&self) -> poem::Result<Json<HealthResponseGet>> {
-        let res = HealthResponseGet { healthy: true };
+        let res = HealthResponseGet {
+            healthy: true,
+            sealed: self.sealed,
+        };
 
         Ok(Json(res))
     }
@@ -0,0 +1,118 @@
+use crate::{controller, middleware, schema};
+
+use poem_openapi::param::Path;
+use poem_openapi::payload::Json;
+use poem_openapi::{Object, OpenApi};
+
+#[derive(Debug, Clone)]
+pub struct InviteService {
+    controller: controller::ControllerInvite,
+    policy: middleware::Policy,
+}
+
+impl InviteService {
+    pub fn new(controller: controller::ControllerInvite, policy: middleware::Policy) -> Self {
+        Self { controller, policy }
+    }
+
+    /// Make sure user may manage invites.
+    async fn check(&self, user: &middleware::User) -> Result<(), crate::error::ServiceError> {
+        self.policy
+            .check(user, schema::Permission::Manage, schema::Resource::Global)
+            .await
+    }
+}
+
+/// Invite request - POST
+#[derive(Debug, Clone, Object)]
+struct InviteRequestPost {
+    /// Invite expires after this many hours, the configured default is used if not set.
+    expires_in_hours: Option<u32>,
+}
+
+/// Invite response - POST
+///
+/// The code is only returned once, pass it on to the invited user right away.
+#[derive(Debug, Clone, Object)]
+struct InviteResponsePost {
+    uuid: uuid::Uuid,
+    code: String,
+    expires_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Invite response - GET
+#[derive(Debug, Clone, Object)]
+struct InviteResponseGet {
+    uuid: uuid::Uuid,
+    uuid_creator: Option<uuid::Uuid>,
+    expires_at: Option<chrono::NaiveDateTime>,
+    created_at: Option<chrono::NaiveDateTime>,
+}
+
+impl From<schema::Invite> for InviteResponseGet {
+    fn from(value: schema::Invite) -> Self {
+        Self {
+            uuid: value.uuid.into_uuid(),
+            uuid_creator: value.uuid_creator.map(|x| x.into_uuid()),
+            expires_at: value.expires_at,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[OpenApi(prefix_path = "/invite")]
+impl InviteService {
+    /// Create Invite
+    ///
+    /// Creates a one-time invite code to register a user with. Managing invites requires the
+    /// global manage permission.
+    #[oai(path = "/", method = "post")]
+    async fn invite_create(
+        &self,
+        user: middleware::JwtAuthorization,
+        request: Json<InviteRequestPost>,
+    ) -> poem::Result<Json<InviteResponsePost>> {
+        self.check(&user.0).await?;
+
+        let (invite, code) = self
+            .controller
+            .create(user.0.uuid, request.0.expires_in_hours)
+            .await?;
+
+        Ok(Json(InviteResponsePost {
+            uuid: invite.uuid.into_uuid(),
+            code,
+            expires_at: invite.expires_at,
+        }))
+    }
+
+    /// List Invites
+    ///
+    /// Lists unused invites which haven't expired yet.
+    #[oai(path = "/", method = "get")]
+    async fn invite_list(
+        &self,
+        user: middleware::JwtAuthorization,
+    ) -> poem::Result<Json<Vec<InviteResponseGet>>> {
+        self.check(&user.0).await?;
+
+        let invites = self.controller.list().await?;
+        let res = invites.into_iter().map(InviteResponseGet::from).collect();
+
+        Ok(Json(res))
+    }
+
+    /// Revoke Invite
+    #[oai(path = "/:uuid", method = "delete")]
+    async fn invite_delete(
+        &self,
+        user: middleware::JwtAuthorization,
+        uuid: Path<uuid::Uuid>,
+    ) -> poem::Result<()> {
+        self.check(&user.0).await?;
+
+        self.controller.revoke(uuid.0).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{controller, middleware, schema};
+
+use poem_openapi::payload::Json;
+use poem_openapi::{Object, OpenApi};
+use shared_core::crypt;
+
+#[derive(Debug, Clone)]
+pub struct JwtService {
+    controller: controller::ControllerJwt,
+    policy: middleware::Policy,
+}
+
+impl JwtService {
+    pub fn new(controller: controller::ControllerJwt, policy: middleware::Policy) -> Self {
+        Self { controller, policy }
+    }
+}
+
+/// Jwt key response - GET
+#[derive(Debug, Clone, Object)]
+struct JwtKeyResponseGet {
+    kid: String,
+    /// Algorithm tokens signed by the key use.
+    algorithm: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+    /// Tokens signed by the key are rejected after this time.
+    retire_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Whether new tokens are signed by the key.
+    active: bool,
+}
+
+impl From<crypt::JwtKeyInfo> for JwtKeyResponseGet {
+    fn from(value: crypt::JwtKeyInfo) -> Self {
+        Self {
+            kid: value.kid,
+            algorithm: value.algorithm.to_string(),
+            created_at: value.created_at,
+            retire_at: value.retire_at,
+            active: value.active,
+        }
+    }
+}
+
+#[OpenApi(prefix_path = "/jwt")]
+impl JwtService {
+    /// List Signing Keys
+    ///
+    /// Lists keys tokens are verified with, requires the global manage permission.
+    #[oai(path = "/key", method = "get")]
+    async fn jwt_key_list(
+        &self,
+        user: middleware::JwtAuthorization,
+    ) -> poem::Result<Json<Vec<JwtKeyResponseGet>>> {
+        self.policy
+            .check(
+                &user.0,
+                schema::Permission::Manage,
+                schema::Resource::Global,
+            )
+            .await?;
+
+        let keys = self.controller.keys();
+        let res = keys.into_iter().map(JwtKeyResponseGet::from).collect();
+
+        Ok(Json(res))
+    }
+
+    /// Rotate Signing Key
+    ///
+    /// New tokens are signed by a new key. Previous keys keep verifying tokens until every
+    /// token they signed has expired. Requires the global manage permission.
+    #[oai(path = "/key/rotate", method = "post")]
+    async fn jwt_key_rotate(
+        &self,
+        user: middleware::JwtAuthorization,
+    ) -> poem::Result<Json<JwtKeyResponseGet>> {
+        self.policy
+            .check(
+                &user.0,
+                schema::Permission::Manage,
+                schema::Resource::Global,
+            )
+            .await?;
+
+        let key = self.controller.rotate().await?;
+
+        Ok(Json(key.into()))
+    }
+}
+
+/// Publish public keys tokens are verified with, so other services can verify tokens offline.
+#[poem::handler]
+pub fn jwks(
+    controller: poem::web::Data<&controller::ControllerJwt>,
+) -> poem::web::Json<crypt::JwkSet> {
+    poem::web::Json(controller.jwks())
+}
@@ -5,7 +5,18 @@ use poem::EndpointExt;
 use std::sync::Arc;
 
 mod client;
+mod collection;
+mod database;
+mod encryption;
+mod grant;
 mod health;
+mod invite;
+mod jwt;
+mod seal;
+mod secret;
+mod service_account;
+mod source;
+mod totp;
 mod user;
 
 /// Convert from controller error to status.
@@ -15,6 +26,12 @@ impl From<error::ServiceError> for poem::Error {
             error::ServiceError::AlreadyExists(x) => {
                 Self::from_string(x, poem::http::StatusCode::CONFLICT)
             }
+            error::ServiceError::Conflict(x) => {
+                Self::from_string(x, poem::http::StatusCode::CONFLICT)
+            }
+            error::ServiceError::InvalidArgument(x) => {
+                Self::from_string(x, poem::http::StatusCode::BAD_REQUEST)
+            }
             error::ServiceError::PermissionDenied(x) => {
                 Self::from_string(x, poem::http::StatusCode::FORBIDDEN)
             }
@@ -24,6 +41,13 @@ impl From<error::ServiceError> for poem::Error {
             error::ServiceError::NotFound(x) => {
                 Self::from_string(x, poem::http::StatusCode::NOT_FOUND)
             }
+            error::ServiceError::Unauthorized(x) => {
+                Self::from_string(x, poem::http::StatusCode::UNAUTHORIZED)
+            }
+            error::ServiceError::TooManyRequests(x) => {
+                Self::from_string(x, poem::http::StatusCode::TOO_MANY_REQUESTS)
+            }
+            error::ServiceError::Locked(x) => Self::from_string(x, poem::http::StatusCode::LOCKED),
         }
     }
 }
@@ -36,6 +60,19 @@ pub async fn create_services(
 ) -> anyhow::Result<impl poem::Endpoint> {
     let controller_client = controller::ControllerClient::new(client.clone());
     let controller_user = controller::ControllerUser::new(config.clone(), client.clone());
+    let controller_secret = controller::ControllerSecret::new(client.clone());
+    let controller_collection = controller::ControllerCollection::new(client.clone());
+    let controller_source = controller::ControllerSource::new(client.clone());
+    let controller_grant = controller::ControllerGrant::new(client.clone());
+    let controller_service_account = controller::ControllerServiceAccount::new(client.clone());
+    let controller_jwt = controller::ControllerJwt::new(client.clone());
+    let controller_totp = controller::ControllerTotp::new(client.clone());
+    let controller_invite = controller::ControllerInvite::new(config.clone(), client.clone());
+    let controller_encryption = controller::ControllerEncryption::new(client.clone());
+    let controller_database = controller::ControllerDatabase::new(config.clone(), client.clone());
+
+    // Create policy checked by services
+    let policy = middleware::Policy::new(client.clone());
 
     // Create data to be injected
     let middleware_data = middleware::MiddlewareData::new(config, client);
@@ -44,9 +81,19 @@ pub async fn create_services(
     const SERVICE_PATH_PREFIX: &str = "/api/v1";
 
     let services = (
-        health::HealthService::new(),
+        health::HealthService::new(false),
         client::ClientService::new(controller_client),
         user::UserService::new(controller_user),
+        totp::TotpService::new(controller_totp),
+        invite::InviteService::new(controller_invite, policy.clone()),
+        secret::SecretService::new(controller_secret, policy.clone()),
+        collection::CollectionService::new(controller_collection, policy.clone()),
+        source::SourceService::new(controller_source, policy.clone()),
+        grant::GrantService::new(controller_grant, policy.clone()),
+        service_account::ServiceAccountService::new(controller_service_account, policy.clone()),
+        jwt::JwtService::new(controller_jwt.clone(), policy.clone()),
+        encryption::EncryptionService::new(controller_encryption, policy.clone()),
+        database::DatabaseService::new(controller_database, policy),
     );
 
     let api = poem_openapi::OpenApiService::new(services, "My Vault", "0.1.0")
@@ -59,11 +106,47 @@ pub async fn create_services(
     } else {
         poem::Route::new()
     }
+    .at(
+        "/.well-known/jwks.json",
+        poem::get(jwt::jwks)
+            .with(middleware::SetDefaultHeader::new())
+            .data(controller_jwt),
+    )
     .nest(
         SERVICE_PATH_PREFIX,
         api.with(middleware::SetDefaultHeader::new())
+            .with(middleware::PeerAuthentication::new())
             .with(poem::middleware::AddData::new(middleware_data)),
     );
 
     Ok(route)
 }
+
+/// Create services of a sealed daemon, only serving health and unseal.
+pub async fn create_sealed_services(
+    enable_ui: bool,
+    controller_seal: controller::ControllerSeal,
+) -> anyhow::Result<impl poem::Endpoint> {
+    // Create API endpoints
+    const SERVICE_PATH_PREFIX: &str = "/api/v1";
+
+    let services = (
+        health::HealthService::new(true),
+        seal::SealService::new(controller_seal),
+    );
+
+    let api = poem_openapi::OpenApiService::new(services, "My Vault", "0.1.0")
+        .url_prefix(SERVICE_PATH_PREFIX);
+
+    let route = if enable_ui {
+        poem::Route::new().nest("/", api.scalar())
+    } else {
+        poem::Route::new()
+    }
+    .nest(
+        SERVICE_PATH_PREFIX,
+        api.with(middleware::SetDefaultHeader::new()),
+    );
+
+    Ok(route)
+}
@@ -0,0 +1,104 @@
+use crate::{controller, error};
+
+use poem_openapi::payload::Json;
+use poem_openapi::{Object, OpenApi};
+
+#[derive(Debug, Clone)]
+pub struct SealService {
+    controller: controller::ControllerSeal,
+}
+
+impl SealService {
+    pub fn new(controller: controller::ControllerSeal) -> Self {
+        Self { controller }
+    }
+}
+
+/// Unseal request - POST
+#[derive(Debug, Clone, Object)]
+struct UnsealRequestPost {
+    /// Database passphrase if the key source is `passphrase`.
+    passphrase: Option<String>,
+    /// Share of the database key if the key source is `shamir`.
+    share: Option<String>,
+}
+
+/// Unseal reset request - POST
+#[derive(Debug, Clone, Object)]
+struct UnsealResetRequestPost {
+    /// Share of the database key, proves the reset is asked for by one of the operators.
+    share: String,
+}
+
+/// Unseal response - GET
+#[derive(Debug, Clone, Object)]
+struct UnsealResponseGet {
+    sealed: bool,
+    key_source: String,
+    /// Number of shares unsealing needs, one for passphrases.
+    threshold: u64,
+    /// Number of shares given so far.
+    progress: u64,
+}
+
+impl From<controller::UnsealStatus> for UnsealResponseGet {
+    fn from(value: controller::UnsealStatus) -> Self {
+        Self {
+            sealed: value.sealed,
+            key_source: value.key_source.to_string(),
+            threshold: value.threshold as u64,
+            progress: value.progress as u64,
+        }
+    }
+}
+
+#[OpenApi(prefix_path = "/unseal")]
+impl SealService {
+    /// Unseal Status
+    ///
+    /// Only available while the daemon is sealed.
+    #[oai(path = "/", method = "get")]
+    async fn unseal_status(&self) -> poem::Result<Json<UnsealResponseGet>> {
+        let status = self.controller.status().await;
+
+        Ok(Json(status.into()))
+    }
+
+    /// Unseal
+    ///
+    /// Opens the database with the passphrase, or adds a share of the database key until enough
+    /// shares were given. Every endpoint is served once the daemon is unsealed. Only available
+    /// while the daemon is sealed.
+    #[oai(path = "/", method = "post")]
+    async fn unseal(
+        &self,
+        request: Json<UnsealRequestPost>,
+    ) -> poem::Result<Json<UnsealResponseGet>> {
+        let status = match (request.0.passphrase, request.0.share) {
+            (Some(x), None) => self.controller.unseal(x).await?,
+            (None, Some(x)) => self.controller.unseal_share(x).await?,
+            _ => {
+                return Err(error::ServiceError::InvalidArgument(
+                    "either passphrase or share must be given".into(),
+                )
+                .into());
+            }
+        };
+
+        Ok(Json(status.into()))
+    }
+
+    /// Reset Unseal
+    ///
+    /// Discards the shares given so far, one of the shares has to be given to reset. Only
+    /// available while the daemon is sealed and the config has the hashes of the shares.
+    #[oai(path = "/reset", method = "post")]
+    async fn unseal_reset(
+        &self,
+        request: Json<UnsealResetRequestPost>,
+    ) -> poem::Result<Json<UnsealResponseGet>> {
+        let status = self.controller.reset(request.0.share).await?;
+
+        Ok(Json(status.into()))
+    }
+}
@@ -0,0 +1,247 @@
+use crate::{controller, error, middleware, schema};
+
+use poem_openapi::param::{Path, Query};
+use poem_openapi::payload::Json;
+use poem_openapi::{Object, OpenApi};
+
+#[derive(Debug, Clone)]
+pub struct SecretService {
+    controller: controller::ControllerSecret,
+    policy: middleware::Policy,
+}
+
+impl SecretService {
+    pub fn new(controller: controller::ControllerSecret, policy: middleware::Policy) -> Self {
+        Self { controller, policy }
+    }
+}
+
+/// Secret request - POST
+#[derive(Debug, Clone, Object)]
+struct SecretRequestPost {
+    name: String,
+    key: Option<String>,
+    description: Option<String>,
+    secret: String,
+    secret_type: u32,
+    /// Collection whose data key encrypts the value, the default data key is used if not set.
+    uuid_collection: Option<uuid::Uuid>,
+}
+
+/// Secret request - PUT
+#[derive(Debug, Clone, Object)]
+struct SecretRequestPut {
+    name: Option<String>,
+    key: Option<String>,
+    description: Option<String>,
+    secret: Option<String>,
+    secret_type: Option<u32>,
+}
+
+/// Secret response - GET
+#[derive(Debug, Clone, Object)]
+pub(super) struct SecretResponseGet {
+    uuid: uuid::Uuid,
+    name: String,
+    key: Option<String>,
+    description: Option<String>,
+    secret: String,
+    secret_type: u32,
+}
+
+impl From<schema::Secret> for SecretResponseGet {
+    fn from(value: schema::Secret) -> Self {
+        Self {
+            uuid: value.uuid.into_uuid(),
+            name: value.name,
+            key: value.key,
+            description: value.description,
+            secret: value.secret,
+            secret_type: value.secret_type,
+        }
+    }
+}
+
+/// Secret list response - GET
+#[derive(Debug, Clone, Object)]
+struct SecretListResponseGet {
+    items: Vec<SecretResponseGet>,
+    next_cursor: Option<String>,
+}
+
+#[OpenApi(prefix_path = "/secret")]
+impl SecretService {
+    /// Create Secret
+    #[oai(path = "/", method = "post")]
+    async fn secret_create(
+        &self,
+        user: middleware::JwtAuthorization,
+        request: Json<SecretRequestPost>,
+    ) -> poem::Result<Json<SecretResponseGet>> {
+        let request = request.0;
+
+        // Secrets added to a collection only need write access to that collection.
+        let resource = match request.uuid_collection {
+            Some(x) => schema::Resource::Collection(x),
+            None => schema::Resource::Global,
+        };
+
+        self.policy
+            .check(&user.0, schema::Permission::Write, resource)
+            .await?;
+
+        let secret = self
+            .controller
+            .add(
+                request.name,
+                request.key,
+                request.description,
+                request.secret,
+                request.secret_type,
+                request.uuid_collection,
+            )
+            .await?;
+
+        Ok(Json(secret.into()))
+    }
+
+    /// List Secrets
+    ///
+    /// Only secrets the user can read and whose name contains `search` are listed. Set `limit`
+    /// to page through secrets, passing the returned `next_cursor` as `cursor` to get the next
+    /// page. Pages hold at most 1000 entries.
+    #[oai(path = "/", method = "get")]
+    async fn secret_list(
+        &self,
+        user: middleware::JwtAuthorization,
+        search: Query<Option<String>>,
+        cursor: Query<Option<String>>,
+        limit: Query<Option<u64>>,
+    ) -> poem::Result<Json<SecretListResponseGet>> {
+        let uuids = self
+            .policy
+            .permitted_secrets(&user.0, schema::Permission::Read)
+            .await?;
+
+        let secrets = self
+            .controller
+            .list(uuids, search.0, cursor.0, limit.0)
+            .await?;
+
+        let res = SecretListResponseGet {
+            items: secrets
+                .items
+                .into_iter()
+                .map(SecretResponseGet::from)
+                .collect(),
+            next_cursor: secrets.next_cursor,
+        };
+
+        Ok(Json(res))
+    }
+
+    /// Secret Info
+    #[oai(path = "/:uuid", method = "get")]
+    async fn secret_info(
+        &self,
+        user: middleware::JwtAuthorization,
+        uuid: Path<uuid::Uuid>,
+    ) -> poem::Result<Json<SecretResponseGet>> {
+        self.policy
+            .check(
+                &user.0,
+                schema::Permission::Read,
+                schema::Resource::Secret(uuid.0),
+            )
+            .await?;
+
+        let secret = self.controller.get(uuid.0).await?;
+
+        Ok(Json(secret.into()))
+    }
+
+    /// Secret Info From Name
+    #[oai(path = "/name/:name", method = "get")]
+    async fn secret_info_name(
+        &self,
+        user: middleware::JwtAuthorization,
+        name: Path<String>,
+    ) -> poem::Result<Json<SecretResponseGet>> {
+        let secret = match self.controller.get_by_name(name.0).await {
+            Ok(x) => x,
+            Err(error::ServiceError::NotFound(e)) => {
+                // Only users who can read every secret learn that a name doesn't exist, everyone
+                // else gets the same error as for secrets they can't read.
+                self.policy
+                    .check(&user.0, schema::Permission::Read, schema::Resource::Global)
+                    .await?;
+
+                return Err(error::ServiceError::NotFound(e).into());
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        self.policy
+            .check(
+                &user.0,
+                schema::Permission::Read,
+                schema::Resource::Secret(secret.uuid.into_uuid()),
+            )
+            .await?;
+
+        Ok(Json(secret.into()))
+    }
+
+    /// Update Secret
+    #[oai(path = "/:uuid", method = "put")]
+    async fn secret_update(
+        &self,
+        user: middleware::JwtAuthorization,
+        uuid: Path<uuid::Uuid>,
+        request: Json<SecretRequestPut>,
+    ) -> poem::Result<Json<SecretResponseGet>> {
+        self.policy
+            .check(
+                &user.0,
+                schema::Permission::Write,
+                schema::Resource::Secret(uuid.0),
+            )
+            .await?;
+
+        let request = request.0;
+
+        let secret = self
+            .controller
+            .update(
+                uuid.0,
+                request.name,
+                request.key,
+                request.description,
+                request.secret,
+                request.secret_type,
+            )
+            .await?;
+
+        Ok(Json(secret.into()))
+    }
+
+    /// Delete Secret
+    #[oai(path = "/:uuid", method = "delete")]
+    async fn secret_delete(
+        &self,
+        user: middleware::JwtAuthorization,
+        uuid: Path<uuid::Uuid>,
+    ) -> poem::Result<()> {
+        self.policy
+            .check(
+                &user.0,
+                schema::Permission::Write,
+                schema::Resource::Secret(uuid.0),
+            )
+            .await?;
+
+        self.controller.delete(uuid.0).await?;
+
+        Ok(())
+    }
+}
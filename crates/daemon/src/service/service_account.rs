@@ -0,0 +1,258 @@
+use crate::{controller, middleware, schema};
+
+use poem_openapi::param::Path;
+use poem_openapi::payload::Json;
+use poem_openapi::{Object, OpenApi};
+
+#[derive(Debug, Clone)]
+pub struct ServiceAccountService {
+    controller: controller::ControllerServiceAccount,
+    policy: middleware::Policy,
+}
+
+impl ServiceAccountService {
+    pub fn new(
+        controller: controller::ControllerServiceAccount,
+        policy: middleware::Policy,
+    ) -> Self {
+        Self { controller, policy }
+    }
+
+    /// Make sure user may manage service accounts.
+    async fn check(&self, user: &middleware::User) -> Result<(), crate::error::ServiceError> {
+        self.policy
+            .check(user, schema::Permission::Manage, schema::Resource::Global)
+            .await
+    }
+}
+
+/// Service account request - POST
+#[derive(Debug, Clone, Object)]
+struct ServiceAccountRequestPost {
+    name: String,
+}
+
+/// Service account response - GET
+#[derive(Debug, Clone, Object)]
+struct ServiceAccountResponseGet {
+    uuid: uuid::Uuid,
+    name: String,
+    created_at: Option<chrono::NaiveDateTime>,
+}
+
+impl From<schema::User> for ServiceAccountResponseGet {
+    fn from(value: schema::User) -> Self {
+        Self {
+            uuid: value.uuid.into_uuid(),
+            name: value.username,
+            created_at: value.created_at,
+        }
+    }
+}
+
+/// Api key scope
+#[derive(Debug, Clone, Object)]
+struct ApiKeyScope {
+    /// Scope of key, `0` for global, `1` for a collection and `2` for a source.
+    scope_type: u32,
+    /// Uuid of collection or source, required unless the scope is global.
+    uuid_scope: Option<uuid::Uuid>,
+}
+
+/// Api key request - POST
+#[derive(Debug, Clone, Object)]
+struct ApiKeyRequestPost {
+    name: String,
+    /// Name of role the key gets on every scope.
+    role: String,
+    scopes: Vec<ApiKeyScope>,
+    /// Key expires after this many days, keys without expiry are valid until revoked.
+    expires_in_days: Option<u32>,
+}
+
+/// Api key response - GET
+#[derive(Debug, Clone, Object)]
+struct ApiKeyResponseGet {
+    uuid: uuid::Uuid,
+    name: String,
+    role: Option<String>,
+    scopes: Vec<ApiKeyScope>,
+    expires_at: Option<chrono::NaiveDateTime>,
+    last_used_at: Option<chrono::NaiveDateTime>,
+    created_at: Option<chrono::NaiveDateTime>,
+    updated_at: Option<chrono::NaiveDateTime>,
+}
+
+impl From<(schema::ApiKey, Vec<schema::Grant>)> for ApiKeyResponseGet {
+    fn from((api_key, grants): (schema::ApiKey, Vec<schema::Grant>)) -> Self {
+        Self {
+            uuid: api_key.uuid.into_uuid(),
+            name: api_key.name,
+            role: grants.first().map(|x| x.role.clone()),
+            scopes: grants
+                .into_iter()
+                .map(|x| ApiKeyScope {
+                    scope_type: x.scope_type,
+                    uuid_scope: x.uuid_scope.map(|x| x.into_uuid()),
+                })
+                .collect(),
+            expires_at: api_key.expires_at,
+            last_used_at: api_key.last_used_at,
+            created_at: api_key.created_at,
+            updated_at: api_key.updated_at,
+        }
+    }
+}
+
+/// Api key response - POST
+///
+/// The token is only returned once, store it right away.
+#[derive(Debug, Clone, Object)]
+struct ApiKeyResponsePost {
+    uuid: uuid::Uuid,
+    name: String,
+    token: String,
+    expires_at: Option<chrono::NaiveDateTime>,
+}
+
+#[OpenApi(prefix_path = "/service-account")]
+impl ServiceAccountService {
+    /// Create Service Account
+    ///
+    /// Managing service accounts and their api keys requires the global manage permission.
+    #[oai(path = "/", method = "post")]
+    async fn service_account_create(
+        &self,
+        user: middleware::JwtAuthorization,
+        request: Json<ServiceAccountRequestPost>,
+    ) -> poem::Result<Json<ServiceAccountResponseGet>> {
+        self.check(&user.0).await?;
+
+        let account = self.controller.add(request.0.name).await?;
+
+        Ok(Json(account.into()))
+    }
+
+    /// List Service Accounts
+    #[oai(path = "/", method = "get")]
+    async fn service_account_list(
+        &self,
+        user: middleware::JwtAuthorization,
+    ) -> poem::Result<Json<Vec<ServiceAccountResponseGet>>> {
+        self.check(&user.0).await?;
+
+        let accounts = self.controller.list().await?;
+        let res = accounts
+            .into_iter()
+            .map(ServiceAccountResponseGet::from)
+            .collect();
+
+        Ok(Json(res))
+    }
+
+    /// Delete Service Account
+    ///
+    /// Api keys of deleted service accounts stop working.
+    #[oai(path = "/:name", method = "delete")]
+    async fn service_account_delete(
+        &self,
+        user: middleware::JwtAuthorization,
+        name: Path<String>,
+    ) -> poem::Result<()> {
+        self.check(&user.0).await?;
+
+        self.controller.delete(name.0).await?;
+
+        Ok(())
+    }
+
+    /// Create Api Key
+    ///
+    /// The key is granted the role on every scope, requests using it get no other permissions.
+    #[oai(path = "/:name/key", method = "post")]
+    async fn api_key_create(
+        &self,
+        user: middleware::JwtAuthorization,
+        name: Path<String>,
+        request: Json<ApiKeyRequestPost>,
+    ) -> poem::Result<Json<ApiKeyResponsePost>> {
+        self.check(&user.0).await?;
+
+        let request = request.0;
+        let scopes = request
+            .scopes
+            .into_iter()
+            .map(|x| (x.scope_type, x.uuid_scope))
+            .collect();
+
+        let (api_key, token) = self
+            .controller
+            .add_key(
+                name.0,
+                request.name,
+                request.role,
+                scopes,
+                request.expires_in_days,
+            )
+            .await?;
+
+        Ok(Json(ApiKeyResponsePost {
+            uuid: api_key.uuid.into_uuid(),
+            name: api_key.name,
+            token,
+            expires_at: api_key.expires_at,
+        }))
+    }
+
+    /// List Api Keys
+    #[oai(path = "/:name/key", method = "get")]
+    async fn api_key_list(
+        &self,
+        user: middleware::JwtAuthorization,
+        name: Path<String>,
+    ) -> poem::Result<Json<Vec<ApiKeyResponseGet>>> {
+        self.check(&user.0).await?;
+
+        let api_keys = self.controller.list_keys(name.0).await?;
+        let res = api_keys.into_iter().map(ApiKeyResponseGet::from).collect();
+
+        Ok(Json(res))
+    }
+
+    /// Rotate Api Key
+    ///
+    /// Issues a new token for the key, the previous token stops working immediately.
+    #[oai(path = "/:name/key/:uuid/rotate", method = "post")]
+    async fn api_key_rotate(
+        &self,
+        user: middleware::JwtAuthorization,
+        name: Path<String>,
+        uuid: Path<uuid::Uuid>,
+    ) -> poem::Result<Json<ApiKeyResponsePost>> {
+        self.check(&user.0).await?;
+
+        let (api_key, token) = self.controller.rotate_key(name.0, uuid.0).await?;
+
+        Ok(Json(ApiKeyResponsePost {
+            uuid: api_key.uuid.into_uuid(),
+            name: api_key.name,
+            token,
+            expires_at: api_key.expires_at,
+        }))
+    }
+
+    /// Revoke Api Key
+    #[oai(path = "/:name/key/:uuid", method = "delete")]
+    async fn api_key_delete(
+        &self,
+        user: middleware::JwtAuthorization,
+        name: Path<String>,
+        uuid: Path<uuid::Uuid>,
+    ) -> poem::Result<()> {
+        self.check(&user.0).await?;
+
+        self.controller.delete_key(name.0, uuid.0).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,261 @@
+use crate::{controller, importer, middleware, schema};
+
+use poem_openapi::param::{Path, Query};
+use poem_openapi::payload::Json;
+use poem_openapi::{Object, OpenApi};
+
+#[derive(Debug, Clone)]
+pub struct SourceService {
+    controller: controller::ControllerSource,
+    policy: middleware::Policy,
+}
+
+impl SourceService {
+    pub fn new(controller: controller::ControllerSource, policy: middleware::Policy) -> Self {
+        Self { controller, policy }
+    }
+}
+
+/// Source request - POST
+#[derive(Debug, Clone, Object)]
+struct SourceRequestPost {
+    name: String,
+    description: Option<String>,
+    source_type: u32,
+    /// Source type specific config as JSON.
+    source_config: Option<String>,
+    source_auth: Option<String>,
+    source_auth_type: u32,
+}
+
+/// Source request - PUT
+#[derive(Debug, Clone, Object)]
+struct SourceRequestPut {
+    description: Option<String>,
+    source_config: Option<String>,
+    source_auth: Option<String>,
+    source_auth_type: Option<u32>,
+}
+
+/// Source response - GET
+///
+/// Source authentication data is write only and never returned.
+#[derive(Debug, Clone, Object)]
+struct SourceResponseGet {
+    uuid: uuid::Uuid,
+    name: String,
+    description: Option<String>,
+    source_type: u32,
+    source_config: Option<String>,
+    source_auth_type: u32,
+    created_at: Option<chrono::NaiveDateTime>,
+    updated_at: Option<chrono::NaiveDateTime>,
+}
+
+impl From<schema::Source> for SourceResponseGet {
+    fn from(value: schema::Source) -> Self {
+        Self {
+            uuid: value.uuid.into_uuid(),
+            name: value.name,
+            description: value.description,
+            source_type: value.source_type,
+            source_config: value.source_config,
+            source_auth_type: value.source_auth_type,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
+/// Source import response - POST
+#[derive(Debug, Clone, Object)]
+struct SourceImportResponsePost {
+    dry_run: bool,
+    created: Vec<String>,
+    updated: Vec<String>,
+    deleted: Vec<String>,
+    unchanged: u64,
+}
+
+impl SourceImportResponsePost {
+    fn new(plan: importer::ImportPlan, dry_run: bool) -> Self {
+        Self {
+            dry_run,
+            created: plan.create.into_iter().map(|x| x.name).collect(),
+            updated: plan.update.into_iter().map(|(_, x)| x.name).collect(),
+            deleted: plan.delete.into_iter().map(|x| x.name).collect(),
+            unchanged: plan.unchanged as u64,
+        }
+    }
+}
+
+/// Source list response - GET
+#[derive(Debug, Clone, Object)]
+struct SourceListResponseGet {
+    items: Vec<SourceResponseGet>,
+    next_cursor: Option<String>,
+}
+
+#[OpenApi(prefix_path = "/source")]
+impl SourceService {
+    /// Register Source
+    #[oai(path = "/", method = "post")]
+    async fn source_create(
+        &self,
+        user: middleware::JwtAuthorization,
+        request: Json<SourceRequestPost>,
+    ) -> poem::Result<Json<SourceResponseGet>> {
+        self.policy
+            .check(&user.0, schema::Permission::Write, schema::Resource::Global)
+            .await?;
+
+        let request = request.0;
+
+        let source = self
+            .controller
+            .add(
+                request.name,
+                request.description,
+                request.source_type,
+                request.source_config,
+                request.source_auth,
+                request.source_auth_type,
+            )
+            .await?;
+
+        Ok(Json(source.into()))
+    }
+
+    /// List Sources
+    ///
+    /// Only sources whose name contains `search` are listed. Set `limit` to page through
+    /// sources, passing the returned `next_cursor` as `cursor` to get the next page.
+    /// Pages hold at most 1000 entries.
+    #[oai(path = "/", method = "get")]
+    async fn source_list(
+        &self,
+        user: middleware::JwtAuthorization,
+        search: Query<Option<String>>,
+        cursor: Query<Option<String>>,
+        limit: Query<Option<u64>>,
+    ) -> poem::Result<Json<SourceListResponseGet>> {
+        self.policy
+            .check(&user.0, schema::Permission::Read, schema::Resource::Global)
+            .await?;
+
+        let sources = self.controller.list(search.0, cursor.0, limit.0).await?;
+
+        let res = SourceListResponseGet {
+            items: sources
+                .items
+                .into_iter()
+                .map(SourceResponseGet::from)
+                .collect(),
+            next_cursor: sources.next_cursor,
+        };
+
+        Ok(Json(res))
+    }
+
+    /// Source Info
+    #[oai(path = "/:uuid", method = "get")]
+    async fn source_info(
+        &self,
+        user: middleware::JwtAuthorization,
+        uuid: Path<uuid::Uuid>,
+    ) -> poem::Result<Json<SourceResponseGet>> {
+        self.policy
+            .check(
+                &user.0,
+                schema::Permission::Read,
+                schema::Resource::Source(uuid.0),
+            )
+            .await?;
+
+        let source = self.controller.get(uuid.0).await?;
+
+        Ok(Json(source.into()))
+    }
+
+    /// Update Source
+    #[oai(path = "/:uuid", method = "put")]
+    async fn source_update(
+        &self,
+        user: middleware::JwtAuthorization,
+        uuid: Path<uuid::Uuid>,
+        request: Json<SourceRequestPut>,
+    ) -> poem::Result<Json<SourceResponseGet>> {
+        self.policy
+            .check(
+                &user.0,
+                schema::Permission::Write,
+                schema::Resource::Source(uuid.0),
+            )
+            .await?;
+
+        let request = request.0;
+
+        let source = self
+            .controller
+            .update(
+                uuid.0,
+                request.description,
+                request.source_config,
+                request.source_auth,
+                request.source_auth_type,
+            )
+            .await?;
+
+        Ok(Json(source.into()))
+    }
+
+    /// Import Source
+    ///
+    /// Set `dry_run` to only report what would change without writing anything.
+    #[oai(path = "/:uuid/import", method = "post")]
+    async fn source_import(
+        &self,
+        user: middleware::JwtAuthorization,
+        uuid: Path<uuid::Uuid>,
+        dry_run: Query<Option<bool>>,
+    ) -> poem::Result<Json<SourceImportResponsePost>> {
+        self.policy
+            .check(
+                &user.0,
+                schema::Permission::Write,
+                schema::Resource::Source(uuid.0),
+            )
+            .await?;
+
+        let dry_run = dry_run.0.unwrap_or(false);
+        let plan = self.controller.import(uuid.0, dry_run).await?;
+
+        Ok(Json(SourceImportResponsePost::new(plan, dry_run)))
+    }
+
+    /// Delete Source
+    ///
+    /// Set `cascade` to detach the source from all collections and delete its secrets,
+    /// otherwise deleting a source which is still referenced is refused.
+    #[oai(path = "/:uuid", method = "delete")]
+    async fn source_delete(
+        &self,
+        user: middleware::JwtAuthorization,
+        uuid: Path<uuid::Uuid>,
+        cascade: Query<Option<bool>>,
+    ) -> poem::Result<()> {
+        self.policy
+            .check(
+                &user.0,
+                schema::Permission::Manage,
+                schema::Resource::Source(uuid.0),
+            )
+            .await?;
+
+        self.controller
+            .delete(uuid.0, cascade.0.unwrap_or(false))
+            .await?;
+
+        Ok(())
+    }
+}
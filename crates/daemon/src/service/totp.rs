@@ -0,0 +1,105 @@
+use crate::{controller, middleware};
+
+use poem_openapi::payload::Json;
+use poem_openapi::{Object, OpenApi};
+
+#[derive(Debug, Clone)]
+pub struct TotpService {
+    controller: controller::ControllerTotp,
+}
+
+impl TotpService {
+    pub fn new(controller: controller::ControllerTotp) -> Self {
+        Self { controller }
+    }
+}
+
+/// Totp response - GET
+#[derive(Debug, Clone, Object)]
+struct TotpResponseGet {
+    enabled: bool,
+    /// Number of unused recovery codes.
+    recovery_codes: u64,
+}
+
+/// Totp enroll response - POST
+#[derive(Debug, Clone, Object)]
+struct TotpEnrollResponsePost {
+    /// Base32 encoded secret, for authenticator apps which can't scan the URI.
+    secret: String,
+    /// `otpauth://` URI of the secret, usually shown as QR code.
+    uri: String,
+}
+
+/// Totp code request - POST
+#[derive(Debug, Clone, Object)]
+struct TotpCodeRequestPost {
+    code: String,
+}
+
+/// Totp confirm response - POST
+///
+/// Recovery codes are only shown once, each can be used once in place of a TOTP code.
+#[derive(Debug, Clone, Object)]
+struct TotpConfirmResponsePost {
+    recovery_codes: Vec<String>,
+}
+
+#[OpenApi(prefix_path = "/user/totp")]
+impl TotpService {
+    /// TOTP Status
+    #[oai(path = "/", method = "get")]
+    async fn totp_status(
+        &self,
+        user: middleware::JwtAuthorization,
+    ) -> poem::Result<Json<TotpResponseGet>> {
+        let (enabled, recovery_codes) = self.controller.status(user.0.uuid).await?;
+
+        Ok(Json(TotpResponseGet {
+            enabled,
+            recovery_codes,
+        }))
+    }
+
+    /// Enroll TOTP
+    ///
+    /// Creates a new TOTP secret for the current user. Logins only need a code once the secret
+    /// is confirmed, enrolling again before that replaces the secret.
+    #[oai(path = "/", method = "post")]
+    async fn totp_enroll(
+        &self,
+        user: middleware::JwtAuthorization,
+    ) -> poem::Result<Json<TotpEnrollResponsePost>> {
+        let (secret, uri) = self.controller.enroll(user.0.uuid).await?;
+
+        Ok(Json(TotpEnrollResponsePost { secret, uri }))
+    }
+
+    /// Confirm TOTP
+    ///
+    /// Enables TOTP with a code of the enrolled secret and returns the recovery codes.
+    #[oai(path = "/confirm", method = "post")]
+    async fn totp_confirm(
+        &self,
+        user: middleware::JwtAuthorization,
+        request: Json<TotpCodeRequestPost>,
+    ) -> poem::Result<Json<TotpConfirmResponsePost>> {
+        let recovery_codes = self.controller.confirm(user.0.uuid, request.0.code).await?;
+
+        Ok(Json(TotpConfirmResponsePost { recovery_codes }))
+    }
+
+    /// Disable TOTP
+    ///
+    /// Disables TOTP of the current user, a TOTP code or recovery code must be given.
+    #[oai(path = "/disable", method = "post")]
+    async fn totp_disable(
+        &self,
+        user: middleware::JwtAuthorization,
+        request: Json<TotpCodeRequestPost>,
+    ) -> poem::Result<()> {
+        self.controller.disable(user.0.uuid, request.0.code).await?;
+
+        Ok(())
+    }
+}
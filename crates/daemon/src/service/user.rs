@@ -1,6 +1,6 @@
-use crate::{controller, middleware};
+use crate::{controller, middleware, schema};
 
-use poem_openapi::param::Path;
+use poem_openapi::param::{Path, Query};
 use poem_openapi::payload::Json;
 use poem_openapi::{Object, OpenApi};
 
@@ -20,6 +20,8 @@ impl UserService {
 struct LoginRequestPost {
     username: String,
     password: String,
+    /// TOTP code or recovery code, required once TOTP is enabled.
+    totp: Option<String>,
 }
 
 /// Login response - POST
@@ -36,9 +38,40 @@ struct RefreshRequestPost {
 }
 
 /// Refresh response - POST
+///
+/// The refresh token is rotated, the token sent in the request can't be used again.
 #[derive(Debug, Clone, Object)]
 struct RefreshResponsePost {
     token_auth: String,
+    token_refresh: String,
+}
+
+/// Logout request - POST
+#[derive(Debug, Clone, Object)]
+struct LogoutRequestPost {
+    token_refresh: String,
+}
+
+/// Logout everywhere response - POST
+#[derive(Debug, Clone, Object)]
+struct LogoutAllResponsePost {
+    revoked: u64,
+}
+
+/// Password request - POST
+#[derive(Debug, Clone, Object)]
+struct PasswordRequestPost {
+    password_old: String,
+    password_new: String,
+}
+
+/// Password response - POST
+///
+/// Every refresh token issued before the change is revoked.
+#[derive(Debug, Clone, Object)]
+struct PasswordResponsePost {
+    token_auth: String,
+    token_refresh: String,
 }
 
 /// User request - POST
@@ -46,6 +79,15 @@ struct RefreshResponsePost {
 struct UserRequestPost {
     username: String,
     password: String,
+    /// Invite code, or the setup token when registering the first admin.
+    invite: Option<String>,
+}
+
+/// User create request - POST
+#[derive(Debug, Clone, Object)]
+struct UserCreateRequestPost {
+    username: String,
+    password: String,
 }
 
 /// User response - POST
@@ -59,22 +101,72 @@ struct UserResponsePost {
 #[derive(Debug, Clone, Object)]
 struct UserResponseGet {
     uuid: uuid::Uuid,
-    name: String,
+    username: String,
+    created_at: Option<chrono::NaiveDateTime>,
+    updated_at: Option<chrono::NaiveDateTime>,
+}
+
+impl From<schema::User> for UserResponseGet {
+    fn from(value: schema::User) -> Self {
+        Self {
+            uuid: value.uuid.into_uuid(),
+            username: value.username,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
+/// User list response - GET
+#[derive(Debug, Clone, Object)]
+struct UserListResponseGet {
+    items: Vec<UserResponseGet>,
+    next_cursor: Option<String>,
+}
+
+/// User purge response - POST
+#[derive(Debug, Clone, Object)]
+struct UserPurgeResponsePost {
+    purged: u64,
+}
+
+/// User unlock response - POST
+///
+/// `unlocked` is false if the user had no failed logins.
+#[derive(Debug, Clone, Object)]
+struct UserUnlockResponsePost {
+    unlocked: bool,
+}
+
+/// User TOTP reset response - POST
+///
+/// `reset` is false if the user had no TOTP enrolled.
+#[derive(Debug, Clone, Object)]
+struct UserTotpResetResponsePost {
+    reset: bool,
 }
 
 #[OpenApi(prefix_path = "/user")]
 impl UserService {
     /// Login
+    ///
+    /// Failed logins are throttled per username and peer address. Logins are refused with 429
+    /// while waiting for the backoff and with 423 while locked. Users with TOTP enabled get 401
+    /// until a TOTP code or recovery code is given.
     #[oai(path = "/login", method = "post")]
     async fn login(
         &self,
+        req: &poem::Request,
         request: Json<LoginRequestPost>,
     ) -> poem::Result<Json<LoginResponsePost>> {
         let request = request.0;
 
+        // Unix socket peers have no address, they are only throttled by username.
+        let peer = req.remote_addr().as_socket_addr().map(|x| x.ip());
+
         let (token_auth, token_refresh) = self
             .controller
-            .auth(request.username, request.password)
+            .login(request.username, request.password, request.totp, peer)
             .await?;
 
         let res = LoginResponsePost {
@@ -93,15 +185,70 @@ impl UserService {
     ) -> poem::Result<Json<RefreshResponsePost>> {
         let request = request.0;
 
-        let token_auth = self.controller.refresh(request.token_refresh).await?;
-        let res = RefreshResponsePost { token_auth };
+        let (token_auth, token_refresh) = self.controller.refresh(request.token_refresh).await?;
+        let res = RefreshResponsePost {
+            token_auth,
+            token_refresh,
+        };
+
+        Ok(Json(res))
+    }
+
+    /// Logout
+    ///
+    /// Revokes the refresh token and every token refreshed from the same login. Auth tokens
+    /// stay valid until they expire.
+    #[oai(path = "/logout", method = "post")]
+    async fn logout(&self, request: Json<LogoutRequestPost>) -> poem::Result<()> {
+        self.controller.logout(request.0.token_refresh).await?;
+
+        Ok(())
+    }
+
+    /// Logout Everywhere
+    ///
+    /// Revokes every refresh token of the current user.
+    #[oai(path = "/logout-all", method = "post")]
+    async fn logout_all(
+        &self,
+        user: middleware::JwtAuthorization,
+    ) -> poem::Result<Json<LogoutAllResponsePost>> {
+        let revoked = self.controller.logout_all(user.0.uuid).await?;
+
+        Ok(Json(LogoutAllResponsePost { revoked }))
+    }
+
+    /// Change Password
+    ///
+    /// Changes the password of the current user, the current password must be given. Every
+    /// session of the user is logged out and new tokens are returned.
+    #[oai(path = "/password", method = "post")]
+    async fn password_change(
+        &self,
+        user: middleware::JwtAuthorization,
+        request: Json<PasswordRequestPost>,
+    ) -> poem::Result<Json<PasswordResponsePost>> {
+        let request = request.0;
+
+        let (token_auth, token_refresh) = self
+            .controller
+            .change_password(user.0.uuid, request.password_old, request.password_new)
+            .await?;
+
+        let res = PasswordResponsePost {
+            token_auth,
+            token_refresh,
+        };
 
         Ok(Json(res))
     }
 
     /// Register New user
+    ///
+    /// The first admin registers with the setup token printed at startup. After that the
+    /// registration policy decides if an invite code is needed or only admins create users.
     #[oai(path = "/", method = "post")]
-    async fn user_create(
+    async fn user_register(
         &self,
         request: Json<UserRequestPost>,
     ) -> poem::Result<Json<UserResponsePost>> {
@@ -109,7 +256,7 @@ impl UserService {
 
         let (token_auth, token_refresh) = self
             .controller
-            .add(request.username, request.password)
+            .register(request.username, request.password, request.invite)
             .await?;
 
         let res = UserResponsePost {
@@ -120,27 +267,116 @@ impl UserService {
         Ok(Json(res))
     }
 
+    /// Create User
+    ///
+    /// Creates a user regardless of the registration policy, admin only. The user logs in with
+    /// the given password.
+    #[oai(path = "/create", method = "post")]
+    async fn user_create(
+        &self,
+        user: middleware::JwtAuthorization,
+        request: Json<UserCreateRequestPost>,
+    ) -> poem::Result<Json<UserResponseGet>> {
+        let request = request.0;
+
+        let user = self
+            .controller
+            .create(&user.0, request.username, request.password)
+            .await?;
+
+        Ok(Json(user.into()))
+    }
+
+    /// List Users
+    ///
+    /// Admin only. Only users whose username contains `search` are listed. Set `limit` to page
+    /// through users, passing the returned `next_cursor` as `cursor` to get the next page.
+    /// Pages hold at most 1000 entries.
+    #[oai(path = "/", method = "get")]
+    async fn user_list(
+        &self,
+        user: middleware::JwtAuthorization,
+        search: Query<Option<String>>,
+        cursor: Query<Option<String>>,
+        limit: Query<Option<u64>>,
+    ) -> poem::Result<Json<UserListResponseGet>> {
+        let users = self
+            .controller
+            .list(&user.0, search.0, cursor.0, limit.0)
+            .await?;
+
+        let res = UserListResponseGet {
+            items: users.items.into_iter().map(UserResponseGet::from).collect(),
+            next_cursor: users.next_cursor,
+        };
+
+        Ok(Json(res))
+    }
+
     /// Existing User Info
     #[oai(path = "/:username", method = "get")]
     async fn user_info(
         &self,
-        _user: middleware::JwtAuthorization,
-        _username: Path<String>,
+        user: middleware::JwtAuthorization,
+        username: Path<String>,
     ) -> poem::Result<Json<UserResponseGet>> {
-        Err(poem::Error::from_status(
-            poem::http::StatusCode::NOT_IMPLEMENTED,
-        ))
+        let user = self.controller.info(&user.0, username.0).await?;
+
+        Ok(Json(user.into()))
     }
 
     /// Delete User
+    ///
+    /// Users are soft deleted and only removed for good once purged.
     #[oai(path = "/:username", method = "delete")]
     async fn user_delete(
         &self,
-        _user: middleware::JwtAuthorization,
-        _username: Path<String>,
+        user: middleware::JwtAuthorization,
+        username: Path<String>,
     ) -> poem::Result<()> {
-        Err(poem::Error::from_status(
-            poem::http::StatusCode::NOT_IMPLEMENTED,
-        ))
+        self.controller.delete(&user.0, username.0).await?;
+
+        Ok(())
+    }
+
+    /// Unlock User
+    ///
+    /// Forget failed logins of a user locked after too many failed logins, admin only.
+    #[oai(path = "/:username/unlock", method = "post")]
+    async fn user_unlock(
+        &self,
+        user: middleware::JwtAuthorization,
+        username: Path<String>,
+    ) -> poem::Result<Json<UserUnlockResponsePost>> {
+        let unlocked = self.controller.unlock(&user.0, username.0).await?;
+
+        Ok(Json(UserUnlockResponsePost { unlocked }))
+    }
+
+    /// Reset User TOTP
+    ///
+    /// Disable TOTP of a user who lost their authenticator and recovery codes, admin only.
+    #[oai(path = "/:username/totp/reset", method = "post")]
+    async fn user_totp_reset(
+        &self,
+        user: middleware::JwtAuthorization,
+        username: Path<String>,
+    ) -> poem::Result<Json<UserTotpResetResponsePost>> {
+        let reset = self.controller.reset_totp(&user.0, username.0).await?;
+
+        Ok(Json(UserTotpResetResponsePost { reset }))
+    }
+
+    /// Purge Deleted Users
+    ///
+    /// Remove users which were deleted longer than the retention period ago, admin only.
+    #[oai(path = "/purge", method = "post")]
+    async fn user_purge(
+        &self,
+        user: middleware::JwtAuthorization,
+    ) -> poem::Result<Json<UserPurgeResponsePost>> {
+        let purged = self.controller.purge(&user.0).await?;
+
+        Ok(Json(UserPurgeResponsePost { purged }))
     }
 }
@@ -0,0 +1,354 @@
+use crate::error::ClientError;
+use crate::http::{self, Endpoint, Method};
+use crate::token::{Token, TokenCache};
+
+/// Prefix of every daemon API path.
+const API_PATH_PREFIX: &str = "/api/v1";
+
+/// Login response - POST
+#[derive(Clone, Debug, serde::Deserialize)]
+struct LoginResponsePost {
+    token_auth: String,
+    token_refresh: String,
+}
+
+/// Refresh response - POST
+#[derive(Clone, Debug, serde::Deserialize)]
+struct RefreshResponsePost {
+    token_auth: String,
+    token_refresh: String,
+}
+
+/// Daemon API client.
+///
+/// Requests which need a login use the cached tokens. An expired auth token is
+/// refreshed once with the refresh token before giving up. Without cached tokens
+/// requests over the unix socket are sent without a token.
+#[derive(Clone, Debug)]
+pub struct VaultClient {
+    endpoint: Endpoint,
+    cache: TokenCache,
+}
+
+impl VaultClient {
+    pub fn new(endpoint: Endpoint, cache: TokenCache) -> Self {
+        Self { endpoint, cache }
+    }
+
+    pub fn endpoint(&self) -> &Endpoint {
+        &self.endpoint
+    }
+
+    /// Cached token for this daemon.
+    pub async fn token(&self) -> Option<Token> {
+        self.cache
+            .load()
+            .await
+            .filter(|x| x.endpoint == self.endpoint.to_string())
+    }
+
+    /// Login and cache tokens.
+    /// Users with TOTP enabled also need a TOTP code or recovery code.
+    pub async fn login(
+        &self,
+        username: String,
+        password: String,
+        totp: Option<String>,
+    ) -> Result<(), ClientError> {
+        let body = serde_json::json!({ "username": &username, "password": password, "totp": totp });
+        let response: LoginResponsePost = self
+            .request(Method::Post, "/user/login", None, Some(body))
+            .await?;
+
+        self.save_token(username, response).await
+    }
+
+    /// Register new user and cache tokens.
+    /// Depending on the registration policy of the daemon an invite code is needed, the first
+    /// admin registers with the setup token of the daemon.
+    pub async fn register(
+        &self,
+        username: String,
+        password: String,
+        invite: Option<String>,
+    ) -> Result<(), ClientError> {
+        let body =
+            serde_json::json!({ "username": &username, "password": password, "invite": invite });
+        let response: LoginResponsePost = self
+            .request(Method::Post, "/user", None, Some(body))
+            .await?;
+
+        self.save_token(username, response).await
+    }
+
+    /// Revoke the cached login and forget cached tokens.
+    /// With `all` every login of the user is revoked, not only the cached one.
+    pub async fn logout(&self, all: bool) -> Result<(), ClientError> {
+        let Some(token) = self.token().await else {
+            return Ok(self.cache.remove().await?);
+        };
+
+        if all {
+            self.request_auth::<serde_json::Value>(Method::Post, "/user/logout-all", None)
+                .await?;
+        } else {
+            // Tokens are forgotten even if the daemon can't be reached.
+            let body = serde_json::json!({ "token_refresh": &token.token_refresh });
+
+            if let Err(e) = self
+                .request::<serde_json::Value>(Method::Post, "/user/logout", None, Some(body))
+                .await
+            {
+                tracing::warn!("could not revoke login: {e}");
+            }
+        }
+
+        Ok(self.cache.remove().await?)
+    }
+
+    /// Request which doesn't need a login.
+    pub async fn get_public<T>(&self, path: &str) -> Result<T, ClientError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        self.request(Method::Get, path, None, None).await
+    }
+
+    /// Request which doesn't need a login.
+    pub async fn post_public<T>(
+        &self,
+        path: &str,
+        body: serde_json::Value,
+    ) -> Result<T, ClientError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        self.request(Method::Post, path, None, Some(body)).await
+    }
+
+    pub async fn get<T>(&self, path: &str) -> Result<T, ClientError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        self.request_auth(Method::Get, path, None).await
+    }
+
+    pub async fn post<T>(&self, path: &str, body: serde_json::Value) -> Result<T, ClientError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        self.request_auth(Method::Post, path, Some(body)).await
+    }
+
+    pub async fn put<T>(&self, path: &str, body: serde_json::Value) -> Result<T, ClientError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        self.request_auth(Method::Put, path, Some(body)).await
+    }
+
+    pub async fn delete(&self, path: &str) -> Result<(), ClientError> {
+        self.request_auth::<serde_json::Value>(Method::Delete, path, None)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn save_token(
+        &self,
+        username: String,
+        response: LoginResponsePost,
+    ) -> Result<(), ClientError> {
+        let token = Token {
+            endpoint: self.endpoint.to_string(),
+            username,
+            token_auth: response.token_auth,
+            token_refresh: response.token_refresh,
+        };
+
+        Ok(self.cache.save(&token).await?)
+    }
+
+    async fn request_auth<T>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> Result<T, ClientError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let Some(mut token) = self.token().await else {
+            // The daemon authenticates unix socket peers without a token by their uid and gid.
+            if let Endpoint::Unix(_) = self.endpoint {
+                return self.request(method, path, None, body).await.map_err(|e| {
+                    match e.status() {
+                        Some(401) => ClientError::NotLoggedIn,
+                        _ => e,
+                    }
+                });
+            }
+
+            return Err(ClientError::NotLoggedIn);
+        };
+
+        match self
+            .request(method, path, Some(&token.token_auth), body.clone())
+            .await
+        {
+            Err(e) if e.status() == Some(401) => {}
+            x => return x,
+        }
+
+        tracing::debug!("auth token rejected, refreshing");
+
+        let body_refresh = serde_json::json!({ "token_refresh": &token.token_refresh });
+        let response: RefreshResponsePost = self
+            .request(Method::Post, "/user/refresh", None, Some(body_refresh))
+            .await
+            .map_err(|e| match e.status() {
+                Some(401 | 403) => ClientError::NotLoggedIn,
+                _ => e,
+            })?;
+
+        // Refresh tokens are rotated, the old one can't be used again.
+        token.token_auth = response.token_auth;
+        token.token_refresh = response.token_refresh;
+        self.cache.save(&token).await?;
+
+        self.request(method, path, Some(&token.token_auth), body)
+            .await
+            .map_err(|e| match e.status() {
+                Some(401) => ClientError::NotLoggedIn,
+                _ => e,
+            })
+    }
+
+    async fn request<T>(
+        &self,
+        method: Method,
+        path: &str,
+        token: Option<&str>,
+        body: Option<serde_json::Value>,
+    ) -> Result<T, ClientError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let path = format!("{}{}", API_PATH_PREFIX, path);
+        let response = http::send(&self.endpoint, method, &path, token, body.as_ref()).await?;
+
+        if !response.is_success() {
+            return Err(ClientError::Status {
+                status: response.status,
+                message: String::from_utf8_lossy(&response.body).trim().to_string(),
+            });
+        }
+
+        // Endpoints without a response body are read as null.
+        if response.body.is_empty() {
+            return Ok(serde_json::from_value(serde_json::Value::Null)?);
+        }
+
+        Ok(serde_json::from_slice(&response.body)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VaultClient;
+    use crate::error::ClientError;
+    use crate::http::Endpoint;
+    use crate::token::TokenCache;
+
+    use shared_core::rng;
+    use std::path::PathBuf;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Build http response with json body.
+    fn response(status: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {}\r\ncontent-length: {}\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )
+    }
+
+    /// Answer each connection with the next response and return the requests.
+    fn serve(
+        listener: tokio::net::TcpListener,
+        responses: Vec<String>,
+    ) -> tokio::task::JoinHandle<Vec<String>> {
+        tokio::spawn(async move {
+            let mut requests = Vec::new();
+
+            for response in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut data = vec![0; 4096];
+                let n = stream.read(&mut data).await.unwrap();
+
+                stream.write_all(response.as_bytes()).await.unwrap();
+                requests.push(String::from_utf8(data[..n].to_vec()).unwrap());
+            }
+
+            requests
+        })
+    }
+
+    #[tokio::test]
+    async fn refresh() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = Endpoint::Tcp(listener.local_addr().unwrap().to_string());
+
+        // Create random file in test data folder
+        let path = PathBuf::from(env!("WORKSPACE_DIR"))
+            .join("test-data")
+            .join("temp")
+            .join(format!("{}.json", rng::random_bytes_str(10)));
+
+        let client = VaultClient::new(endpoint, TokenCache::new(path));
+
+        // Requests need a login
+        let result_err_1 = client.get::<serde_json::Value>("/client/info").await;
+
+        assert!(matches!(result_err_1, Err(ClientError::NotLoggedIn)));
+
+        let server = serve(
+            listener,
+            vec![
+                response("200 OK", r#"{"token_auth":"a1","token_refresh":"r1"}"#),
+                response("401 Unauthorized", ""),
+                response("200 OK", r#"{"token_auth":"a2","token_refresh":"r2"}"#),
+                response("200 OK", r#"{"uptime_seconds":10}"#),
+                response("200 OK", ""),
+            ],
+        );
+
+        let result_1 = client.login("john".into(), "hunter2".into(), None).await;
+
+        assert!(result_1.is_ok());
+
+        let result_2 = client.get::<serde_json::Value>("/client/info").await;
+
+        assert_eq!(result_2.unwrap()["uptime_seconds"], 10);
+
+        // New tokens should be cached
+        let token = client.token().await.unwrap();
+
+        assert_eq!(token.username, "john");
+        assert_eq!(token.token_auth, "a2");
+        assert_eq!(token.token_refresh, "r2");
+
+        assert!(client.logout(false).await.is_ok());
+        assert!(client.token().await.is_none());
+
+        let requests = server.await.unwrap();
+
+        assert!(requests[0].starts_with("POST /api/v1/user/login "));
+        assert!(requests[1].contains("Authorization: Bearer a1\r\n"));
+        assert!(requests[2].starts_with("POST /api/v1/user/refresh "));
+        assert!(requests[3].contains("Authorization: Bearer a2\r\n"));
+        assert!(requests[4].starts_with("POST /api/v1/user/logout "));
+        assert!(requests[4].contains(r#""token_refresh":"r2""#));
+    }
+}
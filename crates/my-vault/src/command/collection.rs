@@ -0,0 +1,164 @@
+use crate::client::VaultClient;
+use crate::command::{ListArgs, ListResponseGet, SecretResponseGet, SourceResponseGet, source};
+use crate::http;
+use crate::output::{self, OutputFormat, Tabular};
+
+/// Collection response - GET
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct CollectionResponseGet {
+    pub uuid: uuid::Uuid,
+    pub name: String,
+}
+
+impl Tabular for CollectionResponseGet {
+    fn headers() -> &'static [&'static str] {
+        &["uuid", "name"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![self.uuid.to_string(), self.name.clone()]
+    }
+}
+
+/// Collection commands
+#[derive(clap::Subcommand, Debug)]
+pub enum CollectionCommand {
+    /// List collections.
+    List(ListArgs),
+
+    /// Create collection.
+    Create { name: String },
+
+    /// Rename collection.
+    Rename { name: String, new_name: String },
+
+    /// Delete collection, attached sources are detached.
+    Rm { name: String },
+
+    /// List sources attached to collection.
+    Sources { name: String },
+
+    /// Attach source to collection.
+    Attach { name: String, source: String },
+
+    /// Detach source from collection.
+    Detach { name: String, source: String },
+
+    /// List secrets reachable through the sources of collection.
+    Secrets { name: String },
+}
+
+impl CollectionCommand {
+    pub async fn run(self, client: &VaultClient, output: OutputFormat) -> anyhow::Result<()> {
+        match self {
+            Self::List(args) => {
+                let collections: ListResponseGet<CollectionResponseGet> =
+                    client.get(&format!("/collection{}", args.query())).await?;
+
+                output::print_list(
+                    output,
+                    &collections.items,
+                    collections.next_cursor.as_deref(),
+                )
+            }
+            Self::Create { name } => {
+                let body = serde_json::json!({ "name": name });
+                let collection: CollectionResponseGet = client.post("/collection", body).await?;
+
+                output::print_item(output, &collection)
+            }
+            Self::Rename { name, new_name } => {
+                let collection = get(client, &name).await?;
+                let body = serde_json::json!({ "name": new_name });
+                let collection: CollectionResponseGet = client
+                    .put(&format!("/collection/{}", collection.uuid), body)
+                    .await?;
+
+                output::print_item(output, &collection)
+            }
+            Self::Rm { name } => {
+                let collection = get(client, &name).await?;
+
+                client
+                    .delete(&format!("/collection/{}", collection.uuid))
+                    .await?;
+
+                eprintln!("deleted collection {}", name);
+
+                Ok(())
+            }
+            Self::Sources { name } => {
+                let collection = get(client, &name).await?;
+                let uuids: Vec<uuid::Uuid> = client
+                    .get(&format!("/collection/{}/source", collection.uuid))
+                    .await?;
+
+                // Only uuids are returned, look up the rest of each source.
+                let mut sources: Vec<SourceResponseGet> = Vec::with_capacity(uuids.len());
+
+                for uuid in uuids {
+                    sources.push(client.get(&format!("/source/{}", uuid)).await?);
+                }
+
+                output::print_list(output, &sources, None)
+            }
+            Self::Attach { name, source } => {
+                let collection = get(client, &name).await?;
+                let source = source::get(client, &source).await?;
+
+                client
+                    .put::<()>(
+                        &format!("/collection/{}/source/{}", collection.uuid, source.uuid),
+                        serde_json::json!({}),
+                    )
+                    .await?;
+
+                eprintln!("attached source {} to collection {}", source.name, name);
+
+                Ok(())
+            }
+            Self::Detach { name, source } => {
+                let collection = get(client, &name).await?;
+                let source = source::get(client, &source).await?;
+
+                client
+                    .delete(&format!(
+                        "/collection/{}/source/{}",
+                        collection.uuid, source.uuid
+                    ))
+                    .await?;
+
+                eprintln!("detached source {} from collection {}", source.name, name);
+
+                Ok(())
+            }
+            Self::Secrets { name } => {
+                let collection = get(client, &name).await?;
+                let secrets: Vec<SecretResponseGet> = client
+                    .get(&format!("/collection/{}/secret", collection.uuid))
+                    .await?;
+
+                output::print_list(output, &secrets, None)
+            }
+        }
+    }
+}
+
+/// Get collection by name or uuid.
+async fn get(client: &VaultClient, name: &str) -> anyhow::Result<CollectionResponseGet> {
+    // Users only granted access to a single collection can't list collections to search by name.
+    if let Ok(uuid) = uuid::Uuid::parse_str(name) {
+        return Ok(client.get(&format!("/collection/{}", uuid)).await?);
+    }
+
+    let collections: ListResponseGet<CollectionResponseGet> = client
+        .get(&format!("/collection?search={}", http::encode(name)))
+        .await?;
+
+    // Search also matches names which only contain the name.
+    collections
+        .items
+        .into_iter()
+        .find(|x| x.name == name)
+        .ok_or_else(|| anyhow::anyhow!("collection {} does not exist", name))
+}
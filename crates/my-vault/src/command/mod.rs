@@ -0,0 +1,126 @@
+mod collection;
+mod secret;
+mod source;
+mod status;
+mod unseal;
+mod user;
+
+pub use collection::*;
+pub use secret::*;
+pub use source::*;
+pub use unseal::UnsealArgs;
+pub use user::{LoginArgs, LogoutArgs, RegisterArgs};
+
+use crate::client::VaultClient;
+use crate::http;
+use crate::output::OutputFormat;
+
+/// Commands
+#[derive(clap::Subcommand, Debug)]
+pub enum Command {
+    /// Login and cache tokens for later commands.
+    Login(LoginArgs),
+
+    /// Register a new user and login as it.
+    Register(RegisterArgs),
+
+    /// Revoke login and forget cached tokens.
+    Logout(LogoutArgs),
+
+    /// Show daemon and login status.
+    Status,
+
+    /// Unseal a sealed daemon with the passphrase or a share of the database key.
+    Unseal(UnsealArgs),
+
+    /// Manage secrets.
+    #[command(subcommand)]
+    Secret(SecretCommand),
+
+    /// Manage collections.
+    #[command(subcommand)]
+    Collection(CollectionCommand),
+
+    /// Manage sources.
+    #[command(subcommand)]
+    Source(SourceCommand),
+}
+
+impl Command {
+    pub async fn run(self, client: &VaultClient, output: OutputFormat) -> anyhow::Result<()> {
+        match self {
+            Self::Login(x) => user::login(client, x).await,
+            Self::Register(x) => user::register(client, x).await,
+            Self::Logout(x) => user::logout(client, x).await,
+            Self::Status => status::status(client, output).await,
+            Self::Unseal(x) => unseal::unseal(client, x, output).await,
+            Self::Secret(x) => x.run(client, output).await,
+            Self::Collection(x) => x.run(client, output).await,
+            Self::Source(x) => x.run(client, output).await,
+        }
+    }
+}
+
+/// Arguments for list commands.
+#[derive(clap::Args, Debug, Default)]
+pub struct ListArgs {
+    /// Only list entries whose name contains text.
+    #[arg(short, long)]
+    search: Option<String>,
+
+    /// Number of entries per page, everything is listed without a limit.
+    #[arg(short, long)]
+    limit: Option<u64>,
+
+    /// Cursor of page to list, printed after the previous page.
+    #[arg(short, long)]
+    cursor: Option<String>,
+}
+
+impl ListArgs {
+    /// Query string with all arguments which are set.
+    fn query(&self) -> String {
+        let params = [
+            self.search
+                .as_ref()
+                .map(|x| format!("search={}", http::encode(x))),
+            self.limit.map(|x| format!("limit={}", x)),
+            self.cursor
+                .as_ref()
+                .map(|x| format!("cursor={}", http::encode(x))),
+        ];
+
+        let params = params.into_iter().flatten().collect::<Vec<_>>();
+
+        if params.is_empty() {
+            String::new()
+        } else {
+            format!("?{}", params.join("&"))
+        }
+    }
+}
+
+/// List response - GET
+#[derive(Clone, Debug, serde::Deserialize)]
+struct ListResponseGet<T> {
+    items: Vec<T>,
+    next_cursor: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ListArgs;
+
+    #[tokio::test]
+    async fn list_query() {
+        assert_eq!(ListArgs::default().query(), "");
+
+        let args = ListArgs {
+            search: Some("api key".into()),
+            limit: Some(10),
+            cursor: None,
+        };
+
+        assert_eq!(args.query(), "?search=api%20key&limit=10");
+    }
+}
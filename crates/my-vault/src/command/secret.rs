@@ -0,0 +1,175 @@
+use crate::client::VaultClient;
+use crate::command::{ListArgs, ListResponseGet};
+use crate::output::{self, OutputFormat, Tabular};
+use crate::{http, prompt};
+
+/// Secret type, values match the daemon.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum SecretType {
+    Cipher = 1,
+    Key = 2,
+}
+
+/// Name of secret type value.
+fn secret_type_name(value: u32) -> &'static str {
+    match value {
+        x if x == SecretType::Cipher as u32 => "cipher",
+        x if x == SecretType::Key as u32 => "key",
+        _ => "unknown",
+    }
+}
+
+/// Secret response - GET
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct SecretResponseGet {
+    pub uuid: uuid::Uuid,
+    pub name: String,
+    pub key: Option<String>,
+    pub description: Option<String>,
+    pub secret: String,
+    pub secret_type: u32,
+}
+
+impl Tabular for SecretResponseGet {
+    fn headers() -> &'static [&'static str] {
+        &["uuid", "name", "key", "type", "description"]
+    }
+
+    // Secret values are only shown by `secret get`.
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.uuid.to_string(),
+            self.name.clone(),
+            output::display_option(&self.key),
+            secret_type_name(self.secret_type).into(),
+            output::display_option(&self.description),
+        ]
+    }
+}
+
+/// Secret commands
+#[derive(clap::Subcommand, Debug)]
+pub enum SecretCommand {
+    /// Show secret.
+    Get {
+        name: String,
+
+        /// Only print the secret value.
+        #[arg(long)]
+        value: bool,
+    },
+
+    /// List secrets, secret values are not shown.
+    List(ListArgs),
+
+    /// Create secret or update existing secret.
+    Set {
+        name: String,
+
+        /// Secret value, asked for when not given.
+        value: Option<String>,
+
+        /// Environment variable name of secret.
+        #[arg(short, long)]
+        key: Option<String>,
+
+        #[arg(short, long)]
+        description: Option<String>,
+
+        #[arg(short = 't', long = "type", value_enum, default_value_t = SecretType::Cipher)]
+        secret_type: SecretType,
+    },
+
+    /// Delete secret.
+    Rm { name: String },
+}
+
+impl SecretCommand {
+    pub async fn run(self, client: &VaultClient, output: OutputFormat) -> anyhow::Result<()> {
+        match self {
+            Self::Get { name, value } => {
+                let secret = get(client, &name).await?;
+
+                if value {
+                    println!("{}", secret.secret);
+                    return Ok(());
+                }
+
+                // Unlike lists, a single secret is shown with its value.
+                output::print_value(output, &secret)
+            }
+            Self::List(args) => {
+                let secrets: ListResponseGet<SecretResponseGet> =
+                    client.get(&format!("/secret{}", args.query())).await?;
+
+                output::print_list(output, &secrets.items, secrets.next_cursor.as_deref())
+            }
+            Self::Set {
+                name,
+                value,
+                key,
+                description,
+                secret_type,
+            } => {
+                let value = match value {
+                    Some(x) => x,
+                    None => prompt::read_password("Secret: ")?,
+                };
+
+                let secret: SecretResponseGet = match find(client, &name).await? {
+                    Some(x) => {
+                        let body = serde_json::json!({
+                            "key": key,
+                            "description": description,
+                            "secret": value,
+                            "secret_type": secret_type as u32,
+                        });
+
+                        client.put(&format!("/secret/{}", x.uuid), body).await?
+                    }
+                    None => {
+                        let body = serde_json::json!({
+                            "name": name,
+                            "key": key,
+                            "description": description,
+                            "secret": value,
+                            "secret_type": secret_type as u32,
+                        });
+
+                        client.post("/secret", body).await?
+                    }
+                };
+
+                output::print_item(output, &secret)
+            }
+            Self::Rm { name } => {
+                let secret = get(client, &name).await?;
+
+                client.delete(&format!("/secret/{}", secret.uuid)).await?;
+
+                eprintln!("deleted secret {}", name);
+
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Get secret by name.
+async fn get(client: &VaultClient, name: &str) -> anyhow::Result<SecretResponseGet> {
+    find(client, name)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("secret {} does not exist", name))
+}
+
+/// Find secret by name.
+async fn find(client: &VaultClient, name: &str) -> anyhow::Result<Option<SecretResponseGet>> {
+    match client
+        .get(&format!("/secret/name/{}", http::encode(name)))
+        .await
+    {
+        Ok(x) => Ok(Some(x)),
+        Err(e) if e.status() == Some(404) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,177 @@
+use crate::client::VaultClient;
+use crate::command::{ListArgs, ListResponseGet};
+use crate::http;
+use crate::output::{self, OutputFormat, Tabular};
+
+/// Source type, values match the daemon.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum SourceType {
+    Csv = 1,
+}
+
+/// Name of source type value.
+fn source_type_name(value: u32) -> &'static str {
+    match value {
+        x if x == SourceType::Csv as u32 => "csv",
+        _ => "unknown",
+    }
+}
+
+/// Source response - GET
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct SourceResponseGet {
+    pub uuid: uuid::Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub source_type: u32,
+    pub source_config: Option<String>,
+    pub source_auth_type: u32,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+impl Tabular for SourceResponseGet {
+    fn headers() -> &'static [&'static str] {
+        &["uuid", "name", "type", "description", "updated_at"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.uuid.to_string(),
+            self.name.clone(),
+            source_type_name(self.source_type).into(),
+            output::display_option(&self.description),
+            output::display_option(&self.updated_at),
+        ]
+    }
+}
+
+/// Source import response - POST
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct SourceImportResponsePost {
+    dry_run: bool,
+    created: Vec<String>,
+    updated: Vec<String>,
+    deleted: Vec<String>,
+    unchanged: u64,
+}
+
+/// Source commands
+#[derive(clap::Subcommand, Debug)]
+pub enum SourceCommand {
+    /// List sources.
+    List(ListArgs),
+
+    /// Show source.
+    Get { name: String },
+
+    /// Register source.
+    Add {
+        name: String,
+
+        #[arg(short = 't', long = "type", value_enum)]
+        source_type: SourceType,
+
+        /// Source type specific config as JSON, for example `{"path": "secrets.csv"}`.
+        #[arg(short, long)]
+        config: String,
+
+        #[arg(short, long)]
+        description: Option<String>,
+    },
+
+    /// Import secrets from source.
+    Import {
+        name: String,
+
+        /// Only show what would change.
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Delete source.
+    Rm {
+        name: String,
+
+        /// Also detach source from collections and delete its secrets.
+        #[arg(long)]
+        cascade: bool,
+    },
+}
+
+impl SourceCommand {
+    pub async fn run(self, client: &VaultClient, output: OutputFormat) -> anyhow::Result<()> {
+        match self {
+            Self::List(args) => {
+                let sources: ListResponseGet<SourceResponseGet> =
+                    client.get(&format!("/source{}", args.query())).await?;
+
+                output::print_list(output, &sources.items, sources.next_cursor.as_deref())
+            }
+            Self::Get { name } => {
+                let source = get(client, &name).await?;
+
+                output::print_value(output, &source)
+            }
+            Self::Add {
+                name,
+                source_type,
+                config,
+                description,
+            } => {
+                let body = serde_json::json!({
+                    "name": name,
+                    "description": description,
+                    "source_type": source_type as u32,
+                    "source_config": config,
+                    "source_auth_type": 0,
+                });
+
+                let source: SourceResponseGet = client.post("/source", body).await?;
+
+                output::print_item(output, &source)
+            }
+            Self::Import { name, dry_run } => {
+                let source = get(client, &name).await?;
+                let plan: SourceImportResponsePost = client
+                    .post(
+                        &format!("/source/{}/import?dry_run={}", source.uuid, dry_run),
+                        serde_json::json!({}),
+                    )
+                    .await?;
+
+                output::print_value(output, &plan)
+            }
+            Self::Rm { name, cascade } => {
+                let source = get(client, &name).await?;
+
+                client
+                    .delete(&format!("/source/{}?cascade={}", source.uuid, cascade))
+                    .await?;
+
+                eprintln!("deleted source {}", name);
+
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Get source by name or uuid.
+pub(super) async fn get(client: &VaultClient, name: &str) -> anyhow::Result<SourceResponseGet> {
+    // Users only granted access to a single source can't list sources to search by name.
+    if let Ok(uuid) = uuid::Uuid::parse_str(name) {
+        return Ok(client.get(&format!("/source/{}", uuid)).await?);
+    }
+
+    let sources: ListResponseGet<SourceResponseGet> = client
+        .get(&format!("/source?search={}", http::encode(name)))
+        .await?;
+
+    // Search also matches names which only contain the name.
+    sources
+        .items
+        .into_iter()
+        .find(|x| x.name == name)
+        .ok_or_else(|| anyhow::anyhow!("source {} does not exist", name))
+}
@@ -0,0 +1,59 @@
+use crate::client::VaultClient;
+use crate::output::{self, OutputFormat};
+
+/// Health response - GET
+#[derive(Clone, Debug, serde::Deserialize)]
+struct HealthResponseGet {
+    healthy: bool,
+    #[serde(default)]
+    sealed: bool,
+}
+
+/// Info response - GET
+#[derive(Clone, Debug, serde::Deserialize)]
+struct InfoResponseGet {
+    uptime_seconds: u64,
+}
+
+/// Daemon and login status
+#[derive(Clone, Debug, serde::Serialize)]
+struct Status {
+    endpoint: String,
+    healthy: bool,
+    sealed: bool,
+    username: Option<String>,
+    uptime_seconds: Option<u64>,
+    error: Option<String>,
+}
+
+pub async fn status(client: &VaultClient, output: OutputFormat) -> anyhow::Result<()> {
+    let mut status = Status {
+        endpoint: client.endpoint().to_string(),
+        healthy: false,
+        sealed: false,
+        username: None,
+        uptime_seconds: None,
+        error: None,
+    };
+
+    // Status is still printed if the daemon can't be reached.
+    match client.get_public::<HealthResponseGet>("/health").await {
+        Ok(x) => {
+            status.healthy = x.healthy;
+            status.sealed = x.sealed;
+        }
+        Err(e) => status.error = Some(e.to_string()),
+    }
+
+    if status.healthy && !status.sealed && client.token().await.is_some() {
+        match client.get::<InfoResponseGet>("/client/info").await {
+            Ok(x) => status.uptime_seconds = Some(x.uptime_seconds),
+            Err(e) => status.error = Some(e.to_string()),
+        }
+
+        // Refreshing may have dropped the login.
+        status.username = client.token().await.map(|x| x.username);
+    }
+
+    output::print_value(output, &status)
+}
@@ -0,0 +1,78 @@
+use crate::client::VaultClient;
+use crate::output::{self, OutputFormat};
+use crate::prompt;
+
+/// Unseal response - GET, POST
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+struct UnsealResponse {
+    sealed: bool,
+    key_source: String,
+    threshold: u64,
+    progress: u64,
+}
+
+/// Arguments for unseal.
+#[derive(clap::Args, Debug)]
+pub struct UnsealArgs {
+    /// Discard the shares given so far instead of giving one, asks for a share to prove the reset.
+    #[arg(long)]
+    reset: bool,
+
+    /// Read passphrase or share from the first line of stdin instead of asking for it.
+    #[arg(long)]
+    key_stdin: bool,
+}
+
+pub async fn unseal(
+    client: &VaultClient,
+    args: UnsealArgs,
+    output: OutputFormat,
+) -> anyhow::Result<()> {
+    // Unseal endpoints are only served while the daemon is sealed.
+    let status = match client.get_public::<UnsealResponse>("/unseal").await {
+        Err(e) if e.status() == Some(404) => anyhow::bail!("daemon isn't sealed"),
+        x => x?,
+    };
+
+    if !status.sealed {
+        anyhow::bail!("daemon isn't sealed");
+    }
+
+    let shamir = status.key_source == "shamir";
+
+    if args.reset && !shamir {
+        anyhow::bail!("only shares can be reset, the database key is from a passphrase");
+    }
+
+    let prompt = if args.reset {
+        "Share: ".to_string()
+    } else if shamir {
+        format!("Share ({}/{}): ", status.progress + 1, status.threshold)
+    } else {
+        "Passphrase: ".to_string()
+    };
+
+    let key = if args.key_stdin {
+        prompt::read_line("")?
+    } else {
+        prompt::read_password(&prompt)?
+    };
+
+    if args.reset {
+        let status: UnsealResponse = client
+            .post_public("/unseal/reset", serde_json::json!({ "share": key }))
+            .await?;
+
+        return output::print_value(output, &status);
+    }
+
+    let body = if shamir {
+        serde_json::json!({ "share": key })
+    } else {
+        serde_json::json!({ "passphrase": key })
+    };
+
+    let status: UnsealResponse = client.post_public("/unseal", body).await?;
+
+    output::print_value(output, &status)
+}
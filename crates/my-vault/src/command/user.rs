@@ -0,0 +1,98 @@
+use crate::client::VaultClient;
+use crate::prompt;
+
+/// Arguments for login and register.
+#[derive(clap::Args, Debug)]
+pub struct LoginArgs {
+    /// Username, asked for when not given.
+    #[arg(short, long, env = "MY_VAULT_USERNAME")]
+    username: Option<String>,
+
+    /// Read password from the first line of stdin instead of asking for it.
+    #[arg(long)]
+    password_stdin: bool,
+}
+
+impl LoginArgs {
+    fn credentials(self, confirm: bool) -> anyhow::Result<(String, String)> {
+        let username = match self.username {
+            Some(x) => x,
+            None => prompt::read_line("Username: ")?,
+        };
+
+        let password = if self.password_stdin {
+            prompt::read_line("")?
+        } else {
+            let password = prompt::read_password("Password: ")?;
+
+            if confirm && prompt::read_password("Confirm password: ")? != password {
+                anyhow::bail!("passwords do not match");
+            }
+
+            password
+        };
+
+        Ok((username, password))
+    }
+}
+
+pub async fn login(client: &VaultClient, args: LoginArgs) -> anyhow::Result<()> {
+    let (username, password) = args.credentials(false)?;
+
+    // The daemon asks for a TOTP code once the password is correct.
+    match client.login(username.clone(), password.clone(), None).await {
+        Err(e) if e.status() == Some(401) => {
+            let totp = prompt::read_line("TOTP or recovery code: ")?;
+
+            client.login(username.clone(), password, Some(totp)).await?;
+        }
+        x => x?,
+    }
+
+    eprintln!("logged in as {}", username);
+
+    Ok(())
+}
+
+/// Arguments for register.
+#[derive(clap::Args, Debug)]
+pub struct RegisterArgs {
+    #[command(flatten)]
+    login: LoginArgs,
+
+    /// Invite code, or the setup token of the daemon when registering the first admin.
+    #[arg(long, env = "MY_VAULT_INVITE", hide_env_values = true)]
+    invite: Option<String>,
+}
+
+pub async fn register(client: &VaultClient, args: RegisterArgs) -> anyhow::Result<()> {
+    let (username, password) = args.login.credentials(true)?;
+
+    client
+        .register(username.clone(), password, args.invite)
+        .await?;
+
+    eprintln!("registered and logged in as {}", username);
+
+    Ok(())
+}
+
+/// Arguments for logout.
+#[derive(clap::Args, Debug)]
+pub struct LogoutArgs {
+    /// Revoke every login of the user, not only the cached one.
+    #[arg(long)]
+    all: bool,
+}
+
+pub async fn logout(client: &VaultClient, args: LogoutArgs) -> anyhow::Result<()> {
+    client.logout(args.all).await?;
+
+    if args.all {
+        eprintln!("logged out everywhere");
+    } else {
+        eprintln!("logged out");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+/// Name of folder for configs, logging, temp, etc.
+/// Shared with the daemon so both find the same socket.
+pub const FOLDER_NAME: &str = "my-vault-rs";
+
+/// Name of file the login tokens are cached in.
+pub const TOKEN_FILE_NAME: &str = "token.json";
+
+use std::path::PathBuf;
+
+lazy_static::lazy_static! {
+    /// Global cache path.
+    pub static ref GLOBAL_CACHE_PATH: PathBuf = {
+        dirs::cache_dir()
+            .expect("could not resolve cache directory for current system")
+            .join(FOLDER_NAME)
+    };
+
+    /// Unix socket the daemon listens on.
+    pub static ref DAEMON_SOCKET_PATH: PathBuf = {
+        PathBuf::from("/tmp")
+            .join(FOLDER_NAME)
+            .join("daemon.sock")
+    };
+}
+
+/// Create folders if they don't exist yet
+pub async fn create_global_paths() -> Result<(), tokio::io::Error> {
+    tokio::fs::create_dir_all(GLOBAL_CACHE_PATH.as_path()).await
+}
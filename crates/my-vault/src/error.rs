@@ -0,0 +1,31 @@
+/// Client Errors
+#[derive(thiserror::Error, Debug)]
+pub enum ClientError {
+    #[error("could not connect to daemon at {0}: {1}")]
+    Connect(String, std::io::Error),
+
+    #[error("io error {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("invalid response from daemon: {0}")]
+    InvalidResponse(String),
+
+    #[error("json error {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("not logged in, run `my-vault login` first")]
+    NotLoggedIn,
+
+    #[error("{message} ({status})")]
+    Status { status: u16, message: String },
+}
+
+impl ClientError {
+    /// Status code returned by the daemon, if any.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::Status { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+}
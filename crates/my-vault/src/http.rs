@@ -0,0 +1,305 @@
+use crate::error::ClientError;
+
+use std::fmt;
+use std::path::PathBuf;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Where the daemon is listening.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Endpoint {
+    Unix(PathBuf),
+    Tcp(String),
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unix(x) => write!(f, "unix:{}", x.display()),
+            Self::Tcp(x) => write!(f, "tcp:{}", x),
+        }
+    }
+}
+
+/// Http method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Post,
+    Put,
+    Delete,
+}
+
+impl Method {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Get => "GET",
+            Self::Post => "POST",
+            Self::Put => "PUT",
+            Self::Delete => "DELETE",
+        }
+    }
+}
+
+/// Response from the daemon.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// Send a single request to the daemon.
+///
+/// Every request uses a new connection which is closed by the daemon once the response
+/// has been written, so the response is read until the end of the stream.
+pub async fn send(
+    endpoint: &Endpoint,
+    method: Method,
+    path: &str,
+    token: Option<&str>,
+    body: Option<&serde_json::Value>,
+) -> Result<Response, ClientError> {
+    let request = build_request(method, path, token, body)?;
+
+    tracing::debug!("{} {} {}", endpoint, method.as_str(), path);
+
+    match endpoint {
+        #[cfg(unix)]
+        Endpoint::Unix(x) => {
+            let stream = tokio::net::UnixStream::connect(x)
+                .await
+                .map_err(|e| ClientError::Connect(endpoint.to_string(), e))?;
+
+            exchange(stream, &request).await
+        }
+        #[cfg(not(unix))]
+        Endpoint::Unix(_) => Err(ClientError::Connect(
+            endpoint.to_string(),
+            std::io::Error::from(std::io::ErrorKind::Unsupported),
+        )),
+        Endpoint::Tcp(x) => {
+            let stream = tokio::net::TcpStream::connect(x)
+                .await
+                .map_err(|e| ClientError::Connect(endpoint.to_string(), e))?;
+
+            exchange(stream, &request).await
+        }
+    }
+}
+
+async fn exchange<S>(mut stream: S, request: &[u8]) -> Result<Response, ClientError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream.write_all(request).await?;
+    stream.flush().await?;
+
+    let mut data = Vec::new();
+    stream.read_to_end(&mut data).await?;
+
+    parse_response(&data)
+}
+
+fn build_request(
+    method: Method,
+    path: &str,
+    token: Option<&str>,
+    body: Option<&serde_json::Value>,
+) -> Result<Vec<u8>, ClientError> {
+    let body = match body {
+        Some(x) => serde_json::to_vec(x)?,
+        None => Vec::new(),
+    };
+
+    let mut head = format!(
+        "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nAccept: application/json\r\n",
+        method.as_str(),
+        path
+    );
+
+    if let Some(token) = token {
+        head.push_str(&format!("Authorization: Bearer {}\r\n", token));
+    }
+
+    if !body.is_empty() {
+        head.push_str("Content-Type: application/json\r\n");
+    }
+
+    head.push_str(&format!("Content-Length: {}\r\n\r\n", body.len()));
+
+    let mut request = head.into_bytes();
+    request.extend(body);
+
+    Ok(request)
+}
+
+fn parse_response(data: &[u8]) -> Result<Response, ClientError> {
+    let mut headers = [httparse::EMPTY_HEADER; 64];
+    let mut response = httparse::Response::new(&mut headers);
+
+    let offset = match response
+        .parse(data)
+        .map_err(|e| ClientError::InvalidResponse(e.to_string()))?
+    {
+        httparse::Status::Complete(x) => x,
+        httparse::Status::Partial => {
+            return Err(ClientError::InvalidResponse("incomplete headers".into()));
+        }
+    };
+
+    let status = response
+        .code
+        .ok_or(ClientError::InvalidResponse("missing status".into()))?;
+
+    let header = |name: &str| {
+        response
+            .headers
+            .iter()
+            .find(|x| x.name.eq_ignore_ascii_case(name))
+            .map(|x| String::from_utf8_lossy(x.value).to_lowercase())
+    };
+
+    let body = &data[offset..];
+
+    let body = if header("transfer-encoding").is_some_and(|x| x.contains("chunked")) {
+        decode_chunked(body)?
+    } else if let Some(length) = header("content-length") {
+        let length = length
+            .trim()
+            .parse::<usize>()
+            .map_err(|e| ClientError::InvalidResponse(e.to_string()))?;
+
+        body.get(..length)
+            .ok_or(ClientError::InvalidResponse("incomplete body".into()))?
+            .to_vec()
+    } else {
+        body.to_vec()
+    };
+
+    Ok(Response { status, body })
+}
+
+fn decode_chunked(mut data: &[u8]) -> Result<Vec<u8>, ClientError> {
+    let mut body = Vec::new();
+
+    loop {
+        let line_end = data
+            .windows(2)
+            .position(|x| x == b"\r\n")
+            .ok_or(ClientError::InvalidResponse("incomplete chunk".into()))?;
+
+        // Chunk extensions after `;` are ignored.
+        let line = String::from_utf8_lossy(&data[..line_end]);
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16)
+            .map_err(|e| ClientError::InvalidResponse(e.to_string()))?;
+
+        data = &data[line_end + 2..];
+
+        if size == 0 {
+            return Ok(body);
+        }
+
+        let chunk = data
+            .get(..size)
+            .ok_or(ClientError::InvalidResponse("incomplete chunk".into()))?;
+
+        body.extend_from_slice(chunk);
+        data = data.get(size + 2..).unwrap_or_default();
+    }
+}
+
+/// Percent encode text so it can be used as a path segment or query value.
+pub fn encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Endpoint, Method};
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn parse_response() {
+        let data = b"HTTP/1.1 200 OK\r\ncontent-length: 13\r\n\r\n{\"ok\": true}\n";
+        let result_1 = super::parse_response(data).unwrap();
+
+        assert_eq!(result_1.status, 200);
+        assert_eq!(result_1.body, b"{\"ok\": true}\n");
+
+        let data = b"HTTP/1.1 404 Not Found\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nnot \r\n5;x=y\r\nfound\r\n0\r\n\r\n";
+        let result_2 = super::parse_response(data).unwrap();
+
+        assert_eq!(result_2.status, 404);
+        assert_eq!(result_2.body, b"not found");
+
+        // Missing body should error
+        let data = b"HTTP/1.1 200 OK\r\ncontent-length: 13\r\n\r\n{}";
+        let result_err_1 = super::parse_response(data);
+
+        assert!(result_err_1.is_err());
+    }
+
+    #[tokio::test]
+    async fn encode() {
+        assert_eq!(super::encode("api-key_1.0~"), "api-key_1.0~");
+        assert_eq!(super::encode("a b/c&d"), "a%20b%2Fc%26d");
+        assert_eq!(super::encode("ü"), "%C3%BC");
+    }
+
+    #[tokio::test]
+    async fn send() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = Endpoint::Tcp(listener.local_addr().unwrap().to_string());
+
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut data = vec![0; 1024];
+            let n = stream.read(&mut data).await.unwrap();
+
+            stream
+                .write_all(b"HTTP/1.1 201 Created\r\ncontent-length: 2\r\n\r\n{}")
+                .await
+                .unwrap();
+
+            String::from_utf8(data[..n].to_vec()).unwrap()
+        });
+
+        let body = serde_json::json!({"name": "api-key"});
+        let result_1 = super::send(
+            &endpoint,
+            Method::Post,
+            "/api/v1/secret/",
+            Some("token"),
+            Some(&body),
+        )
+        .await
+        .unwrap();
+
+        let request = server.await.unwrap();
+
+        assert_eq!(result_1.status, 201);
+        assert!(result_1.is_success());
+        assert!(request.starts_with("POST /api/v1/secret/ HTTP/1.1\r\n"));
+        assert!(request.contains("Authorization: Bearer token\r\n"));
+        assert!(request.contains("Content-Length: 18\r\n"));
+        assert!(request.ends_with("\r\n\r\n{\"name\":\"api-key\"}"));
+    }
+}
@@ -0,0 +1,56 @@
+mod client;
+mod command;
+mod constants;
+mod error;
+mod http;
+mod output;
+mod prompt;
+mod token;
+
+use crate::client::VaultClient;
+use crate::http::Endpoint;
+use crate::token::TokenCache;
+
+use clap::Parser;
+
+/// My Vault command line client.
+#[derive(clap::Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Connect to daemon over TCP instead of the unix socket.
+    /// This can be either an ipv4 or ipv6 address.
+    #[arg(short = 'a', long, env = "MY_VAULT_TCP_ADDRESS", global = true)]
+    tcp_address: Option<String>,
+
+    /// Output format.
+    #[arg(short, long, value_enum, default_value_t, global = true)]
+    output: output::OutputFormat,
+
+    #[command(subcommand)]
+    command: command::Command,
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run().await {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    }
+}
+
+async fn run() -> anyhow::Result<()> {
+    constants::create_global_paths().await?;
+    shared_core::sys::init_tracing_subscriber(&constants::GLOBAL_CACHE_PATH.join("cli"), false)?;
+
+    let args = Args::parse();
+
+    let endpoint = match args.tcp_address {
+        Some(x) => Endpoint::Tcp(x),
+        None => Endpoint::Unix(constants::DAEMON_SOCKET_PATH.to_path_buf()),
+    };
+
+    let cache = TokenCache::new(constants::GLOBAL_CACHE_PATH.join(constants::TOKEN_FILE_NAME));
+    let client = VaultClient::new(endpoint, cache);
+
+    args.command.run(&client, args.output).await
+}
@@ -0,0 +1,183 @@
+/// How results are printed.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+}
+
+/// Type which can be printed as a table row.
+pub trait Tabular {
+    fn headers() -> &'static [&'static str];
+
+    fn row(&self) -> Vec<String>;
+}
+
+/// Print a single item.
+pub fn print_item<T>(format: OutputFormat, item: &T) -> anyhow::Result<()>
+where
+    T: serde::Serialize + Tabular,
+{
+    match format {
+        OutputFormat::Table => {
+            // A single item reads better as a list of fields.
+            let rows = T::headers()
+                .iter()
+                .zip(item.row())
+                .map(|(k, v)| vec![k.to_string(), v])
+                .collect::<Vec<_>>();
+
+            print!("{}", render_table(&[], &rows));
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(item)?),
+    }
+
+    Ok(())
+}
+
+/// Print a list of items.
+/// The cursor for the next page is printed after the table when there are more items.
+pub fn print_list<T>(
+    format: OutputFormat,
+    items: &[T],
+    next_cursor: Option<&str>,
+) -> anyhow::Result<()>
+where
+    T: serde::Serialize + Tabular,
+{
+    match format {
+        OutputFormat::Table => {
+            let rows = items.iter().map(Tabular::row).collect::<Vec<_>>();
+
+            print!("{}", render_table(T::headers(), &rows));
+
+            if let Some(next_cursor) = next_cursor {
+                eprintln!("more results, continue with --cursor {}", next_cursor);
+            }
+        }
+        OutputFormat::Json => {
+            let value = serde_json::json!({ "items": items, "next_cursor": next_cursor });
+            println!("{}", serde_json::to_string_pretty(&value)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Print any serializable value, tables print each field on its own row.
+pub fn print_value<T>(format: OutputFormat, value: &T) -> anyhow::Result<()>
+where
+    T: serde::Serialize,
+{
+    match format {
+        OutputFormat::Table => {
+            let rows = match serde_json::to_value(value)? {
+                serde_json::Value::Object(x) => x
+                    .into_iter()
+                    .map(|(k, v)| vec![k, display_value(&v)])
+                    .collect(),
+                x => vec![vec![display_value(&x)]],
+            };
+
+            print!("{}", render_table(&[], &rows));
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value)?),
+    }
+
+    Ok(())
+}
+
+/// Render rows with every column padded to the same width.
+/// Headers are left out when empty.
+pub fn render_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let columns = rows
+        .iter()
+        .map(Vec::len)
+        .chain([headers.len()])
+        .max()
+        .unwrap_or_default();
+
+    let mut widths = vec![0; columns];
+
+    for (i, header) in headers.iter().enumerate() {
+        widths[i] = header.chars().count();
+    }
+
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(cell.chars().count());
+        }
+    }
+
+    let mut table = String::new();
+
+    let mut push_row = |cells: &[String]| {
+        let line = cells
+            .iter()
+            .enumerate()
+            .map(|(i, x)| format!("{:width$}", x, width = widths[i]))
+            .collect::<Vec<_>>()
+            .join("  ");
+
+        table.push_str(line.trim_end());
+        table.push('\n');
+    };
+
+    if !headers.is_empty() {
+        push_row(&headers.iter().map(|x| x.to_uppercase()).collect::<Vec<_>>());
+    }
+
+    for row in rows {
+        push_row(row);
+    }
+
+    table
+}
+
+/// Display optional cell value, missing values are shown as `-`.
+pub fn display_option<T>(value: &Option<T>) -> String
+where
+    T: ToString,
+{
+    value
+        .as_ref()
+        .map(ToString::to_string)
+        .unwrap_or_else(|| "-".into())
+}
+
+fn display_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => "-".into(),
+        serde_json::Value::String(x) => x.clone(),
+        x => x.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn render_table() {
+        let rows = vec![
+            vec!["api-key".to_string(), "key".to_string()],
+            vec!["db".to_string(), "cipher".to_string()],
+        ];
+
+        let result_1 = super::render_table(&["name", "type"], &rows);
+
+        assert_eq!(result_1, "NAME     TYPE\napi-key  key\ndb       cipher\n");
+
+        let result_2 = super::render_table(&[], &rows[1..]);
+
+        assert_eq!(result_2, "db  cipher\n");
+
+        let result_3 = super::render_table(&["name"], &[]);
+
+        assert_eq!(result_3, "NAME\n");
+    }
+
+    #[tokio::test]
+    async fn display_option() {
+        assert_eq!(super::display_option(&Some("a")), "a");
+        assert_eq!(super::display_option::<String>(&None), "-");
+    }
+}
@@ -0,0 +1,80 @@
+use std::io::{BufRead, Write};
+
+/// Ask for a line of input on stderr.
+pub fn read_line(prompt: &str) -> std::io::Result<String> {
+    eprint!("{}", prompt);
+    std::io::stderr().flush()?;
+
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line)?;
+
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Ask for a line of input without echoing it back.
+///
+/// Echo is only turned off when stdin is a terminal, piped input is read as is.
+pub fn read_password(prompt: &str) -> std::io::Result<String> {
+    #[cfg(unix)]
+    {
+        let echo = EchoGuard::disable();
+        let line = read_line(prompt);
+
+        // The newline typed by the user was not echoed either.
+        if echo.termios.is_some() {
+            eprintln!();
+        }
+
+        line
+    }
+
+    #[cfg(not(unix))]
+    {
+        read_line(prompt)
+    }
+}
+
+/// Turns off terminal echo of stdin until dropped.
+#[cfg(unix)]
+struct EchoGuard {
+    termios: Option<libc::termios>,
+}
+
+#[cfg(unix)]
+impl EchoGuard {
+    fn disable() -> Self {
+        // SAFETY: termios is plain data which is fully written by tcgetattr before it is used.
+        unsafe {
+            if libc::isatty(libc::STDIN_FILENO) != 1 {
+                return Self { termios: None };
+            }
+
+            let mut termios = std::mem::zeroed::<libc::termios>();
+
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut termios) != 0 {
+                return Self { termios: None };
+            }
+
+            let mut silent = termios;
+            silent.c_lflag &= !libc::ECHO;
+
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &silent);
+
+            Self {
+                termios: Some(termios),
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for EchoGuard {
+    fn drop(&mut self) {
+        if let Some(termios) = &self.termios {
+            // SAFETY: restores the settings read in `disable`.
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, termios);
+            }
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+
+/// Tokens from the last login.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Token {
+    /// Daemon the tokens were issued by.
+    pub endpoint: String,
+    pub username: String,
+    pub token_auth: String,
+    pub token_refresh: String,
+}
+
+/// Token cache file.
+///
+/// The file is only readable by the current user since the tokens give full access
+/// to the vault.
+#[derive(Clone, Debug)]
+pub struct TokenCache {
+    path: PathBuf,
+}
+
+impl TokenCache {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Load cached token, missing or unreadable caches count as logged out.
+    pub async fn load(&self) -> Option<Token> {
+        let data = tokio::fs::read(&self.path).await.ok()?;
+
+        match serde_json::from_slice(&data) {
+            Ok(x) => Some(x),
+            Err(e) => {
+                tracing::warn!("ignoring invalid token cache {}: {e}", self.path.display());
+                None
+            }
+        }
+    }
+
+    pub async fn save(&self, token: &Token) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let data = serde_json::to_vec_pretty(token)?;
+
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+
+        #[cfg(unix)]
+        options.mode(0o600);
+
+        let mut file = options.open(&self.path).await?;
+
+        tokio::io::AsyncWriteExt::write_all(&mut file, &data).await?;
+
+        Ok(())
+    }
+
+    pub async fn remove(&self) -> std::io::Result<()> {
+        match tokio::fs::remove_file(&self.path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Token, TokenCache};
+
+    use shared_core::rng;
+    use std::path::PathBuf;
+
+    #[tokio::test]
+    async fn cache() {
+        // Create random file in test data folder
+        let path = PathBuf::from(env!("WORKSPACE_DIR"))
+            .join("test-data")
+            .join("temp")
+            .join(format!("{}.json", rng::random_bytes_str(10)));
+
+        let cache = TokenCache::new(path.clone());
+
+        assert_eq!(cache.load().await, None);
+
+        let token = Token {
+            endpoint: "tcp:127.0.0.1:10001".into(),
+            username: "john".into(),
+            token_auth: "auth".into(),
+            token_refresh: "refresh".into(),
+        };
+
+        assert!(cache.save(&token).await.is_ok());
+        assert_eq!(cache.load().await, Some(token));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let metadata = tokio::fs::metadata(&path).await.unwrap();
+
+            assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+        }
+
+        assert!(cache.remove().await.is_ok());
+        assert!(cache.remove().await.is_ok());
+        assert_eq!(cache.load().await, None);
+    }
+}
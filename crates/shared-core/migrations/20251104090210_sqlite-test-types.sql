@@ -0,0 +1,12 @@
+-- Add table with every column type crud has to handle
+CREATE TABLE my_types_table (
+    name        TEXT NOT NULL,
+    data        BLOB,
+    enabled     BOOLEAN NOT NULL,
+    count       INTEGER NOT NULL,
+    ratio       REAL NOT NULL,
+    note        TEXT,
+    created_at  TIMESTAMP,
+
+    PRIMARY KEY (name)
+);
@@ -0,0 +1,9 @@
+-- Add table whose rows are soft deleted
+CREATE TABLE my_soft_table (
+    name        TEXT NOT NULL,
+    password    TEXT NOT NULL,
+    deleted     BOOLEAN NOT NULL DEFAULT FALSE,
+    deleted_at  TIMESTAMP,
+
+    PRIMARY KEY (name)
+);
@@ -25,7 +25,23 @@ where
         // Serialize the current struct into a toml config file.
         let file_contents = toml::to_string_pretty(&self.0)?;
 
-        tokio::fs::write(config_path, file_contents).await?;
+        // Written next to the config first and renamed over it, so a failed save never leaves a
+        // truncated config behind. Configs can hold keys and are only readable by their owner.
+        let mut temp_path = config_path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+
+        #[cfg(unix)]
+        options.mode(0o600);
+
+        let mut file = options.open(&temp_path).await?;
+
+        tokio::io::AsyncWriteExt::write_all(&mut file, file_contents.as_bytes()).await?;
+        file.sync_all().await?;
+
+        tokio::fs::rename(&temp_path, config_path).await?;
 
         Ok(())
     }
@@ -0,0 +1,123 @@
+use crate::rng;
+
+use sha2::Digest;
+use subtle::ConstantTimeEq;
+
+/// Prefix of api keys, keeps them apart from JWTs and easy to spot when leaked.
+const API_KEY_PREFIX: &str = "mvk";
+
+/// Length of random secret part of api keys.
+const API_KEY_SECRET_LENGTH: usize = 40;
+
+/// Api key in the form `mvk_<id>_<secret>`.
+///
+/// The id is used to look up the key while only a hash of the secret is stored. Secrets are
+/// long and random, so a plain SHA-256 hash is used instead of a slow password hash.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ApiKey {
+    pub id: uuid::Uuid,
+    pub secret: String,
+}
+
+impl ApiKey {
+    /// Generate new secret for key with id.
+    pub fn generate(id: uuid::Uuid) -> Self {
+        Self {
+            id,
+            secret: rng::random_bytes_str(API_KEY_SECRET_LENGTH),
+        }
+    }
+
+    /// Parse key from token, returns `None` if the token is not an api key.
+    pub fn parse(token: &str) -> Option<Self> {
+        let mut parts = token.splitn(3, '_');
+
+        if parts.next()? != API_KEY_PREFIX {
+            return None;
+        }
+
+        let id = uuid::Uuid::try_parse(parts.next()?).ok()?;
+        let secret = parts.next()?;
+
+        if secret.len() != API_KEY_SECRET_LENGTH {
+            return None;
+        }
+
+        Some(Self {
+            id,
+            secret: secret.to_string(),
+        })
+    }
+
+    /// Hex encoded hash of secret.
+    pub fn hash(&self) -> String {
+        sha2::Sha256::digest(self.secret.as_bytes())
+            .iter()
+            .map(|x| format!("{:02x}", x))
+            .collect()
+    }
+
+    /// Checks if secret matches hash in constant time.
+    pub fn verify(&self, hash: &str) -> bool {
+        self.hash().as_bytes().ct_eq(hash.as_bytes()).into()
+    }
+}
+
+impl std::fmt::Display for ApiKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}_{}_{}",
+            API_KEY_PREFIX,
+            self.id.as_simple(),
+            self.secret
+        )
+    }
+}
+
+// Secrets must not end up in logs.
+impl std::fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiKey")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ApiKey;
+
+    #[tokio::test]
+    async fn parse() {
+        let key = ApiKey::generate(uuid::Uuid::new_v4());
+        let token = key.to_string();
+
+        assert!(token.starts_with("mvk_"));
+        assert_eq!(ApiKey::parse(&token), Some(key.clone()));
+
+        // JWTs and malformed keys are not api keys
+        assert_eq!(ApiKey::parse("eyJhbGciOiJSUzI1NiJ9.e30.c2ln"), None);
+        assert_eq!(ApiKey::parse("mvk_not-a-uuid_secret"), None);
+        assert_eq!(
+            ApiKey::parse(&format!("mvk_{}_short", key.id.as_simple())),
+            None
+        );
+        assert_eq!(ApiKey::parse(&token[..token.len() - 1]), None);
+
+        assert!(!format!("{:?}", key).contains(&key.secret));
+    }
+
+    #[tokio::test]
+    async fn verify() {
+        let key_1 = ApiKey::generate(uuid::Uuid::new_v4());
+        let key_2 = ApiKey::generate(key_1.id);
+
+        let hash = key_1.hash();
+
+        assert_eq!(hash.len(), 64);
+        assert!(key_1.verify(&hash));
+        assert!(!key_2.verify(&hash));
+        assert!(!key_1.verify(""));
+    }
+}
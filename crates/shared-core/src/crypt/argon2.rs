@@ -1,45 +1,68 @@
-use argon2::PasswordHasher;
+use argon2::{PasswordHasher, PasswordVerifier};
+use sha2::Digest;
 use std::sync::Arc;
 
 /// Default argon2 algorithm.
 const DEFAULT_ARGON2_ALGORITHM: argon2::Algorithm = argon2::Algorithm::Argon2id;
 
+/// Length of generated salts in bytes.
+const SALT_LENGTH: usize = 16;
+
 /// Argon2 factory
+///
+/// Passwords are stored as PHC strings which contain the salt and parameters they were hashed
+/// with. If a pepper is set it's mixed into every new hash as argon2 secret and its id is stored
+/// as `keyid` of the PHC string, hashes without a `keyid` are verified without pepper.
 #[derive(Debug)]
 pub struct Argon2Factory {
-    argon: Arc<argon2::Argon2<'static>>,
+    params: argon2::Params,
+    pepper: Option<Arc<[u8]>>,
 }
 
 impl Argon2Factory {
     /// Create new argon 2 factory.
     pub fn new(iters: u32, memory_mb: u32, parallelism: u32) -> Result<Self, argon2::Error> {
-        let memory_kb = memory_mb * (2 ^ 10);
+        let memory_kb = memory_mb.saturating_mul(1024);
 
         let params = argon2::Params::new(memory_kb, iters, parallelism, None)?;
-        let argon =
-            argon2::Argon2::new(DEFAULT_ARGON2_ALGORITHM, argon2::Version::default(), params);
 
         Ok(Self {
-            argon: Arc::new(argon),
+            params,
+            pepper: None,
         })
     }
 
-    /// Encode passphrase
-    pub async fn encode(
-        &self,
-        passphrase: &[u8],
-        salt: &[u8],
-    ) -> Result<String, crate::error::Error> {
-        let argon = self.argon.clone();
+    /// Mix secret pepper into new hashes.
+    pub fn with_pepper(mut self, pepper: &[u8]) -> Result<Self, argon2::Error> {
+        let mut params = argon2::ParamsBuilder::new();
+
+        params
+            .m_cost(self.params.m_cost())
+            .t_cost(self.params.t_cost())
+            .p_cost(self.params.p_cost())
+            .keyid(argon2::KeyId::new(&Self::pepper_id(pepper))?);
+
+        self.params = params.build()?;
+        self.pepper = Some(pepper.into());
+
+        Ok(self)
+    }
+
+    /// Hash password with a new random salt, returns a PHC string.
+    pub async fn hash(&self, password: &[u8]) -> Result<String, crate::error::Error> {
+        let params = self.params.clone();
+        let pepper = self.pepper.clone();
 
         tokio::task::spawn_blocking({
-            let passphrase = passphrase.to_vec();
-            let salt = argon2::password_hash::SaltString::encode_b64(salt)
-                .map_err(|_| crate::error::Error::Crypto)?;
+            let password = password.to_vec();
+            let salt = argon2::password_hash::SaltString::encode_b64(&crate::rng::random_bytes(
+                SALT_LENGTH,
+            ))
+            .map_err(|_| crate::error::Error::Crypto)?;
 
             move || {
-                argon
-                    .hash_password(&passphrase, &salt)
+                Self::argon(pepper.as_deref(), params)?
+                    .hash_password(&password, &salt)
                     .map(|x| x.to_string())
                     .map_err(|_| crate::error::Error::Crypto)
             }
@@ -47,6 +70,87 @@ impl Argon2Factory {
         .await
         .map_err(crate::error::Error::TaskJoin)?
     }
+
+    /// Verify password against PHC string, comparison of the hashes is constant-time.
+    ///
+    /// Hashes made with a pepper never match unless the same pepper is set.
+    pub async fn verify(&self, phc: &str, password: &[u8]) -> Result<bool, crate::error::Error> {
+        let pepper = self.pepper.clone();
+
+        tokio::task::spawn_blocking({
+            let phc = phc.to_string();
+            let password = password.to_vec();
+
+            move || {
+                // Invalid hashes never match any password.
+                let Ok(hash) = argon2::PasswordHash::new(&phc) else {
+                    return Ok(false);
+                };
+
+                let params =
+                    argon2::Params::try_from(&hash).map_err(|_| crate::error::Error::Crypto)?;
+
+                let pepper = match (params.keyid(), pepper.as_deref()) {
+                    ([], _) => None,
+                    (keyid, Some(pepper)) if keyid == Self::pepper_id(pepper) => Some(pepper),
+                    _ => return Ok(false),
+                };
+
+                match Self::argon(pepper, params)?.verify_password(&password, &hash) {
+                    Ok(_) => Ok(true),
+                    Err(argon2::password_hash::Error::Password) => Ok(false),
+                    Err(_) => Err(crate::error::Error::Crypto),
+                }
+            }
+        })
+        .await
+        .map_err(crate::error::Error::TaskJoin)?
+    }
+
+    /// Checks if PHC string was hashed with weaker parameters or another pepper than set.
+    pub fn needs_rehash(&self, phc: &str) -> bool {
+        let Some(params) = argon2::PasswordHash::new(phc)
+            .ok()
+            .and_then(|x| argon2::Params::try_from(&x).ok())
+        else {
+            return true;
+        };
+
+        params.m_cost() < self.params.m_cost()
+            || params.t_cost() < self.params.t_cost()
+            || params.p_cost() < self.params.p_cost()
+            || params.keyid() != self.params.keyid()
+    }
+
+    fn argon(
+        pepper: Option<&[u8]>,
+        params: argon2::Params,
+    ) -> Result<argon2::Argon2<'_>, crate::error::Error> {
+        match pepper {
+            Some(x) => argon2::Argon2::new_with_secret(
+                x,
+                DEFAULT_ARGON2_ALGORITHM,
+                argon2::Version::default(),
+                params,
+            )
+            .map_err(|_| crate::error::Error::Crypto),
+            None => Ok(argon2::Argon2::new(
+                DEFAULT_ARGON2_ALGORITHM,
+                argon2::Version::default(),
+                params,
+            )),
+        }
+    }
+
+    /// Id of pepper stored in hashes, the pepper itself can't be derived from it.
+    fn pepper_id(pepper: &[u8]) -> [u8; argon2::Params::MAX_KEYID_LEN] {
+        let digest = sha2::Sha256::digest(pepper);
+        let mut id = [0; argon2::Params::MAX_KEYID_LEN];
+
+        id.copy_from_slice(&digest[..argon2::Params::MAX_KEYID_LEN]);
+
+        id
+    }
 }
 
 impl Default for Argon2Factory {
@@ -57,7 +161,9 @@ impl Default for Argon2Factory {
 
 #[cfg(test)]
 mod tests {
-    use crate::rng;
+    use argon2::PasswordHasher;
+
+    const PASSPHRASE: &str = "hello world!";
 
     #[tokio::test]
     async fn invalid_args() {
@@ -79,22 +185,116 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn encode() {
-        let argon = super::Argon2Factory::new(2, 32, 2);
+    async fn verify() {
+        let argon = super::Argon2Factory::new(2, 32, 2).unwrap();
+
+        let result_1 = argon.hash(PASSPHRASE.as_bytes()).await.unwrap();
+        let result_2 = argon.hash(PASSPHRASE.as_bytes()).await.unwrap();
+
+        // Every hash gets its own salt
+        assert_ne!(result_1, result_2);
+        assert!(result_1.starts_with("$argon2id$v=19$m=32768,t=2,p=2$"));
+
+        assert!(
+            argon
+                .verify(&result_1, PASSPHRASE.as_bytes())
+                .await
+                .unwrap()
+        );
+        assert!(
+            argon
+                .verify(&result_2, PASSPHRASE.as_bytes())
+                .await
+                .unwrap()
+        );
+        assert!(!argon.verify(&result_1, b"hello world").await.unwrap());
+        assert!(
+            !argon
+                .verify("not a hash", PASSPHRASE.as_bytes())
+                .await
+                .unwrap()
+        );
+
+        // Parameters are read from the hash
+        let argon_weak = super::Argon2Factory::new(1, 1, 1).unwrap();
+        let result_3 = argon_weak.hash(PASSPHRASE.as_bytes()).await.unwrap();
+
+        assert!(
+            argon
+                .verify(&result_3, PASSPHRASE.as_bytes())
+                .await
+                .unwrap()
+        );
+        assert!(argon.needs_rehash(&result_3));
+        assert!(!argon.needs_rehash(&result_1));
+        assert!(!argon_weak.needs_rehash(&result_1));
+    }
+
+    #[tokio::test]
+    async fn verify_legacy() {
+        // Hashes used to be made from a salt stored next to the hash
+        let salt = argon2::password_hash::SaltString::encode_b64(b"1111111111111111").unwrap();
+        let params = argon2::Params::new(256, 2, 2, None).unwrap();
+        let legacy = argon2::Argon2::new(
+            argon2::Algorithm::Argon2id,
+            argon2::Version::default(),
+            params,
+        )
+        .hash_password(PASSPHRASE.as_bytes(), &salt)
+        .unwrap()
+        .to_string();
+
+        let argon = super::Argon2Factory::default();
 
-        assert!(argon.is_ok());
+        assert!(argon.verify(&legacy, PASSPHRASE.as_bytes()).await.unwrap());
+        assert!(!argon.verify(&legacy, b"hello world").await.unwrap());
+        assert!(argon.needs_rehash(&legacy));
+    }
+
+    #[tokio::test]
+    async fn pepper() {
+        let argon = super::Argon2Factory::default();
+        let argon_pepper_1 = super::Argon2Factory::default()
+            .with_pepper(b"pepper-1")
+            .unwrap();
+        let argon_pepper_2 = super::Argon2Factory::default()
+            .with_pepper(b"pepper-2")
+            .unwrap();
 
-        let argon = argon.unwrap();
+        let result_1 = argon.hash(PASSPHRASE.as_bytes()).await.unwrap();
+        let result_2 = argon_pepper_1.hash(PASSPHRASE.as_bytes()).await.unwrap();
 
-        const PASSPHRASE: &str = "hello world!";
+        assert!(result_2.contains(",keyid="));
 
-        let salt_1 = b"my salt sucks";
-        let salt_2 = rng::random_bytes(16);
+        // Hashes without pepper are still verified but need a rehash
+        assert!(
+            argon_pepper_1
+                .verify(&result_1, PASSPHRASE.as_bytes())
+                .await
+                .unwrap()
+        );
+        assert!(argon_pepper_1.needs_rehash(&result_1));
 
-        let result_1 = argon.encode(PASSPHRASE.as_bytes(), salt_1).await;
-        let result_2 = argon.encode(PASSPHRASE.as_bytes(), salt_2.as_slice()).await;
+        assert!(
+            argon_pepper_1
+                .verify(&result_2, PASSPHRASE.as_bytes())
+                .await
+                .unwrap()
+        );
+        assert!(!argon_pepper_1.needs_rehash(&result_2));
 
-        assert!(result_1.is_ok());
-        assert!(result_2.is_ok());
+        // Peppered hashes never match without the same pepper
+        assert!(
+            !argon
+                .verify(&result_2, PASSPHRASE.as_bytes())
+                .await
+                .unwrap()
+        );
+        assert!(
+            !argon_pepper_2
+                .verify(&result_2, PASSPHRASE.as_bytes())
+                .await
+                .unwrap()
+        );
     }
 }
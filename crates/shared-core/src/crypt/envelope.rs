@@ -0,0 +1,395 @@
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use base64::Engine;
+use rand::RngCore;
+use sha2::Digest;
+use zeroize::Zeroizing;
+
+/// Length of master keys and data keys in bytes.
+const KEY_LENGTH: usize = 32;
+
+/// Version of sealed values, bumped if their format ever changes.
+const SEALED_VERSION: &str = "v1";
+
+/// Associated data binding wrapped data keys to their purpose.
+const DATA_KEY_AAD: &[u8] = b"my-vault data key";
+
+/// Length of master key ids in bytes.
+const MASTER_KEY_ID_LENGTH: usize = 8;
+
+/// Authenticated cipher used for envelope encryption.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum EnvelopeCipher {
+    /// AES-256 in Galois/Counter mode with 96 bit random nonces
+    #[default]
+    #[serde(rename = "aes-256-gcm")]
+    Aes256Gcm,
+    /// XChaCha20-Poly1305 with 192 bit random nonces
+    #[serde(rename = "xchacha20-poly1305")]
+    XChaCha20Poly1305,
+}
+
+impl EnvelopeCipher {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Aes256Gcm => "aes-256-gcm",
+            Self::XChaCha20Poly1305 => "xchacha20-poly1305",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "aes-256-gcm" => Some(Self::Aes256Gcm),
+            "xchacha20-poly1305" => Some(Self::XChaCha20Poly1305),
+            _ => None,
+        }
+    }
+
+    fn nonce_length(&self) -> usize {
+        match self {
+            Self::Aes256Gcm => 12,
+            Self::XChaCha20Poly1305 => 24,
+        }
+    }
+
+    fn encrypt(
+        &self,
+        key: &[u8; KEY_LENGTH],
+        nonce: &[u8],
+        plaintext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, crate::error::Error> {
+        let payload = Payload {
+            msg: plaintext,
+            aad,
+        };
+
+        match self {
+            Self::Aes256Gcm => aes_gcm::Aes256Gcm::new(key.into())
+                .encrypt(&nonce_array::<12>(nonce)?.into(), payload),
+            Self::XChaCha20Poly1305 => chacha20poly1305::XChaCha20Poly1305::new(key.into())
+                .encrypt(&nonce_array::<24>(nonce)?.into(), payload),
+        }
+        .map_err(|_| crate::error::Error::Crypto)
+    }
+
+    fn decrypt(
+        &self,
+        key: &[u8; KEY_LENGTH],
+        nonce: &[u8],
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, crate::error::Error> {
+        let payload = Payload {
+            msg: ciphertext,
+            aad,
+        };
+
+        match self {
+            Self::Aes256Gcm => aes_gcm::Aes256Gcm::new(key.into())
+                .decrypt(&nonce_array::<12>(nonce)?.into(), payload),
+            Self::XChaCha20Poly1305 => chacha20poly1305::XChaCha20Poly1305::new(key.into())
+                .decrypt(&nonce_array::<24>(nonce)?.into(), payload),
+        }
+        .map_err(|_| crate::error::Error::Crypto)
+    }
+}
+
+impl std::fmt::Display for EnvelopeCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Master key wrapping data keys.
+///
+/// The master key never touches the database, only data keys wrapped by it are stored. Losing
+/// it means losing every value encrypted with its data keys.
+pub struct MasterKey {
+    key: Zeroizing<[u8; KEY_LENGTH]>,
+}
+
+impl MasterKey {
+    /// Generate new random key.
+    pub fn generate() -> Self {
+        Self { key: random_key() }
+    }
+
+    /// Parse base64 encoded key, returns `None` if it's not a valid key.
+    pub fn from_base64(value: &str) -> Option<Self> {
+        let bytes = Zeroizing::new(
+            base64::engine::general_purpose::STANDARD
+                .decode(value.trim())
+                .ok()?,
+        );
+
+        let mut key = Zeroizing::new([0; KEY_LENGTH]);
+        key.copy_from_slice(
+            bytes
+                .get(..KEY_LENGTH)
+                .filter(|_| bytes.len() == KEY_LENGTH)?,
+        );
+
+        Some(Self { key })
+    }
+
+    /// Base64 encoded key as written to key files.
+    pub fn to_base64(&self) -> Zeroizing<String> {
+        Zeroizing::new(base64::engine::general_purpose::STANDARD.encode(self.key.as_slice()))
+    }
+
+    /// Hex encoded id of key, derived from the key so it tells which key wrapped a data key
+    /// without revealing it.
+    pub fn id(&self) -> String {
+        sha2::Sha256::digest(self.key.as_slice())
+            .iter()
+            .take(MASTER_KEY_ID_LENGTH)
+            .map(|x| format!("{:02x}", x))
+            .collect()
+    }
+
+    /// Wrap data key so it can be stored.
+    pub fn wrap(
+        &self,
+        data_key: &DataKey,
+        cipher: EnvelopeCipher,
+    ) -> Result<String, crate::error::Error> {
+        seal(&self.key, cipher, data_key.key.as_slice(), DATA_KEY_AAD)
+    }
+
+    /// Unwrap data key wrapped by this key.
+    pub fn unwrap(&self, wrapped: &str) -> Result<DataKey, crate::error::Error> {
+        let bytes = Zeroizing::new(open(&self.key, wrapped, DATA_KEY_AAD)?);
+
+        if bytes.len() != KEY_LENGTH {
+            return Err(crate::error::Error::Crypto);
+        }
+
+        let mut key = Zeroizing::new([0; KEY_LENGTH]);
+        key.copy_from_slice(&bytes);
+
+        Ok(DataKey { key })
+    }
+
+    /// Unwrap data key wrapped by `previous` and wrap it with this key, used when the master
+    /// key rotates. The data key itself stays the same so values don't need to be re-encrypted.
+    pub fn rewrap(
+        &self,
+        previous: &MasterKey,
+        wrapped: &str,
+        cipher: EnvelopeCipher,
+    ) -> Result<String, crate::error::Error> {
+        self.wrap(&previous.unwrap(wrapped)?, cipher)
+    }
+}
+
+// Keys must not end up in logs.
+impl std::fmt::Debug for MasterKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MasterKey")
+            .field("id", &self.id())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Data key encrypting values, stored wrapped by a [`MasterKey`].
+pub struct DataKey {
+    key: Zeroizing<[u8; KEY_LENGTH]>,
+}
+
+impl DataKey {
+    /// Generate new random key.
+    pub fn generate() -> Self {
+        Self { key: random_key() }
+    }
+
+    /// Encrypt value, `aad` is authenticated but not encrypted and must be given again to
+    /// decrypt. It binds the value to its context, e.g. the row it is stored in.
+    pub fn encrypt(
+        &self,
+        cipher: EnvelopeCipher,
+        plaintext: &[u8],
+        aad: &[u8],
+    ) -> Result<String, crate::error::Error> {
+        seal(&self.key, cipher, plaintext, aad)
+    }
+
+    /// Decrypt value encrypted with this key, the cipher is read from the value.
+    pub fn decrypt(&self, sealed: &str, aad: &[u8]) -> Result<Vec<u8>, crate::error::Error> {
+        open(&self.key, sealed, aad)
+    }
+}
+
+impl std::fmt::Debug for DataKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DataKey").finish_non_exhaustive()
+    }
+}
+
+fn nonce_array<const N: usize>(nonce: &[u8]) -> Result<[u8; N], crate::error::Error> {
+    nonce.try_into().map_err(|_| crate::error::Error::Crypto)
+}
+
+fn random_key() -> Zeroizing<[u8; KEY_LENGTH]> {
+    let mut key = Zeroizing::new([0; KEY_LENGTH]);
+
+    rand::thread_rng().fill_bytes(key.as_mut_slice());
+
+    key
+}
+
+/// Encrypt with a random nonce, sealed values look like `v1:<cipher>:<base64 nonce and ciphertext>`.
+fn seal(
+    key: &[u8; KEY_LENGTH],
+    cipher: EnvelopeCipher,
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<String, crate::error::Error> {
+    let mut data = vec![0; cipher.nonce_length()];
+
+    rand::thread_rng().fill_bytes(&mut data);
+
+    let ciphertext = cipher.encrypt(key, &data, plaintext, aad)?;
+    data.extend_from_slice(&ciphertext);
+
+    Ok(format!(
+        "{}:{}:{}",
+        SEALED_VERSION,
+        cipher.name(),
+        base64::engine::general_purpose::STANDARD.encode(data)
+    ))
+}
+
+/// Decrypt value sealed with [`seal`].
+fn open(key: &[u8; KEY_LENGTH], sealed: &str, aad: &[u8]) -> Result<Vec<u8>, crate::error::Error> {
+    let mut parts = sealed.splitn(3, ':');
+
+    if parts.next() != Some(SEALED_VERSION) {
+        return Err(crate::error::Error::Crypto);
+    }
+
+    let cipher = parts
+        .next()
+        .and_then(EnvelopeCipher::from_name)
+        .ok_or(crate::error::Error::Crypto)?;
+
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(parts.next().ok_or(crate::error::Error::Crypto)?)
+        .map_err(|_| crate::error::Error::Crypto)?;
+
+    if data.len() < cipher.nonce_length() {
+        return Err(crate::error::Error::Crypto);
+    }
+
+    let (nonce, ciphertext) = data.split_at(cipher.nonce_length());
+
+    cipher.decrypt(key, nonce, ciphertext, aad)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DataKey, EnvelopeCipher, MasterKey};
+
+    fn hex(value: &str) -> Vec<u8> {
+        (0..value.len())
+            .step_by(2)
+            .map(|x| u8::from_str_radix(&value[x..x + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn cipher() {
+        // AES-256-GCM test cases 13 and 14 of the GCM specification
+        let key = [0; 32];
+        let nonce = [0; 12];
+
+        assert_eq!(
+            EnvelopeCipher::Aes256Gcm
+                .encrypt(&key, &nonce, &[], &[])
+                .unwrap(),
+            hex("530f8afbc74536b9a963b4f1c4cb738b")
+        );
+        assert_eq!(
+            EnvelopeCipher::Aes256Gcm
+                .encrypt(&key, &nonce, &[0; 16], &[])
+                .unwrap(),
+            hex("cea7403d4d606b6e074ec5d3baf39d18d0d1c8a799996bf0265b98b5d48ab919")
+        );
+
+        for cipher in [EnvelopeCipher::Aes256Gcm, EnvelopeCipher::XChaCha20Poly1305] {
+            let nonce = vec![7; cipher.nonce_length()];
+            let ciphertext = cipher.encrypt(&key, &nonce, b"secret", b"aad").unwrap();
+
+            assert_eq!(
+                cipher.decrypt(&key, &nonce, &ciphertext, b"aad").unwrap(),
+                b"secret"
+            );
+            assert!(cipher.decrypt(&key, &nonce, &ciphertext, b"other").is_err());
+            assert!(
+                cipher
+                    .decrypt(&[1; 32], &nonce, &ciphertext, b"aad")
+                    .is_err()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn encrypt() {
+        let data_key = DataKey::generate();
+
+        for cipher in [EnvelopeCipher::Aes256Gcm, EnvelopeCipher::XChaCha20Poly1305] {
+            let sealed_1 = data_key.encrypt(cipher, b"hunter2", b"row-1").unwrap();
+            let sealed_2 = data_key.encrypt(cipher, b"hunter2", b"row-1").unwrap();
+
+            assert!(sealed_1.starts_with(&format!("v1:{}:", cipher)));
+            assert!(!sealed_1.contains("hunter2"));
+
+            // Nonces are random
+            assert_ne!(sealed_1, sealed_2);
+            assert_eq!(data_key.decrypt(&sealed_1, b"row-1").unwrap(), b"hunter2");
+
+            // Values can't be moved to another row or opened with another key
+            assert!(data_key.decrypt(&sealed_1, b"row-2").is_err());
+            assert!(DataKey::generate().decrypt(&sealed_1, b"row-1").is_err());
+        }
+
+        assert!(data_key.decrypt("hunter2", b"row-1").is_err());
+        assert!(data_key.decrypt("v1:rot13:aHVudGVyMg==", b"row-1").is_err());
+        assert!(data_key.decrypt("v1:aes-256-gcm:AAAA", b"row-1").is_err());
+    }
+
+    #[tokio::test]
+    async fn wrap() {
+        let master_key_1 = MasterKey::generate();
+        let master_key_2 = MasterKey::generate();
+        let data_key = DataKey::generate();
+
+        let sealed = data_key
+            .encrypt(EnvelopeCipher::Aes256Gcm, b"hunter2", b"row-1")
+            .unwrap();
+        let wrapped_1 = master_key_1
+            .wrap(&data_key, EnvelopeCipher::XChaCha20Poly1305)
+            .unwrap();
+
+        assert!(master_key_2.unwrap(&wrapped_1).is_err());
+
+        // Rotating the master key keeps the data key
+        let wrapped_2 = master_key_2
+            .rewrap(&master_key_1, &wrapped_1, EnvelopeCipher::Aes256Gcm)
+            .unwrap();
+        let data_key = master_key_2.unwrap(&wrapped_2).unwrap();
+
+        assert!(master_key_1.unwrap(&wrapped_2).is_err());
+        assert_eq!(data_key.decrypt(&sealed, b"row-1").unwrap(), b"hunter2");
+
+        // Keys survive being written to a file
+        let master_key_3 = MasterKey::from_base64(&master_key_1.to_base64()).unwrap();
+
+        assert_eq!(master_key_3.id(), master_key_1.id());
+        assert_ne!(master_key_2.id(), master_key_1.id());
+        assert_eq!(master_key_1.id().len(), 16);
+        assert!(master_key_3.unwrap(&wrapped_1).is_ok());
+        assert!(MasterKey::from_base64("c2hvcnQ=").is_none());
+        assert!(MasterKey::from_base64("not base64").is_none());
+        assert!(!format!("{:?}", master_key_1).contains(master_key_1.to_base64().as_str()));
+    }
+}
@@ -0,0 +1,70 @@
+use crate::rng;
+
+use sha2::Digest;
+
+/// Prefix of generated invite codes, keeps them apart from other tokens.
+const INVITE_CODE_PREFIX: &str = "mvi";
+
+/// Length of random part of generated invite codes.
+const INVITE_CODE_SECRET_LENGTH: usize = 32;
+
+/// One-time code allowing to register a user.
+///
+/// Codes are random, so like api keys only a plain SHA-256 hash is stored.
+#[derive(Clone, PartialEq, Eq)]
+pub struct InviteCode(String);
+
+impl InviteCode {
+    /// Generate new random code.
+    pub fn generate() -> Self {
+        Self(format!(
+            "{}_{}",
+            INVITE_CODE_PREFIX,
+            rng::random_bytes_str(INVITE_CODE_SECRET_LENGTH)
+        ))
+    }
+
+    /// Parse code as given by a user, surrounding whitespace is ignored.
+    pub fn parse(code: &str) -> Self {
+        Self(code.trim().to_string())
+    }
+
+    /// Hex encoded hash of code.
+    pub fn hash(&self) -> String {
+        sha2::Sha256::digest(self.0.as_bytes())
+            .iter()
+            .map(|x| format!("{:02x}", x))
+            .collect()
+    }
+}
+
+impl std::fmt::Display for InviteCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// Codes must not end up in logs.
+impl std::fmt::Debug for InviteCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("InviteCode").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InviteCode;
+
+    #[tokio::test]
+    async fn generate() {
+        let code = InviteCode::generate();
+        let formatted = code.to_string();
+
+        assert!(formatted.starts_with("mvi_"));
+        assert_eq!(formatted.len(), 36);
+        assert_eq!(InviteCode::parse(&format!(" {}\n", formatted)), code);
+        assert_eq!(code.hash().len(), 64);
+        assert_ne!(InviteCode::generate().hash(), code.hash());
+        assert!(!format!("{:?}", code).contains(&formatted));
+    }
+}
@@ -1,7 +1,17 @@
+mod api_key;
 mod argon2;
+mod envelope;
+mod invite;
 mod jwt;
 mod jwt_claim;
+mod shamir;
+mod totp;
 
+pub use api_key::*;
 pub use argon2::*;
+pub use envelope::*;
+pub use invite::*;
 pub use jwt::*;
 pub use jwt_claim::*;
+pub use shamir::*;
+pub use totp::*;
@@ -0,0 +1,363 @@
+use rand::RngCore;
+use sha2::Digest;
+use zeroize::Zeroizing;
+
+/// Prefix of encoded shares, keeps them apart from other tokens.
+const SECRET_SHARE_PREFIX: &str = "mvs";
+
+/// Share of a secret split with Shamir's secret sharing over GF(256).
+///
+/// Every byte of the secret is the constant term of its own random polynomial, a share holds
+/// the value of every polynomial at the index of the share.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretShare {
+    index: u8,
+    value: Zeroizing<Vec<u8>>,
+}
+
+impl SecretShare {
+    /// Index of share, the polynomials were evaluated at it.
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    /// Hex encoded hash of share, lets shares be checked one at a time without keeping them.
+    pub fn hash(&self) -> String {
+        sha2::Sha256::digest(self.to_string().as_bytes())
+            .iter()
+            .map(|x| format!("{:02x}", x))
+            .collect()
+    }
+
+    /// Parse share as given by a user, surrounding whitespace is ignored.
+    /// Index and value must only be decimal and hex digits, signs aren't accepted.
+    pub fn parse(share: &str) -> Option<Self> {
+        let (index, value) = share
+            .trim()
+            .strip_prefix(SECRET_SHARE_PREFIX)?
+            .strip_prefix('_')?
+            .split_once('_')?;
+
+        if !index.bytes().all(|x| x.is_ascii_digit())
+            || !value.bytes().all(|x| x.is_ascii_hexdigit())
+        {
+            return None;
+        }
+
+        let index = index.parse().ok().filter(|x| *x != 0)?;
+
+        if value.is_empty() || value.len() % 2 != 0 {
+            return None;
+        }
+
+        let value = (0..value.len())
+            .step_by(2)
+            .map(|x| u8::from_str_radix(value.get(x..x + 2)?, 16).ok())
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Self {
+            index,
+            value: Zeroizing::new(value),
+        })
+    }
+}
+
+impl std::fmt::Display for SecretShare {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}_{}_", SECRET_SHARE_PREFIX, self.index)?;
+
+        for x in self.value.iter() {
+            write!(f, "{:02x}", x)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Shares must not end up in logs.
+impl std::fmt::Debug for SecretShare {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretShare")
+            .field("index", &self.index)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Split secret into shares, any `threshold` of them recover the secret while fewer reveal
+/// nothing about it. Up to 255 shares can be created.
+pub fn split_secret(
+    secret: &[u8],
+    threshold: u8,
+    shares: u8,
+) -> Result<Vec<SecretShare>, crate::error::Error> {
+    if secret.is_empty() {
+        return Err(crate::error::Error::SecretShares(
+            "secret must not be empty".into(),
+        ));
+    }
+
+    if threshold == 0 || threshold > shares {
+        return Err(crate::error::Error::SecretShares(format!(
+            "threshold must be between 1 and {} shares",
+            shares
+        )));
+    }
+
+    // Coefficients of every degree above zero, one for each byte of the secret.
+    let mut coefficients = Zeroizing::new(vec![0; secret.len() * (threshold as usize - 1)]);
+    rand::thread_rng().fill_bytes(&mut coefficients);
+
+    Ok(split_with_coefficients(secret, &coefficients, shares))
+}
+
+/// Recover secret from shares of the same split.
+/// At least as many shares as the threshold are needed, fewer give a different secret.
+pub fn combine_shares(shares: &[SecretShare]) -> Result<Zeroizing<Vec<u8>>, crate::error::Error> {
+    let Some(first) = shares.first() else {
+        return Err(crate::error::Error::SecretShares("no shares given".into()));
+    };
+
+    for (i, share) in shares.iter().enumerate() {
+        if share.value.len() != first.value.len() {
+            return Err(crate::error::Error::SecretShares(
+                "shares are of different secrets".into(),
+            ));
+        }
+
+        if shares[..i].iter().any(|x| x.index == share.index) {
+            return Err(crate::error::Error::SecretShares(format!(
+                "share {} was given twice",
+                share.index
+            )));
+        }
+    }
+
+    // Lagrange interpolation at zero, subtraction is xor in GF(256).
+    let weights = shares
+        .iter()
+        .map(|share| {
+            shares
+                .iter()
+                .filter(|x| x.index != share.index)
+                .fold(1, |weight, x| {
+                    gf_mul(weight, gf_mul(x.index, gf_inv(x.index ^ share.index)))
+                })
+        })
+        .collect::<Vec<_>>();
+
+    let secret = (0..first.value.len())
+        .map(|i| {
+            shares
+                .iter()
+                .zip(&weights)
+                .fold(0, |secret, (share, weight)| {
+                    secret ^ gf_mul(share.value[i], *weight)
+                })
+        })
+        .collect();
+
+    Ok(Zeroizing::new(secret))
+}
+
+/// Split secret with the given coefficients, ordered by degree and then by byte of the secret.
+fn split_with_coefficients(secret: &[u8], coefficients: &[u8], shares: u8) -> Vec<SecretShare> {
+    let degree = coefficients.len() / secret.len();
+
+    (1..=shares)
+        .map(|index| {
+            let value = secret
+                .iter()
+                .enumerate()
+                .map(|(i, x)| {
+                    // Horner's method starting at the highest degree.
+                    let value = (0..degree).rev().fold(0, |value, d| {
+                        gf_mul(value, index) ^ coefficients[d * secret.len() + i]
+                    });
+
+                    gf_mul(value, index) ^ x
+                })
+                .collect();
+
+            SecretShare {
+                index,
+                value: Zeroizing::new(value),
+            }
+        })
+        .collect()
+}
+
+/// Multiply in GF(256) reduced by the AES polynomial x^8 + x^4 + x^3 + x + 1.
+/// Secret values are never branched on.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+
+    for _ in 0..8 {
+        product ^= a & (b & 1).wrapping_neg();
+
+        let carry = (a >> 7).wrapping_neg();
+
+        a = (a << 1) ^ (0x1b & carry);
+        b >>= 1;
+    }
+
+    product
+}
+
+/// Multiplicative inverse in GF(256), which is `a^254`. Zero has none and stays zero.
+fn gf_inv(a: u8) -> u8 {
+    let a_2 = gf_mul(a, a);
+    let a_3 = gf_mul(a_2, a);
+    let a_6 = gf_mul(a_3, a_3);
+    let a_12 = gf_mul(a_6, a_6);
+    let a_15 = gf_mul(a_12, a_3);
+    let a_30 = gf_mul(a_15, a_15);
+    let a_60 = gf_mul(a_30, a_30);
+    let a_63 = gf_mul(a_60, a_3);
+    let a_126 = gf_mul(a_63, a_63);
+    let a_127 = gf_mul(a_126, a);
+
+    gf_mul(a_127, a_127)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        SecretShare, combine_shares, gf_inv, gf_mul, split_secret, split_with_coefficients,
+    };
+
+    #[test]
+    fn gf() {
+        // FIPS-197 section 4.2
+        assert_eq!(gf_mul(0x57, 0x83), 0xc1);
+        assert_eq!(gf_mul(0x57, 0x13), 0xfe);
+        assert_eq!(gf_mul(0x53, 0xca), 0x01);
+
+        assert_eq!(gf_inv(0x53), 0xca);
+        assert_eq!(gf_inv(0x01), 0x01);
+        assert_eq!(gf_inv(0x00), 0x00);
+
+        for a in 1..=255 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1);
+        }
+    }
+
+    #[test]
+    fn split_fixed_coefficients() {
+        let secret = b"my-vault";
+        let coefficients = [
+            [0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17],
+            [0xa0, 0xa1, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7],
+        ]
+        .concat();
+
+        let shares = split_with_coefficients(secret, &coefficients, 5)
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            shares,
+            [
+                "mvs_1_ddc99dc6d1c5dcc4",
+                "mvs_2_fbe9b7eaeffdeef0",
+                "mvs_3_4b59075a5f4d5e40",
+                "mvs_4_c3c3abe49f9fbab6",
+                "mvs_5_73731b542f2f0a06",
+            ]
+        );
+
+        // At index 1 every power is one, so share 1 is the secret xor both coefficients.
+        let share_1 = secret
+            .iter()
+            .zip(&coefficients[..8])
+            .zip(&coefficients[8..])
+            .map(|((x, c_1), c_2)| x ^ c_1 ^ c_2)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            SecretShare::parse(&shares[0]).unwrap().value.as_slice(),
+            share_1
+        );
+
+        for indexes in [[0, 1, 2], [4, 2, 0], [1, 3, 4]] {
+            let shares = indexes
+                .iter()
+                .map(|x| SecretShare::parse(&shares[*x]).unwrap())
+                .collect::<Vec<_>>();
+
+            assert_eq!(combine_shares(&shares).unwrap().as_slice(), secret);
+        }
+    }
+
+    #[test]
+    fn split_combine() {
+        let secret = b"super-secret-database-key";
+
+        let shares = split_secret(secret, 3, 5).unwrap();
+
+        assert_eq!(shares.len(), 5);
+
+        let result_1 = combine_shares(&shares[1..4]).unwrap();
+        let result_2 = combine_shares(&shares).unwrap();
+        let result_3 = combine_shares(&shares[..2]).unwrap();
+
+        assert_eq!(result_1.as_slice(), secret);
+        assert_eq!(result_2.as_slice(), secret);
+        assert_ne!(result_3.as_slice(), secret);
+
+        // Threshold of one gives the secret as every share
+        let shares = split_secret(secret, 1, 2).unwrap();
+
+        assert_eq!(combine_shares(&shares[1..]).unwrap().as_slice(), secret);
+
+        assert!(split_secret(secret, 0, 2).is_err());
+        assert!(split_secret(secret, 3, 2).is_err());
+        assert!(split_secret(b"", 2, 3).is_err());
+    }
+
+    #[test]
+    fn combine_invalid() {
+        let shares = split_secret(b"secret", 2, 3).unwrap();
+        let shares_other = split_secret(b"other secret", 2, 3).unwrap();
+
+        assert!(combine_shares(&[]).is_err());
+        assert!(combine_shares(&[shares[0].clone(), shares[0].clone()]).is_err());
+        assert!(combine_shares(&[shares[0].clone(), shares_other[1].clone()]).is_err());
+    }
+
+    #[test]
+    fn parse() {
+        let share = split_secret(b"secret", 2, 3).unwrap().remove(2);
+        let formatted = share.to_string();
+
+        assert!(formatted.starts_with("mvs_3_"));
+        assert_eq!(
+            SecretShare::parse(&format!(" {}\n", formatted)),
+            Some(share.clone())
+        );
+        assert!(!format!("{:?}", share).contains(&formatted[6..]));
+
+        // Hashes tell shares apart without containing them.
+        let shares = split_secret(b"secret", 2, 3).unwrap();
+
+        assert_eq!(share.hash().len(), 64);
+        assert_eq!(SecretShare::parse(&formatted).unwrap().hash(), share.hash());
+        assert_ne!(shares[2].hash(), share.hash());
+        assert!(!share.hash().contains(&formatted[6..]));
+
+        for invalid in [
+            "",
+            "mvs_0_ab",
+            "mvs_1_",
+            "mvs_1_abc",
+            "mvs_1_zz",
+            "mvi_1_ab",
+            "mvs_256_ab",
+            "mvs_+1_ab",
+            "mvs_1_+a",
+            "mvs_1_-a",
+        ] {
+            assert_eq!(SecretShare::parse(invalid), None);
+        }
+    }
+}
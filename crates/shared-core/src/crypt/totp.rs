@@ -0,0 +1,342 @@
+use hmac::Mac;
+use rand::RngCore;
+use sha2::Digest;
+use subtle::ConstantTimeEq;
+
+/// Number of digits of TOTP codes.
+const TOTP_DIGITS: usize = 6;
+
+/// Seconds each TOTP code is valid for.
+pub const TOTP_PERIOD: u64 = 30;
+
+/// Length of generated TOTP secrets in bytes, as recommended for HMAC-SHA1 by RFC 4226.
+const TOTP_SECRET_LENGTH: usize = 20;
+
+/// Steps before and after the current one which are accepted to allow for clock drift.
+const TOTP_SKEW: u64 = 1;
+
+/// Random bytes of recovery codes, encoded as 12 base32 characters.
+const RECOVERY_CODE_LENGTH: usize = 7;
+
+/// RFC 4648 base32 alphabet.
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Characters escaped in otpauth URI labels and parameters.
+const URI_ESCAPE: &percent_encoding::AsciiSet = &percent_encoding::NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+type HmacSha1 = hmac::Hmac<sha1::Sha1>;
+
+/// Time-based one-time password (RFC 6238)
+///
+/// Codes are 6 digit HMAC-SHA1 codes changing every 30 seconds, the defaults every
+/// authenticator app supports.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Totp {
+    secret: Vec<u8>,
+}
+
+impl Totp {
+    /// Generate new random secret.
+    pub fn generate() -> Self {
+        let mut secret = vec![0; TOTP_SECRET_LENGTH];
+
+        rand::thread_rng().fill_bytes(&mut secret);
+
+        Self { secret }
+    }
+
+    /// Parse base32 encoded secret, returns `None` if the secret is invalid.
+    pub fn from_base32(secret: &str) -> Option<Self> {
+        let secret = base32_decode(secret)?;
+
+        if secret.is_empty() {
+            return None;
+        }
+
+        Some(Self { secret })
+    }
+
+    /// Base32 encoded secret as shown to users.
+    pub fn to_base32(&self) -> String {
+        base32_encode(&self.secret)
+    }
+
+    /// `otpauth://` URI of secret, usually shown as QR code to enroll an authenticator app.
+    pub fn uri(&self, issuer: &str, account: &str) -> String {
+        let issuer = percent_encoding::utf8_percent_encode(issuer, URI_ESCAPE);
+        let account = percent_encoding::utf8_percent_encode(account, URI_ESCAPE);
+
+        format!(
+            "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+            issuer,
+            account,
+            self.to_base32(),
+            issuer,
+            TOTP_DIGITS,
+            TOTP_PERIOD
+        )
+    }
+
+    /// Time step of unix timestamp.
+    pub fn step(timestamp: u64) -> u64 {
+        timestamp / TOTP_PERIOD
+    }
+
+    /// Code of time step.
+    pub fn code(&self, step: u64) -> String {
+        let mut mac = HmacSha1::new_from_slice(&self.secret).expect("hmac accepts any key length");
+        mac.update(&step.to_be_bytes());
+
+        let digest = mac.finalize().into_bytes();
+
+        // Dynamic truncation (RFC 4226 section 5.3)
+        let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+        let value = u32::from_be_bytes([
+            digest[offset] & 0x7f,
+            digest[offset + 1],
+            digest[offset + 2],
+            digest[offset + 3],
+        ]);
+
+        format!(
+            "{:0width$}",
+            value % 10u32.pow(TOTP_DIGITS as u32),
+            width = TOTP_DIGITS
+        )
+    }
+
+    /// Verify code at unix timestamp, codes of adjacent steps are accepted to allow for clock
+    /// drift. Returns the step of the matching code, callers must not accept it twice.
+    pub fn verify(&self, code: &str, timestamp: u64) -> Option<u64> {
+        let code = code.trim();
+
+        if code.len() != TOTP_DIGITS {
+            return None;
+        }
+
+        let step = Self::step(timestamp);
+
+        // Compare every step so the time taken doesn't tell which step matched.
+        (step.saturating_sub(TOTP_SKEW)..=step.saturating_add(TOTP_SKEW)).fold(None, |result, x| {
+            match bool::from(self.code(x).as_bytes().ct_eq(code.as_bytes())) {
+                true => Some(x),
+                false => result,
+            }
+        })
+    }
+}
+
+// Secrets must not end up in logs.
+impl std::fmt::Debug for Totp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Totp").finish_non_exhaustive()
+    }
+}
+
+/// One-time recovery code, used in place of a TOTP code when the authenticator is lost.
+///
+/// Codes are random, so like api keys only a plain SHA-256 hash is stored.
+#[derive(Clone, PartialEq, Eq)]
+pub struct RecoveryCode(String);
+
+impl RecoveryCode {
+    /// Generate new random code.
+    pub fn generate() -> Self {
+        let mut bytes = [0; RECOVERY_CODE_LENGTH];
+
+        rand::thread_rng().fill_bytes(&mut bytes);
+
+        Self(base32_encode(&bytes).to_lowercase())
+    }
+
+    /// Parse code as typed by a user, case, spaces and dashes are ignored.
+    pub fn parse(code: &str) -> Self {
+        Self(
+            code.chars()
+                .filter(|x| !x.is_whitespace() && *x != '-')
+                .collect::<String>()
+                .to_lowercase(),
+        )
+    }
+
+    /// Hex encoded hash of code.
+    pub fn hash(&self) -> String {
+        sha2::Sha256::digest(self.0.as_bytes())
+            .iter()
+            .map(|x| format!("{:02x}", x))
+            .collect()
+    }
+}
+
+/// Codes are shown split in two halves so they are easier to copy by hand.
+impl std::fmt::Display for RecoveryCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (first, second) = self.0.split_at(self.0.len() / 2);
+
+        write!(f, "{}-{}", first, second)
+    }
+}
+
+impl std::fmt::Debug for RecoveryCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("RecoveryCode").finish_non_exhaustive()
+    }
+}
+
+/// Encode bytes as unpadded base32.
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer = 0u16;
+    let mut bits = 0;
+
+    for byte in bytes {
+        buffer = (buffer << 8) | *byte as u16;
+        bits += 8;
+
+        while bits >= 5 {
+            bits -= 5;
+            result.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+
+    if bits > 0 {
+        result.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+
+    result
+}
+
+/// Decode base32, case, spaces and padding are ignored.
+fn base32_decode(value: &str) -> Option<Vec<u8>> {
+    let mut result = Vec::with_capacity(value.len() * 5 / 8);
+    let mut buffer = 0u16;
+    let mut bits = 0;
+
+    for char in value.chars().filter(|x| !x.is_whitespace() && *x != '=') {
+        let index = BASE32_ALPHABET
+            .iter()
+            .position(|x| *x as char == char.to_ascii_uppercase())?;
+
+        buffer = (buffer << 5) | index as u16;
+        bits += 5;
+
+        if bits >= 8 {
+            bits -= 8;
+            result.push((buffer >> bits) as u8);
+        }
+    }
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RecoveryCode, Totp};
+
+    /// Secret of the RFC 6238 SHA1 test vectors.
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[tokio::test]
+    async fn base32() {
+        // RFC 4648 test vectors
+        let vectors = [
+            ("", ""),
+            ("f", "MY"),
+            ("fo", "MZXQ"),
+            ("foo", "MZXW6"),
+            ("foob", "MZXW6YQ"),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI"),
+        ];
+
+        for (plain, encoded) in vectors {
+            assert_eq!(super::base32_encode(plain.as_bytes()), encoded);
+            assert_eq!(
+                super::base32_decode(encoded).unwrap(),
+                plain.as_bytes().to_vec()
+            );
+        }
+
+        assert_eq!(
+            super::base32_decode("mzxw 6ytb oi======").unwrap(),
+            b"foobar".to_vec()
+        );
+        assert_eq!(super::base32_decode("MZXW1"), None);
+    }
+
+    #[tokio::test]
+    async fn code() {
+        let totp = Totp {
+            secret: RFC_SECRET.to_vec(),
+        };
+
+        // RFC 6238 appendix B, truncated to 6 digits
+        let vectors = [
+            (59, "287082"),
+            (1111111109, "081804"),
+            (1111111111, "050471"),
+            (1234567890, "005924"),
+            (2000000000, "279037"),
+            (20000000000, "353130"),
+        ];
+
+        for (timestamp, code) in vectors {
+            assert_eq!(totp.code(Totp::step(timestamp)), code);
+        }
+    }
+
+    #[tokio::test]
+    async fn verify() {
+        let totp = Totp::generate();
+        let timestamp = 1_700_000_000;
+        let step = Totp::step(timestamp);
+
+        assert_eq!(totp.verify(&totp.code(step), timestamp), Some(step));
+
+        // Codes of adjacent steps are accepted
+        assert_eq!(totp.verify(&totp.code(step - 1), timestamp), Some(step - 1));
+        assert_eq!(totp.verify(&totp.code(step + 1), timestamp), Some(step + 1));
+        assert_eq!(totp.verify(&totp.code(step - 2), timestamp), None);
+        assert_eq!(totp.verify(&totp.code(step + 2), timestamp), None);
+
+        assert_eq!(totp.verify("", timestamp), None);
+        assert_eq!(totp.verify("12345", timestamp), None);
+        assert_eq!(Totp::generate().verify(&totp.code(step), timestamp), None);
+    }
+
+    #[tokio::test]
+    async fn uri() {
+        let totp = Totp::from_base32("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ").unwrap();
+
+        assert_eq!(totp.secret, RFC_SECRET.to_vec());
+        assert_eq!(totp.to_base32(), "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        assert_eq!(
+            totp.uri("my vault", "carl@example.com"),
+            "otpauth://totp/my%20vault:carl%40example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ\
+                &issuer=my%20vault&algorithm=SHA1&digits=6&period=30"
+        );
+
+        assert_eq!(Totp::from_base32(""), None);
+        assert_eq!(Totp::from_base32("not base32!"), None);
+        assert!(!format!("{:?}", totp).contains("GEZDGNBV"));
+    }
+
+    #[tokio::test]
+    async fn recovery_code() {
+        let code = RecoveryCode::generate();
+        let formatted = code.to_string();
+
+        assert_eq!(formatted.len(), 13);
+        assert_eq!(RecoveryCode::parse(&formatted), code);
+        assert_eq!(
+            RecoveryCode::parse(&formatted.to_uppercase().replace('-', " ")).hash(),
+            code.hash()
+        );
+        assert_ne!(RecoveryCode::generate().hash(), code.hash());
+        assert!(!format!("{:?}", code).contains(&formatted));
+    }
+}
@@ -1,243 +1,324 @@
 use sqlx::sqlite;
-use std::collections::HashMap;
 
 /// Trait for providing the database table name
 pub trait TableName {
     const NAME: &'static str;
 }
 
+/// Anything crud functions can run on, either a pool, a connection or a transaction.
+///
+/// Passing `&mut *transaction` lets multiple crud functions run atomically.
+pub trait Acquire<'c>: sqlx::Acquire<'c, Database = sqlx::Sqlite> + Send {}
+
+impl<'c, A> Acquire<'c> for A where A: sqlx::Acquire<'c, Database = sqlx::Sqlite> + Send {}
+
+/// Future returned by database functions.
+///
+/// Boxed so the future does not depend on the executor type, which keeps futures
+/// borrowing a connection or transaction `Send`.
+pub type DatabaseFuture<'c, T> = futures::future::BoxFuture<'c, Result<T, crate::error::Error>>;
+
 /// Create new database entry
-pub async fn create<N, T>(database: &sqlite::SqlitePool, data: T) -> Result<T, crate::error::Error>
+pub fn create<'c, N, T>(database: impl Acquire<'c> + 'c, data: T) -> DatabaseFuture<'c, T>
 where
     N: TableName,
     T: serde::ser::Serialize + serde::de::DeserializeOwned,
-    T: for<'a> sqlx::FromRow<'a, sqlite::SqliteRow> + Unpin + Send,
+    T: for<'a> sqlx::FromRow<'a, sqlite::SqliteRow> + Unpin + Send + 'c,
 {
-    // Get hash map with field names and values.
-    let s = serde_json::to_string(&data).unwrap();
-    let data_map: HashMap<String, serde_json::Value> = serde_json::from_str(&s).unwrap();
-
-    let keys = data_map
-        .keys()
-        .map(|x| x.to_string())
-        .collect::<Vec<_>>()
-        .join(", ");
-
-    let values = data_map
-        .values()
-        .map(|x| x.to_string())
-        .collect::<Vec<_>>()
-        .join(", ");
-
-    let returning = crate::serde::struct_fields::<T>()
-        .iter()
-        .map(|x| x.to_string())
-        .collect::<Vec<_>>()
-        .join(", ");
-
-    let query = format!(
-        "INSERT INTO {} ({}) VALUES ({}) RETURNING {}",
-        N::NAME,
-        &keys,
-        &values,
-        &returning,
-    );
-
-    let result = sqlx::query_as(&query)
-        .fetch_one(database)
-        .await
-        .map_err(crate::error::Error::from)?;
-
-    Ok(result)
+    Box::pin(async move {
+        let data_map = to_column_map::<T>(&data)?;
+        let returning = returning::<T>();
+
+        let mut query: sqlx::QueryBuilder<'_, sqlx::Sqlite> =
+            sqlx::query_builder::QueryBuilder::new(format!("INSERT INTO {} (", N::NAME));
+
+        let mut separated = query.separated(", ");
+
+        for k in data_map.keys() {
+            separated.push(k);
+        }
+
+        query.push(") VALUES (");
+
+        for (i, v) in data_map.values().enumerate() {
+            if i > 0 {
+                query.push(", ");
+            }
+
+            push_bind_value(&mut query, v);
+        }
+
+        query.push(") RETURNING ");
+        query.push(returning);
+
+        let mut conn = database.acquire().await?;
+
+        let result = query
+            .build_query_as()
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(crate::error::Error::from)?;
+
+        Ok(result)
+    })
 }
 
 /// Read from database.
-pub async fn read<N, T>(
-    database: &sqlite::SqlitePool,
+pub fn read<'c, N, T>(
+    database: impl Acquire<'c> + 'c,
     where_map: Vec<(&'static str, String)>,
-) -> Result<T, crate::error::Error>
+) -> DatabaseFuture<'c, T>
 where
     N: TableName,
     T: serde::ser::Serialize + serde::de::DeserializeOwned,
-    T: for<'a> sqlx::FromRow<'a, sqlite::SqliteRow> + Unpin + Send,
+    T: for<'a> sqlx::FromRow<'a, sqlite::SqliteRow> + Unpin + Send + 'c,
 {
-    let returning = crate::serde::struct_fields::<T>()
-        .iter()
-        .map(|x| x.to_string())
-        .collect::<Vec<_>>()
-        .join(", ");
+    Box::pin(async move {
+        let returning = returning::<T>();
 
-    let mut query: sqlx::QueryBuilder<'_, sqlx::Sqlite> =
-        sqlx::query_builder::QueryBuilder::new(format!("SELECT {} FROM {}", returning, N::NAME));
+        let mut query: sqlx::QueryBuilder<'_, sqlx::Sqlite> =
+            sqlx::query_builder::QueryBuilder::new(format!(
+                "SELECT {} FROM {}",
+                returning,
+                N::NAME
+            ));
 
-    if !where_map.is_empty() {
-        query.push(" WHERE");
+        push_where::<T>(&mut query, where_map)?;
 
-        for (i, (k, v)) in where_map.iter().enumerate() {
-            if i > 0 {
-                query.push(" AND");
-            }
+        query.push(" LIMIT 1");
 
-            query.push(" ");
-            query.push(k);
-            query.push(" = ");
-            query.push(format!("'{}'", v));
-        }
-    }
-
-    query.push(" LIMIT 1");
+        let mut conn = database.acquire().await?;
 
-    let result = sqlx::query_as(query.sql())
-        .fetch_one(database)
-        .await
-        .map_err(crate::error::Error::from)?;
+        let result = query
+            .build_query_as()
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(crate::error::Error::from)?;
 
-    Ok(result)
+        Ok(result)
+    })
 }
 
 /// Update new database entry
-pub async fn update<N, T>(
-    database: &sqlite::SqlitePool,
+pub fn update<'c, N, T>(
+    database: impl Acquire<'c> + 'c,
     where_map: Vec<(&'static str, String)>,
     data: T,
-) -> Result<T, crate::error::Error>
+) -> DatabaseFuture<'c, T>
 where
     N: TableName,
     T: serde::ser::Serialize + serde::de::DeserializeOwned,
-    T: for<'a> sqlx::FromRow<'a, sqlite::SqliteRow> + Unpin + Send,
+    T: for<'a> sqlx::FromRow<'a, sqlite::SqliteRow> + Unpin + Send + 'c,
 {
-    // Get hash map with field names and values.
-    let s = serde_json::to_string(&data).unwrap();
-    let data_map: HashMap<String, serde_json::Value> = serde_json::from_str(&s).unwrap();
-
-    let returning = crate::serde::struct_fields::<T>()
-        .iter()
-        .map(|x| x.to_string())
-        .collect::<Vec<_>>()
-        .join(", ");
-
-    if data_map.is_empty() {
-        return Err(crate::error::Error::Sqlx("data type invalid".into()));
-    }
+    Box::pin(async move {
+        let data_map = to_column_map::<T>(&data)?;
+        let returning = returning::<T>();
 
-    let mut query: sqlx::QueryBuilder<'_, sqlx::Sqlite> =
-        sqlx::query_builder::QueryBuilder::new(format!("UPDATE {} SET", N::NAME));
-
-    for (i, (k, v)) in data_map.iter().enumerate() {
-        if i > 0 {
-            query.push(",");
+        if data_map.is_empty() {
+            return Err(crate::error::Error::Sqlx("data type invalid".into()));
         }
 
-        query.push(" ");
-        query.push(k);
-        query.push(" = ");
-        query.push(format!("{}", v));
-    }
-
-    if !where_map.is_empty() {
-        query.push(" WHERE");
+        let mut query: sqlx::QueryBuilder<'_, sqlx::Sqlite> =
+            sqlx::query_builder::QueryBuilder::new(format!("UPDATE {} SET ", N::NAME));
 
-        for (i, (k, v)) in where_map.iter().enumerate() {
+        for (i, (k, v)) in data_map.iter().enumerate() {
             if i > 0 {
-                query.push(" AND");
+                query.push(", ");
             }
 
-            query.push(" ");
             query.push(k);
             query.push(" = ");
-            query.push(format!("'{}'", v));
+            push_bind_value(&mut query, v);
         }
-    }
 
-    query.push(" RETURNING ");
-    query.push(returning);
+        push_where::<T>(&mut query, where_map)?;
+
+        query.push(" RETURNING ");
+        query.push(returning);
 
-    println!("{}", query.sql());
+        let mut conn = database.acquire().await?;
 
-    let result = sqlx::query_as(query.sql())
-        .fetch_one(database)
-        .await
-        .map_err(crate::error::Error::from)?;
+        let result = query
+            .build_query_as()
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(crate::error::Error::from)?;
 
-    Ok(result)
+        Ok(result)
+    })
 }
 
 /// Delete a database entry
-pub async fn delete<N, T>(
-    database: &sqlite::SqlitePool,
+pub fn delete<'c, N, T>(
+    database: impl Acquire<'c> + 'c,
     where_map: Vec<(&'static str, String)>,
-) -> Result<(), crate::error::Error>
+) -> DatabaseFuture<'c, ()>
 where
     N: TableName,
+    T: serde::de::DeserializeOwned,
 {
-    let mut query: sqlx::QueryBuilder<'_, sqlx::Sqlite> =
-        sqlx::query_builder::QueryBuilder::new(format!("DELETE FROM {}", N::NAME));
+    Box::pin(async move {
+        let mut query: sqlx::QueryBuilder<'_, sqlx::Sqlite> =
+            sqlx::query_builder::QueryBuilder::new(format!("DELETE FROM {}", N::NAME));
 
-    if !where_map.is_empty() {
-        query.push(" WHERE");
+        push_where::<T>(&mut query, where_map)?;
 
-        for (i, (k, v)) in where_map.iter().enumerate() {
-            if i > 0 {
-                query.push(" AND");
-            }
+        // If rows changed is not 0 then it means we modified (deleted a row) the table.
+        let mut conn = database.acquire().await?;
 
-            query.push(" ");
-            query.push(k);
-            query.push(" = ");
-            query.push(format!("'{}'", v));
+        let rows_affected = query
+            .build()
+            .execute(&mut *conn)
+            .await
+            .map_err(crate::error::Error::from)?
+            .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(sqlx::error::Error::RowNotFound.into());
         }
-    }
 
-    println!("{}", query.sql());
+        Ok(())
+    })
+}
+
+/// Checks if something exists
+pub fn exists<'c, N, T>(
+    database: impl Acquire<'c> + 'c,
+    where_map: Vec<(&'static str, String)>,
+) -> DatabaseFuture<'c, bool>
+where
+    N: TableName,
+    T: serde::de::DeserializeOwned,
+{
+    Box::pin(async move {
+        let mut query: sqlx::QueryBuilder<'_, sqlx::Sqlite> =
+            sqlx::query_builder::QueryBuilder::new(format!(
+                "SELECT EXISTS(SELECT 1 FROM {}",
+                N::NAME
+            ));
+
+        push_where::<T>(&mut query, where_map)?;
+
+        query.push(")");
+
+        let mut conn = database.acquire().await?;
+
+        let result = query
+            .build_query_scalar()
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(crate::error::Error::from)?;
 
-    // If rows changed is not 0 then it means we modified (deleted a row) the table.
-    let rows_affected = sqlx::query(query.sql())
-        .execute(database)
-        .await
-        .map_err(crate::error::Error::from)?
-        .rows_affected();
+        Ok(result)
+    })
+}
+
+/// Get comma separated list of all columns of row type.
+pub(super) fn returning<T>() -> String
+where
+    T: serde::de::DeserializeOwned,
+{
+    crate::serde::struct_fields::<T>().join(", ")
+}
 
-    if rows_affected == 0 {
-        return Err(sqlx::error::Error::RowNotFound.into());
+/// Make sure column is a field of row type.
+///
+/// Column names can't be bound so they are pasted into the query, only names which
+/// are known fields of the row type are allowed.
+pub(super) fn check_column<T>(column: &str) -> Result<(), crate::error::Error>
+where
+    T: serde::de::DeserializeOwned,
+{
+    if !crate::serde::struct_fields::<T>().contains(&column) {
+        return Err(crate::error::Error::InvalidColumn(column.to_string()));
     }
 
     Ok(())
 }
 
-/// Checks if something exists
-pub async fn exists<N>(
-    database: &sqlite::SqlitePool,
-    where_map: Vec<(&'static str, String)>,
-) -> Result<bool, crate::error::Error>
+/// Get map with column names and values of row.
+fn to_column_map<T>(
+    data: &T,
+) -> Result<serde_json::Map<String, serde_json::Value>, crate::error::Error>
 where
-    N: TableName,
+    T: serde::ser::Serialize + serde::de::DeserializeOwned,
 {
-    let mut select_query: sqlx::QueryBuilder<'_, sqlx::Sqlite> =
-        sqlx::query_builder::QueryBuilder::new(format!("SELECT 1 FROM {}", N::NAME));
+    let data_map = match serde_json::to_value(data) {
+        Ok(serde_json::Value::Object(x)) => x,
+        _ => return Err(crate::error::Error::Sqlx("data type invalid".into())),
+    };
 
-    if !where_map.is_empty() {
-        select_query.push(" WHERE");
+    for k in data_map.keys() {
+        check_column::<T>(k)?;
+    }
 
-        for (i, (k, v)) in where_map.iter().enumerate() {
-            if i > 0 {
-                select_query.push(" AND");
-            }
+    Ok(data_map)
+}
 
-            select_query.push(" ");
-            select_query.push(k);
-            select_query.push(" = ");
-            select_query.push(format!("'{}'", v));
-        }
+/// Push where clause with all conditions joined by `AND`.
+pub(super) fn push_where<T>(
+    query: &mut sqlx::QueryBuilder<'_, sqlx::Sqlite>,
+    where_map: Vec<(&'static str, String)>,
+) -> Result<(), crate::error::Error>
+where
+    T: serde::de::DeserializeOwned,
+{
+    if where_map.is_empty() {
+        return Ok(());
     }
 
-    let query = format!("SELECT EXISTS({})", select_query.sql());
+    query.push(" WHERE ");
+
+    let mut separated = query.separated(" AND ");
 
-    let result = sqlx::query_scalar(&query)
-        .fetch_one(database)
-        .await
-        .map_err(crate::error::Error::from)?;
+    for (k, v) in where_map {
+        check_column::<T>(k)?;
 
-    Ok(result)
+        separated.push(k);
+        separated.push_unseparated(" = ");
+        separated.push_bind_unseparated(v);
+    }
+
+    Ok(())
+}
+
+/// Bind serialized value with a type sqlite understands.
+pub(super) fn push_bind_value(
+    query: &mut sqlx::QueryBuilder<'_, sqlx::Sqlite>,
+    value: &serde_json::Value,
+) {
+    match value {
+        serde_json::Value::Null => {
+            query.push_bind(None::<String>);
+        }
+        serde_json::Value::Bool(x) => {
+            query.push_bind(*x);
+        }
+        serde_json::Value::Number(x) => {
+            if let Some(x) = x.as_i64() {
+                query.push_bind(x);
+            } else {
+                query.push_bind(x.as_f64());
+            }
+        }
+        serde_json::Value::String(x) => {
+            query.push_bind(x.clone());
+        }
+        serde_json::Value::Array(x) => {
+            // Byte arrays are serialized as a list of numbers.
+            match x
+                .iter()
+                .map(|x| x.as_u64().and_then(|x| u8::try_from(x).ok()))
+                .collect::<Option<Vec<_>>>()
+            {
+                Some(x) => query.push_bind(x),
+                None => query.push_bind(value.to_string()),
+            };
+        }
+        serde_json::Value::Object(_) => {
+            query.push_bind(value.to_string());
+        }
+    }
 }
 
 #[cfg(test)]
@@ -257,6 +338,25 @@ mod tests {
         pub password: String,
     }
 
+    pub struct TestTypesDatabase;
+
+    impl super::TableName for TestTypesDatabase {
+        const NAME: &'static str = "my_types_table";
+    }
+
+    /// Test row with every column type
+    #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, sqlx::FromRow)]
+    pub struct TestTypesRow {
+        pub name: String,
+        pub data: Option<Vec<u8>>,
+        pub enabled: bool,
+        pub count: i64,
+        pub ratio: f64,
+        pub note: Option<String>,
+        #[serde(with = "crate::serde::datetime::option")]
+        pub created_at: Option<chrono::NaiveDateTime>,
+    }
+
     #[sqlx::test]
     async fn read(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
         let result_1 =
@@ -349,10 +449,13 @@ mod tests {
 
     #[sqlx::test]
     async fn exists(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
-        let result_1 = super::exists::<TestDatabase>(&pool, vec![("name", "bob".into())]).await;
-        let result_2 = super::exists::<TestDatabase>(&pool, vec![("name", "jeff".into())]).await;
+        let result_1 =
+            super::exists::<TestDatabase, TestRow>(&pool, vec![("name", "bob".into())]).await;
+        let result_2 =
+            super::exists::<TestDatabase, TestRow>(&pool, vec![("name", "jeff".into())]).await;
         let result_3 =
-            super::exists::<TestDatabase>(&pool, vec![("firstname", "larry".into())]).await;
+            super::exists::<TestDatabase, TestRow>(&pool, vec![("firstname", "larry".into())])
+                .await;
 
         assert!(result_1.is_ok());
         assert!(result_2.is_ok());
@@ -366,4 +469,272 @@ mod tests {
 
         Ok(())
     }
+
+    #[sqlx::test]
+    async fn hostile_values(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let entry = TestRow {
+            name: "bob'); DROP TABLE my_table; --".into(),
+            password: "\"quoted\" 'single' `tick` \\ ; --".into(),
+        };
+
+        let result_1 = super::create::<TestDatabase, _>(&pool, entry.clone()).await;
+
+        assert!(result_1.is_ok());
+
+        let result_1 = result_1.unwrap();
+
+        assert_eq!(result_1.name, entry.name);
+        assert_eq!(result_1.password, entry.password);
+
+        // Values are compared as is and never change the query
+        let result_2 =
+            super::read::<TestDatabase, TestRow>(&pool, vec![("name", entry.name.clone())]).await;
+        let result_3 =
+            super::read::<TestDatabase, TestRow>(&pool, vec![("name", "' OR '1'='1".into())]).await;
+        let result_4 =
+            super::exists::<TestDatabase, TestRow>(&pool, vec![("name", "x' OR 1=1 --".into())])
+                .await;
+        let result_5 =
+            super::delete::<TestDatabase, TestRow>(&pool, vec![("name", "' OR ''='".into())]).await;
+
+        assert_eq!(result_2.unwrap().password, entry.password);
+        assert!(result_3.is_err());
+        assert!(!result_4.unwrap());
+        assert!(result_5.is_err());
+
+        let data = TestRow {
+            name: entry.name.clone(),
+            password: "'; UPDATE my_table SET password = 'pwned'; --".into(),
+        };
+
+        let result_6 =
+            super::update::<TestDatabase, TestRow>(&pool, vec![("name", entry.name.clone())], data)
+                .await;
+
+        assert!(result_6.is_ok());
+
+        // Only the targeted row was changed and no table was dropped
+        let result_7: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM my_table WHERE password = 'pwned'")
+                .fetch_one(&pool)
+                .await?;
+        let result_8: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM my_table")
+            .fetch_one(&pool)
+            .await?;
+
+        assert_eq!(result_7, 0);
+        assert_eq!(result_8, 5);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn hostile_columns(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let result_1 = super::read::<TestDatabase, TestRow>(
+            &pool,
+            vec![("name = 'bob' OR 1=1 --", "bob".into())],
+        )
+        .await;
+        let result_2 = super::exists::<TestDatabase, TestRow>(
+            &pool,
+            vec![("1=1) OR EXISTS(SELECT 1", "bob".into())],
+        )
+        .await;
+        let result_3 = super::delete::<TestDatabase, TestRow>(
+            &pool,
+            vec![("name IS NOT NULL OR name", "bob".into())],
+        )
+        .await;
+
+        assert!(matches!(
+            result_1,
+            Err(crate::error::Error::InvalidColumn(_))
+        ));
+        assert!(matches!(
+            result_2,
+            Err(crate::error::Error::InvalidColumn(_))
+        ));
+        assert!(matches!(
+            result_3,
+            Err(crate::error::Error::InvalidColumn(_))
+        ));
+
+        let result_4: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM my_table")
+            .fetch_one(&pool)
+            .await?;
+
+        assert_eq!(result_4, 4);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn types(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let created_at =
+            chrono::NaiveDateTime::parse_from_str("2025-11-03 02:15:40", "%Y-%m-%d %H:%M:%S")
+                .unwrap();
+
+        let entry = TestTypesRow {
+            name: "types".into(),
+            data: Some(vec![0, 1, 127, 255]),
+            enabled: true,
+            count: -42,
+            ratio: 0.5,
+            note: None,
+            created_at: Some(created_at),
+        };
+
+        let result_1 = super::create::<TestTypesDatabase, _>(&pool, entry.clone()).await;
+
+        assert!(result_1.is_ok());
+        assert_eq!(result_1.unwrap(), entry);
+
+        // Values must be stored with their sqlite type
+        let result_2: (String, String, String, String, String, String) = sqlx::query_as(
+            "SELECT typeof(data), typeof(enabled), typeof(count), typeof(ratio), typeof(note), created_at
+                FROM my_types_table",
+        )
+        .fetch_one(&pool)
+        .await?;
+
+        assert_eq!(
+            result_2,
+            (
+                "blob".into(),
+                "integer".into(),
+                "integer".into(),
+                "real".into(),
+                "null".into(),
+                "2025-11-03 02:15:40".into()
+            )
+        );
+
+        // Timestamps compare correctly against ones written by sqlite
+        let result_3: bool = sqlx::query_scalar(
+            "SELECT created_at < DATETIME('NOW') AND created_at > '2025-11-03' FROM my_types_table",
+        )
+        .fetch_one(&pool)
+        .await?;
+
+        assert!(result_3);
+
+        let data = TestTypesRow {
+            data: None,
+            enabled: false,
+            note: Some("note".into()),
+            created_at: None,
+            ..entry
+        };
+
+        let result_4 = super::update::<TestTypesDatabase, _>(
+            &pool,
+            vec![("name", "types".into())],
+            data.clone(),
+        )
+        .await;
+
+        assert!(result_4.is_ok());
+        assert_eq!(result_4.unwrap(), data);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn transaction(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let database = crate::database::Database::from(pool.clone());
+
+        let entry = TestRow {
+            name: "jeffery".into(),
+            password: "steveiscool123".into(),
+        };
+
+        let data = TestRow {
+            name: "bob".into(),
+            password: "bobthebuilder".into(),
+        };
+
+        // Partial failure should roll back everything done in the transaction
+        let mut tx = database.begin().await.unwrap();
+
+        let result_1 = super::create::<TestDatabase, _>(&mut *tx, entry.clone()).await;
+        let result_2 = super::update::<TestDatabase, TestRow>(
+            &mut *tx,
+            vec![("name", "bob".into())],
+            data.clone(),
+        )
+        .await;
+        let result_3 =
+            super::delete::<TestDatabase, TestRow>(&mut *tx, vec![("name", "alice".into())]).await;
+        let result_4 = super::create::<TestDatabase, _>(&mut *tx, entry.clone()).await;
+
+        assert!(result_1.is_ok());
+        assert!(result_2.is_ok());
+        assert!(result_3.is_ok());
+        assert!(result_4.is_err());
+
+        // Changes are visible inside the transaction but not outside of it
+        let result_5 =
+            super::exists::<TestDatabase, TestRow>(&mut *tx, vec![("name", "jeffery".into())])
+                .await;
+        let result_6 =
+            super::exists::<TestDatabase, TestRow>(&pool, vec![("name", "jeffery".into())]).await;
+
+        assert!(result_5.unwrap());
+        assert!(!result_6.unwrap());
+
+        tx.rollback().await?;
+
+        let result_7 =
+            super::read::<TestDatabase, TestRow>(&pool, vec![("name", "bob".into())]).await;
+        let result_8 =
+            super::exists::<TestDatabase, TestRow>(&pool, vec![("name", "alice".into())]).await;
+        let result_9 =
+            super::exists::<TestDatabase, TestRow>(&pool, vec![("name", "jeffery".into())]).await;
+
+        assert_eq!(result_7.unwrap().password, "123");
+        assert!(result_8.unwrap());
+        assert!(!result_9.unwrap());
+
+        // Dropping a transaction without commit also rolls back
+        {
+            let mut tx = database.begin().await.unwrap();
+
+            assert!(
+                super::create::<TestDatabase, _>(&mut *tx, entry.clone())
+                    .await
+                    .is_ok()
+            );
+        }
+
+        let result_10 =
+            super::exists::<TestDatabase, TestRow>(&pool, vec![("name", "jeffery".into())]).await;
+
+        assert!(!result_10.unwrap());
+
+        // Committed changes are kept
+        let mut tx = database.begin().await.unwrap();
+
+        assert!(
+            super::create::<TestDatabase, _>(&mut *tx, entry.clone())
+                .await
+                .is_ok()
+        );
+        assert!(
+            super::update::<TestDatabase, TestRow>(&mut *tx, vec![("name", "bob".into())], data)
+                .await
+                .is_ok()
+        );
+
+        tx.commit().await?;
+
+        let result_11 =
+            super::read::<TestDatabase, TestRow>(&pool, vec![("name", "bob".into())]).await;
+        let result_12 =
+            super::exists::<TestDatabase, TestRow>(&pool, vec![("name", "jeffery".into())]).await;
+
+        assert_eq!(result_11.unwrap().password, "bobthebuilder");
+        assert!(result_12.unwrap());
+
+        Ok(())
+    }
 }
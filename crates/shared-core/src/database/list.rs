@@ -0,0 +1,734 @@
+use crate::database::crud::{check_column, push_bind_value, returning};
+use crate::database::{Acquire, DatabaseFuture, TableName};
+
+use sqlx::sqlite;
+
+/// Condition rows must match to be listed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// Column is equal to value.
+    Eq(&'static str, serde_json::Value),
+    /// Column is not equal to value.
+    Ne(&'static str, serde_json::Value),
+    /// Column matches `LIKE` pattern, `\` is used as escape character.
+    Like(&'static str, String),
+    /// Column is equal to any of the values.
+    In(&'static str, Vec<serde_json::Value>),
+    /// Column is within inclusive lower and exclusive upper bound, either can be left open.
+    Range(
+        &'static str,
+        Option<serde_json::Value>,
+        Option<serde_json::Value>,
+    ),
+    /// Column is null.
+    IsNull(&'static str),
+    /// Column is not null.
+    IsNotNull(&'static str),
+}
+
+impl Filter {
+    pub fn eq<C, V>(column: C, value: V) -> Self
+    where
+        C: Into<&'static str>,
+        V: Into<serde_json::Value>,
+    {
+        Self::Eq(column.into(), value.into())
+    }
+
+    pub fn ne<C, V>(column: C, value: V) -> Self
+    where
+        C: Into<&'static str>,
+        V: Into<serde_json::Value>,
+    {
+        Self::Ne(column.into(), value.into())
+    }
+
+    pub fn like<C, V>(column: C, pattern: V) -> Self
+    where
+        C: Into<&'static str>,
+        V: ToString,
+    {
+        Self::Like(column.into(), pattern.to_string())
+    }
+
+    /// Column contains text, wildcards in text are matched literally.
+    pub fn contains<C, V>(column: C, text: V) -> Self
+    where
+        C: Into<&'static str>,
+        V: ToString,
+    {
+        let text = text
+            .to_string()
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+
+        Self::Like(column.into(), format!("%{}%", text))
+    }
+
+    pub fn is_in<C, V, I>(column: C, values: I) -> Self
+    where
+        C: Into<&'static str>,
+        V: Into<serde_json::Value>,
+        I: IntoIterator<Item = V>,
+    {
+        Self::In(column.into(), values.into_iter().map(Into::into).collect())
+    }
+
+    pub fn range<C, V>(column: C, from: Option<V>, to: Option<V>) -> Self
+    where
+        C: Into<&'static str>,
+        V: Into<serde_json::Value>,
+    {
+        Self::Range(column.into(), from.map(Into::into), to.map(Into::into))
+    }
+
+    pub fn is_null<C>(column: C) -> Self
+    where
+        C: Into<&'static str>,
+    {
+        Self::IsNull(column.into())
+    }
+
+    pub fn is_not_null<C>(column: C) -> Self
+    where
+        C: Into<&'static str>,
+    {
+        Self::IsNotNull(column.into())
+    }
+
+    fn column(&self) -> &'static str {
+        match self {
+            Self::Eq(x, _)
+            | Self::Ne(x, _)
+            | Self::Like(x, _)
+            | Self::In(x, _)
+            | Self::Range(x, _, _)
+            | Self::IsNull(x)
+            | Self::IsNotNull(x) => x,
+        }
+    }
+}
+
+/// Sort direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Asc,
+    Desc,
+}
+
+/// Column rows are sorted by.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Order {
+    pub column: &'static str,
+    pub direction: Direction,
+}
+
+impl Order {
+    pub fn asc<C>(column: C) -> Self
+    where
+        C: Into<&'static str>,
+    {
+        Self {
+            column: column.into(),
+            direction: Direction::Asc,
+        }
+    }
+
+    pub fn desc<C>(column: C) -> Self
+    where
+        C: Into<&'static str>,
+    {
+        Self {
+            column: column.into(),
+            direction: Direction::Desc,
+        }
+    }
+}
+
+/// How rows are split into pages.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Pagination {
+    /// Every row is returned.
+    #[default]
+    All,
+    /// Skip `offset` rows and return at most `limit` rows.
+    Offset { offset: u64, limit: u64 },
+    /// Return at most `limit` rows after the row the cursor points to.
+    ///
+    /// The last order column must be unique and none of the order columns can be null,
+    /// otherwise rows can be skipped.
+    Cursor { cursor: Option<String>, limit: u64 },
+}
+
+/// Filter, order and pagination of a list query.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListQuery {
+    pub filters: Vec<Filter>,
+    pub order: Vec<Order>,
+    pub pagination: Pagination,
+}
+
+impl ListQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    pub fn order(mut self, order: Order) -> Self {
+        self.order.push(order);
+        self
+    }
+
+    pub fn offset(mut self, offset: u64, limit: u64) -> Self {
+        self.pagination = Pagination::Offset { offset, limit };
+        self
+    }
+
+    pub fn cursor(mut self, cursor: Option<String>, limit: u64) -> Self {
+        self.pagination = Pagination::Cursor { cursor, limit };
+        self
+    }
+}
+
+/// Page of listed rows.
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Number of rows matching the filters, not set for cursor pagination.
+    pub total: Option<u64>,
+    /// Cursor pointing to the next page, not set if this is the last page.
+    pub next_cursor: Option<String>,
+}
+
+/// List database entries
+pub fn list<'c, N, T>(
+    database: impl Acquire<'c> + 'c,
+    query: ListQuery,
+) -> DatabaseFuture<'c, Page<T>>
+where
+    N: TableName,
+    T: serde::ser::Serialize + serde::de::DeserializeOwned,
+    T: for<'a> sqlx::FromRow<'a, sqlite::SqliteRow> + Unpin + Send + 'c,
+{
+    Box::pin(async move {
+        for filter in &query.filters {
+            check_column::<T>(filter.column())?;
+        }
+
+        for order in &query.order {
+            check_column::<T>(order.column)?;
+        }
+
+        let mut select: sqlx::QueryBuilder<'_, sqlx::Sqlite> =
+            sqlx::query_builder::QueryBuilder::new(format!(
+                "SELECT {} FROM {}",
+                returning::<T>(),
+                N::NAME
+            ));
+
+        let has_where = push_filters(&mut select, &query.filters);
+
+        // Continue after the row the cursor points to.
+        if let Pagination::Cursor {
+            cursor: Some(cursor),
+            ..
+        } = &query.pagination
+        {
+            let values = decode_cursor(cursor, query.order.len())?;
+
+            select.push(if has_where { " AND " } else { " WHERE " });
+            push_after(&mut select, &query.order, &values);
+        }
+
+        if !query.order.is_empty() {
+            select.push(" ORDER BY ");
+
+            for (i, order) in query.order.iter().enumerate() {
+                if i > 0 {
+                    select.push(", ");
+                }
+
+                select.push(order.column);
+                select.push(match order.direction {
+                    Direction::Asc => " ASC",
+                    Direction::Desc => " DESC",
+                });
+            }
+        }
+
+        let mut conn = database.acquire().await?;
+
+        match &query.pagination {
+            Pagination::All => {
+                let items: Vec<T> = select
+                    .build_query_as()
+                    .fetch_all(&mut *conn)
+                    .await
+                    .map_err(crate::error::Error::from)?;
+
+                Ok(Page {
+                    total: Some(items.len() as u64),
+                    items,
+                    next_cursor: None,
+                })
+            }
+            Pagination::Offset { offset, limit } => {
+                select.push(" LIMIT ");
+                select.push_bind(sql_integer(*limit));
+                select.push(" OFFSET ");
+                select.push_bind(sql_integer(*offset));
+
+                let items = select
+                    .build_query_as()
+                    .fetch_all(&mut *conn)
+                    .await
+                    .map_err(crate::error::Error::from)?;
+
+                let mut count: sqlx::QueryBuilder<'_, sqlx::Sqlite> =
+                    sqlx::query_builder::QueryBuilder::new(format!(
+                        "SELECT COUNT(*) FROM {}",
+                        N::NAME
+                    ));
+
+                push_filters(&mut count, &query.filters);
+
+                let total: i64 = count
+                    .build_query_scalar()
+                    .fetch_one(&mut *conn)
+                    .await
+                    .map_err(crate::error::Error::from)?;
+
+                Ok(Page {
+                    items,
+                    total: Some(total as u64),
+                    next_cursor: None,
+                })
+            }
+            Pagination::Cursor { limit, .. } => {
+                if query.order.is_empty() {
+                    return Err(crate::error::Error::InvalidCursor(
+                        "cursor pagination requires an order".into(),
+                    ));
+                }
+
+                // Fetch one extra row to know if there is a next page.
+                select.push(" LIMIT ");
+                select.push_bind(sql_integer(*limit).saturating_add(1));
+
+                let mut items: Vec<T> = select
+                    .build_query_as()
+                    .fetch_all(&mut *conn)
+                    .await
+                    .map_err(crate::error::Error::from)?;
+
+                let next_cursor = if items.len() as u64 > *limit {
+                    items.truncate(*limit as usize);
+                    items
+                        .last()
+                        .map(|x| encode_cursor(x, &query.order))
+                        .transpose()?
+                } else {
+                    None
+                };
+
+                Ok(Page {
+                    items,
+                    total: None,
+                    next_cursor,
+                })
+            }
+        }
+    })
+}
+
+/// Convert to SQLite integer, which is signed. Larger values are clamped as negative limits
+/// mean no limit at all.
+fn sql_integer(value: u64) -> i64 {
+    i64::try_from(value).unwrap_or(i64::MAX)
+}
+
+/// Push where clause with all filters joined by `AND`.
+/// Returns true if a where clause was pushed.
+fn push_filters(query: &mut sqlx::QueryBuilder<'_, sqlx::Sqlite>, filters: &[Filter]) -> bool {
+    for (i, filter) in filters.iter().enumerate() {
+        query.push(if i == 0 { " WHERE " } else { " AND " });
+        query.push(filter.column());
+
+        match filter {
+            Filter::Eq(_, v) => {
+                query.push(" = ");
+                push_bind_value(query, v);
+            }
+            Filter::Ne(_, v) => {
+                query.push(" != ");
+                push_bind_value(query, v);
+            }
+            Filter::Like(_, v) => {
+                query.push(" LIKE ");
+                query.push_bind(v.clone());
+                query.push(" ESCAPE '\\'");
+            }
+            Filter::In(_, v) => {
+                query.push(" IN (");
+
+                for (i, v) in v.iter().enumerate() {
+                    if i > 0 {
+                        query.push(", ");
+                    }
+
+                    push_bind_value(query, v);
+                }
+
+                query.push(")");
+            }
+            Filter::Range(column, from, to) => {
+                // Open bounds are always true so the condition stays valid.
+                query.push(" IS NOT NULL");
+
+                if let Some(from) = from {
+                    query.push(" AND ");
+                    query.push(*column);
+                    query.push(" >= ");
+                    push_bind_value(query, from);
+                }
+
+                if let Some(to) = to {
+                    query.push(" AND ");
+                    query.push(*column);
+                    query.push(" < ");
+                    push_bind_value(query, to);
+                }
+            }
+            Filter::IsNull(_) => {
+                query.push(" IS NULL");
+            }
+            Filter::IsNotNull(_) => {
+                query.push(" IS NOT NULL");
+            }
+        }
+    }
+
+    !filters.is_empty()
+}
+
+/// Push condition matching rows which are sorted after the cursor values.
+fn push_after(
+    query: &mut sqlx::QueryBuilder<'_, sqlx::Sqlite>,
+    order: &[Order],
+    values: &[serde_json::Value],
+) {
+    // (a > x) OR (a = x AND b > y) OR ...
+    query.push("(");
+
+    for i in 0..order.len() {
+        if i > 0 {
+            query.push(" OR ");
+        }
+
+        query.push("(");
+
+        for j in 0..i {
+            query.push(order[j].column);
+            query.push(" = ");
+            push_bind_value(query, &values[j]);
+            query.push(" AND ");
+        }
+
+        query.push(order[i].column);
+        query.push(match order[i].direction {
+            Direction::Asc => " > ",
+            Direction::Desc => " < ",
+        });
+        push_bind_value(query, &values[i]);
+
+        query.push(")");
+    }
+
+    query.push(")");
+}
+
+/// Encode order column values of row as cursor.
+fn encode_cursor<T>(row: &T, order: &[Order]) -> Result<String, crate::error::Error>
+where
+    T: serde::ser::Serialize,
+{
+    let row =
+        serde_json::to_value(row).map_err(|e| crate::error::Error::InvalidCursor(e.to_string()))?;
+
+    let values = order
+        .iter()
+        .map(|x| row.get(x.column).cloned().unwrap_or_default())
+        .collect::<Vec<_>>();
+
+    let cursor = serde_json::to_string(&values)
+        .map_err(|e| crate::error::Error::InvalidCursor(e.to_string()))?;
+
+    Ok(cursor.bytes().map(|x| format!("{:02x}", x)).collect())
+}
+
+/// Decode cursor into order column values.
+fn decode_cursor(
+    cursor: &str,
+    length: usize,
+) -> Result<Vec<serde_json::Value>, crate::error::Error> {
+    let invalid = || crate::error::Error::InvalidCursor(cursor.to_string());
+
+    if !cursor.is_ascii() || !cursor.len().is_multiple_of(2) {
+        return Err(invalid());
+    }
+
+    let bytes = (0..cursor.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&cursor[i..i + 2], 16))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| invalid())?;
+
+    let values: Vec<serde_json::Value> = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+
+    if values.len() != length {
+        return Err(invalid());
+    }
+
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Filter, ListQuery, Order};
+    use crate::database::TableName;
+
+    use sqlx::sqlite;
+
+    pub struct TestDatabase;
+
+    impl TableName for TestDatabase {
+        const NAME: &'static str = "my_table";
+    }
+
+    /// Test row
+    #[derive(Clone, Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow)]
+    pub struct TestRow {
+        pub name: String,
+        pub password: String,
+    }
+
+    fn names(rows: &[TestRow]) -> Vec<&str> {
+        rows.iter().map(|x| x.name.as_str()).collect()
+    }
+
+    #[sqlx::test]
+    async fn list_all(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let result_1 =
+            super::list::<TestDatabase, TestRow>(&pool, ListQuery::new().order(Order::asc("name")))
+                .await;
+
+        let result_2 = super::list::<TestDatabase, TestRow>(
+            &pool,
+            ListQuery::new().order(Order::desc("password")),
+        )
+        .await;
+
+        assert!(result_1.is_ok());
+        assert!(result_2.is_ok());
+
+        let result_1 = result_1.unwrap();
+        let result_2 = result_2.unwrap();
+
+        assert_eq!(result_1.total, Some(4));
+        assert_eq!(result_1.next_cursor, None);
+        assert_eq!(names(&result_1.items), ["alice", "bob", "fart", "steve"]);
+        // Passwords are text so they are sorted as strings
+        assert_eq!(names(&result_2.items), ["steve", "alice", "bob", "fart"]);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn list_filter(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let list = |filter: Filter| {
+            let pool = pool.clone();
+
+            async move {
+                super::list::<TestDatabase, TestRow>(
+                    &pool,
+                    ListQuery::new().filter(filter).order(Order::asc("name")),
+                )
+                .await
+                .map(|x| names(&x.items).join(","))
+            }
+        };
+
+        assert_eq!(list(Filter::eq("name", "bob")).await.unwrap(), "bob");
+        assert_eq!(
+            list(Filter::ne("name", "bob")).await.unwrap(),
+            "alice,fart,steve"
+        );
+        assert_eq!(
+            list(Filter::like("name", "%e%")).await.unwrap(),
+            "alice,steve"
+        );
+        assert_eq!(
+            list(Filter::contains("name", "t")).await.unwrap(),
+            "fart,steve"
+        );
+        assert_eq!(list(Filter::contains("name", "%")).await.unwrap(), "");
+        assert_eq!(
+            list(Filter::is_in("name", ["bob", "steve", "jeff"]))
+                .await
+                .unwrap(),
+            "bob,steve"
+        );
+        assert_eq!(
+            list(Filter::is_in("name", Vec::<String>::new()))
+                .await
+                .unwrap(),
+            ""
+        );
+        assert_eq!(
+            list(Filter::range("name", Some("b"), Some("g")))
+                .await
+                .unwrap(),
+            "bob,fart"
+        );
+        assert_eq!(
+            list(Filter::range("name", None, Some("b"))).await.unwrap(),
+            "alice"
+        );
+        assert_eq!(list(Filter::is_null("password")).await.unwrap(), "");
+        assert_eq!(
+            list(Filter::is_not_null("password")).await.unwrap(),
+            "alice,bob,fart,steve"
+        );
+
+        // Values are bound and columns must be known
+        assert_eq!(
+            list(Filter::eq("name", "bob' OR '1'='1")).await.unwrap(),
+            ""
+        );
+        assert!(
+            list(Filter::eq("name = name OR name", "bob"))
+                .await
+                .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn list_offset(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let result_1 = super::list::<TestDatabase, TestRow>(
+            &pool,
+            ListQuery::new().order(Order::asc("name")).offset(1, 2),
+        )
+        .await;
+
+        let result_2 = super::list::<TestDatabase, TestRow>(
+            &pool,
+            ListQuery::new()
+                .filter(Filter::ne("name", "alice"))
+                .order(Order::asc("name"))
+                .offset(2, 2),
+        )
+        .await;
+
+        let result_3 = super::list::<TestDatabase, TestRow>(
+            &pool,
+            ListQuery::new().order(Order::desc("name")).offset(10, 2),
+        )
+        .await;
+
+        let result_1 = result_1.unwrap();
+        let result_2 = result_2.unwrap();
+        let result_3 = result_3.unwrap();
+
+        assert_eq!(names(&result_1.items), ["bob", "fart"]);
+        assert_eq!(result_1.total, Some(4));
+        assert_eq!(names(&result_2.items), ["steve"]);
+        assert_eq!(result_2.total, Some(3));
+        assert!(result_3.items.is_empty());
+        assert_eq!(result_3.total, Some(4));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn list_large_limit(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        // Limits which don't fit into a signed integer must not wrap around
+        for limit in [u64::MAX, 1 << 63, i64::MAX as u64] {
+            let result_1 = super::list::<TestDatabase, TestRow>(
+                &pool,
+                ListQuery::new()
+                    .order(Order::asc("name"))
+                    .cursor(None, limit),
+            )
+            .await;
+            let result_2 = super::list::<TestDatabase, TestRow>(
+                &pool,
+                ListQuery::new()
+                    .order(Order::asc("name"))
+                    .offset(limit, limit),
+            )
+            .await;
+
+            let result_1 = result_1.unwrap();
+
+            assert_eq!(names(&result_1.items), ["alice", "bob", "fart", "steve"]);
+            assert_eq!(result_1.next_cursor, None);
+            assert!(result_2.unwrap().items.is_empty());
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn list_cursor(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let query = |cursor: Option<String>| {
+            ListQuery::new()
+                .order(Order::desc("password"))
+                .order(Order::asc("name"))
+                .cursor(cursor, 3)
+        };
+
+        let result_1 = super::list::<TestDatabase, TestRow>(&pool, query(None)).await;
+
+        assert!(result_1.is_ok());
+
+        let result_1 = result_1.unwrap();
+
+        assert_eq!(names(&result_1.items), ["steve", "alice", "bob"]);
+        assert_eq!(result_1.total, None);
+        assert!(result_1.next_cursor.is_some());
+
+        let result_2 =
+            super::list::<TestDatabase, TestRow>(&pool, query(result_1.next_cursor)).await;
+
+        assert!(result_2.is_ok());
+
+        let result_2 = result_2.unwrap();
+
+        assert_eq!(names(&result_2.items), ["fart"]);
+        assert_eq!(result_2.next_cursor, None);
+
+        // Invalid cursors and cursors without order must error
+        let result_3 =
+            super::list::<TestDatabase, TestRow>(&pool, query(Some("not-a-cursor".into()))).await;
+        let result_4 =
+            super::list::<TestDatabase, TestRow>(&pool, ListQuery::new().cursor(None, 3)).await;
+
+        assert!(matches!(
+            result_3,
+            Err(crate::error::Error::InvalidCursor(_))
+        ));
+        assert!(matches!(
+            result_4,
+            Err(crate::error::Error::InvalidCursor(_))
+        ));
+
+        Ok(())
+    }
+}
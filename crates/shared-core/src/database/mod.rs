@@ -1,19 +1,35 @@
 mod crud;
+mod list;
+mod table;
 
-use sqlx::sqlite;
+use sqlx::{Connection, sqlite};
 use std::path::Path;
 use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
 
 pub use crud::*;
+pub use list::*;
+pub use shared_derive::Table;
+pub use table::*;
+
+/// Sqlite error code of files which aren't a database, or can't be decrypted with the key.
+const SQLITE_NOTADB: &str = "26";
 
 #[derive(Debug)]
 pub struct Database {
     sqlite_pool: sqlite::SqlitePool,
+
+    /// Time the encryption key was last changed, connections opened before use the previous key.
+    rekeyed_at: Arc<RwLock<Option<Instant>>>,
 }
 
 impl From<sqlite::SqlitePool> for Database {
     fn from(sqlite_pool: sqlite::SqlitePool) -> Self {
-        Self { sqlite_pool }
+        Self {
+            sqlite_pool,
+            rekeyed_at: Arc::default(),
+        }
     }
 }
 
@@ -30,16 +46,164 @@ impl Database {
 
         // Create a new SQLX connection to local database file.
         let options = sqlite::SqliteConnectOptions::from_str(&sqlite_file_path)?
-            .pragma("key", encryption_key)
+            .pragma("key", key_literal(&encryption_key))
             .read_only(false)
             .create_if_missing(true);
 
+        let rekeyed_at: Arc<RwLock<Option<Instant>>> = Arc::default();
+
+        // Pooled connections opened with a previous key can't read the database anymore.
+        let sqlite_pool = sqlite::SqlitePoolOptions::new()
+            .before_acquire({
+                let rekeyed_at = rekeyed_at.clone();
+
+                move |_, metadata| {
+                    let rekeyed_at = *rekeyed_at.read().expect("rekey lock poisoned");
+                    let current = rekeyed_at.is_none_or(|x| metadata.age < x.elapsed());
+
+                    Box::pin(async move { Ok(current) })
+                }
+            })
+            .connect_with(options)
+            .await?;
+
+        // Sqlcipher only notices a wrong key once the database is read.
+        if let Err(e) = sqlx::query("SELECT count(*) FROM sqlite_master")
+            .execute(&sqlite_pool)
+            .await
+        {
+            sqlite_pool.close().await;
+
+            return Err(match e {
+                sqlx::Error::Database(e) if e.code().as_deref() == Some(SQLITE_NOTADB) => {
+                    crate::error::Error::DatabaseKey
+                }
+                e => e.into(),
+            });
+        }
+
         Ok(Self {
-            sqlite_pool: sqlite::SqlitePool::connect_with(options).await?,
+            sqlite_pool,
+            rekeyed_at,
         })
     }
 
     pub fn get_pool(&self) -> &sqlite::SqlitePool {
         &self.sqlite_pool
     }
+
+    /// Start a new transaction.
+    /// Changes are rolled back when the transaction is dropped without being committed.
+    pub async fn begin(
+        &self,
+    ) -> Result<sqlx::Transaction<'static, sqlx::Sqlite>, crate::error::Error> {
+        Ok(self.sqlite_pool.begin().await?)
+    }
+
+    /// Write a copy of the database to a new file, encrypted with the current key.
+    pub async fn backup(&self, path: &Path) -> Result<(), crate::error::Error> {
+        sqlx::query("VACUUM INTO ?")
+            .bind(path.display().to_string())
+            .execute(&self.sqlite_pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Change the encryption key of the database.
+    /// The database is opened with the new key before the pool uses it for new connections,
+    /// queries running on other connections while the key changes can fail.
+    pub async fn rekey(&self, encryption_key: String) -> Result<(), crate::error::Error> {
+        let mut conn = self.sqlite_pool.acquire().await?;
+
+        sqlx::query(&format!("PRAGMA rekey = {}", key_literal(&encryption_key)))
+            .execute(&mut *conn)
+            .await?;
+
+        let options = (*self.sqlite_pool.connect_options())
+            .clone()
+            .pragma("key", key_literal(&encryption_key));
+
+        let mut conn_verify = sqlite::SqliteConnection::connect_with(&options).await?;
+
+        sqlx::query("SELECT count(*) FROM sqlite_master")
+            .execute(&mut conn_verify)
+            .await?;
+
+        conn_verify.close().await?;
+
+        self.sqlite_pool.set_connect_options(options);
+        *self.rekeyed_at.write().expect("rekey lock poisoned") = Some(Instant::now());
+
+        Ok(())
+    }
+
+    /// Close every connection of the database.
+    pub async fn close(&self) {
+        self.sqlite_pool.close().await;
+    }
+}
+
+/// Quote encryption key as string literal for key pragmas.
+/// Unquoted keys are parsed as sql and fail if they start with a digit.
+fn key_literal(encryption_key: &str) -> String {
+    format!("'{}'", encryption_key.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::Database;
+    use crate::error::Error;
+    use crate::rng;
+    use std::path::PathBuf;
+
+    #[tokio::test]
+    async fn rekey() {
+        let path = PathBuf::from(env!("WORKSPACE_DIR"))
+            .join("test-data")
+            .join("temp")
+            .join(format!("{}.sqlite3", rng::random_bytes_str(10)));
+        let path_backup = path.with_extension("sqlite3.bak");
+
+        let database = Database::load(&path, "1-old'key".into()).await.unwrap();
+
+        sqlx::query("CREATE TABLE items (name TEXT)")
+            .execute(database.get_pool())
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO items (name) VALUES ('item')")
+            .execute(database.get_pool())
+            .await
+            .unwrap();
+
+        assert!(database.backup(&path_backup).await.is_ok());
+        assert!(database.rekey("2-new'key".into()).await.is_ok());
+
+        // Pooled connections keep working with the new key
+        for _ in 0..3 {
+            let result_1: Result<(String,), _> = sqlx::query_as("SELECT name FROM items")
+                .fetch_one(database.get_pool())
+                .await;
+
+            assert_eq!(result_1.unwrap().0, "item");
+        }
+
+        database.close().await;
+
+        // Database only opens with the new key, the backup with the old key
+        let result_2 = Database::load(&path, "1-old'key".into()).await;
+
+        assert!(matches!(result_2, Err(Error::DatabaseKey)));
+
+        for (path, key) in [(&path, "2-new'key"), (&path_backup, "1-old'key")] {
+            let result_3 = Database::load(path, key.into()).await;
+
+            assert!(result_3.is_ok());
+
+            result_3.unwrap().close().await;
+        }
+
+        assert!(tokio::fs::remove_file(&path).await.is_ok());
+        assert!(tokio::fs::remove_file(&path_backup).await.is_ok());
+    }
 }
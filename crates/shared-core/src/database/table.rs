@@ -0,0 +1,333 @@
+use crate::database::{Acquire, DatabaseFuture, Filter, ListQuery, Page, TableName};
+
+use sqlx::sqlite;
+use std::marker::PhantomData;
+
+/// Column of table `T`.
+///
+/// Columns are tied to their table so a column of one table can't be used to
+/// query another. Usually created by `#[derive(Table)]`.
+pub struct Column<T> {
+    name: &'static str,
+    table: PhantomData<fn() -> T>,
+}
+
+impl<T> Column<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            table: PhantomData,
+        }
+    }
+
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl<T> Clone for Column<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Column<T> {}
+
+impl<T> PartialEq for Column<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl<T> Eq for Column<T> {}
+
+impl<T> std::fmt::Debug for Column<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Column").field(&self.name).finish()
+    }
+}
+
+impl<T> From<Column<T>> for &'static str {
+    fn from(column: Column<T>) -> Self {
+        column.name
+    }
+}
+
+/// Row type of a database table.
+///
+/// Implemented with `#[derive(Table)]`, which also adds a `COLUMN_*` constant for
+/// every field. The provided functions wrap the crud functions and skip rows which
+/// have been soft deleted.
+pub trait Table:
+    TableName
+    + serde::ser::Serialize
+    + serde::de::DeserializeOwned
+    + for<'a> sqlx::FromRow<'a, sqlite::SqliteRow>
+    + Unpin
+    + Send
+    + Sized
+    + 'static
+{
+    /// Every column of the table.
+    const COLUMNS: &'static [&'static str];
+
+    /// Columns identifying a row.
+    const PRIMARY_KEY: &'static [Column<Self>];
+
+    /// Boolean column marking a row as deleted.
+    const SOFT_DELETE: Option<Column<Self>>;
+
+    /// Timestamp column set when a row is soft deleted.
+    const DELETED_AT: Option<Column<Self>>;
+
+    /// Primary key columns with the values of this row.
+    fn primary_key(&self) -> Vec<(Column<Self>, String)>;
+
+    /// Create new row.
+    fn create<'c>(executor: impl Acquire<'c> + 'c, data: Self) -> DatabaseFuture<'c, Self> {
+        crate::database::create::<Self, Self>(executor, data)
+    }
+
+    /// Read first row matching all conditions.
+    fn read<'c>(
+        executor: impl Acquire<'c> + 'c,
+        filter: Vec<(Column<Self>, String)>,
+    ) -> DatabaseFuture<'c, Self> {
+        crate::database::read::<Self, Self>(executor, where_active::<Self>(filter))
+    }
+
+    /// Checks if a row matching all conditions exists.
+    fn exists<'c>(
+        executor: impl Acquire<'c> + 'c,
+        filter: Vec<(Column<Self>, String)>,
+    ) -> DatabaseFuture<'c, bool> {
+        crate::database::exists::<Self, Self>(executor, where_active::<Self>(filter))
+    }
+
+    /// Update row, the row is identified by its primary key.
+    fn update<'c>(executor: impl Acquire<'c> + 'c, data: Self) -> DatabaseFuture<'c, Self> {
+        let filter = where_active::<Self>(data.primary_key());
+        crate::database::update::<Self, Self>(executor, filter, data)
+    }
+
+    /// Delete rows matching all conditions.
+    /// Tables with a soft delete column only mark the rows as deleted.
+    fn delete<'c>(
+        executor: impl Acquire<'c> + 'c,
+        filter: Vec<(Column<Self>, String)>,
+    ) -> DatabaseFuture<'c, ()> {
+        match Self::SOFT_DELETE {
+            Some(column) => soft_delete::<Self>(executor, column, filter),
+            None => crate::database::delete::<Self, Self>(executor, where_map(filter)),
+        }
+    }
+
+    /// List rows.
+    fn list<'c>(
+        executor: impl Acquire<'c> + 'c,
+        query: ListQuery,
+    ) -> DatabaseFuture<'c, Page<Self>> {
+        let query = match Self::SOFT_DELETE {
+            Some(column) => query.filter(Filter::eq(column, false)),
+            None => query,
+        };
+
+        crate::database::list::<Self, Self>(executor, query)
+    }
+}
+
+fn where_map<T>(filter: Vec<(Column<T>, String)>) -> Vec<(&'static str, String)> {
+    filter.into_iter().map(|(k, v)| (k.name(), v)).collect()
+}
+
+/// Add condition skipping soft deleted rows.
+fn where_active<T>(filter: Vec<(Column<T>, String)>) -> Vec<(&'static str, String)>
+where
+    T: Table,
+{
+    let mut where_map = where_map(filter);
+
+    // Booleans are stored as integers, the bound text is compared as a number
+    // because of the column affinity.
+    if let Some(column) = T::SOFT_DELETE {
+        where_map.push((column.name(), "0".into()));
+    }
+
+    where_map
+}
+
+fn soft_delete<'c, T>(
+    executor: impl Acquire<'c> + 'c,
+    column: Column<T>,
+    filter: Vec<(Column<T>, String)>,
+) -> DatabaseFuture<'c, ()>
+where
+    T: Table,
+{
+    Box::pin(async move {
+        let mut query: sqlx::QueryBuilder<'_, sqlx::Sqlite> =
+            sqlx::query_builder::QueryBuilder::new(format!(
+                "UPDATE {} SET {} = TRUE",
+                T::NAME,
+                column.name()
+            ));
+
+        if let Some(deleted_at) = T::DELETED_AT {
+            query.push(format!(", {} = DATETIME('NOW')", deleted_at.name()));
+        }
+
+        crate::database::crud::push_where::<T>(&mut query, where_active::<T>(filter))?;
+
+        let mut conn = executor.acquire().await?;
+
+        let rows_affected = query
+            .build()
+            .execute(&mut *conn)
+            .await
+            .map_err(crate::error::Error::from)?
+            .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(sqlx::error::Error::RowNotFound.into());
+        }
+
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::{self, Table};
+
+    use sqlx::sqlite;
+
+    /// Test row
+    #[derive(
+        Clone, Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow, database::Table,
+    )]
+    #[table(name = "my_table")]
+    pub struct TestRow {
+        #[table(primary_key)]
+        pub name: String,
+        pub password: String,
+    }
+
+    /// Test row which is soft deleted
+    #[derive(
+        Clone, Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow, database::Table,
+    )]
+    #[table(name = "my_soft_table")]
+    pub struct TestSoftRow {
+        #[table(primary_key)]
+        pub name: String,
+        pub password: String,
+        #[table(soft_delete)]
+        pub deleted: bool,
+        #[table(deleted_at)]
+        pub deleted_at: Option<String>,
+    }
+
+    #[tokio::test]
+    async fn derive() {
+        assert_eq!(<TestRow as database::TableName>::NAME, "my_table");
+        assert_eq!(TestRow::COLUMNS, &["name", "password"]);
+        assert_eq!(TestRow::PRIMARY_KEY, &[TestRow::COLUMN_NAME]);
+        assert_eq!(TestRow::SOFT_DELETE, None);
+        assert_eq!(TestSoftRow::SOFT_DELETE, Some(TestSoftRow::COLUMN_DELETED));
+        assert_eq!(
+            TestSoftRow::DELETED_AT,
+            Some(TestSoftRow::COLUMN_DELETED_AT)
+        );
+        assert_eq!(TestRow::COLUMN_PASSWORD.name(), "password");
+
+        let row = TestRow {
+            name: "john".into(),
+            password: "hunter2".into(),
+        };
+
+        assert_eq!(
+            row.primary_key(),
+            vec![(TestRow::COLUMN_NAME, "john".to_string())]
+        );
+    }
+
+    #[sqlx::test]
+    async fn crud(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        let row = TestRow {
+            name: "john".into(),
+            password: "hunter2".into(),
+        };
+
+        let result_1 = TestRow::create(&pool, row.clone()).await;
+
+        assert!(result_1.is_ok());
+
+        let result_2 = TestRow::update(
+            &pool,
+            TestRow {
+                password: "hunter3".into(),
+                ..row
+            },
+        )
+        .await;
+
+        assert_eq!(result_2.unwrap().password, "hunter3");
+
+        let filter = vec![(TestRow::COLUMN_NAME, "john".to_string())];
+        let result_3 = TestRow::read(&pool, filter.clone()).await;
+
+        assert_eq!(result_3.unwrap().password, "hunter3");
+
+        let result_4 = TestRow::delete(&pool, filter.clone()).await;
+        let result_5 = TestRow::exists(&pool, filter).await;
+
+        assert!(result_4.is_ok());
+        assert!(!result_5.unwrap());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn soft_delete(pool: sqlite::SqlitePool) -> sqlx::Result<()> {
+        for name in ["john", "jane"] {
+            let row = TestSoftRow {
+                name: name.into(),
+                password: "hunter2".into(),
+                deleted: false,
+                deleted_at: None,
+            };
+
+            assert!(TestSoftRow::create(&pool, row).await.is_ok());
+        }
+
+        let filter = vec![(TestSoftRow::COLUMN_NAME, "john".to_string())];
+
+        let result_1 = TestSoftRow::delete(&pool, filter.clone()).await;
+
+        assert!(result_1.is_ok());
+
+        // Deleted row is hidden but kept in the table
+        let result_2 = TestSoftRow::exists(&pool, filter.clone()).await;
+        let result_3 = TestSoftRow::read(&pool, filter.clone()).await;
+        let result_4 = TestSoftRow::list(&pool, database::ListQuery::new()).await;
+
+        assert!(!result_2.unwrap());
+        assert!(result_3.is_err());
+        assert_eq!(result_4.unwrap().items.len(), 1);
+
+        let result_5: (bool, Option<String>) =
+            sqlx::query_as("SELECT deleted, deleted_at FROM my_soft_table WHERE name = 'john'")
+                .fetch_one(&pool)
+                .await?;
+
+        assert!(result_5.0);
+        assert!(result_5.1.is_some());
+
+        // Deleting again should fail since row is already deleted
+        let result_err_1 = TestSoftRow::delete(&pool, filter).await;
+
+        assert!(result_err_1.is_err());
+
+        Ok(())
+    }
+}
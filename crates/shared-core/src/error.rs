@@ -18,14 +18,26 @@ pub enum Error {
     #[error("error running cryptographic function")]
     Crypto,
 
+    #[error("invalid secret shares - {0}")]
+    SecretShares(String),
+
     #[error("{0}")]
     Sqlx(String),
 
+    #[error("invalid column {0}")]
+    InvalidColumn(String),
+
+    #[error("invalid cursor {0}")]
+    InvalidCursor(String),
+
     #[error("error from migration - ${0}")]
     Migration(#[source] Box<sqlx::migrate::MigrateError>),
 
     #[error("error from database - ${0}")]
     Database(#[source] Box<dyn sqlx::error::DatabaseError>),
+
+    #[error("database can't be opened with the given key")]
+    DatabaseKey,
 }
 
 impl From<std::io::Error> for Error {
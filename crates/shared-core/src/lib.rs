@@ -1,3 +1,6 @@
+// Lets code generated by `shared_derive` refer to this crate by name from inside it.
+extern crate self as shared_core;
+
 pub mod config;
 pub mod crypt;
 pub mod database;
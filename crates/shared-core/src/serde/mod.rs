@@ -1,7 +1,9 @@
 mod derive;
 mod enum_variants;
 mod struct_fields;
+mod timestamp;
 
 pub use derive::*;
 pub use enum_variants::*;
 pub use struct_fields::*;
+pub use timestamp::*;
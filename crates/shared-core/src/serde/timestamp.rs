@@ -0,0 +1,112 @@
+/// (De)serialize `chrono::NaiveDateTime` in the format sqlite uses for `DATETIME('NOW')`.
+///
+/// Chrono's default format separates date and time with a `T`, which doesn't compare
+/// correctly against timestamps written by sqlite.
+pub mod datetime {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    /// Format used by sqlite.
+    const FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+    /// Format used by chrono.
+    const FORMAT_ISO_8601: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+    pub fn serialize<S>(value: &chrono::NaiveDateTime, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(&value.format(FORMAT))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<chrono::NaiveDateTime, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+
+        chrono::NaiveDateTime::parse_from_str(&value, FORMAT)
+            .or_else(|_| chrono::NaiveDateTime::parse_from_str(&value, FORMAT_ISO_8601))
+            .map_err(serde::de::Error::custom)
+    }
+
+    /// Same as parent but for optional timestamps.
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S>(
+            value: &Option<chrono::NaiveDateTime>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            match value {
+                Some(x) => super::serialize(x, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D>(
+            deserializer: D,
+        ) -> Result<Option<chrono::NaiveDateTime>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            #[derive(Deserialize)]
+            struct Wrapper(#[serde(deserialize_with = "super::deserialize")] chrono::NaiveDateTime);
+
+            let value = Option::<Wrapper>::deserialize(deserializer)?;
+
+            Ok(value.map(|x| x.0))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Test {
+        #[serde(with = "super::datetime")]
+        a: chrono::NaiveDateTime,
+        #[serde(with = "super::datetime::option")]
+        b: Option<chrono::NaiveDateTime>,
+    }
+
+    #[tokio::test]
+    async fn datetime() {
+        let timestamp =
+            chrono::NaiveDateTime::parse_from_str("2025-11-03 02:15:40", "%Y-%m-%d %H:%M:%S")
+                .unwrap();
+
+        let test_1 = Test {
+            a: timestamp,
+            b: Some(timestamp),
+        };
+
+        let json = serde_json::to_string(&test_1);
+
+        assert!(json.is_ok());
+
+        let json = json.unwrap();
+
+        assert_eq!(
+            json,
+            "{\"a\":\"2025-11-03 02:15:40\",\"b\":\"2025-11-03 02:15:40\"}"
+        );
+
+        // Both sqlite and chrono formats should be accepted
+        let test_2 = serde_json::from_str::<Test>(&json);
+        let test_3 = serde_json::from_str::<Test>("{\"a\":\"2025-11-03T02:15:40\",\"b\":null}");
+
+        assert_eq!(test_2.unwrap(), test_1);
+        assert_eq!(
+            test_3.unwrap(),
+            Test {
+                a: timestamp,
+                b: None
+            }
+        );
+    }
+}
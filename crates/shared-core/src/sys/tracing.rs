@@ -6,7 +6,9 @@ use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 
 /// Install global tracing subscriber
-pub fn init_tracing_subscriber(log_path: &Path) -> Result<(), crate::error::Error> {
+///
+/// Logs are always written to a daily rolling file, `stdout` also writes them to the console.
+pub fn init_tracing_subscriber(log_path: &Path, stdout: bool) -> Result<(), crate::error::Error> {
     let filename = log_path.file_stem().unwrap().to_str().unwrap().to_string();
     let parent = log_path.parent().unwrap();
 
@@ -20,10 +22,12 @@ pub fn init_tracing_subscriber(log_path: &Path) -> Result<(), crate::error::Erro
 
     // Only write to stdout if we are not the CLI client.
     // The CLI client uses a prettier console writer.
-    let layer_stdout = tracing_subscriber::fmt::layer()
-        .compact()
-        .with_writer(std::io::stdout)
-        .with_filter(LevelFilter::INFO);
+    let layer_stdout = stdout.then(|| {
+        tracing_subscriber::fmt::layer()
+            .compact()
+            .with_writer(std::io::stdout)
+            .with_filter(LevelFilter::INFO)
+    });
 
     let layer_logfile = tracing_subscriber::fmt::layer()
         .compact()
@@ -0,0 +1,30 @@
+mod table;
+
+/// Derive `shared_core::database::Table` for a row type.
+///
+/// ```ignore
+/// #[derive(serde::Serialize, serde::Deserialize, sqlx::FromRow, Table)]
+/// #[table(name = "secrets")]
+/// pub struct Secret {
+///     #[table(primary_key)]
+///     pub uuid: uuid::fmt::Hyphenated,
+///     pub name: String,
+///     #[table(soft_delete)]
+///     pub deleted: bool,
+///     #[table(deleted_at)]
+///     pub deleted_at: Option<chrono::NaiveDateTime>,
+/// }
+/// ```
+///
+/// Every field gets a typed column constant prefixed with `COLUMN_`, for example
+/// `Secret::COLUMN_NAME` can't be passed where a column of another table is expected.
+/// Columns are named after their fields, `#[serde(rename)]` and `#[serde(rename_all)]` are
+/// rejected.
+#[proc_macro_derive(Table, attributes(table))]
+pub fn derive_table(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+
+    table::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,417 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+/// Options set on the struct with `#[table(...)]`.
+#[derive(Default)]
+struct TableOptions {
+    name: Option<syn::LitStr>,
+}
+
+/// Options set on a field with `#[table(...)]`.
+#[derive(Default)]
+struct ColumnOptions {
+    primary_key: bool,
+    soft_delete: bool,
+    deleted_at: bool,
+}
+
+pub fn expand(input: syn::DeriveInput) -> syn::Result<TokenStream> {
+    let ident = &input.ident;
+
+    let fields = match &input.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(x),
+            ..
+        }) => &x.named,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "Table can only be derived for structs with named fields",
+            ));
+        }
+    };
+
+    // Crud checks columns against the serde field names, so they have to match the field names.
+    reject_serde_rename(&input.attrs, "rename_all")?;
+
+    let mut table = TableOptions::default();
+
+    for attr in input.attrs.iter().filter(|x| x.path().is_ident("table")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                table.name = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unknown table attribute"))
+            }
+        })?;
+    }
+
+    let name = table.name.ok_or_else(|| {
+        syn::Error::new_spanned(ident, "missing table name, add #[table(name = \"...\")]")
+    })?;
+
+    let mut columns = Vec::new();
+    let mut constants = Vec::new();
+    let mut primary_keys = Vec::new();
+    let mut soft_delete = None;
+    let mut deleted_at = None;
+
+    for field in fields {
+        // Named fields always have an identifier.
+        let field_ident = field.ident.as_ref().unwrap();
+        let column = field_ident.to_string();
+        let constant = format_ident!("COLUMN_{}", column.to_uppercase());
+        let options = parse_column(field)?;
+
+        if options.primary_key {
+            primary_keys.push((field_ident, column.clone()));
+        }
+
+        if options.soft_delete {
+            if soft_delete.is_some() {
+                return Err(syn::Error::new_spanned(
+                    field_ident,
+                    "only one column can be marked soft_delete",
+                ));
+            }
+
+            soft_delete = Some(column.clone());
+        }
+
+        if options.deleted_at {
+            if deleted_at.is_some() {
+                return Err(syn::Error::new_spanned(
+                    field_ident,
+                    "only one column can be marked deleted_at",
+                ));
+            }
+
+            deleted_at = Some(column.clone());
+        }
+
+        let doc = format!("Column `{}`.", column);
+
+        constants.push(quote! {
+            #[doc = #doc]
+            pub const #constant: ::shared_core::database::Column<Self> =
+                ::shared_core::database::Column::new(#column);
+        });
+
+        columns.push(column);
+    }
+
+    if primary_keys.is_empty() {
+        return Err(syn::Error::new_spanned(
+            ident,
+            "missing primary key, add #[table(primary_key)] to at least one field",
+        ));
+    }
+
+    if deleted_at.is_some() && soft_delete.is_none() {
+        return Err(syn::Error::new_spanned(
+            ident,
+            "deleted_at requires a column marked soft_delete",
+        ));
+    }
+
+    let primary_key_columns = primary_keys.iter().map(|(_, x)| x);
+    let primary_key_values = primary_keys.iter().map(|(field, column)| {
+        quote! {
+            (
+                ::shared_core::database::Column::new(#column),
+                ::std::string::ToString::to_string(&self.#field),
+            )
+        }
+    });
+
+    let soft_delete = option_column(soft_delete);
+    let deleted_at = option_column(deleted_at);
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #(#constants)*
+        }
+
+        impl #impl_generics ::shared_core::database::TableName for #ident #ty_generics #where_clause {
+            const NAME: &'static str = #name;
+        }
+
+        impl #impl_generics ::shared_core::database::Table for #ident #ty_generics #where_clause {
+            const COLUMNS: &'static [&'static str] = &[#(#columns),*];
+            const PRIMARY_KEY: &'static [::shared_core::database::Column<Self>] =
+                &[#(::shared_core::database::Column::new(#primary_key_columns)),*];
+            const SOFT_DELETE: ::std::option::Option<::shared_core::database::Column<Self>> =
+                #soft_delete;
+            const DELETED_AT: ::std::option::Option<::shared_core::database::Column<Self>> =
+                #deleted_at;
+
+            fn primary_key(
+                &self,
+            ) -> ::std::vec::Vec<(::shared_core::database::Column<Self>, ::std::string::String)> {
+                ::std::vec![#(#primary_key_values),*]
+            }
+        }
+    })
+}
+
+/// Parse `#[table(...)]` attributes of a field.
+fn parse_column(field: &syn::Field) -> syn::Result<ColumnOptions> {
+    let mut options = ColumnOptions::default();
+
+    reject_serde_rename(&field.attrs, "rename")?;
+
+    for attr in field.attrs.iter().filter(|x| x.path().is_ident("table")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("primary_key") {
+                options.primary_key = true;
+            } else if meta.path.is_ident("soft_delete") {
+                options.soft_delete = true;
+            } else if meta.path.is_ident("deleted_at") {
+                options.deleted_at = true;
+            } else {
+                return Err(meta.error("unknown column attribute"));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(options)
+}
+
+/// Reject `#[serde(...)]` attributes with the given renaming key.
+fn reject_serde_rename(attrs: &[syn::Attribute], key: &str) -> syn::Result<()> {
+    for attr in attrs.iter().filter(|x| x.path().is_ident("serde")) {
+        let metas = attr.parse_args_with(
+            syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated,
+        )?;
+
+        if let Some(meta) = metas.iter().find(|x| x.path().is_ident(key)) {
+            return Err(syn::Error::new_spanned(
+                meta,
+                format!(
+                    "serde {} is not supported, columns are named after fields",
+                    key
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn option_column(column: Option<String>) -> TokenStream {
+    match column {
+        Some(x) => quote! {
+            ::std::option::Option::Some(::shared_core::database::Column::new(#x))
+        },
+        None => quote! { ::std::option::Option::None },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proc_macro2::TokenStream;
+    use quote::quote;
+
+    /// Expand derive of input, errors are returned as their message.
+    fn expand(input: TokenStream) -> Result<String, String> {
+        super::expand(syn::parse2(input).unwrap())
+            .map(|x| x.to_string())
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn expand_table() {
+        let result = expand(quote! {
+            #[table(name = "secrets")]
+            struct Secret {
+                #[table(primary_key)]
+                uuid: String,
+                #[table(primary_key)]
+                name: String,
+                secret_type: u32,
+                #[table(soft_delete)]
+                deleted: bool,
+                #[table(deleted_at)]
+                deleted_at: Option<String>,
+            }
+        })
+        .unwrap();
+
+        // Every field gets a column constant named after it in upper case.
+        for (constant, column) in [
+            (quote! { COLUMN_UUID }, "uuid"),
+            (quote! { COLUMN_NAME }, "name"),
+            (quote! { COLUMN_SECRET_TYPE }, "secret_type"),
+            (quote! { COLUMN_DELETED }, "deleted"),
+            (quote! { COLUMN_DELETED_AT }, "deleted_at"),
+        ] {
+            let expected = quote! {
+                pub const #constant: ::shared_core::database::Column<Self> =
+                    ::shared_core::database::Column::new(#column);
+            };
+
+            assert!(result.contains(&expected.to_string()), "{}", column);
+        }
+
+        for expected in [
+            quote! { const NAME: &'static str = "secrets"; },
+            quote! {
+                const COLUMNS: &'static [&'static str] =
+                    &["uuid", "name", "secret_type", "deleted", "deleted_at"];
+            },
+            quote! {
+                const PRIMARY_KEY: &'static [::shared_core::database::Column<Self>] = &[
+                    ::shared_core::database::Column::new("uuid"),
+                    ::shared_core::database::Column::new("name")
+                ];
+            },
+            quote! {
+                const SOFT_DELETE: ::std::option::Option<::shared_core::database::Column<Self>> =
+                    ::std::option::Option::Some(::shared_core::database::Column::new("deleted"));
+            },
+            quote! {
+                const DELETED_AT: ::std::option::Option<::shared_core::database::Column<Self>> =
+                    ::std::option::Option::Some(::shared_core::database::Column::new("deleted_at"));
+            },
+        ] {
+            assert!(result.contains(&expected.to_string()), "{}", expected);
+        }
+    }
+
+    #[test]
+    fn expand_table_without_soft_delete() {
+        let result = expand(quote! {
+            #[table(name = "grants")]
+            struct Grant<'a> {
+                #[table(primary_key)]
+                uuid: &'a str,
+            }
+        })
+        .unwrap();
+
+        let expected = quote! {
+            const SOFT_DELETE: ::std::option::Option<::shared_core::database::Column<Self>> =
+                ::std::option::Option::None;
+        };
+
+        assert!(result.contains(&expected.to_string()));
+        assert!(result.contains(&quote! { for Grant<'a> }.to_string()));
+    }
+
+    #[test]
+    fn expand_errors() {
+        for (input, error) in [
+            (
+                quote! {
+                    struct Secret {
+                        #[table(primary_key)]
+                        uuid: String,
+                    }
+                },
+                "missing table name",
+            ),
+            (
+                quote! {
+                    #[table(name = "secrets", schema = "main")]
+                    struct Secret {
+                        #[table(primary_key)]
+                        uuid: String,
+                    }
+                },
+                "unknown table attribute",
+            ),
+            (
+                quote! {
+                    #[table(name = "secrets")]
+                    struct Secret {
+                        #[table(unique)]
+                        uuid: String,
+                    }
+                },
+                "unknown column attribute",
+            ),
+            (
+                quote! {
+                    #[table(name = "secrets")]
+                    struct Secret {
+                        uuid: String,
+                    }
+                },
+                "missing primary key",
+            ),
+            (
+                quote! {
+                    #[table(name = "secrets")]
+                    struct Secret {
+                        #[table(primary_key)]
+                        uuid: String,
+                        #[table(soft_delete)]
+                        deleted: bool,
+                        #[table(soft_delete)]
+                        removed: bool,
+                    }
+                },
+                "only one column can be marked soft_delete",
+            ),
+            (
+                quote! {
+                    #[table(name = "secrets")]
+                    struct Secret {
+                        #[table(primary_key)]
+                        uuid: String,
+                        #[table(deleted_at)]
+                        deleted_at: Option<String>,
+                    }
+                },
+                "deleted_at requires a column marked soft_delete",
+            ),
+            (
+                quote! {
+                    #[table(name = "secrets")]
+                    struct Secret {
+                        #[table(primary_key)]
+                        #[serde(default, rename = "id")]
+                        uuid: String,
+                    }
+                },
+                "serde rename is not supported",
+            ),
+            (
+                quote! {
+                    #[table(name = "secrets")]
+                    #[serde(rename_all = "camelCase")]
+                    struct Secret {
+                        #[table(primary_key)]
+                        uuid: String,
+                    }
+                },
+                "serde rename_all is not supported",
+            ),
+            (
+                quote! {
+                    #[table(name = "secrets")]
+                    struct Secret(String);
+                },
+                "structs with named fields",
+            ),
+            (
+                quote! {
+                    #[table(name = "secrets")]
+                    enum Secret {
+                        Key,
+                    }
+                },
+                "structs with named fields",
+            ),
+        ] {
+            let result = expand(input);
+
+            assert!(result.unwrap_err().contains(error), "{}", error);
+        }
+    }
+}